@@ -0,0 +1,63 @@
+//! 前缀树与逐个比较前缀的匹配速度
+//!
+//! 分别用 10、1000 和 100000 个随机前缀（3 到 6 个字符）匹配同一批随机地址，
+//! 打印每个地址的平均耗时。`cargo bench --bench prefix_match` 运行，不依赖额外的基准测试库。
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use solana_vanity_address::Search;
+use solana_vanity_address::base58::ALPHABET;
+
+/// 每组匹配的地址数
+const ADDRESSES: usize = 20_000;
+
+/// 每个计时至少跑这么久，取多轮中最快的一轮
+const MIN_TIME: Duration = Duration::from_millis(500);
+
+fn main() {
+    let mut rng = ChaCha20Rng::seed_from_u64(169);
+    let addresses: Vec<String> = (0..ADDRESSES)
+        .map(|_| bs58::encode(rng.r#gen::<[u8; 32]>()).into_string())
+        .collect();
+
+    println!("{:>8}  {:>14}  {:>14}  {:>8}", "前缀数", "前缀树 ns/地址", "逐个 ns/地址", "倍数");
+    for count in [10, 1_000, 100_000] {
+        let prefixes: Vec<String> = (0..count)
+            .map(|_| {
+                let length = rng.gen_range(3..=6);
+                (0..length).map(|_| ALPHABET.as_bytes()[rng.gen_range(0..58)] as char).collect()
+            })
+            .collect();
+        let search = Search::new(prefixes.iter().cloned());
+        let trie = per_address(&addresses, |address| search.find_all(address).len());
+        let naive = per_address(&addresses, |address| {
+            prefixes.iter().filter(|prefix| address.starts_with(prefix.as_str())).count()
+        });
+        println!(
+            "{:>8}  {:>14.1}  {:>14.1}  {:>7.1}x",
+            count,
+            trie,
+            naive,
+            naive / trie
+        );
+    }
+}
+
+/// 对每个地址调用一次 `find`，返回多轮中最快一轮的每个地址平均纳秒数
+fn per_address(addresses: &[String], find: impl Fn(&str) -> usize) -> f64 {
+    let start = Instant::now();
+    let mut best = f64::INFINITY;
+    while start.elapsed() < MIN_TIME || best.is_infinite() {
+        let round = Instant::now();
+        let mut total = 0;
+        for address in addresses {
+            total += find(black_box(address));
+        }
+        black_box(total);
+        best = best.min(round.elapsed().as_nanos() as f64 / addresses.len() as f64);
+    }
+    best
+}
@@ -0,0 +1,22 @@
+use std::path::Path;
+use std::process::Command;
+
+/// 把当前提交写入 `SOLVANITY_GIT_HASH`，供审计日志记录；不在 git 仓库中构建时为空
+fn main() {
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=SOLVANITY_GIT_HASH={}", hash);
+
+    // 提交变化时重新运行；不存在的路径会让 cargo 每次都重新运行，所以只监视存在的文件
+    for path in [".git/HEAD", ".git/refs/heads"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
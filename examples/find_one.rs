@@ -0,0 +1,47 @@
+//! 用库找一个指定前缀的地址并打印，是库接口的最小用法示例
+//!
+//! ```bash
+//! cargo run --release --example find_one -- So
+//! ```
+//!
+//! 单线程、不写任何文件；私钥打印在终端上，只用于演示，不要用它生成真实资产的地址。
+
+use solana_sdk::signer::Signer;
+use solana_vanity_address::Search;
+use solana_vanity_address::difficulty::expected_attempts;
+use solana_vanity_address::keygen::{KeyGenerator, RngSource};
+use solana_vanity_address::matcher::Matcher;
+use solana_vanity_address::search::{self, MatchCounts};
+
+fn main() {
+    let prefix = std::env::args().nth(1).unwrap_or_else(|| "So".to_string());
+    if let Err(err) = search::validate_prefix(&prefix) {
+        eprintln!("错误: {}", err);
+        std::process::exit(2);
+    }
+    println!("查找以 {} 开头的地址，期望约 {:.0} 次尝试", prefix, expected_attempts(&prefix, false));
+
+    let search = Search::new([Matcher::Prefix(prefix)]);
+    let mut generator = KeyGenerator::new(RngSource::Os);
+    let mut attempts = 0u64;
+    let (keypair, address, found) = loop {
+        let keypair = generator.generate();
+        let address = keypair.pubkey().to_string();
+        attempts += 1;
+        let found = search.find_all(&address);
+        if !found.is_empty() {
+            break (keypair, address, found);
+        }
+    };
+
+    // 计数按批合并，这里整个搜索就是一批
+    let mut matches = MatchCounts::new(search.prefixes().len());
+    matches.credit(&found);
+    search.record_batch(attempts, &mut matches);
+    let stats = search.stats();
+
+    println!("地址: {}", address);
+    println!("前缀: {}", search.matched_text(&found, &address));
+    println!("私钥: {}", bs58::encode(keypair.to_bytes()).into_string());
+    println!("尝试 {} 次，匹配 {} 个，用时 {:.2} 秒", stats.generated, stats.matched, stats.elapsed.as_secs_f64());
+}
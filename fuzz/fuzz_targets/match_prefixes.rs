@@ -0,0 +1,103 @@
+//! 前缀匹配的模糊测试
+//!
+//! 输入按换行切开：最后一段是“地址”，前面各段是前缀（最多 [`MAX_PATTERNS`] 个），
+//! 可以是普通前缀、字符类、`<palindrome N>`、`<mirror N>`、`<fuzzy WORD K>` 或 `0x…` 字节模式，
+//! 后几种写法由 [`parse`] 转成对应的 [`Matcher`]，其余都是文本前缀。
+//! 检查 [`Search::find_all`]（前缀树）与逐个前缀直接比较的朴素实现给出相同的结果，
+//! [`Search::find`] 是其中的第一个，且整个过程不会 panic。地址可以是任意字节，
+//! 只有 ASCII 地址（真实地址都是）才继续检查输出用的前缀列和高亮位置。
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solana_vanity_address::byte_pattern::BytePattern;
+use solana_vanity_address::char_class;
+use solana_vanity_address::fuzzy::Fuzzy;
+use solana_vanity_address::matcher::Matcher;
+use solana_vanity_address::search::Search;
+use solana_vanity_address::symmetry::Symmetry;
+
+/// 前缀数量的上限，避免字符类展开把单次执行拖得太慢
+const MAX_PATTERNS: usize = 4;
+
+/// 单个前缀的字节数上限
+const MAX_PATTERN_BYTES: usize = 16;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let mut parts: Vec<&str> = text.split('\n').collect();
+    let address = parts.pop().unwrap_or_default();
+    if parts.len() > MAX_PATTERNS || parts.iter().any(|part| part.len() > MAX_PATTERN_BYTES) {
+        return;
+    }
+
+    let search = Search::new(parts.iter().map(|part| parse(part)));
+    let found = search.find_all(address);
+    let expected: Vec<usize> = search
+        .matchers()
+        .iter()
+        .enumerate()
+        .filter(|(_, matcher)| naive_matches(matcher, address))
+        .map(|(index, _)| index)
+        .collect();
+    assert_eq!(found, expected, "前缀 {:?}，地址 {:?}", search.prefixes(), address);
+    assert_eq!(search.find(address), expected.first().copied());
+
+    if address.is_ascii() {
+        let text = search.matched_text(&found, address);
+        assert_eq!(text.is_empty(), found.iter().all(|index| search.prefixes()[*index].is_empty()));
+        for span in search.matched_spans(&found, address) {
+            assert!(span.start <= span.end && span.end <= address.len());
+        }
+    }
+});
+
+/// 把一段输入转成目标：字节模式、对称匹配器和模糊前缀的写法转成对应的变体，其余是文本前缀
+fn parse(part: &str) -> Matcher {
+    if let Some(pattern) = BytePattern::from_label(part) {
+        return Matcher::Bytes(pattern);
+    }
+    if let Some(symmetry) = Symmetry::from_label(part) {
+        return Matcher::Symmetry(symmetry);
+    }
+    match Fuzzy::from_label(part) {
+        Some(fuzzy) => Matcher::Fuzzy(fuzzy),
+        None => Matcher::Prefix(part.to_string()),
+    }
+}
+
+/// 逐个目标直接比较：字节模式不参与文本匹配，回文和镜像检查对称，模糊前缀对地址的每个开头
+/// 分别算完整的编辑距离，
+/// 文本前缀展开字符类后看地址是否以任一具体前缀开头，无法展开的按字面比较
+fn naive_matches(matcher: &Matcher, address: &str) -> bool {
+    match matcher {
+        Matcher::Bytes(_) => false,
+        Matcher::Symmetry(symmetry) => symmetry.matches(address),
+        Matcher::Fuzzy(fuzzy) => (0..=address.len())
+            .any(|length| levenshtein(fuzzy.word().as_bytes(), &address.as_bytes()[..length]) <= fuzzy.max_distance()),
+        Matcher::Prefix(prefix) => char_class::expand(prefix)
+            .unwrap_or_else(|_| vec![prefix.to_string()])
+            .iter()
+            .any(|literal| address.starts_with(literal.as_str())),
+    }
+}
+
+/// 两个字节串的编辑距离，完整的二维表
+fn levenshtein(a: &[u8], b: &[u8]) -> usize {
+    let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in table.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for j in 0..=b.len() {
+        table[0][j] = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitute = table[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            table[i][j] = substitute.min(table[i - 1][j] + 1).min(table[i][j - 1] + 1);
+        }
+    }
+    table[a.len()][b.len()]
+}
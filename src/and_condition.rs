@@ -0,0 +1,126 @@
+//! 与条件（`--and`）
+//!
+//! `-p`、`--palindrome`、`--fuzzy` 等目标之间是“或”：满足任一个就算匹配。`--and KIND:TEXT`
+//! 加上必须同时满足的条件，例如 `-p Sol --and contains:777` 要求以 `Sol` 开头，并且任意位置含有 `777`。
+//! KIND 为 `prefix`（开头）、`suffix`（结尾）或 `contains`（任意位置），TEXT 只能是 Base58 字符，区分大小写。
+//!
+//! 优先级固定：先在各目标之间取“或”，再与所有 `--and` 条件、字节过滤、计数条件和形状取“与”，
+//! 即 `(目标1 或 目标2 …) 且 条件1 且 条件2 …`；没有目标时只要求满足条件。
+//! 与计数条件一样在 Base58 编码之后检查，由 [`crate::Search::accepts_address`] 统一判断。
+//!
+//! 概率：按 [`address_classes`] 逐类计算。开头和结尾条件是对应各位概率之积；含有条件对每个起点
+//! 算出从那里出现的概率，按各起点互相独立合成 `1 - ∏(1 - p)`，忽略了 `777` 这类自身重叠的字符串
+//! 各次出现之间的相关性，是略偏高的近似。与前缀同时使用时，前缀占据的位置已经确定，
+//! 与前缀矛盾的位概率为 0，一致的位概率为 1。多个条件按独立事件相乘。
+
+use std::fmt;
+
+use crate::base58::Alphabet;
+use crate::char_stats::POSITIONS;
+use crate::difficulty::address_classes;
+
+/// 条件要求文本出现的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    Prefix,
+    Suffix,
+    Contains,
+}
+
+impl Anchor {
+    fn name(self) -> &'static str {
+        match self {
+            Anchor::Prefix => "prefix",
+            Anchor::Suffix => "suffix",
+            Anchor::Contains => "contains",
+        }
+    }
+}
+
+/// 一个与条件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AndCondition {
+    pub anchor: Anchor,
+    pub text: String,
+}
+
+impl AndCondition {
+    /// 检查地址是否满足条件
+    pub fn accepts(&self, address: &str) -> bool {
+        match self.anchor {
+            Anchor::Prefix => address.starts_with(&self.text),
+            Anchor::Suffix => address.ends_with(&self.text),
+            Anchor::Contains => address.contains(&self.text),
+        }
+    }
+
+    /// 随机地址以 `prefix` 开头时满足条件的概率，`prefix` 为空时即单独满足的概率
+    pub fn probability(&self, prefix: &str) -> f64 {
+        address_classes()
+            .iter()
+            .map(|(weight, positions)| weight * self.probability_with(prefix.as_bytes(), positions))
+            .sum()
+    }
+
+    /// 给定每一位上各数字的概率时满足条件的概率
+    fn probability_with(&self, prefix: &[u8], positions: &[[f64; 58]]) -> f64 {
+        let text = self.text.as_bytes();
+        let Some(last) = positions.len().checked_sub(text.len()) else {
+            return 0.0;
+        };
+        match self.anchor {
+            Anchor::Prefix => occurs_at(text, 0, prefix, positions),
+            Anchor::Suffix => occurs_at(text, last, prefix, positions),
+            Anchor::Contains => {
+                1.0 - (0..=last)
+                    .map(|start| 1.0 - occurs_at(text, start, prefix, positions))
+                    .product::<f64>()
+            }
+        }
+    }
+}
+
+impl fmt::Display for AndCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.anchor.name(), self.text)
+    }
+}
+
+/// `text` 从第 `start` 位起出现的概率：落在前缀中的位必须与前缀一致，其余位按该位的分布
+fn occurs_at(text: &[u8], start: usize, prefix: &[u8], positions: &[[f64; 58]]) -> f64 {
+    text.iter()
+        .enumerate()
+        .map(|(offset, &byte)| match prefix.get(start + offset) {
+            Some(known) if *known == byte => 1.0,
+            Some(_) => 0.0,
+            None => Alphabet::BITCOIN
+                .digit(byte as char)
+                .map_or(0.0, |digit| positions[start + offset][digit]),
+        })
+        .product()
+}
+
+/// 解析 `KIND:TEXT`，如 `contains:777`
+pub fn parse_and_condition(value: &str) -> Result<AndCondition, String> {
+    let invalid = || format!("无效的与条件: {}（应为 prefix:TEXT、suffix:TEXT 或 contains:TEXT）", value);
+    let (kind, text) = value.split_once(':').ok_or_else(invalid)?;
+    let anchor = match kind.trim() {
+        "prefix" => Anchor::Prefix,
+        "suffix" => Anchor::Suffix,
+        "contains" => Anchor::Contains,
+        _ => return Err(invalid()),
+    };
+    if text.is_empty() {
+        return Err(invalid());
+    }
+    if let Err(c) = Alphabet::BITCOIN.validate(text) {
+        return Err(format!("字符 '{}' 不在 Base58 字母表中，条件 {} 永远不会满足", c, value));
+    }
+    if text.len() > POSITIONS {
+        return Err(format!("地址最多 {} 个字符，条件 {} 永远不会满足", POSITIONS, value));
+    }
+    Ok(AndCondition {
+        anchor,
+        text: text.to_string(),
+    })
+}
@@ -0,0 +1,123 @@
+//! 关联代币账户（ATA）地址推导
+//!
+//! 钱包对某个 SPL 代币的关联代币账户是关联代币账户程序下的 PDA，
+//! 种子依次为钱包地址、代币所属的 Token 程序地址和代币 mint 地址，与官方
+//! `spl-associated-token-account` 的 `get_associated_token_address_with_program_id` 完全一致。
+//! 原版 SPL Token 和 Token-2022 的代币用同一个关联代币账户程序，只是种子中的 Token 程序不同，
+//! 所以同一个钱包对同一个 mint 在两个程序下的 ATA 不同。mint 属于哪个程序要查链上的账户，
+//! 这里不访问网络，由调用方用 `MINT:token-2022` 指明，默认是原版 SPL Token。推导是纯计算。
+
+use std::fmt;
+
+use solana_sdk::pubkey::Pubkey;
+
+/// SPL Token 程序
+pub const TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/// Token-2022 程序
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// 关联代币账户程序
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = solana_sdk::pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// mint 后面加上它表示代币属于 Token-2022
+pub const TOKEN_2022_SUFFIX: &str = ":token-2022";
+
+/// `--derive-ata` 的一项：代币 mint 和它所属的 Token 程序
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mint {
+    pub mint: Pubkey,
+    pub token_2022: bool,
+}
+
+impl Mint {
+    /// 代币所属的 Token 程序
+    pub fn token_program(&self) -> &'static Pubkey {
+        if self.token_2022 { &TOKEN_2022_PROGRAM_ID } else { &TOKEN_PROGRAM_ID }
+    }
+}
+
+/// 原版 SPL Token 的代币是 mint 本身，Token-2022 的代币加上 [`TOKEN_2022_SUFFIX`]，可以由 [`parse_mint`] 解析回来
+impl fmt::Display for Mint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.mint)?;
+        if self.token_2022 {
+            f.write_str(TOKEN_2022_SUFFIX)?;
+        }
+        Ok(())
+    }
+}
+
+/// 钱包对 `mint` 的关联代币账户地址
+pub fn associated_token_address(wallet: &Pubkey, mint: &Mint) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), mint.token_program().as_ref(), mint.mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// 解析代币 mint 地址，`MINT:token-2022` 表示 Token-2022 的代币
+pub fn parse_mint(value: &str) -> Result<Mint, String> {
+    let value = value.trim();
+    let (mint, token_2022) = match value.strip_suffix(TOKEN_2022_SUFFIX) {
+        Some(mint) => (mint, true),
+        None => (value, false),
+    };
+    let mint = mint.parse().map_err(|_| format!("无效的代币 mint 地址: {}", value))?;
+    Ok(Mint { mint, token_2022 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC: Pubkey = solana_sdk::pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+    /// spl-associated-token-account 2.3.0 中 `get_associated_token_address_and_bump_seed_internal` 的原样复制
+    fn reference(wallet: &Pubkey, mint: &Pubkey, program_id: &Pubkey, token_program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[&wallet.to_bytes(), &token_program_id.to_bytes(), &mint.to_bytes()], program_id)
+    }
+
+    // 离线环境中拿不到可以核对的主网 (钱包, mint, ATA) 三元组，这里与官方实现的推导逐项核对，
+    // 并用 create_program_address 确认得到的是该 bump 下的合法 PDA
+    #[test]
+    fn matches_the_official_derivation() {
+        for token_2022 in [false, true] {
+            let token_program = if token_2022 { TOKEN_2022_PROGRAM_ID } else { TOKEN_PROGRAM_ID };
+            for _ in 0..50 {
+                let wallet = Pubkey::new_unique();
+                let mint = Mint { mint: Pubkey::new_unique(), token_2022 };
+                let (expected, bump) = reference(&wallet, &mint.mint, &ASSOCIATED_TOKEN_PROGRAM_ID, &token_program);
+                let address = associated_token_address(&wallet, &mint);
+                assert_eq!(address, expected);
+                assert!(!address.is_on_curve());
+                let seeds: [&[u8]; 4] = [wallet.as_ref(), token_program.as_ref(), mint.mint.as_ref(), &[bump]];
+                assert_eq!(Pubkey::create_program_address(&seeds, &ASSOCIATED_TOKEN_PROGRAM_ID).unwrap(), address);
+            }
+        }
+    }
+
+    #[test]
+    fn token_2022_accounts_differ_from_token_accounts() {
+        let wallet = Pubkey::new_unique();
+        let token = associated_token_address(&wallet, &Mint { mint: USDC, token_2022: false });
+        let token_2022 = associated_token_address(&wallet, &Mint { mint: USDC, token_2022: true });
+        assert_ne!(token, token_2022);
+    }
+
+    #[test]
+    fn mints_are_parsed_and_displayed() {
+        let token = parse_mint(" EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v ").unwrap();
+        assert_eq!(token, Mint { mint: USDC, token_2022: false });
+        assert_eq!(token.to_string(), USDC.to_string());
+
+        let token_2022 = parse_mint("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v:token-2022").unwrap();
+        assert_eq!(token_2022, Mint { mint: USDC, token_2022: true });
+        assert_eq!(parse_mint(&token_2022.to_string()).unwrap(), token_2022);
+        assert_eq!(token_2022.token_program(), &TOKEN_2022_PROGRAM_ID);
+
+        assert!(parse_mint("not-a-mint").is_err());
+        assert!(parse_mint("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v:token-2021").is_err());
+    }
+}
@@ -0,0 +1,40 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 每个匹配的尝试次数日志
+///
+/// 每找到一个匹配追加一行 `prefix,attempt_number,elapsed_ms`，供离线研究搜索时间的分布，
+/// 比运气汇总里的直方图细得多。尝试次数取匹配时全局的生成计数（与运气统计相同的估算），
+/// 各工作线程按批合并计数，所以并发时相邻几行的尝试次数可能略有先后颠倒，误差不超过各线程一批的总和。
+/// 每行写完立即刷新，文件以追加方式打开，与主输出文件互不影响。
+pub struct AttemptsLog {
+    file: Mutex<File>,
+}
+
+impl AttemptsLog {
+    pub const HEADER: &'static str = "prefix,attempt_number,elapsed_ms";
+
+    /// 打开（或创建）日志文件，空文件会先写入标题行
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if file.metadata()?.len() == 0 {
+            writeln!(file, "{}", Self::HEADER)?;
+            file.flush()?;
+        }
+        Ok(Self { file: Mutex::new(file) })
+    }
+
+    /// 追加一个匹配：前缀、匹配时的全局尝试次数、不含暂停的运行时长
+    pub fn record(&self, prefix: &str, attempt: u64, elapsed: Duration) -> io::Result<()> {
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{},{},{}", prefix, attempt, elapsed.as_millis())?;
+        file.flush()
+    }
+}
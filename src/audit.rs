@@ -0,0 +1,145 @@
+//! 运行审计日志
+//!
+//! 每次搜索在一个 JSON Lines 文件（默认 `~/.solvanity/runs.jsonl`）里追加两条记录：
+//! 开始时记录时间、生效的参数、主机、版本和提交；结束时记录耗时、尝试次数、
+//! 每个前缀的匹配数、结束原因以及输出文件的路径和哈希。日志只追加不改写，
+//! 永远不包含私钥；写入失败只警告，不影响搜索。
+
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::manifest::{self, FileEntry};
+
+/// 相对于用户主目录的默认日志路径
+pub const DEFAULT_RELATIVE_PATH: &str = ".solvanity/runs.jsonl";
+
+/// 参数值被隐去时写入的占位符
+pub const REDACTED: &str = "<redacted>";
+
+/// 默认日志路径，找不到用户主目录时返回 `None`
+pub fn default_path() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|home| !home.is_empty())
+        .map(|home| PathBuf::from(home).join(DEFAULT_RELATIVE_PATH))
+}
+
+/// 构建时的 git 提交，不在 git 仓库中构建时为 `None`
+pub fn git_hash() -> Option<&'static str> {
+    option_env!("SOLVANITY_GIT_HASH").filter(|hash| !hash.is_empty())
+}
+
+/// 主机名，依次尝试环境变量和 `/etc/hostname`
+pub fn host_name() -> Option<String> {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// 开始记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunStart {
+    pub run_id: String,
+    pub timestamp: String,
+    pub version: String,
+    pub git_hash: Option<String>,
+    pub host: Option<String>,
+    /// 生效的参数（含默认值），敏感的值已隐去
+    pub config: BTreeMap<String, Vec<String>>,
+}
+
+/// 结束记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunEnd {
+    pub run_id: String,
+    pub timestamp: String,
+    pub duration_secs: f64,
+    pub attempts: u64,
+    /// 每个前缀的匹配数
+    pub matches: BTreeMap<String, u64>,
+    pub exit_reason: String,
+    /// 输出文件，尽量保存为绝对路径
+    pub outputs: Vec<FileEntry>,
+}
+
+/// 日志中的一行
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditRecord {
+    Start(RunStart),
+    End(RunEnd),
+}
+
+impl AuditRecord {
+    pub fn run_id(&self) -> &str {
+        match self {
+            AuditRecord::Start(start) => &start.run_id,
+            AuditRecord::End(end) => &end.run_id,
+        }
+    }
+}
+
+/// 只追加的审计日志文件
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 追加一条记录，必要时创建目录
+    pub fn append(&self, record: &AuditRecord) -> io::Result<()> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let line = serde_json::to_string(record).map_err(io::Error::other)?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", line)?;
+        file.flush()
+    }
+
+    /// 读取所有记录，返回记录和无法解析的行号（从 1 开始）
+    pub fn read(&self) -> io::Result<(Vec<AuditRecord>, Vec<usize>)> {
+        let content = std::fs::read_to_string(&self.path)?;
+        let mut records = Vec::new();
+        let mut invalid = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(record) => records.push(record),
+                Err(_) => invalid.push(index + 1),
+            }
+        }
+        Ok((records, invalid))
+    }
+}
+
+/// 计算输出文件的绝对路径、长度和 SHA-256，不存在或无法读取的文件跳过
+pub fn output_entries(files: &[PathBuf]) -> Vec<FileEntry> {
+    files
+        .iter()
+        .filter_map(|file| {
+            Some(FileEntry {
+                path: file.canonicalize().unwrap_or_else(|_| file.clone()).display().to_string(),
+                bytes: std::fs::metadata(file).ok()?.len(),
+                sha256: manifest::sha256_file(file).ok()?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,131 @@
+//! Base58 字母表
+//!
+//! 前缀校验、难度计算和字符统计都需要知道哪些字符能出现在地址里、各自代表几，
+//! 统一从这里取，避免各处抄一份字母表。默认字母表与 bs58 的默认（比特币）字母表一致；
+//! 需要验证其他字母表下的行为时，可以用 [`Alphabet::new`] 构造一个自定义字母表传入。
+
+/// 地址使用的 Base58 字母表（与 bs58 默认一致），不含 `0`、`O`、`I`、`l`
+pub const ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// 一个 58 字符的字母表，字符的位置就是它代表的数字
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alphabet {
+    chars: &'static str,
+}
+
+impl Alphabet {
+    /// 地址使用的默认字母表
+    pub const BITCOIN: Alphabet = Alphabet { chars: ALPHABET };
+
+    /// 构造自定义字母表，必须是 58 个互不相同的 ASCII 字符
+    pub fn new(chars: &'static str) -> Result<Self, String> {
+        if !chars.is_ascii() || chars.len() != 58 {
+            return Err(format!("字母表必须是 58 个 ASCII 字符，实际为 {} 个字符", chars.chars().count()));
+        }
+        let bytes = chars.as_bytes();
+        if let Some(index) = (1..bytes.len()).find(|index| bytes[..*index].contains(&bytes[*index])) {
+            return Err(format!("字母表中的字符 '{}' 重复", bytes[index] as char));
+        }
+        Ok(Self { chars })
+    }
+
+    pub fn chars(&self) -> &'static str {
+        self.chars
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.chars.contains(c)
+    }
+
+    /// 字符代表的数字（0 到 57）
+    pub fn digit(&self, c: char) -> Option<usize> {
+        self.chars.find(c)
+    }
+
+    /// 检查字符串只含本字母表的字符，否则返回第一个不合法的字符
+    pub fn validate(&self, value: &str) -> Result<(), char> {
+        match value.chars().find(|c| !self.contains(*c)) {
+            Some(c) => Err(c),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Alphabet::BITCOIN
+    }
+}
+
+/// 检查字符串只含默认 Base58 字母表的字符，否则返回第一个不合法的字符
+pub fn is_valid_base58(value: &str) -> Result<(), char> {
+    Alphabet::BITCOIN.validate(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// XRP Ledger 使用的字母表，与比特币字母表字符相同、顺序不同
+    const RIPPLE: &str = "rpshnaf39wBUDNEGHJKLM4PQRST7VWXYZ2bcdeCg65jkm8oFqi1tuvAxyz";
+
+    #[test]
+    fn matches_the_bs58_default_alphabet() {
+        for digit in 0..58u8 {
+            // 58 + d 编码为两位：`2` 和 d
+            let encoded = bs58::encode([58 + digit]).into_string();
+            assert_eq!(encoded.len(), 2);
+            let last = encoded.chars().last().unwrap();
+            assert_eq!(Alphabet::BITCOIN.digit(last), Some(digit as usize), "{}", encoded);
+        }
+        assert_eq!(bs58::encode([0u8]).into_string(), "1");
+    }
+
+    #[test]
+    fn each_excluded_character_is_rejected() {
+        for excluded in ['0', 'O', 'I', 'l'] {
+            assert_eq!(is_valid_base58(&format!("So{}", excluded)), Err(excluded));
+            assert!(!Alphabet::BITCOIN.contains(excluded));
+        }
+        assert_eq!(ALPHABET.len(), 58);
+    }
+
+    #[test]
+    fn symbols_and_non_ascii_are_rejected() {
+        for invalid in ["a b", "ab-", "+", "é", "Ｓ", "😀"] {
+            assert!(is_valid_base58(invalid).is_err(), "{}", invalid);
+        }
+        assert_eq!(is_valid_base58("So1ana"), Ok(()));
+        assert_eq!(is_valid_base58(""), Ok(()));
+    }
+
+    #[test]
+    fn reports_the_first_invalid_character() {
+        assert_eq!(is_valid_base58("AB0lO"), Err('0'));
+    }
+
+    #[test]
+    fn custom_alphabets_can_be_injected() {
+        let ripple = Alphabet::new(RIPPLE).unwrap();
+        assert_eq!(ripple.digit('r'), Some(0));
+        assert_eq!(Alphabet::BITCOIN.digit('r'), Some(49));
+        // 自定义字母表中的合法性同样由它自己决定
+        let custom = Alphabet::new("0123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxy").unwrap();
+        assert_eq!(custom.validate("0x"), Ok(()));
+        assert_eq!(custom.validate("z"), Err('z'));
+        assert_eq!(Alphabet::BITCOIN.validate("0x"), Err('0'));
+    }
+
+    #[test]
+    fn invalid_custom_alphabets_are_refused() {
+        assert!(Alphabet::new("abc").is_err());
+        let duplicated = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyy";
+        assert!(Alphabet::new(duplicated).is_err_and(|err| err.contains("重复")));
+    }
+
+    #[test]
+    fn default_is_bitcoin() {
+        assert_eq!(Alphabet::default(), Alphabet::BITCOIN);
+        assert_eq!(Alphabet::BITCOIN.chars(), ALPHABET);
+    }
+}
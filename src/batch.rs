@@ -0,0 +1,177 @@
+use std::time::Duration;
+
+/// 每个工作线程的批大小控制器
+///
+/// 工作线程每完成一批才更新一次共享计数器。批太小时共享状态的开销占主导，
+/// 批太大时停止条件和进度刷新都会变慢。自适应模式先用一个较小的批测出单个地址的耗时，
+/// 直接按目标值换算出批大小（校准），之后再根据每批实际耗时逐步放大或缩小，
+/// 使每批耗时接近目标值，无论机器快慢。
+#[derive(Debug, Clone)]
+pub struct BatchController {
+    size: u64,
+    target: Duration,
+    adaptive: bool,
+    calibrated: bool,
+}
+
+impl BatchController {
+    /// 自适应模式下每批的默认目标耗时
+    pub const DEFAULT_TARGET: Duration = Duration::from_millis(50);
+    /// 自适应模式的初始批大小
+    pub const INITIAL_SIZE: u64 = 64;
+    /// 批大小下限
+    pub const MIN_SIZE: u64 = 1;
+    /// 批大小上限
+    pub const MAX_SIZE: u64 = 1 << 20;
+
+    /// 自适应模式，每批耗时向 `target` 收敛
+    pub fn adaptive(target: Duration) -> Self {
+        Self {
+            size: Self::INITIAL_SIZE,
+            target,
+            adaptive: true,
+            calibrated: false,
+        }
+    }
+
+    /// 固定批大小，不做调整
+    pub fn fixed(size: u64) -> Self {
+        Self {
+            size: size.clamp(Self::MIN_SIZE, Self::MAX_SIZE),
+            target: Duration::ZERO,
+            adaptive: false,
+            calibrated: true,
+        }
+    }
+
+    /// 当前批大小
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// 是否为自适应模式
+    pub fn is_adaptive(&self) -> bool {
+        self.adaptive
+    }
+
+    /// 记录刚完成的一批耗时，返回调整后的批大小
+    ///
+    /// 第一批用于校准，批大小直接按单个地址的耗时换算；之后每次最多放大 2 倍或缩小一半，
+    /// 避免单次测量抖动造成大幅震荡。
+    pub fn record(&mut self, elapsed: Duration) -> u64 {
+        if !self.adaptive {
+            return self.size;
+        }
+
+        // 校准批太快、测不出耗时时按常规步长放大，下一批再校准
+        if !self.calibrated && !elapsed.is_zero() {
+            self.calibrated = true;
+            let per_key = elapsed.as_secs_f64() / self.size as f64;
+            let next = (self.target.as_secs_f64() / per_key).round() as u64;
+            self.size = next.clamp(Self::MIN_SIZE, Self::MAX_SIZE);
+            return self.size;
+        }
+
+        let factor = if elapsed.is_zero() {
+            2.0
+        } else {
+            (self.target.as_secs_f64() / elapsed.as_secs_f64()).clamp(0.5, 2.0)
+        };
+        let next = (self.size as f64 * factor).round() as u64;
+        self.size = next.clamp(Self::MIN_SIZE, Self::MAX_SIZE);
+        self.size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET: Duration = BatchController::DEFAULT_TARGET;
+
+    /// 假的时钟：每个地址耗时 `per_key`，一批的耗时与批大小成正比
+    fn elapsed(size: u64, per_key: Duration) -> Duration {
+        per_key * size as u32
+    }
+
+    /// 按假时钟连续运行 `batches` 批，返回最后的批大小
+    fn run(controller: &mut BatchController, per_key: Duration, batches: usize) -> u64 {
+        for _ in 0..batches {
+            controller.record(elapsed(controller.size(), per_key));
+        }
+        controller.size()
+    }
+
+    #[test]
+    fn calibration_jumps_straight_to_the_target() {
+        let mut controller = BatchController::adaptive(TARGET);
+        assert_eq!(controller.size(), BatchController::INITIAL_SIZE);
+        // 每个地址 10µs：目标 50ms 对应 5000 个
+        let size = controller.record(elapsed(controller.size(), Duration::from_micros(10)));
+        assert_eq!(size, 5000);
+    }
+
+    #[test]
+    fn grows_at_most_twofold_per_batch_after_calibration() {
+        let mut controller = BatchController::adaptive(TARGET);
+        controller.record(elapsed(controller.size(), Duration::from_micros(10)));
+        // 机器突然快了 100 倍
+        let fast = Duration::from_nanos(100);
+        let mut previous = controller.size();
+        for _ in 0..5 {
+            let size = controller.record(elapsed(previous, fast));
+            assert_eq!(size, previous * 2);
+            previous = size;
+        }
+        assert_eq!(run(&mut controller, fast, 20), 500_000);
+    }
+
+    #[test]
+    fn shrinks_at_most_by_half_per_batch() {
+        let mut controller = BatchController::adaptive(TARGET);
+        controller.record(elapsed(controller.size(), Duration::from_micros(10)));
+        // 机器慢了 10 倍（例如其他进程占满了 CPU）
+        let slow = Duration::from_micros(100);
+        assert_eq!(controller.record(elapsed(controller.size(), slow)), 2500);
+        assert_eq!(controller.record(elapsed(controller.size(), slow)), 1250);
+        assert_eq!(run(&mut controller, slow, 10), 500);
+    }
+
+    #[test]
+    fn converges_and_stays_at_the_target() {
+        for per_key in [Duration::from_nanos(300), Duration::from_micros(7), Duration::from_micros(130)] {
+            let mut controller = BatchController::adaptive(TARGET);
+            let size = run(&mut controller, per_key, 30);
+            let batch = elapsed(size, per_key).as_secs_f64();
+            assert!((batch / TARGET.as_secs_f64() - 1.0).abs() < 0.01, "{:?}: {} 个，{} 秒", per_key, size, batch);
+            assert_eq!(run(&mut controller, per_key, 10), size);
+        }
+    }
+
+    #[test]
+    fn zero_elapsed_doubles_and_postpones_calibration() {
+        let mut controller = BatchController::adaptive(TARGET);
+        assert_eq!(controller.record(Duration::ZERO), BatchController::INITIAL_SIZE * 2);
+        assert_eq!(controller.record(Duration::ZERO), BatchController::INITIAL_SIZE * 4);
+        // 第一次测出耗时时才校准
+        assert_eq!(controller.record(elapsed(controller.size(), Duration::from_micros(1))), 50_000);
+    }
+
+    #[test]
+    fn sizes_are_clamped() {
+        let mut controller = BatchController::adaptive(TARGET);
+        assert_eq!(run(&mut controller, Duration::from_nanos(1), 40), BatchController::MAX_SIZE);
+        let mut controller = BatchController::adaptive(TARGET);
+        assert_eq!(run(&mut controller, Duration::from_secs(1), 40), BatchController::MIN_SIZE);
+    }
+
+    #[test]
+    fn fixed_size_never_changes() {
+        let mut controller = BatchController::fixed(1000);
+        assert!(!controller.is_adaptive());
+        assert_eq!(controller.record(Duration::from_secs(10)), 1000);
+        assert_eq!(controller.record(Duration::ZERO), 1000);
+        assert_eq!(BatchController::fixed(0).size(), BatchController::MIN_SIZE);
+        assert_eq!(BatchController::fixed(u64::MAX).size(), BatchController::MAX_SIZE);
+    }
+}
@@ -0,0 +1,86 @@
+//! 地址黑名单
+//!
+//! 黑名单中的地址无论如何都不会写入任何输出：以前泄露过的地址、属于已知实体的地址，
+//! 或者之前批次里已经用过的地址。地址按 32 字节公钥保存，检查时不需要 Base58 编码。
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::ops::ControlFlow;
+use std::path::Path;
+
+/// 从文件加载的地址黑名单
+#[derive(Debug, Default)]
+pub struct Blacklist {
+    pubkeys: HashSet<[u8; 32]>,
+}
+
+impl Blacklist {
+    /// 加载黑名单文件：每行一个 Base58 地址，忽略空行和 `#` 开头的注释
+    ///
+    /// 有无效行时返回的错误带有行号。
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        Ok(Self {
+            pubkeys: load_pubkeys(path.as_ref(), "黑名单")?,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.pubkeys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pubkeys.is_empty()
+    }
+
+    /// 公钥是否在黑名单中
+    pub fn contains(&self, pubkey: &[u8; 32]) -> bool {
+        self.pubkeys.contains(pubkey)
+    }
+}
+
+/// 读取每行一个 Base58 地址的文件，忽略空行和 `#` 开头的注释；`kind` 用于错误信息，如“黑名单”
+pub(crate) fn load_pubkeys(path: &Path, kind: &str) -> Result<HashSet<[u8; 32]>, String> {
+    // 按平均每行约 45 字节预估容量，避免百万行文件加载时反复扩容
+    let estimated = std::fs::metadata(path).map(|metadata| metadata.len() / 45).unwrap_or(0);
+    let mut pubkeys = HashSet::with_capacity(estimated as usize);
+    for_each_pubkey(path, kind, |pubkey| {
+        pubkeys.insert(pubkey);
+        ControlFlow::Continue(())
+    })?;
+    Ok(pubkeys)
+}
+
+/// 逐行读取与 [`load_pubkeys`] 格式相同的文件，不把整个名单放进内存；`f` 返回 `Break` 时提前结束
+pub(crate) fn for_each_pubkey(
+    path: &Path,
+    kind: &str,
+    mut f: impl FnMut([u8; 32]) -> ControlFlow<()>,
+) -> Result<(), String> {
+    let file = File::open(path).map_err(|err| format!("无法打开{}文件 {}: {}", kind, path.display(), err))?;
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|err| format!("读取{}文件 {} 失败: {}", kind, path.display(), err))?;
+        let address = line.trim();
+        if address.is_empty() || address.starts_with('#') {
+            continue;
+        }
+        let mut pubkey = [0u8; 32];
+        match bs58::decode(address).into(&mut pubkey) {
+            Ok(32) => {
+                if f(pubkey).is_break() {
+                    break;
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "{}文件 {} 第 {} 行不是有效的地址: {}",
+                    kind,
+                    path.display(),
+                    index + 1,
+                    address
+                ));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,207 @@
+//! 公钥原始字节的前缀和后缀（`--byte-prefix`、`--byte-suffix`）
+//!
+//! 有些集成关心的是 32 字节公钥本身，而不是它的 Base58 文本，例如希望公钥的第一个字节是
+//! 某个值，使账户在链上按字节排序的数据结构中排在最前面。这样的模式直接与公钥字节比较，
+//! 不需要 Base58 编码，只有这类模式时不匹配的密钥可以跳过编码。
+//!
+//! 字节模式作为 [`crate::matcher::Matcher::Bytes`] 与 `-p` 的前缀一起放进 [`crate::Search`]，
+//! 是“任一命中即匹配”的关系，用 [`BytePattern::label`] 作为它的名字参与计数和输出：
+//! 前缀写作 `0xdead…`，后缀写作 `…0xdead`。名字只用于显示，匹配时按类型分派。
+
+use crate::filter::ByteFilter;
+
+/// 一个原始字节模式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BytePattern {
+    /// 公钥以这些字节开头
+    Prefix(Vec<u8>),
+    /// 公钥以这些字节结尾
+    Suffix(Vec<u8>),
+}
+
+/// 前缀名中省略号的写法
+const ELLIPSIS: char = '…';
+
+impl BytePattern {
+    /// 显示用的名字：`0xdead…` 或 `…0xdead`
+    pub fn label(&self) -> String {
+        match self {
+            BytePattern::Prefix(bytes) => format!("0x{}{}", to_hex(bytes), ELLIPSIS),
+            BytePattern::Suffix(bytes) => format!("{}0x{}", ELLIPSIS, to_hex(bytes)),
+        }
+    }
+
+    /// 从 [`BytePattern::label`] 的写法解析，不是字节模式时返回 `None`；用于读取文本形式的目标列表，如模糊测试的输入
+    pub fn from_label(label: &str) -> Option<Self> {
+        if let Some(hex) = label.strip_suffix(ELLIPSIS).and_then(|rest| rest.strip_prefix("0x")) {
+            return parse_hex(hex).ok().map(BytePattern::Prefix);
+        }
+        let hex = label.strip_prefix(ELLIPSIS)?.strip_prefix("0x")?;
+        parse_hex(hex).ok().map(BytePattern::Suffix)
+    }
+
+    /// 检查公钥字节是否符合
+    pub fn matches(&self, pubkey: &[u8]) -> bool {
+        match self {
+            BytePattern::Prefix(bytes) => pubkey.starts_with(bytes),
+            BytePattern::Suffix(bytes) => pubkey.ends_with(bytes),
+        }
+    }
+
+    /// 随机公钥符合这个模式且满足所有字节过滤条件的概率，每个字节 1/256
+    ///
+    /// 首字节上限对字节前缀是精确的（要么首字节已经固定且不超过上限，要么不可能），
+    /// 对字节后缀按独立事件相乘；取模条件同样按独立事件相乘。
+    pub fn probability(&self, filters: &[ByteFilter]) -> f64 {
+        let bytes = match self {
+            BytePattern::Prefix(bytes) | BytePattern::Suffix(bytes) => bytes,
+        };
+        let base = 256f64.powi(-(bytes.len() as i32));
+        filters.iter().fold(base, |probability, filter| match (self, filter) {
+            (BytePattern::Prefix(bytes), ByteFilter::FirstByteMax(max)) => {
+                if bytes[0] <= *max { probability } else { 0.0 }
+            }
+            _ => probability * filter.probability(),
+        })
+    }
+}
+
+/// 解析 `--byte-prefix` 的值，如 `0xdead` 或 `dead`
+pub fn parse_byte_prefix(value: &str) -> Result<BytePattern, String> {
+    parse_hex(value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")).unwrap_or(value))
+        .map(BytePattern::Prefix)
+        .map_err(|err| format!("无效的字节前缀 {}: {}", value, err))
+}
+
+/// 解析 `--byte-suffix` 的值，如 `0xbeef` 或 `beef`
+pub fn parse_byte_suffix(value: &str) -> Result<BytePattern, String> {
+    parse_hex(value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")).unwrap_or(value))
+        .map(BytePattern::Suffix)
+        .map_err(|err| format!("无效的字节后缀 {}: {}", value, err))
+}
+
+/// 解析 1 到 32 个字节的十六进制，字母大小写均可
+fn parse_hex(hex: &str) -> Result<Vec<u8>, String> {
+    if hex.is_empty() || !hex.len().is_multiple_of(2) {
+        return Err("应为偶数个十六进制数字".to_string());
+    }
+    if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err("含有非十六进制字符".to_string());
+    }
+    let bytes: Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .map(|start| u8::from_str_radix(&hex[start..start + 2], 16).unwrap())
+        .collect();
+    if bytes.len() > 32 {
+        return Err(format!("公钥只有 32 个字节，这里给了 {} 个", bytes.len()));
+    }
+    Ok(bytes)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signer::Signer;
+    use solana_sdk::signer::keypair::keypair_from_seed;
+
+    use super::*;
+    use crate::Search;
+    use crate::matcher::Matcher;
+
+    /// RFC 8032 第 7.1 节 TEST 1 的私钥种子和公钥
+    const RFC8032_SEED: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+    const RFC8032_PUBKEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+    fn pubkey_from_seed(seed: &[u8]) -> [u8; 32] {
+        keypair_from_seed(seed).unwrap().pubkey().to_bytes()
+    }
+
+    #[test]
+    fn known_vector_prefix_and_suffix() {
+        let pubkey = pubkey_from_seed(&parse_hex(RFC8032_SEED).unwrap());
+        assert_eq!(to_hex(&pubkey), RFC8032_PUBKEY);
+
+        assert!(parse_byte_prefix("0xd75a").unwrap().matches(&pubkey));
+        assert!(parse_byte_prefix("D75A98").unwrap().matches(&pubkey));
+        assert!(parse_byte_suffix("0x511a").unwrap().matches(&pubkey));
+        assert!(parse_byte_prefix(RFC8032_PUBKEY).unwrap().matches(&pubkey));
+        assert!(parse_byte_suffix(RFC8032_PUBKEY).unwrap().matches(&pubkey));
+
+        assert!(!parse_byte_prefix("0xd75b").unwrap().matches(&pubkey));
+        assert!(!parse_byte_prefix("0x511a").unwrap().matches(&pubkey));
+        assert!(!parse_byte_suffix("0xd75a").unwrap().matches(&pubkey));
+    }
+
+    #[test]
+    fn fixed_keypairs_match_their_own_bytes_only() {
+        let pubkeys: Vec<[u8; 32]> = (0u8..32).map(|i| pubkey_from_seed(&[i; 32])).collect();
+        for (i, pubkey) in pubkeys.iter().enumerate() {
+            let prefix = BytePattern::Prefix(pubkey[..3].to_vec());
+            let suffix = BytePattern::Suffix(pubkey[29..].to_vec());
+            assert!(prefix.matches(pubkey));
+            assert!(suffix.matches(pubkey));
+            for (j, other) in pubkeys.iter().enumerate() {
+                if j != i {
+                    assert_eq!(prefix.matches(other), other.starts_with(&pubkey[..3]));
+                    assert_eq!(suffix.matches(other), other.ends_with(&pubkey[29..]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn search_finds_byte_patterns_without_text_matches() {
+        let pubkey = pubkey_from_seed(&parse_hex(RFC8032_SEED).unwrap());
+        let search = Search::new([
+            Matcher::Bytes(parse_byte_prefix("0xd7").unwrap()),
+            Matcher::Bytes(parse_byte_suffix("0x1a").unwrap()),
+            Matcher::Bytes(parse_byte_prefix("0x00").unwrap()),
+        ]);
+        assert!(search.bytes_only());
+        let found: Vec<String> = search.find_bytes(&pubkey).iter().map(|index| search.prefixes()[*index].clone()).collect();
+        assert_eq!(found, ["0xd7…", "…0x1a"]);
+
+        // 字节模式不参与文本匹配，地址文本恰好是名字也不算
+        assert!(search.find_all("0xd7…").is_empty());
+        let address = bs58::encode(pubkey).into_string();
+        assert!(search.find_all(&address).is_empty());
+        assert!(search.matched_spans(&search.find_bytes(&pubkey), &address).is_empty());
+    }
+
+    #[test]
+    fn label_round_trip() {
+        for pattern in [
+            BytePattern::Prefix(vec![0xde, 0xad]),
+            BytePattern::Suffix(vec![0x00]),
+            BytePattern::Prefix(vec![0xff; 32]),
+        ] {
+            assert_eq!(BytePattern::from_label(&pattern.label()), Some(pattern));
+        }
+        assert_eq!(BytePattern::from_label("dead"), None);
+        assert_eq!(BytePattern::from_label("0xdea…"), None);
+    }
+
+    #[test]
+    fn rejects_malformed_hex() {
+        assert!(parse_byte_prefix("").is_err());
+        assert!(parse_byte_prefix("0x").is_err());
+        assert!(parse_byte_prefix("abc").is_err());
+        assert!(parse_byte_prefix("zz").is_err());
+        assert!(parse_byte_suffix(&"00".repeat(33)).is_err());
+        assert!(parse_byte_suffix(&"00".repeat(32)).is_ok());
+    }
+
+    #[test]
+    fn probability_with_first_byte_max() {
+        let prefix = BytePattern::Prefix(vec![0x10]);
+        assert_eq!(prefix.probability(&[]), 1.0 / 256.0);
+        assert_eq!(prefix.probability(&[ByteFilter::FirstByteMax(0x10)]), 1.0 / 256.0);
+        assert_eq!(prefix.probability(&[ByteFilter::FirstByteMax(0x0f)]), 0.0);
+        let suffix = BytePattern::Suffix(vec![0x10, 0x20]);
+        let filter = ByteFilter::FirstByteMax(0x7f);
+        assert_eq!(suffix.probability(std::slice::from_ref(&filter)), filter.probability() / 65536.0);
+    }
+}
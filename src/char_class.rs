@@ -0,0 +1,117 @@
+//! 前缀中的字符类
+//!
+//! 常见的需求是“S 后接任意一个数字”，为此写正则表达式太重。前缀中可以用方括号写字符类：
+//!
+//! - `[abc]`：其中任意一个字符
+//! - `[a-z]`：范围内任意一个 Base58 字符，范围按 ASCII 顺序，不在 Base58 字母表中的字符
+//!   自动跳过（如 `[0-9]` 即 `1`–`9`，`[A-Z]` 不含 `I`、`O`）
+//! - `[^...]`：不在其中的任意一个 Base58 字符
+//!
+//! 启动时把含字符类的前缀展开成全部具体的前缀（`S[1-9]` 展开为 `S1`…`S9`），一起放进前缀树，
+//! 匹配的开销与普通前缀相同；统计和输出仍按原来的写法归到同一个前缀下。
+
+use crate::base58::Alphabet;
+
+/// 一个前缀最多展开成多少个具体前缀
+pub const MAX_EXPANSIONS: usize = 100_000;
+
+/// 前缀是否含有字符类
+pub fn has_class(pattern: &str) -> bool {
+    pattern.contains(['[', ']'])
+}
+
+/// 解析前缀，返回每一位允许的字符（按字母表顺序）
+pub fn parse(pattern: &str) -> Result<Vec<Vec<u8>>, String> {
+    let alphabet = Alphabet::BITCOIN;
+    let invalid = |c: char| {
+        format!(
+            "前缀 \"{}\" 中的字符 '{}' 不在 Base58 字母表中（Base58 不含 0、O、I、l 和符号）",
+            pattern, c
+        )
+    };
+    let mut positions = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '[' => {
+                let rest = chars.as_str();
+                let Some(end) = rest.find(']') else {
+                    return Err(format!("前缀 \"{}\" 中的 '[' 没有对应的 ']'", pattern));
+                };
+                let body = &rest[..end];
+                chars = rest[end + 1..].chars();
+
+                let (negated, items) = match body.strip_prefix('^') {
+                    Some(items) => (true, items),
+                    None => (false, body),
+                };
+                let mut members = [false; 58];
+                let mut items = items.chars();
+                while let Some(start) = items.next() {
+                    let mut range = items.clone();
+                    if range.next() == Some('-')
+                        && let Some(end) = range.next()
+                    {
+                        if end < start {
+                            return Err(format!("前缀 \"{}\" 中的范围 {}-{} 起点大于终点", pattern, start, end));
+                        }
+                        for c in start..=end {
+                            if let Some(digit) = alphabet.digit(c) {
+                                members[digit] = true;
+                            }
+                        }
+                        items = range;
+                    } else {
+                        members[alphabet.digit(start).ok_or_else(|| invalid(start))?] = true;
+                    }
+                }
+                let allowed: Vec<u8> = alphabet
+                    .chars()
+                    .bytes()
+                    .zip(members)
+                    .filter(|(_, member)| *member != negated)
+                    .map(|(byte, _)| byte)
+                    .collect();
+                if allowed.is_empty() {
+                    return Err(format!("前缀 \"{}\" 中的字符类 [{}] 不含任何 Base58 字符", pattern, body));
+                }
+                positions.push(allowed);
+            }
+            ']' => return Err(format!("前缀 \"{}\" 中有多余的 ']'", pattern)),
+            _ if alphabet.contains(c) => positions.push(vec![c as u8]),
+            _ => return Err(invalid(c)),
+        }
+    }
+    Ok(positions)
+}
+
+/// 前缀覆盖的地址位数：字符类算一位；无法解析时按字符数计
+pub fn len(pattern: &str) -> usize {
+    parse(pattern).map_or_else(|_| pattern.chars().count(), |positions| positions.len())
+}
+
+/// 展开成全部具体的前缀，按字典序排列；不含字符类的前缀原样返回
+pub fn expand(pattern: &str) -> Result<Vec<String>, String> {
+    if !has_class(pattern) {
+        return Ok(vec![pattern.to_string()]);
+    }
+    let positions = parse(pattern)?;
+    let count = positions
+        .iter()
+        .try_fold(1usize, |count, allowed| count.checked_mul(allowed.len()))
+        .filter(|count| *count <= MAX_EXPANSIONS);
+    if count.is_none() {
+        return Err(format!(
+            "前缀 \"{}\" 展开后超过 {} 个具体前缀，请缩小字符类的范围",
+            pattern, MAX_EXPANSIONS
+        ));
+    }
+    let mut expanded = vec![String::new()];
+    for allowed in &positions {
+        expanded = expanded
+            .iter()
+            .flat_map(|head| allowed.iter().map(move |byte| format!("{}{}", head, *byte as char)))
+            .collect();
+    }
+    Ok(expanded)
+}
@@ -0,0 +1,111 @@
+//! 字符计数条件
+//!
+//! 有人想要某个吉利字符特别多的地址，例如任意位置至少八个 `8`，这无法用前缀表达。
+//! `--count-char 8x8` 在 Base58 编码之后统计字符出现的次数，与前缀和字节过滤条件同时满足才算匹配。
+//!
+//! 概率：把地址按编码方式分成几类（见 [`address_classes`]），每一类中各位独立，
+//! 出现次数服从泊松二项分布，用动态规划精确计算“至少 N 次”的尾部概率，再按各类的概率加权。与前缀同时使用时，前缀占据的位置已经确定，
+//! 先扣掉前缀里已有的次数，只对其余位置计算。多个计数条件按独立事件把概率相乘。
+
+use std::fmt;
+
+use crate::base58::Alphabet;
+use crate::char_stats::POSITIONS;
+use crate::difficulty::address_classes;
+
+/// 计数列列名的前缀，后接字符
+pub const COLUMN_PREFIX: &str = "count_";
+
+/// 地址中 `ch` 至少出现 `min` 次
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CharCount {
+    pub ch: char,
+    pub min: usize,
+}
+
+impl CharCount {
+    /// 地址中该字符出现的次数
+    pub fn count(&self, address: &str) -> usize {
+        address.chars().filter(|c| *c == self.ch).count()
+    }
+
+    /// 检查地址是否满足条件
+    pub fn accepts(&self, address: &str) -> bool {
+        self.count(address) >= self.min
+    }
+
+    /// 随机地址以 `prefix` 开头时满足条件的概率，`prefix` 为空时即单独满足的概率
+    pub fn probability(&self, prefix: &str) -> f64 {
+        let Some(digit) = Alphabet::BITCOIN.digit(self.ch) else {
+            return 0.0;
+        };
+        let need = self.min.saturating_sub(prefix.chars().filter(|c| *c == self.ch).count());
+        address_classes()
+            .iter()
+            .map(|(weight, positions)| {
+                let rest = positions.iter().skip(prefix.len()).map(|probabilities| probabilities[digit]);
+                weight * at_least(need, rest)
+            })
+            .sum()
+    }
+}
+
+impl fmt::Display for CharCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.ch, self.min)
+    }
+}
+
+/// 每一位独立地以给定概率命中时，至少命中 `need` 次的概率
+fn at_least(need: usize, probabilities: impl Iterator<Item = f64>) -> f64 {
+    if need == 0 {
+        return 1.0;
+    }
+    // distribution[k] 为恰好 k 次的概率，最后一项累计“至少 need 次”
+    let mut distribution = vec![0.0; need + 1];
+    distribution[0] = 1.0;
+    for p in probabilities {
+        distribution[need] += distribution[need - 1] * p;
+        for k in (1..need).rev() {
+            distribution[k] = distribution[k] * (1.0 - p) + distribution[k - 1] * p;
+        }
+        distribution[0] *= 1.0 - p;
+    }
+    distribution[need]
+}
+
+/// 字符在地址中出现的位置，从 0 开始
+pub fn positions(ch: char, address: &str) -> Vec<usize> {
+    address
+        .chars()
+        .enumerate()
+        .filter(|(_, c)| *c == ch)
+        .map(|(position, _)| position)
+        .collect()
+}
+
+/// 计数列的值：`次数:位置;位置;…`，位置从 0 开始
+pub fn describe(ch: char, address: &str) -> String {
+    let positions = positions(ch, address);
+    let list: Vec<String> = positions.iter().map(usize::to_string).collect();
+    format!("{}:{}", positions.len(), list.join(";"))
+}
+
+/// 解析 `字符x次数`，如 `8x8` 表示至少八个 `8`
+pub fn parse_char_count(value: &str) -> Result<CharCount, String> {
+    let invalid = || format!("无效的字符计数条件: {}（应为 字符x次数，如 8x8）", value);
+    let mut chars = value.chars();
+    let ch = chars.next().ok_or_else(invalid)?;
+    let min = chars.as_str().strip_prefix('x').ok_or_else(invalid)?;
+    let min: usize = min.trim().parse().map_err(|_| invalid())?;
+    if !Alphabet::BITCOIN.contains(ch) {
+        return Err(format!("字符 '{}' 不在 Base58 字母表中，永远不会出现在地址里", ch));
+    }
+    if min == 0 {
+        return Err("次数必须大于 0".to_string());
+    }
+    if min > POSITIONS {
+        return Err(format!("地址最多 {} 个字符，不可能出现 {} 次", POSITIONS, min));
+    }
+    Ok(CharCount { ch, min })
+}
@@ -0,0 +1,309 @@
+//! 生成地址的字符频率统计
+//!
+//! 如果随机数或密钥生成出了细微的问题，地址的字符分布会最先暴露出来。
+//! 这里按 1/1000 抽样，统计每个位置上各字符出现的次数，运行结束时与理论分布
+//! 做卡方检验。
+//!
+//! 地址是把 32 字节公钥当作大端整数做 Base58 编码。统计时把地址右对齐到 44 位、
+//! 左边用 `1`（数字 0）补齐，这样每个位置就是该整数在 58 进制下的一位。
+//! 最高位（第 0 位）只能取 0 到 17，且 17 的概率更低；其余位置在统计精度内是均匀的。
+
+use crate::base58::ALPHABET;
+
+/// 地址右对齐后的位数
+pub const POSITIONS: usize = 44;
+
+/// 每多少个地址抽样一个
+pub const SAMPLE_INTERVAL: u64 = 1000;
+
+/// 判定异常的 z 分数阈值
+pub const ANOMALY_Z: f64 = 5.0;
+
+/// 每个工作线程各自累积，结束时合并
+#[derive(Debug, Clone)]
+pub struct CharStats {
+    counts: Vec<[u64; 58]>,
+    samples: u64,
+    /// 含有非 Base58 字符或超长的地址数量
+    invalid: u64,
+}
+
+impl Default for CharStats {
+    fn default() -> Self {
+        Self {
+            counts: vec![[0; 58]; POSITIONS],
+            samples: 0,
+            invalid: 0,
+        }
+    }
+}
+
+impl CharStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 已抽样的地址数量
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// 记录一个地址
+    pub fn record(&mut self, address: &str) {
+        let bytes = address.as_bytes();
+        if bytes.len() > POSITIONS {
+            self.invalid += 1;
+            return;
+        }
+        let mut digits = [0usize; POSITIONS];
+        let offset = POSITIONS - bytes.len();
+        for (i, byte) in bytes.iter().enumerate() {
+            match ALPHABET.bytes().position(|c| c == *byte) {
+                Some(digit) => digits[offset + i] = digit,
+                None => {
+                    self.invalid += 1;
+                    return;
+                }
+            }
+        }
+        for (position, digit) in digits.iter().enumerate() {
+            self.counts[position][*digit] += 1;
+        }
+        self.samples += 1;
+    }
+
+    /// 合并另一个线程的统计
+    pub fn merge(mut self, other: Self) -> Self {
+        for (mine, theirs) in self.counts.iter_mut().zip(&other.counts) {
+            for (a, b) in mine.iter_mut().zip(theirs) {
+                *a += b;
+            }
+        }
+        self.samples += other.samples;
+        self.invalid += other.invalid;
+        self
+    }
+
+    /// 与理论分布做卡方检验
+    pub fn analyze(&self) -> CharStatsReport {
+        let mut positions = Vec::with_capacity(POSITIONS);
+        let mut total_chi_square = 0.0;
+        let mut total_df = 0.0;
+
+        for (position, counts) in self.counts.iter().enumerate() {
+            let expected = expected_distribution(position);
+            let mut chi_square = 0.0;
+            let mut categories = 0;
+            let mut impossible = 0;
+            for (observed, probability) in counts.iter().zip(expected) {
+                if probability > 0.0 {
+                    let expected_count = probability * self.samples as f64;
+                    chi_square += (*observed as f64 - expected_count).powi(2) / expected_count;
+                    categories += 1;
+                } else {
+                    impossible += observed;
+                }
+            }
+            let df = (categories - 1) as f64;
+            total_chi_square += chi_square;
+            total_df += df;
+            positions.push(PositionDeviation {
+                position,
+                chi_square,
+                z: z_score(chi_square, df),
+                impossible,
+            });
+        }
+
+        CharStatsReport {
+            samples: self.samples,
+            invalid: self.invalid,
+            z: z_score(total_chi_square, total_df),
+            positions,
+        }
+    }
+}
+
+/// 卡方值换算成近似的 z 分数（df 较大时卡方分布近似正态）
+fn z_score(chi_square: f64, df: f64) -> f64 {
+    if df > 0.0 {
+        (chi_square - df) / (2.0 * df).sqrt()
+    } else {
+        0.0
+    }
+}
+
+/// 右对齐第 `position` 位（0 为最高位）上各数字的理论概率
+///
+/// 公钥是 [0, 2^256) 上的均匀整数。第 k 位（从最低位数起）按 58^k 分块，
+/// 共 `q = floor(2^256 / 58^k)` 个完整块，第 b 块的该位数字为 `b mod 58`，
+/// 最后还有一个不完整的块。块数很多时分布与均匀分布的差别远小于抽样误差。
+pub fn expected_distribution(position: usize) -> [f64; 58] {
+    let k = (POSITIONS - 1 - position) as i32;
+    let block = 58f64.powi(k) / 2f64.powi(256);
+    let blocks = 1.0 / block;
+    if blocks > 1e12 {
+        return [1.0 / 58.0; 58];
+    }
+
+    let full = blocks.floor();
+    let remainder = blocks - full;
+    let full = full as u64;
+    let mut distribution = [0.0; 58];
+    for (digit, probability) in distribution.iter_mut().enumerate() {
+        let digit = digit as u64;
+        let count = full / 58 + u64::from(digit < full % 58);
+        *probability = count as f64 * block;
+        if digit == full % 58 {
+            *probability += remainder * block;
+        }
+    }
+    distribution
+}
+
+/// 单个位置的偏离程度
+#[derive(Debug, Clone)]
+pub struct PositionDeviation {
+    pub position: usize,
+    pub chi_square: f64,
+    pub z: f64,
+    /// 出现在理论上不可能位置上的字符数量
+    pub impossible: u64,
+}
+
+impl PositionDeviation {
+    pub fn is_anomalous(&self) -> bool {
+        self.z > ANOMALY_Z || self.impossible > 0
+    }
+}
+
+/// 字符频率检验结果
+#[derive(Debug, Clone)]
+pub struct CharStatsReport {
+    pub samples: u64,
+    pub invalid: u64,
+    /// 所有位置合并后的 z 分数
+    pub z: f64,
+    pub positions: Vec<PositionDeviation>,
+}
+
+impl CharStatsReport {
+    /// 偏离显著的位置
+    pub fn anomalies(&self) -> impl Iterator<Item = &PositionDeviation> {
+        self.positions.iter().filter(|position| position.is_anomalous())
+    }
+
+    pub fn is_anomalous(&self) -> bool {
+        self.invalid > 0 || self.z > ANOMALY_Z || self.anomalies().next().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    const SAMPLES: usize = 20_000;
+
+    /// 用固定种子生成 `count` 个均匀的 32 字节整数，交给 `tamper` 修改后编码成地址
+    fn stats(count: usize, seed: u64, mut tamper: impl FnMut(&mut [u8; 32], &mut String)) -> CharStats {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        let mut stats = CharStats::new();
+        for _ in 0..count {
+            let mut bytes: [u8; 32] = rng.r#gen();
+            let mut address = bs58::encode(bytes).into_string();
+            tamper(&mut bytes, &mut address);
+            stats.record(&address);
+        }
+        stats
+    }
+
+    /// 只改字节、重新编码
+    fn from_bytes(count: usize, seed: u64, mut tamper: impl FnMut(&mut [u8; 32])) -> CharStats {
+        stats(count, seed, |bytes, address| {
+            tamper(bytes);
+            *address = bs58::encode(*bytes).into_string();
+        })
+    }
+
+    #[test]
+    fn expected_distributions_are_probabilities() {
+        for position in 0..POSITIONS {
+            let distribution = expected_distribution(position);
+            assert!((distribution.iter().sum::<f64>() - 1.0).abs() < 1e-9, "第 {} 位", position);
+        }
+        // 最高位只能取 0 到 17
+        let first = expected_distribution(0);
+        assert!(first[..18].iter().all(|p| *p > 0.0));
+        assert!(first[18..].iter().all(|p| *p == 0.0));
+        assert!(first[17] < first[16]);
+    }
+
+    #[test]
+    fn unbiased_addresses_pass() {
+        for seed in 0..3 {
+            let report = stats(SAMPLES, seed, |_, _| {}).analyze();
+            assert_eq!(report.samples, SAMPLES as u64);
+            assert!(!report.is_anomalous(), "种子 {}: z = {}", seed, report.z);
+        }
+    }
+
+    #[test]
+    fn cleared_top_bit_is_flagged_at_the_first_position() {
+        let report = from_bytes(SAMPLES, 1, |bytes| bytes[0] &= 0x7f).analyze();
+        assert!(report.is_anomalous());
+        assert!(report.anomalies().any(|position| position.position == 0));
+    }
+
+    #[test]
+    fn constant_last_byte_is_flagged_near_the_end() {
+        let report = from_bytes(SAMPLES, 2, |bytes| bytes[31] = 0x42).analyze();
+        assert!(report.is_anomalous());
+        assert!(report.anomalies().any(|position| position.position == POSITIONS - 1));
+    }
+
+    #[test]
+    fn slightly_overrepresented_character_is_flagged() {
+        // 5% 的地址第 20 位被替换为 `z`：该位上 `z` 的频率从 1/58 变成约 6.6%
+        let mut rng = ChaCha20Rng::seed_from_u64(99);
+        let report = stats(SAMPLES, 3, |_, address| {
+            if rng.gen_bool(0.05) {
+                let offset = address.len() - (POSITIONS - 20);
+                address.replace_range(offset..offset + 1, "z");
+            }
+        })
+        .analyze();
+        let anomalous: Vec<usize> = report.anomalies().map(|position| position.position).collect();
+        assert_eq!(anomalous, vec![20]);
+    }
+
+    #[test]
+    fn invalid_addresses_are_counted_not_recorded() {
+        let mut stats = CharStats::new();
+        stats.record("0OIl");
+        stats.record(&"2".repeat(POSITIONS + 1));
+        stats.record("So1ana");
+        assert_eq!(stats.samples(), 1);
+        let report = stats.analyze();
+        assert_eq!(report.invalid, 2);
+        assert!(report.is_anomalous());
+    }
+
+    #[test]
+    fn merging_equals_recording_in_one_place() {
+        let all = stats(3000, 7, |_, _| {});
+        let first = stats(1000, 7, |_, _| {});
+        let mut rng = ChaCha20Rng::seed_from_u64(7);
+        let mut second = CharStats::new();
+        for index in 0..3000 {
+            let bytes: [u8; 32] = rng.r#gen();
+            if index >= 1000 {
+                second.record(&bs58::encode(bytes).into_string());
+            }
+        }
+        let merged = first.merge(second);
+        assert_eq!(merged.samples(), all.samples());
+        assert_eq!(merged.counts, all.counts);
+    }
+}
@@ -0,0 +1,86 @@
+//! 与目标地址的接近程度（`--compare-addresses`）
+//!
+//! 教学和基准用途：给定一组完整的目标地址，运行一段固定的时间，报告生成的地址与每个目标
+//! 开头最多重合了几个字符。没有人指望真的撞上，但重合长度每增加一个字符，所需的尝试次数
+//! 就多约 58 倍，这张表直观地展示了难度曲线。
+//!
+//! 实现上直接把目标地址当作前缀交给 [`Search`]，复用前缀树上的最长公共前缀跟踪
+//! （[`Search::track_partial`]）和近似命中榜。
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::difficulty;
+use crate::search::Search;
+
+/// 读取每行一个目标地址的文件，忽略空行和 `#` 开头的注释，重复的地址只保留一个
+pub fn load_targets(path: impl AsRef<Path>) -> Result<Vec<String>, String> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|err| format!("无法打开目标地址文件 {}: {}", path.display(), err))?;
+    let mut seen = HashSet::new();
+    let mut targets = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|err| format!("读取目标地址文件 {} 失败: {}", path.display(), err))?;
+        let address = line.trim();
+        if address.is_empty() || address.starts_with('#') {
+            continue;
+        }
+        let mut pubkey = [0u8; 32];
+        if !matches!(bs58::decode(address).into(&mut pubkey), Ok(32)) {
+            return Err(format!(
+                "目标地址文件 {} 第 {} 行不是有效的地址: {}",
+                path.display(),
+                index + 1,
+                address
+            ));
+        }
+        if seen.insert(address.to_string()) {
+            targets.push(address.to_string());
+        }
+    }
+    if targets.is_empty() {
+        return Err(format!("目标地址文件 {} 中没有任何地址", path.display()));
+    }
+    Ok(targets)
+}
+
+/// 一个目标地址的最好成绩
+#[derive(Debug, Clone, PartialEq)]
+pub struct Closest {
+    pub target: String,
+    /// 开头重合的最多字符数
+    pub matched: usize,
+    /// 重合最多的地址，一个字符也没有重合时为 `None`
+    pub address: Option<String>,
+    /// 随机地址的开头与目标重合 `matched` 个字符的期望尝试次数
+    pub expected_attempts: f64,
+    /// 再多重合一个字符的期望尝试次数
+    pub next_attempts: f64,
+}
+
+/// 按 [`Search::prefixes`] 的顺序（即目标地址的字典序）汇总每个目标的最好成绩
+pub fn closest(search: &Search) -> Vec<Closest> {
+    let stats = search.stats();
+    let near_misses = search.near_misses();
+    search
+        .prefixes()
+        .iter()
+        .zip(&stats.best_partial)
+        .zip(near_misses)
+        .map(|((target, &matched), board)| Closest {
+            target: target.clone(),
+            matched,
+            address: board.first().map(|best| best.address.clone()),
+            expected_attempts: expected_attempts(&target[..matched]),
+            next_attempts: expected_attempts(&target[..(matched + 1).min(target.len())]),
+        })
+        .collect()
+}
+
+/// 随机地址以 `prefix` 开头的期望尝试次数，空前缀为 1
+fn expected_attempts(prefix: &str) -> f64 {
+    let probability = difficulty::match_probability(prefix, &[]);
+    if probability > 0.0 { 1.0 / probability } else { f64::INFINITY }
+}
@@ -0,0 +1,522 @@
+//! 前缀的匹配概率与“到现在应该找到了吗”的估计
+//!
+//! 地址是 32 字节公钥（大端整数 x）的 Base58 编码，开头的每个零字节编码为一个 `1`。
+//! 前缀 `1…1R`（k 个 `1` 后接非 `1` 的部分 R）匹配，当且仅当公钥恰好有 k 个前导零字节，
+//! 且去掉零字节后的整数在 58 进制下以 R 的各位开头。后者是若干个整数区间的并：
+//! 对每个可能的位数 L，x 落在 `[R·58^(L-m), (R+1)·58^(L-m))` 中（m 为 R 的长度），
+//! 把这些区间与 x 的取值范围求交再除以 2^256 即得精确概率，不依赖“每个字符 1/58”的近似。
+//!
+//! 尝试次数的归属：每个生成的地址都会与所有前缀比较，因此对每个前缀都算一次尝试，
+//! 各前缀的尝试次数都等于总生成数。一个地址满足几个前缀（如同时搜索 `a` 和 `ab`）时每个前缀
+//! 各计一次，所以每个前缀的有效概率就是它单独的匹配概率，与其他前缀无关。
+//!
+//! 含字符类的前缀（见 [`crate::char_class`]）展开成若干个具体前缀，它们长度相同、互不重叠，
+//! 概率按各个具体前缀相加。
+//!
+//! `--leading-ones` 要求至少 k 个前导零字节，前缀中的 `1` 不够 k 个时补足；`--max-length` 把恰好 k 个
+//! 零字节时 x 的上界收窄到 58^(N-k)，都直接作用在上面的区间上，不按独立事件相乘。
+//!
+//! 字符计数条件和形状的概率见 [`crate::char_count`] 和 [`crate::shape`]，按独立事件乘到每个前缀的概率上。
+//!
+//! 原始字节模式（见 [`crate::byte_pattern`]）按 256 进制计算，每个字节 1/256。回文和镜像匹配器
+//! （见 [`crate::symmetry`]）和模糊前缀（见 [`crate::fuzzy`]）单独计算。
+//!
+//! 命令行程序和嵌入方估算难度都应使用 [`expected_attempts`] 和 [`estimate_duration`]，
+//! 两者的结果因此完全一致。
+
+use std::time::Duration;
+
+use crate::base58::{Alphabet, ALPHABET};
+use crate::char_class;
+use crate::char_stats::POSITIONS;
+use crate::filter::{length_range, ByteFilter};
+use crate::search::Search;
+
+/// 判断可能配置有误的阈值：到现在应该找到的概率超过它却仍未命中
+pub const SUSPICIOUS_PROBABILITY: f64 = 0.95;
+
+/// [`address_classes`] 精确拆分到的前导零字节数，更多零字节的地址合为一类
+pub const EXACT_ZERO_BYTES: usize = 2;
+
+/// 随机公钥的地址以 `prefix` 开头且满足所有字节过滤条件的概率
+///
+/// 前缀含有非 Base58 字符时返回 0。
+///
+/// 取模条件与前缀几乎无关，按独立事件把概率相乘。前导零字节和长度上限按地址的编码精确计算。
+pub fn match_probability(prefix: &str, filters: &[ByteFilter]) -> f64 {
+    let bounds = filters.iter().fold(Bounds::default(), |mut bounds, filter| {
+        match filter {
+            // 首字节条件对应的整数上界
+            ByteFilter::FirstByteMax(max) => bounds.upper = bounds.upper.min((*max as f64 + 1.0) * 2f64.powi(248)),
+            ByteFilter::ValueMod { .. } => {}
+            ByteFilter::LeadingZeros(count) => bounds.min_zeros = bounds.min_zeros.max(*count as usize),
+            ByteFilter::MaxLength(length) => {
+                bounds.max_length = Some(bounds.max_length.map_or(*length, |max| max.min(*length)))
+            }
+        }
+        bounds
+    });
+    let modulus: f64 = filters
+        .iter()
+        .filter(|filter| matches!(filter, ByteFilter::ValueMod { .. }))
+        .map(ByteFilter::probability)
+        .product();
+    range_probability(prefix, &bounds) * modulus
+}
+
+/// 与 [`match_probability`] 相同，但前缀可以含字符类，按展开后的各个具体前缀相加
+///
+/// 无法展开的前缀返回 0。
+pub fn pattern_probability(pattern: &str, filters: &[ByteFilter]) -> f64 {
+    char_class::expand(pattern).map_or(0.0, |literals| {
+        literals.iter().map(|literal| match_probability(literal, filters)).sum()
+    })
+}
+
+/// 找到一个以 `prefix` 开头的地址的期望尝试次数，即匹配概率的倒数
+///
+/// 按精确概率计算，首字符不均匀和开头的 `1` 对应零字节都已考虑在内。`case_insensitive` 时
+/// 前缀的每个字母大小写均可，只有一种写法在 Base58 中的字母（如 `o`、`L`）只算这一种。
+/// 前缀可以含字符类；不可能匹配时返回 `f64::INFINITY`。
+pub fn expected_attempts(prefix: &str, case_insensitive: bool) -> f64 {
+    let probability = if case_insensitive {
+        case_insensitive_probability(prefix)
+    } else {
+        pattern_probability(prefix, &[])
+    };
+    1.0 / probability
+}
+
+/// 按每秒 `keys_per_sec` 个的速率完成 `attempts` 次尝试所需的时间
+///
+/// 速率不是正数或结果超出 [`Duration`] 的范围时返回 [`Duration::MAX`]。
+pub fn estimate_duration(attempts: f64, keys_per_sec: f64) -> Duration {
+    if keys_per_sec <= 0.0 {
+        return Duration::MAX;
+    }
+    Duration::try_from_secs_f64(attempts / keys_per_sec).unwrap_or(Duration::MAX)
+}
+
+/// 不区分大小写时的匹配概率：把每个字母的两种写法展开成具体前缀相加
+///
+/// 展开数超过 [`char_class::MAX_EXPANSIONS`] 后，后面各位按每个字符 1/58 近似，
+/// 此时开头几位已经精确展开，近似的误差可以忽略。
+fn case_insensitive_probability(prefix: &str) -> f64 {
+    let alphabet = Alphabet::BITCOIN;
+    // 写成了 Base58 中没有的大小写（如 `SOL` 中的 `O`）时先换成另一种写法
+    let normalized: String = prefix
+        .chars()
+        .map(|c| {
+            if alphabet.contains(c) {
+                c
+            } else if c.is_ascii_uppercase() {
+                c.to_ascii_lowercase()
+            } else {
+                c.to_ascii_uppercase()
+            }
+        })
+        .collect();
+    let Ok(positions) = char_class::parse(&normalized) else {
+        return 0.0;
+    };
+    let mut literals = vec![String::new()];
+    let mut tail = 1.0;
+    for chars in positions {
+        let mut options: Vec<char> = chars
+            .iter()
+            .map(|byte| *byte as char)
+            .flat_map(|c| [c.to_ascii_lowercase(), c.to_ascii_uppercase()])
+            .filter(|c| alphabet.digit(*c).is_some())
+            .collect();
+        options.sort_unstable();
+        options.dedup();
+        if tail == 1.0 && literals.len() * options.len() <= char_class::MAX_EXPANSIONS {
+            literals = literals
+                .iter()
+                .flat_map(|head| options.iter().map(move |c| format!("{}{}", head, c)))
+                .collect();
+        } else {
+            tail *= options.len() as f64 / 58.0;
+        }
+    }
+    literals.iter().map(|literal| match_probability(literal, &[])).sum::<f64>() * tail
+}
+
+/// 字节过滤条件对公钥整数的限制
+struct Bounds {
+    /// 整数上界，来自首字节条件
+    upper: f64,
+    /// 至少这么多个前导零字节
+    min_zeros: usize,
+    /// 地址长度上限
+    max_length: Option<u8>,
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Self {
+            upper: 2f64.powi(256),
+            min_zeros: 0,
+            max_length: None,
+        }
+    }
+}
+
+/// 地址以 `prefix` 开头且公钥整数满足 `bounds` 的概率
+fn range_probability(prefix: &str, bounds: &Bounds) -> f64 {
+    let space = 2f64.powi(256);
+
+    let mut zeros = prefix.bytes().take_while(|byte| *byte == b'1').count();
+    if zeros > 32 {
+        return 0.0;
+    }
+    let rest = &prefix[zeros..];
+    if zeros < bounds.min_zeros {
+        // 前缀的 `1` 不够时，后面紧接着的非 `1` 字符与要求的零字节矛盾
+        if !rest.is_empty() {
+            return 0.0;
+        }
+        zeros = bounds.min_zeros;
+    }
+    if rest.is_empty() {
+        // 只要求至少 k 个前导零字节：按恰好有 k、k+1、… 个零字节的范围相加
+        let count: f64 = (zeros..=32)
+            .map(|zeros| {
+                let (low, high) = length_range(zeros, bounds.max_length);
+                (high.min(bounds.upper) - low).max(0.0)
+            })
+            .sum();
+        return count / space;
+    }
+
+    let Some(digits) = rest
+        .chars()
+        .map(|c| Alphabet::BITCOIN.digit(c).map(|digit| digit as f64))
+        .collect::<Option<Vec<f64>>>()
+    else {
+        return 0.0;
+    };
+    let value = digits.iter().fold(0.0, |value, digit| value * 58.0 + digit);
+
+    // 恰好 k 个前导零字节时 x 的取值范围；零字节开头的公钥总能通过首字节过滤
+    let (low, high) = length_range(zeros, bounds.max_length);
+    let high = high.min(bounds.upper);
+    if high <= low {
+        return 0.0;
+    }
+
+    // 每个区间的宽度就是 scale；前缀超过 9 位左右时 value + 1 在 f64 中等于 value，
+    // 不能用两端相减，只按区间与取值范围的边界裁剪
+    let mut count = 0.0;
+    let mut scale = 1.0;
+    while value * scale < high {
+        let start = value * scale;
+        count += (high - start).clamp(0.0, scale) - (low - start).clamp(0.0, scale);
+        scale *= 58.0;
+    }
+    count / space
+}
+
+/// 按地址的编码方式把随机地址分成几类：（这类地址的概率，从首字符起每一位上各数字的概率）
+///
+/// 同一类中各位相互独立，字符计数条件和形状的概率在每一类中按独立的各位计算再加权。
+/// 恰好 k 个前导零字节时 x ∈ [2^(248-8k), 2^(256-8k))，地址为 k 个 `1` 后接 x 的 L 位 58 进制表示；
+/// 按位数 L 把这个范围切开，每一段中首位数字 d 占 `[d·58^(L-1), (d+1)·58^(L-1))` 与该段的交集，
+/// 首位的分布按交集的宽度精确计算。段的两端不在块边界上时，首位为该块数字的地址第二位也不均匀
+/// （如 x ≥ 17·58^43 时首字符为 `J`，第二位只能取很小的几个值），这部分单独成一类，前两位都精确计算，
+/// 其余各位均匀（与均匀分布的差别小于 58^-40）。k = 0 时是以下几类：
+///
+/// - 58^43 ≤ x < 17·58^43：44 个字符，首字符为 `2` 到 `H`
+/// - x ≥ 17·58^43：44 个字符，首字符为 `J`
+/// - 2^248 ≤ x < 58^43：43 个字符，首字符从 `4` 开始，首字符为 `4` 时第二位只能取最后几个值
+///
+/// k = 1、2 同样按段精确拆分，例如一个零字节时 `1` 之后的首字符集中在 `2` 到 `5`，
+/// 而不是在 58 个字符中均匀分布。至少 [`EXACT_ZERO_BYTES`] + 1 个零字节（概率 2^-24）
+/// 合为一类，`1` 之后近似均匀。
+pub fn address_classes() -> Vec<(f64, Vec<[f64; 58]>)> {
+    let space = 2f64.powi(256);
+    // 整数落在 [low, high) 中时，以 block 为一位的那一位数字的分布
+    let digit = |block: f64, low: f64, high: f64| {
+        let mut distribution = [0.0; 58];
+        for (digit, probability) in distribution.iter_mut().enumerate() {
+            let start = (digit as f64 * block).max(low);
+            let end = ((digit as f64 + 1.0) * block).min(high);
+            if end > start {
+                *probability = (end - start) / (high - low);
+            }
+        }
+        distribution
+    };
+    let one = digit(1.0, 0.0, 1.0);
+    let class = |zeros: usize, leading: &[[f64; 58]], length: usize| {
+        let mut positions = vec![[1.0 / 58.0; 58]; length];
+        positions[..zeros].fill(one);
+        positions[zeros..zeros + leading.len()].copy_from_slice(leading);
+        positions
+    };
+
+    let mut classes = Vec::new();
+    for zeros in 0..=EXACT_ZERO_BYTES {
+        let low = 2f64.powi(248 - 8 * zeros as i32);
+        let high = 2f64.powi(256 - 8 * zeros as i32);
+        for digits in 2..=POSITIONS - zeros {
+            let block = 58f64.powi(digits as i32 - 1);
+            let (start, end) = (low.max(block), high.min(block * 58.0));
+            if end <= start {
+                continue;
+            }
+            let length = zeros + digits;
+            // 首位为 d 的块完整落在段中的部分只需首位分布；两端不完整的块再算第二位
+            let (first, last) = ((start / block).ceil() * block, (end / block).floor() * block);
+            let mut partial = |from: f64, to: f64| {
+                if to > from {
+                    let base = (from / block).floor() * block;
+                    let leading = [digit(block, from, to), digit(block / 58.0, from - base, to - base)];
+                    classes.push(((to - from) / space, class(zeros, &leading, length)));
+                }
+            };
+            if first >= last {
+                partial(start, end);
+                continue;
+            }
+            partial(start, first);
+            partial(last, end);
+            classes.push(((last - first) / space, class(zeros, &[digit(block, first, last)], length)));
+        }
+    }
+    let rest = 2f64.powi(-8 * (EXACT_ZERO_BYTES as i32 + 1));
+    classes.push((rest, class(EXACT_ZERO_BYTES + 1, &[], POSITIONS)));
+    classes
+}
+
+/// 随机地址首字符为字母表中各字符的精确概率，按字母表的顺序
+///
+/// 与 [`expected_attempts`] 一样按区间计算。首字符远不均匀：44 个字符的地址首字符只能是 `2` 到 `J`，
+/// 各约 1/17；`K` 到 `z` 只出现在 43 个字符的地址中，各约 1/1000；`1` 对应一个零字节，为 1/256。
+/// 按“每个字符 1/58”估计会把以 `z` 开头的前缀的难度低估十几倍。
+pub fn first_char_distribution() -> [f64; 58] {
+    let mut distribution = [0.0; 58];
+    for (probability, c) in distribution.iter_mut().zip(ALPHABET.chars()) {
+        *probability = match_probability(&c.to_string(), &[]);
+    }
+    distribution
+}
+
+/// 每个前缀的有效匹配概率，按 [`Search::prefixes`] 的顺序
+///
+/// 各前缀分别计数，互不影响，见模块说明。含字符类的前缀按
+/// [`Search::literals`] 中属于它的具体前缀相加。
+/// 有字符计数条件、与条件或形状时乘上以该具体前缀开头时满足它们的概率；原始字节模式、回文、镜像匹配器
+/// 和模糊前缀乘上它们单独满足的概率。
+pub fn effective_probabilities(search: &Search) -> Vec<f64> {
+    let patterns = search.patterns();
+    let mut probabilities = vec![0.0; patterns.len()];
+    // 以 `prefix` 开头时满足所有计数条件和与条件的概率，各条件按独立事件相乘
+    let conditions = |prefix: &str| -> f64 {
+        let counts: f64 = search.char_counts().iter().map(|condition| condition.probability(prefix)).product();
+        let and: f64 = search.and_conditions().iter().map(|condition| condition.probability(prefix)).product();
+        counts * and
+    };
+    for (index, pattern) in patterns.byte_patterns() {
+        let shape = search.shape().map_or(1.0, |shape| shape.probability(""));
+        probabilities[*index] = pattern.probability(search.filters()) * conditions("") * shape;
+    }
+    for (index, symmetry) in patterns.symmetries() {
+        let shape = search.shape().map_or(1.0, |shape| shape.probability(""));
+        let filters: f64 = search.filters().iter().map(ByteFilter::probability).product();
+        probabilities[*index] = symmetry.probability() * filters * conditions("") * shape;
+    }
+    for (index, fuzzy) in patterns.fuzzies() {
+        let shape = search.shape().map_or(1.0, |shape| shape.probability(""));
+        let filters: f64 = search.filters().iter().map(ByteFilter::probability).product();
+        probabilities[*index] = fuzzy.probability() * filters * conditions("") * shape;
+    }
+    for (literal, index) in patterns.literals() {
+        let shape = search.shape().map_or(1.0, |shape| shape.probability(literal));
+        probabilities[index] += match_probability(literal, search.filters()) * conditions(literal) * shape;
+    }
+    probabilities
+}
+
+/// 尝试 `attempts` 次后至少命中一次的概率：`1 - exp(-attempts / E)`，E 为期望尝试次数
+pub fn found_by_now(attempts: u64, probability: f64) -> f64 {
+    -(-(attempts as f64) * probability).exp_m1()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand_chacha::rand_core::{RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+    use solana_sdk::signature::Signer;
+
+    use super::*;
+    use crate::keygen::KeyGenerator;
+
+    /// 相对误差不超过 `tolerance`
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            ((actual - expected) / expected).abs() <= tolerance,
+            "实际 {:e}，期望 {:e}",
+            actual,
+            expected
+        );
+    }
+
+    /// 58^n / 2^256，用对数计算，与被测代码的区间算法无关
+    fn block(n: i32) -> f64 {
+        (n as f64 * 58f64.ln() - 256.0 * 2f64.ln()).exp()
+    }
+
+    #[test]
+    fn leading_ones_are_zero_bytes() {
+        assert_eq!(match_probability("", &[]), 1.0);
+        assert_close(match_probability("1", &[]), 1.0 / 256.0, 1e-12);
+        assert_close(match_probability("11", &[]), 1.0 / 65536.0, 1e-12);
+        assert_close(expected_attempts("111", false), 256f64.powi(3), 1e-12);
+    }
+
+    #[test]
+    fn first_characters_known_values() {
+        // 44 个字符的地址：x ∈ [58^43, 2^256)，首字符 `2` 到 `H` 各占一整块 58^43；
+        // `2`、`3` 在 43 个字符的地址中对应 x < 4·58^42 < 2^248，不会出现
+        for c in ['2', '3'] {
+            assert_close(match_probability(&c.to_string(), &[]), block(43), 1e-9);
+        }
+        // `5` 到 `H` 还各占 43 个字符的地址中的一整块 58^42
+        for c in ['5', '9', 'A', 'H'] {
+            assert_close(match_probability(&c.to_string(), &[]), block(43) + block(42), 1e-9);
+        }
+        // 44 个字符时首字符 `J` 只占 [17·58^43, 2^256) 这一段
+        assert_close(match_probability("J", &[]), 1.0 - 17.0 * block(43) + block(42), 1e-9);
+        // `K` 到 `z` 只出现在 43 个字符的地址中，各占一整块 58^42
+        for c in ['K', 'a', 'z'] {
+            assert_close(match_probability(&c.to_string(), &[]), block(42), 1e-9);
+        }
+        assert_close(expected_attempts("z", false), 1.0 / block(42), 1e-9);
+        // 约 1/17 和约 1/1000
+        assert!((16.0..18.0).contains(&(1.0 / block(43))));
+        assert!((900.0..1100.0).contains(&(1.0 / block(42))));
+    }
+
+    #[test]
+    fn distribution_sums_to_one() {
+        let total: f64 = first_char_distribution().iter().sum();
+        assert_close(total, 1.0, 1e-12);
+        let two: f64 = ALPHABET
+            .chars()
+            .flat_map(|a| ALPHABET.chars().map(move |b| format!("{}{}", a, b)))
+            .map(|prefix| match_probability(&prefix, &[]))
+            .sum();
+        assert_close(two, 1.0, 1e-9);
+    }
+
+    #[test]
+    fn long_prefixes_keep_their_precision() {
+        // 首字符 `2` 的块内各位均匀，每多一位概率恰好除以 58
+        let base = match_probability("2", &[]);
+        let mut prefix = "2".to_string();
+        for length in 1..=12 {
+            prefix.push('z');
+            assert_close(match_probability(&prefix, &[]), base / 58f64.powi(length), 1e-9);
+        }
+    }
+
+    #[test]
+    fn case_insensitive_known_values() {
+        // `z` 和 `Z` 都只出现在 43 个字符的地址中
+        assert_close(expected_attempts("z", true), 1.0 / (2.0 * block(42)), 1e-9);
+        // `o` 没有大写写法，`L` 没有小写写法
+        assert_eq!(expected_attempts("o", true), expected_attempts("o", false));
+        assert_eq!(expected_attempts("L", true), expected_attempts("L", false));
+        assert_eq!(expected_attempts("2", true), expected_attempts("2", false));
+    }
+
+    #[test]
+    fn impossible_prefixes() {
+        assert_eq!(expected_attempts("0", false), f64::INFINITY);
+        assert_eq!(expected_attempts("S[", false), f64::INFINITY);
+        assert_eq!(match_probability("l", &[]), 0.0);
+        assert_eq!(match_probability("2", &[ByteFilter::FirstByteMax(0)]), 0.0);
+    }
+
+    #[test]
+    fn character_classes_sum_their_literals() {
+        let sum: f64 = ["2", "3", "4"].iter().map(|prefix| match_probability(prefix, &[])).sum();
+        assert_close(pattern_probability("[2-4]", &[]), sum, 1e-12);
+    }
+
+    /// `count` 次命中与 `n` 次试验、概率 `p` 的二项分布期望相差不超过 5 个标准差
+    fn assert_binomial(label: &str, count: usize, n: usize, p: f64) {
+        let (expected, sigma) = (n as f64 * p, (n as f64 * p * (1.0 - p)).sqrt());
+        assert!(
+            (count as f64 - expected).abs() <= 5.0 * sigma,
+            "{}：实际 {} 次，期望 {:.1} ± {:.1}",
+            label,
+            count,
+            expected,
+            sigma
+        );
+    }
+
+    /// 按字母表顺序统计各首字符出现的次数
+    fn first_char_counts(addresses: impl Iterator<Item = String>) -> [usize; 58] {
+        let mut counts = [0; 58];
+        for address in addresses {
+            let first = address.chars().next().unwrap();
+            counts[ALPHABET.chars().position(|c| c == first).unwrap()] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn first_char_model_matches_real_keys() {
+        // 固定种子生成真实的密钥对，结果确定；按模型各首字符的命中数都应在 5 个标准差之内
+        const KEYS: usize = 20_000;
+        let mut keygen = KeyGenerator::seeded(203, 0, 1);
+        let counts = first_char_counts((0..KEYS).map(|_| keygen.generate().pubkey().to_string()));
+        let model = first_char_distribution();
+        for ((c, &count), &p) in ALPHABET.chars().zip(&counts).zip(&model) {
+            assert_binomial(&c.to_string(), count, KEYS, p);
+        }
+        // `K` 到 `z` 合在一起约 1/25，按“每个字符 1/58”则约 2/3，样本足以区分
+        let tail = |values: &[f64]| -> f64 { values[ALPHABET.find('K').unwrap()..].iter().sum() };
+        let tail_count: usize = counts[ALPHABET.find('K').unwrap()..].iter().sum();
+        assert_binomial("K 到 z", tail_count, KEYS, tail(&model));
+        let naive = tail(&[1.0 / 58.0; 58]);
+        let sigma = (KEYS as f64 * naive * (1.0 - naive)).sqrt();
+        assert!((tail_count as f64 - KEYS as f64 * naive).abs() > 50.0 * sigma);
+    }
+
+    #[test]
+    fn leading_ones_and_partial_blocks_match_uniform_bytes() {
+        // 公钥在 32 字节上近似均匀，直接编码固定种子的随机字节可以取更大的样本，
+        // 检验 `1` 和两端不完整的块：`J` 之后的第二位、一个零字节后 `1` 之后的字符
+        const SAMPLES: usize = 200_000;
+        let mut rng = ChaCha20Rng::seed_from_u64(203);
+        let addresses: Vec<String> = (0..SAMPLES)
+            .map(|_| {
+                let mut bytes = [0u8; 32];
+                rng.fill_bytes(&mut bytes);
+                bs58::encode(bytes).into_string()
+            })
+            .collect();
+        let counts = first_char_counts(addresses.iter().cloned());
+        for ((c, &count), &p) in ALPHABET.chars().zip(&counts).zip(&first_char_distribution()) {
+            assert_binomial(&c.to_string(), count, SAMPLES, p);
+        }
+        for prefix in ["J", "JA", "JB", "4z", "41", "12", "15", "16", "1z"] {
+            let count = addresses.iter().filter(|address| address.starts_with(prefix)).count();
+            assert_binomial(prefix, count, SAMPLES, match_probability(prefix, &[]));
+        }
+    }
+
+    #[test]
+    fn duration_known_values() {
+        assert_eq!(estimate_duration(1000.0, 100.0), Duration::from_secs(10));
+        assert_eq!(estimate_duration(1.5, 1.0), Duration::from_millis(1500));
+        assert_eq!(estimate_duration(1.0, 0.0), Duration::MAX);
+        assert_eq!(estimate_duration(1.0, -1.0), Duration::MAX);
+        assert_eq!(estimate_duration(f64::INFINITY, 1.0), Duration::MAX);
+        assert_eq!(estimate_duration(1e30, 1.0), Duration::MAX);
+    }
+}
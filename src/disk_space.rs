@@ -0,0 +1,163 @@
+//! 磁盘空间保护（`--min-free-disk`）
+//!
+//! 长时间运行、`-n` 又很大时，输出文件可能写满磁盘，写入失败会让整次运行丢失。
+//! 写入线程每隔 [`CHECK_INTERVAL`] 检查一次输出文件所在文件系统的剩余空间，低于阈值时
+//! [`DiskGuard`] 进入空间不足状态：写入线程丢弃非匹配的采样记录，进度线程暂停搜索，
+//! 空间释放后自动恢复。匹配记录很小又最有价值，任何时候都照常写入。
+//!
+//! 剩余空间按非特权用户可用的块计算；目前只支持 Unix，其他平台上不做检查。
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// `--min-free-disk` 的默认值
+pub const DEFAULT_MIN_FREE: &str = "500MB";
+
+/// 写入线程检查剩余空间的间隔
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 估算非匹配文件大小时每行的字节数（地址、64 字节私钥的 Base58 和分隔符）
+pub const SAMPLE_ROW_BYTES: u64 = 140;
+
+/// 解析 `500MB`、`2G`、`1048576` 这样的大小，单位按 1024 进位，不区分大小写
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let text = value.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number.parse().map_err(|_| format!("无效的大小: {}", value))?;
+    let shift = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" => 10,
+        "M" | "MB" => 20,
+        "G" | "GB" => 30,
+        "T" | "TB" => 40,
+        _ => return Err(format!("无效的大小单位: {}（可用 B、KB、MB、GB、TB）", value)),
+    };
+    number
+        .checked_mul(1 << shift)
+        .ok_or_else(|| format!("大小超出范围: {}", value))
+}
+
+/// 按 1024 进位格式化字节数，如 `512.0 MB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// 路径所在文件系统的可用字节数；路径还不存在时查它最近的已存在的上级目录
+///
+/// `statvfs` 各字段的宽度随平台不同，在 64 位 Linux 上转换是多余的。
+#[cfg(unix)]
+#[allow(clippy::useless_conversion)]
+pub fn available_space(path: &Path) -> io::Result<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let existing = existing_ancestor(path);
+    let c_path = CString::new(existing.as_os_str().as_bytes()).map_err(io::Error::other)?;
+    // SAFETY: c_path 是以 NUL 结尾的有效路径，stat 由 statvfs 完整填写
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(u64::from(stat.f_bavail).saturating_mul(u64::from(stat.f_frsize)))
+}
+
+#[cfg(not(unix))]
+pub fn available_space(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "当前平台无法检查磁盘剩余空间"))
+}
+
+fn existing_ancestor(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    absolute
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// 检查结果中空间最少的一个路径
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lowest {
+    pub path: PathBuf,
+    pub available: u64,
+}
+
+/// 输出路径的剩余空间阈值和当前状态，写入线程和进度线程共享
+#[derive(Debug)]
+pub struct DiskGuard {
+    paths: Vec<PathBuf>,
+    min_free: u64,
+    low: AtomicBool,
+    /// 空间不足期间丢弃的非匹配记录数
+    dropped_samples: AtomicU64,
+    lowest: Mutex<Option<Lowest>>,
+}
+
+impl DiskGuard {
+    /// `paths` 为所有输出文件或目录，同一文件系统上的路径重复检查也无妨
+    pub fn new(paths: Vec<PathBuf>, min_free: u64) -> Self {
+        Self {
+            paths,
+            min_free,
+            low: AtomicBool::new(false),
+            dropped_samples: AtomicU64::new(0),
+            lowest: Mutex::new(None),
+        }
+    }
+
+    pub fn min_free(&self) -> u64 {
+        self.min_free
+    }
+
+    /// 检查所有路径，更新并返回空间是否不足；无法检查的路径跳过
+    pub fn check(&self) -> bool {
+        let lowest = self
+            .paths
+            .iter()
+            .filter_map(|path| {
+                available_space(path).ok().map(|available| Lowest {
+                    path: path.clone(),
+                    available,
+                })
+            })
+            .min_by_key(|lowest| lowest.available);
+        let low = lowest.as_ref().is_some_and(|lowest| lowest.available < self.min_free);
+        *self.lowest.lock().unwrap() = lowest;
+        self.low.store(low, Ordering::Relaxed);
+        low
+    }
+
+    /// 上次检查时空间是否不足
+    pub fn is_low(&self) -> bool {
+        self.low.load(Ordering::Relaxed)
+    }
+
+    /// 上次检查时剩余空间最少的路径
+    pub fn lowest(&self) -> Option<Lowest> {
+        self.lowest.lock().unwrap().clone()
+    }
+
+    /// 记下一条因空间不足丢弃的非匹配记录
+    pub fn drop_sample(&self) {
+        self.dropped_samples.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dropped_samples(&self) -> u64 {
+        self.dropped_samples.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,110 @@
+//! 重复密钥检测
+//!
+//! 正常的随机数来源几乎不可能生成两个相同的公钥（2^256 的空间）。如果随机数彻底坏掉
+//! （例如虚拟机快照恢复后熵池状态相同），就可能反复生成同样的密钥而毫无察觉。
+//!
+//! 每个工作线程持有一个 [`DupChecker`]：
+//!
+//! - 布隆过滤器记录本线程生成过的所有公钥，内存固定；
+//! - 最近 [`RECENT_WINDOW`] 个公钥原样保存，用于精确比较。
+//!
+//! 只有在精确比较确认完全相同时才判定为重复；布隆过滤器命中但最近窗口里没有的，
+//! 可能是误判，也可能是更早的密钥重复了，无法确认，只计数并与误判率的期望值比较。
+
+use std::collections::{HashSet, VecDeque};
+
+/// 原样保存、用于精确比较的最近公钥数量
+pub const RECENT_WINDOW: usize = 65536;
+
+/// 布隆过滤器的哈希函数个数
+const HASHES: u32 = 7;
+
+/// 一次检查的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DupResult {
+    /// 没有见过
+    New,
+    /// 与最近生成的某个公钥完全相同
+    Duplicate,
+    /// 布隆过滤器命中但无法精确确认
+    Unconfirmed,
+}
+
+/// 单个工作线程的重复检测器
+pub struct DupChecker {
+    bits: Vec<u64>,
+    bit_count: u64,
+    recent: VecDeque<[u8; 32]>,
+    recent_set: HashSet<[u8; 32]>,
+    inserted: u64,
+    unconfirmed: u64,
+    /// 按每次检查时的误判率累加的期望误判次数
+    expected_false_positives: f64,
+}
+
+impl DupChecker {
+    /// 创建使用 `bytes` 字节布隆过滤器的检测器
+    pub fn new(bytes: usize) -> Self {
+        let words = (bytes / 8).max(1);
+        Self {
+            bits: vec![0; words],
+            bit_count: words as u64 * 64,
+            recent: VecDeque::with_capacity(RECENT_WINDOW),
+            recent_set: HashSet::with_capacity(RECENT_WINDOW),
+            inserted: 0,
+            unconfirmed: 0,
+            expected_false_positives: 0.0,
+        }
+    }
+
+    /// 检查公钥是否出现过，并把它加入记录
+    pub fn check(&mut self, pubkey: &[u8; 32]) -> DupResult {
+        if self.recent_set.contains(pubkey) {
+            return DupResult::Duplicate;
+        }
+
+        // 公钥本身是均匀随机的，直接取其中的字节作为哈希值
+        let h1 = u64::from_le_bytes(pubkey[..8].try_into().unwrap());
+        let h2 = u64::from_le_bytes(pubkey[8..16].try_into().unwrap()) | 1;
+        let mut present = true;
+        for i in 0..HASHES {
+            let bit = h1.wrapping_add(h2.wrapping_mul(i as u64)) % self.bit_count;
+            let (word, mask) = ((bit / 64) as usize, 1u64 << (bit % 64));
+            present &= self.bits[word] & mask != 0;
+            self.bits[word] |= mask;
+        }
+
+        self.expected_false_positives += self.false_positive_rate();
+        self.inserted += 1;
+        if self.recent.len() == RECENT_WINDOW
+            && let Some(oldest) = self.recent.pop_front()
+        {
+            self.recent_set.remove(&oldest);
+        }
+        self.recent.push_back(*pubkey);
+        self.recent_set.insert(*pubkey);
+
+        if present {
+            self.unconfirmed += 1;
+            DupResult::Unconfirmed
+        } else {
+            DupResult::New
+        }
+    }
+
+    /// 当前填充程度下布隆过滤器的误判率
+    pub fn false_positive_rate(&self) -> f64 {
+        let fill = -(HASHES as f64) * self.inserted as f64 / self.bit_count as f64;
+        (-fill.exp_m1()).powi(HASHES as i32)
+    }
+
+    /// 无法确认的布隆过滤器命中次数
+    pub fn unconfirmed(&self) -> u64 {
+        self.unconfirmed
+    }
+
+    /// 在随机数正常的前提下，期望的误判次数
+    pub fn expected_false_positives(&self) -> f64 {
+        self.expected_false_positives
+    }
+}
@@ -0,0 +1,148 @@
+/// 作用于公钥原始字节的过滤条件
+///
+/// 在 Base58 编码之前检查，不通过的密钥可以直接跳过编码，几乎没有开销。
+/// 取模条件逐字节计算 32 次 128 位取余，同样远比 Base58 编码便宜。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ByteFilter {
+    /// 公钥第一个字节不大于给定值
+    FirstByteMax(u8),
+    /// 公钥作为 256 位大端整数除以 `divisor` 的余数等于 `remainder`
+    ValueMod { divisor: u64, remainder: u64 },
+    /// 公钥至少有这么多个前导零字节，即地址至少以这么多个 `1` 开头
+    LeadingZeros(u8),
+    /// 地址的 Base58 编码不超过这么多个字符
+    MaxLength(u8),
+}
+
+/// 最短的地址（全零公钥）有 32 个字符
+pub const MIN_ADDRESS_LENGTH: u8 = 32;
+
+/// 最长的地址有 44 个字符
+pub const MAX_ADDRESS_LENGTH: u8 = 44;
+
+/// 58^k 的 32 字节大端表示，k 为 0 到 43；58^44 已超过 2^256
+const POWERS_OF_58: [[u8; 32]; 44] = {
+    let mut powers = [[0u8; 32]; 44];
+    powers[0][31] = 1;
+    let mut k = 1;
+    while k < 44 {
+        let mut carry = 0u32;
+        let mut i = 32;
+        while i > 0 {
+            i -= 1;
+            let value = powers[k - 1][i] as u32 * 58 + carry;
+            powers[k][i] = value as u8;
+            carry = value >> 8;
+        }
+        k += 1;
+    }
+    powers
+};
+
+impl ByteFilter {
+    /// 检查公钥字节是否满足条件
+    pub fn accepts(&self, pubkey: &[u8]) -> bool {
+        match self {
+            ByteFilter::FirstByteMax(max) => pubkey[0] <= *max,
+            ByteFilter::ValueMod { divisor, remainder } => value_mod(pubkey, *divisor) == *remainder,
+            ByteFilter::LeadingZeros(count) => pubkey.iter().take(*count as usize).all(|byte| *byte == 0),
+            ByteFilter::MaxLength(length) => fits_length(pubkey, *length),
+        }
+    }
+
+    /// 随机公钥满足条件的概率
+    pub fn probability(&self) -> f64 {
+        match self {
+            ByteFilter::FirstByteMax(max) => (*max as f64 + 1.0) / 256.0,
+            // 2^256 远大于除数，各余数的概率相差不到 2^-190，可以视为均匀
+            ByteFilter::ValueMod { divisor, .. } => 1.0 / *divisor as f64,
+            ByteFilter::LeadingZeros(count) => 256f64.powi(-(*count as i32)),
+            ByteFilter::MaxLength(length) => {
+                let ranges = (0..=32).map(|zeros| {
+                    let (low, high) = length_range(zeros, Some(*length));
+                    (high - low).max(0.0)
+                });
+                ranges.sum::<f64>() / 2f64.powi(256)
+            }
+        }
+    }
+}
+
+/// 地址不超过 `max` 个字符：k 个前导零字节编码为 k 个 `1`，其余部分不超过 `max - k` 位，
+/// 即公钥整数小于 58^(max-k)，按字节比较即可，不需要 Base58 编码
+fn fits_length(pubkey: &[u8], max: u8) -> bool {
+    let zeros = pubkey.iter().take_while(|byte| **byte == 0).count();
+    let Some(digits) = (max as usize).checked_sub(zeros) else {
+        return false;
+    };
+    zeros == pubkey.len() || digits >= POWERS_OF_58.len() || pubkey < POWERS_OF_58[digits].as_slice()
+}
+
+/// 公钥恰好有 `zeros` 个前导零字节、且地址不超过 `max_length` 个字符时，整数的取值范围 `[low, high)`
+///
+/// 范围为空时 `high <= low`。
+pub fn length_range(zeros: usize, max_length: Option<u8>) -> (f64, f64) {
+    if zeros >= 32 {
+        // 全零公钥编码为 32 个 `1`
+        let fits = max_length.is_none_or(|max| max >= MIN_ADDRESS_LENGTH);
+        return (0.0, if fits { 1.0 } else { 0.0 });
+    }
+    let low = 2f64.powi(8 * (31 - zeros as i32));
+    let high = 2f64.powi(8 * (32 - zeros as i32));
+    let limit = max_length.map_or(high, |max| match (max as usize).checked_sub(zeros) {
+        Some(digits) => 58f64.powi(digits as i32),
+        None => 0.0,
+    });
+    (low, high.min(limit))
+}
+
+/// 解析 `--leading-ones`，1 到 32
+pub fn parse_leading_ones(value: &str) -> Result<u8, String> {
+    match value.parse::<u8>() {
+        Ok(count) if (1..=32).contains(&count) => Ok(count),
+        _ => Err(format!("前导 1 的个数应为 1 到 32 之间的整数，而不是 {}", value)),
+    }
+}
+
+/// 解析 `--max-length`，[`MIN_ADDRESS_LENGTH`] 到 [`MAX_ADDRESS_LENGTH`]
+pub fn parse_max_length(value: &str) -> Result<u8, String> {
+    match value.parse::<u8>() {
+        Ok(length) if (MIN_ADDRESS_LENGTH..=MAX_ADDRESS_LENGTH).contains(&length) => Ok(length),
+        _ => Err(format!(
+            "地址长度上限应为 {} 到 {} 之间的整数，而不是 {}",
+            MIN_ADDRESS_LENGTH, MAX_ADDRESS_LENGTH, value
+        )),
+    }
+}
+
+/// 把字节当作大端整数，逐字节计算除以 `divisor` 的余数，不需要大整数类型
+pub fn value_mod(bytes: &[u8], divisor: u64) -> u64 {
+    let divisor = divisor as u128;
+    bytes
+        .iter()
+        .fold(0u128, |remainder, byte| (remainder * 256 + *byte as u128) % divisor) as u64
+}
+
+/// 解析 `DIVISOR=REMAINDER`，要求除数大于 0 且余数小于除数
+pub fn parse_value_mod(value: &str) -> Result<ByteFilter, String> {
+    let invalid = || format!("无效的取模条件: {}（应为 除数=余数，如 1000=777）", value);
+    let (divisor, remainder) = value.split_once('=').ok_or_else(invalid)?;
+    let divisor: u64 = divisor.trim().parse().map_err(|_| invalid())?;
+    let remainder: u64 = remainder.trim().parse().map_err(|_| invalid())?;
+    if divisor == 0 {
+        return Err("除数不能为 0".to_string());
+    }
+    if remainder >= divisor {
+        return Err(format!("余数 {} 必须小于除数 {}", remainder, divisor));
+    }
+    Ok(ByteFilter::ValueMod { divisor, remainder })
+}
+
+/// 解析十进制或 `0x` 开头的十六进制字节值
+pub fn parse_byte(value: &str) -> Result<u8, String> {
+    let parsed = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|_| format!("无效的字节值: {}（应为 0-255 或 0x00-0xff）", value))
+}
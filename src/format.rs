@@ -0,0 +1,632 @@
+//! 输出文件的格式和列
+//!
+//! 匹配文件和非匹配采样文件用途不同（一个用来导入真实密钥，一个用来做分析），
+//! 可以分别指定格式和列。
+//!
+//! 除了 CSV 和 TSV，还可以写成 ndjson：每行一个 JSON 对象，第一个键是 `schema_version`，
+//! 其余的键就是列名，值都是与 CSV 中相同的字符串。第 1 版的约定见 [`NDJSON_SCHEMA_VERSION`]。
+//!
+//! `--excel-compat` 面向用 Excel 打开文件的人：文件以 UTF-8 BOM 开头（否则 Windows 上的
+//! Excel 按本地编码读取，标题乱码），换行为 CRLF，以 `=`、`+`、`-`、`@`、制表符或回车开头、
+//! 会被表格软件当作公式的字段前加一个 `'`（CSV 注入的常见防护写法）。本来就以 `'` 开头的字段
+//! 也加一个，读取时带 BOM 的文件去掉一个前导 `'` 就能原样还原，见 [`unescape_formula`]。
+
+use std::fmt;
+use std::io::{self, BufWriter, Write};
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::ata;
+use crate::char_count;
+use crate::integrity;
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// 逗号分隔
+    #[default]
+    Csv,
+    /// 制表符分隔
+    Tsv,
+    /// 每行一个 JSON 对象，带 schema_version 字段
+    Ndjson,
+}
+
+impl OutputFormat {
+    /// 字段分隔符，ndjson 没有分隔符
+    pub fn delimiter(&self) -> Option<u8> {
+        match self {
+            OutputFormat::Csv => Some(b','),
+            OutputFormat::Tsv => Some(b'\t'),
+            OutputFormat::Ndjson => None,
+        }
+    }
+}
+
+/// ndjson 每行的 `schema_version`
+///
+/// 第 1 版：每行是一个 JSON 对象，`schema_version` 之后按输出列的顺序给出各列，键为列名
+/// （`address`、`private_key`、`prefix`……），值都是字符串，与 CSV 中的文本完全相同，
+/// `row_hash` 同样按其余各列的字符串计算。之后只会增加新的列；已有键的含义或取值写法改变时
+/// 版本号加一，读取方遇到不认识的版本应当拒绝，遇到不认识的键可以忽略。
+pub const NDJSON_SCHEMA_VERSION: u64 = 1;
+
+/// ndjson 中版本号的键
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// 关联代币账户列名的前缀，后接 mint 地址
+pub const ATA_COLUMN_PREFIX: &str = "ata_";
+
+/// `--excel-compat` 时写在文件开头的 UTF-8 BOM
+pub const UTF8_BOM: &str = "\u{feff}";
+
+/// 以这些字符开头的字段会被表格软件当作公式
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// 字段是否需要在前面加 `'`：可能被当作公式，或本来就以 `'` 开头
+fn needs_formula_escape(field: &str) -> bool {
+    field.starts_with(FORMULA_PREFIXES) || field.starts_with('\'')
+}
+
+/// 在可能被当作公式的字段前加 `'`
+pub fn escape_formula(field: &mut String) {
+    if needs_formula_escape(field) {
+        field.insert(0, '\'');
+    }
+}
+
+/// [`escape_formula`] 的逆操作：去掉加上的 `'`，其他字段原样返回
+pub fn unescape_formula(field: &str) -> &str {
+    match field.strip_prefix('\'') {
+        Some(rest) if needs_formula_escape(rest) => rest,
+        _ => field,
+    }
+}
+
+/// 输出列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    /// Base58 地址
+    Address,
+    /// 64 字节密钥对的 Base58 编码
+    PrivateKey,
+    /// 32 字节种子的 Base58 编码，供只接受种子的工具使用
+    Secret32,
+    /// 64 字节密钥对的 Base58 编码，与 `private_key` 相同，列名写明了长度
+    Keypair64,
+    /// 匹配到的前缀
+    Prefix,
+    /// 32 字节公钥的小写十六进制，供按原始字节索引的系统使用
+    PubkeyHex,
+    /// Shamir 门限方案（此时不输出私钥）
+    Shamir,
+    /// 私钥在系统密钥存储中的引用（此时不输出私钥），见 `--output-append-secret-env`
+    SecretRef,
+    /// 匹配地址对某个代币 mint 的关联代币账户，由 `--derive-ata` 自动添加
+    Ata(ata::Mint),
+    /// 字符在地址中的出现次数和位置，由 `--count-char` 自动添加
+    CharCount(char),
+    /// 产生这一行的机器或运行，值为 `--tag` 的字符串
+    Source,
+    /// 匹配在本次运行中的发现顺序，从 1 开始，非匹配地址为空
+    Index,
+    /// 匹配写入时的本地时间（RFC 3339），非匹配地址为空
+    FoundAt,
+    /// 距同一前缀上次匹配（或开始）的尝试次数，由 `--luck` 添加
+    Attempts,
+    /// 运气倍数：期望尝试次数 / 实际尝试次数
+    Luck,
+    /// 几何分布下不超过实际尝试次数就命中的概率
+    LuckPercentile,
+    /// 地址在已知地址快照中时为 `confirmed` 或 `unverified`（无法复核），否则为空，由 `--known-addresses` 添加
+    Known,
+    /// 行哈希，按其余各列计算
+    RowHash,
+}
+
+impl Column {
+    pub const ALL: [Column; 16] = [
+        Column::Address,
+        Column::PrivateKey,
+        Column::Secret32,
+        Column::Keypair64,
+        Column::Prefix,
+        Column::PubkeyHex,
+        Column::Shamir,
+        Column::SecretRef,
+        Column::Source,
+        Column::Index,
+        Column::FoundAt,
+        Column::Attempts,
+        Column::Luck,
+        Column::LuckPercentile,
+        Column::Known,
+        Column::RowHash,
+    ];
+
+    /// 标题中的列名，关联代币账户列为 `ata_<mint>`，计数列为 `count_<字符>`
+    pub fn name(&self) -> String {
+        match self {
+            Column::Address => "address".to_string(),
+            Column::PrivateKey => "private_key".to_string(),
+            Column::Secret32 => "secret32_bs58".to_string(),
+            Column::Keypair64 => "keypair64_bs58".to_string(),
+            Column::Prefix => "prefix".to_string(),
+            Column::PubkeyHex => "pubkey_hex".to_string(),
+            Column::Shamir => "shamir".to_string(),
+            Column::SecretRef => "secret_ref".to_string(),
+            Column::Ata(mint) => format!("{}{}", ATA_COLUMN_PREFIX, mint),
+            Column::CharCount(ch) => format!("{}{}", char_count::COLUMN_PREFIX, ch),
+            Column::Source => "source".to_string(),
+            Column::Index => "index".to_string(),
+            Column::FoundAt => "found_at".to_string(),
+            Column::Attempts => "attempts".to_string(),
+            Column::Luck => "luck".to_string(),
+            Column::LuckPercentile => "luck_percentile".to_string(),
+            Column::Known => "known".to_string(),
+            Column::RowHash => "row_hash".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name())
+    }
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Column::ALL
+            .into_iter()
+            .find(|column| column.name() == value.trim())
+            .ok_or_else(|| {
+                let names: Vec<String> = Column::ALL.iter().map(Column::name).collect();
+                format!("未知的列: {}（可选: {}）", value, names.join(", "))
+            })
+    }
+}
+
+/// 一个输出文件的格式和列
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schema {
+    pub format: OutputFormat,
+    pub columns: Vec<Column>,
+    /// `source` 列的值
+    pub source: String,
+    /// 写成 Excel 能直接打开的形式：BOM、CRLF、转义可能被当作公式的字段
+    pub excel_compat: bool,
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::Csv,
+            columns: vec![Column::Address, Column::PrivateKey],
+            source: String::new(),
+            excel_compat: false,
+        }
+    }
+}
+
+impl Schema {
+    /// 默认的列：地址和私钥
+    pub const DEFAULT_COLUMNS: &'static str = "address,private_key";
+
+    /// 创建并校验：列不能为空或重复，必须包含地址，行哈希只能是最后一列
+    pub fn new(format: OutputFormat, columns: Vec<Column>) -> Result<Self, String> {
+        if !columns.contains(&Column::Address) {
+            return Err("列中必须包含 address".to_string());
+        }
+        for (index, column) in columns.iter().enumerate() {
+            if columns[..index].contains(column) {
+                return Err(format!("列 {} 重复", column));
+            }
+        }
+        if let Some(position) = columns.iter().position(|column| *column == Column::RowHash)
+            && position != columns.len() - 1
+        {
+            return Err("row_hash 必须是最后一列".to_string());
+        }
+        Ok(Self {
+            format,
+            columns,
+            source: String::new(),
+            excel_compat: false,
+        })
+    }
+
+    /// 解析逗号分隔的列名
+    pub fn parse_columns(value: &str) -> Result<Vec<Column>, String> {
+        value.split(',').map(str::parse).collect()
+    }
+
+    pub fn contains(&self, column: Column) -> bool {
+        self.columns.contains(&column)
+    }
+
+    /// 是否含有任何形式的明文私钥列
+    pub fn has_secret(&self) -> bool {
+        [Column::PrivateKey, Column::Secret32, Column::Keypair64]
+            .iter()
+            .any(|column| self.contains(*column))
+    }
+
+    /// 每行带上来源标记：设置 `source` 列的值，没有这一列时加在 `row_hash` 之前
+    pub fn with_source(mut self, tag: &str) -> Self {
+        if !self.contains(Column::Source) {
+            let position = self
+                .columns
+                .iter()
+                .position(|column| *column == Column::RowHash)
+                .unwrap_or(self.columns.len());
+            self.columns.insert(position, Column::Source);
+        }
+        self.source = tag.to_string();
+        self
+    }
+
+    /// 写成 Excel 能直接打开的形式，见模块说明
+    pub fn with_excel_compat(mut self) -> Self {
+        self.excel_compat = true;
+        self
+    }
+
+    /// 拆成公开索引和私钥文件（`--split-output`），两者都以 `address` 开头，按地址对应
+    ///
+    /// 私钥列（没有时为 `private_key`）移到私钥文件，公开索引保留其余各列，缺少 `prefix`
+    /// 和 `found_at` 时加在 `row_hash` 之前。格式、来源和 Excel 兼容两边相同，有行哈希时两边各算各的。
+    pub fn split_secrets(self) -> (Schema, Schema) {
+        let is_secret = |column: &Column| matches!(column, Column::PrivateKey | Column::Secret32 | Column::Keypair64);
+        let mut secrets: Vec<Column> = self.columns.iter().copied().filter(is_secret).collect();
+        if secrets.is_empty() {
+            secrets.push(Column::PrivateKey);
+        }
+        let row_hash = self.contains(Column::RowHash);
+        secrets.insert(0, Column::Address);
+        if row_hash {
+            secrets.push(Column::RowHash);
+        }
+        let mut public = self.clone();
+        public.columns.retain(|column| !is_secret(column));
+        for column in [Column::Prefix, Column::FoundAt] {
+            if !public.contains(column) {
+                let position = public.columns.len() - usize::from(row_hash);
+                public.columns.insert(position, column);
+            }
+        }
+        let secret = Schema {
+            columns: secrets,
+            ..self
+        };
+        (public, secret)
+    }
+
+    /// 同时写出私钥的两种形式：`private_key` 换成 `secret32_bs58,keypair64_bs58`，
+    /// 没有 `private_key` 列时把两列加在 `row_hash` 之前，已经列出的不会重复
+    pub fn with_both_secret_forms(mut self) -> Self {
+        let both = [Column::Secret32, Column::Keypair64];
+        self.columns.retain(|column| !both.contains(column));
+        let position = match self.columns.iter().position(|column| *column == Column::PrivateKey) {
+            Some(position) => {
+                self.columns.remove(position);
+                position
+            }
+            None => self
+                .columns
+                .iter()
+                .position(|column| *column == Column::RowHash)
+                .unwrap_or(self.columns.len()),
+        };
+        self.columns.splice(position..position, both);
+        self
+    }
+
+    /// 私钥只存入系统密钥存储：各私钥列换成一个 `secret_ref` 列，放在第一个私钥列的位置，
+    /// 没有私钥列时加在 `row_hash` 之前
+    pub fn with_secret_ref(mut self) -> Self {
+        let is_secret = |column: &Column| matches!(column, Column::PrivateKey | Column::Secret32 | Column::Keypair64);
+        if !self.contains(Column::SecretRef) {
+            let position = self
+                .columns
+                .iter()
+                .position(is_secret)
+                .or_else(|| self.columns.iter().position(|column| *column == Column::RowHash))
+                .unwrap_or(self.columns.len());
+            self.columns.insert(position, Column::SecretRef);
+        }
+        self.columns.retain(|column| !is_secret(column));
+        self
+    }
+
+    /// 标题行的各列名
+    pub fn header(&self) -> Vec<String> {
+        self.columns.iter().map(Column::name).collect()
+    }
+
+    /// 按本格式创建写入器：CSV 和 TSV 中含分隔符、引号或换行的字段会被正确转义，
+    /// ndjson 按 JSON 规则转义
+    pub fn record_writer<W: Write>(&self, writer: W) -> RecordWriter<W> {
+        let Some(delimiter) = self.format.delimiter() else {
+            return RecordWriter::Ndjson {
+                writer: BufWriter::new(writer),
+                keys: self.header(),
+            };
+        };
+        let terminator = if self.excel_compat {
+            csv::Terminator::CRLF
+        } else {
+            csv::Terminator::Any(b'\n')
+        };
+        RecordWriter::Delimited(Box::new(
+            csv::WriterBuilder::new()
+                .delimiter(delimiter)
+                .terminator(terminator)
+                .from_writer(writer),
+        ))
+    }
+
+    /// 在新文件开头写入标题行（`excel_compat` 时先写 BOM），返回写入器；ndjson 没有标题行
+    pub fn header_writer<W: Write>(&self, mut writer: W) -> io::Result<RecordWriter<W>> {
+        if self.format == OutputFormat::Ndjson {
+            return Ok(self.record_writer(writer));
+        }
+        if self.excel_compat {
+            writer.write_all(UTF8_BOM.as_bytes())?;
+        }
+        let mut writer = self.record_writer(writer);
+        writer.write_row(&self.header())?;
+        Ok(writer)
+    }
+
+    /// 按列取值得到一行的各字段，行哈希由其余各列计算
+    ///
+    /// 字段可能含有私钥，调用方写出后应立即清零。行哈希按转义前的值计算。
+    pub fn row(&self, mut value: impl FnMut(Column) -> String) -> Vec<String> {
+        let mut fields: Vec<String> = self
+            .columns
+            .iter()
+            .filter(|column| **column != Column::RowHash)
+            .map(|column| value(*column))
+            .collect();
+        if self.contains(Column::RowHash) {
+            let refs: Vec<&str> = fields.iter().map(String::as_str).collect();
+            fields.push(integrity::row_hash(&refs));
+        }
+        if self.excel_compat && self.format != OutputFormat::Ndjson {
+            fields.iter_mut().for_each(escape_formula);
+        }
+        fields
+    }
+}
+
+/// 一个输出文件的写入器，按格式写 CSV/TSV 或 ndjson
+pub enum RecordWriter<W: Write> {
+    Delimited(Box<csv::Writer<W>>),
+    Ndjson { writer: BufWriter<W>, keys: Vec<String> },
+}
+
+impl<W: Write> RecordWriter<W> {
+    /// 写入一行，字段与输出列一一对应
+    pub fn write_row<S: AsRef<str>>(&mut self, fields: &[S]) -> io::Result<()> {
+        match self {
+            RecordWriter::Delimited(writer) => Ok(writer.write_record(fields.iter().map(|field| field.as_ref()))?),
+            RecordWriter::Ndjson { writer, keys } => {
+                serde_json::to_writer(&mut *writer, &JsonRow { keys, values: fields }).map_err(io::Error::other)?;
+                writer.write_all(b"\n")
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self {
+            RecordWriter::Delimited(writer) => writer.flush(),
+            RecordWriter::Ndjson { writer, .. } => writer.flush(),
+        }
+    }
+
+    /// 刷新后取回底层的写入目标
+    pub fn into_inner(self) -> io::Result<W> {
+        match self {
+            RecordWriter::Delimited(writer) => writer.into_inner().map_err(|err| err.into_error()),
+            RecordWriter::Ndjson { writer, .. } => writer.into_inner().map_err(|err| err.into_error()),
+        }
+    }
+}
+
+/// ndjson 的一行：先写版本号，再按列的顺序写各列
+struct JsonRow<'a, S> {
+    keys: &'a [String],
+    values: &'a [S],
+}
+
+impl<S: AsRef<str>> Serialize for JsonRow<'_, S> {
+    fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        let mut map = serializer.serialize_map(Some(self.keys.len() + 1))?;
+        map.serialize_entry(SCHEMA_VERSION_KEY, &NDJSON_SCHEMA_VERSION)?;
+        for (key, value) in self.keys.iter().zip(self.values) {
+            map.serialize_entry(key, value.as_ref())?;
+        }
+        map.end()
+    }
+}
+
+/// 解析 ndjson 的一行，按原来的顺序返回版本号之外的各列；版本号缺失或比
+/// [`NDJSON_SCHEMA_VERSION`] 新时返回错误，非字符串的值按 JSON 文本返回
+pub fn parse_ndjson_line(line: &str) -> Result<Vec<(String, String)>, String> {
+    let OrderedObject(entries) = serde_json::from_str(line).map_err(|err| format!("不是 JSON 对象: {}", err))?;
+    let mut version = None;
+    let mut fields = Vec::with_capacity(entries.len());
+    for (key, value) in entries {
+        if key == SCHEMA_VERSION_KEY {
+            version = value.as_u64();
+            continue;
+        }
+        let value = match value {
+            serde_json::Value::String(value) => value,
+            value => value.to_string(),
+        };
+        fields.push((key, value));
+    }
+    match version {
+        None => Err(format!("缺少 {}", SCHEMA_VERSION_KEY)),
+        Some(version) if version > NDJSON_SCHEMA_VERSION => Err(format!(
+            "{} 为 {}，本程序只认识到第 {} 版",
+            SCHEMA_VERSION_KEY, version, NDJSON_SCHEMA_VERSION
+        )),
+        Some(_) => Ok(fields),
+    }
+}
+
+/// 保留键顺序的 JSON 对象，行哈希按列的顺序计算，不能用按键排序的映射
+struct OrderedObject(Vec<(String, serde_json::Value)>);
+
+impl<'de> Deserialize<'de> for OrderedObject {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ObjectVisitor;
+
+        impl<'de> Visitor<'de> for ObjectVisitor {
+            type Value = OrderedObject;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("JSON 对象")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<OrderedObject, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(OrderedObject(entries))
+            }
+        }
+
+        deserializer.deserialize_map(ObjectVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// 形如公式的来源标记
+    const FORMULA_TAG: &str = "=HYPERLINK(\"http://x\",\"y\")";
+
+    /// 测试专用的临时文件路径
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sva-format-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    /// 按 `schema` 写一个含标题行和 `rows` 的文件
+    fn write_file(schema: &Schema, name: &str, rows: &[(&str, &str)]) -> PathBuf {
+        let path = temp_path(name);
+        let mut writer = schema.header_writer(std::fs::File::create(&path).unwrap()).unwrap();
+        for (address, secret) in rows {
+            let row = schema.row(|column| match column {
+                Column::Address => address.to_string(),
+                Column::PrivateKey => secret.to_string(),
+                Column::Source => schema.source.clone(),
+                _ => String::new(),
+            });
+            writer.write_row(&row).unwrap();
+        }
+        writer.flush().unwrap();
+        path
+    }
+
+    /// 由固定种子得到的地址和私钥
+    fn fixed_rows() -> Vec<(String, String)> {
+        use solana_sdk::signer::Signer;
+        use solana_sdk::signer::keypair::keypair_from_seed;
+        (1u8..4)
+            .map(|seed| {
+                let keypair = keypair_from_seed(&[seed; 32]).unwrap();
+                (keypair.pubkey().to_string(), keypair.to_base58_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn formula_escape_round_trip() {
+        for field in ["", "plain", "=1+1", "+1", "-1", "@SUM(A1)", "\tx", "\rx", "'", "'quoted", "''=x", "'=x", "a=b"] {
+            let mut escaped = field.to_string();
+            escape_formula(&mut escaped);
+            assert!(!escaped.starts_with(['=', '+', '-', '@', '\t', '\r']), "{:?}", escaped);
+            assert_eq!(unescape_formula(&escaped), field);
+        }
+        // 不需要转义的字段原样保留
+        let mut plain = "So1ana".to_string();
+        escape_formula(&mut plain);
+        assert_eq!(plain, "So1ana");
+    }
+
+    #[test]
+    fn excel_compat_writes_bom_crlf_and_escapes() {
+        let schema = Schema::new(OutputFormat::Csv, vec![Column::Address, Column::PrivateKey, Column::RowHash])
+            .unwrap()
+            .with_source(FORMULA_TAG)
+            .with_excel_compat();
+        let rows = fixed_rows();
+        let refs: Vec<(&str, &str)> = rows.iter().map(|(a, s)| (a.as_str(), s.as_str())).collect();
+        let path = write_file(&schema, "excel.csv", &refs);
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with(UTF8_BOM));
+        assert_eq!(content.matches("\r\n").count(), rows.len() + 1);
+        assert_eq!(content.matches('\n').count(), rows.len() + 1);
+        assert!(content.contains("'=HYPERLINK"), "{}", content);
+        assert!(!content.contains(",=HYPERLINK"), "{}", content);
+    }
+
+    #[test]
+    fn excel_compat_round_trips_through_verify() {
+        let rows = fixed_rows();
+        let refs: Vec<(&str, &str)> = rows.iter().map(|(a, s)| (a.as_str(), s.as_str())).collect();
+        for format in [OutputFormat::Csv, OutputFormat::Tsv] {
+            let schema = Schema::new(format, vec![Column::Address, Column::PrivateKey, Column::RowHash])
+                .unwrap()
+                .with_source(FORMULA_TAG)
+                .with_excel_compat();
+            let path = write_file(&schema, &format!("excel-{:?}.txt", format), &refs);
+
+            // 行哈希按转义前的值计算，校验时还原后应一致
+            let report = integrity::verify_file(&path).unwrap();
+            assert!(report.is_ok(), "{:?}: {:?}", format, report.issues);
+            assert_eq!(report.rows, rows.len());
+            assert_eq!(report.hashed_rows, rows.len());
+
+            let records: Vec<csv::StringRecord> =
+                integrity::open_table(&path).unwrap().map(|record| record.unwrap()).collect();
+            assert_eq!(records[0].iter().collect::<Vec<_>>(), ["address", "private_key", "source", "row_hash"]);
+            for (record, (address, secret)) in records[1..].iter().zip(&rows) {
+                assert_eq!(&record[0], address);
+                assert_eq!(&record[1], secret);
+                assert_eq!(&record[2], FORMULA_TAG);
+            }
+        }
+    }
+
+    #[test]
+    fn tampered_excel_row_fails_verify() {
+        let rows = fixed_rows();
+        let schema = Schema::new(OutputFormat::Csv, vec![Column::Address, Column::PrivateKey, Column::RowHash])
+            .unwrap()
+            .with_source(FORMULA_TAG)
+            .with_excel_compat();
+        let path = write_file(&schema, "excel-tampered.csv", &[(rows[0].0.as_str(), rows[0].1.as_str())]);
+        let content = std::fs::read_to_string(&path).unwrap().replace("'=HYPERLINK", "'=HYPERLINX");
+        std::fs::write(&path, content).unwrap();
+        let report = integrity::verify_file(&path).unwrap();
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].message.contains("行哈希"), "{}", report.issues[0]);
+    }
+}
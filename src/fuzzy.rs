@@ -0,0 +1,200 @@
+//! 模糊前缀（`--fuzzy`、`--max-distance`）
+//!
+//! 地址开头若干个字符与目标单词的编辑距离（Levenshtein 距离，插入、删除、替换各算 1）
+//! 不超过 K 即匹配。Base58 没有 `0`、`O`、`I`、`l`，`solana` 这样的单词无法精确匹配，
+//! 允许 1 的距离就能找到 `so1ana…`、`soana…` 这类读起来相近的地址。
+//!
+//! 开头取多少个字符不固定：对地址的每个前缀长度都算一次到单词的距离，取最小的那个，
+//! 所以单词中多一个或少一个字符都能匹配。做法是标准的动态规划，但只保留一列，
+//! 每读入地址的一个字符更新一次，最多读单词长度加 K 个字符；整列都超过 K 后不可能再回落，
+//! 立即停止。随机地址通常读两三个字符就停下，代价在每个候选地址上是几十次字节比较，
+//! 与 Base58 编码相比很小，但与前缀树不同，它要对每个候选地址都算一次。
+//!
+//! 与回文和镜像匹配器一样作为 [`crate::matcher::Matcher`] 放进 [`crate::Search`]，用 [`Fuzzy::label`] 作为名字参与计数，
+//! 输出文件的前缀列记录实际匹配的字符串和距离，如 `so1ana~1`。区分大小写。
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::base58::ALPHABET;
+use crate::difficulty::address_classes;
+
+/// 单词的最大长度，单词越长动态规划越慢
+pub const MAX_WORD_LEN: usize = 16;
+
+/// 距离的默认值
+pub const DEFAULT_MAX_DISTANCE: usize = 1;
+
+/// 一个模糊前缀匹配器
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fuzzy {
+    word: String,
+    max_distance: usize,
+}
+
+/// 一次模糊匹配的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// 实际的编辑距离
+    pub distance: usize,
+    /// 参与比较的地址开头字符数
+    pub length: usize,
+}
+
+impl Fuzzy {
+    /// 单词只能由 ASCII 字母和数字组成，可以含 Base58 之外的字符（它们总要替换掉，各占 1 的距离）；
+    /// 距离必须小于单词长度，否则任何地址都匹配
+    pub fn new(word: &str, max_distance: usize) -> Result<Self, String> {
+        if word.is_empty() || word.len() > MAX_WORD_LEN || !word.bytes().all(|byte| byte.is_ascii_alphanumeric()) {
+            return Err(format!("--fuzzy 的单词应为 1 到 {} 个字母或数字，而不是 {}", MAX_WORD_LEN, word));
+        }
+        if max_distance >= word.len() {
+            return Err(format!(
+                "--max-distance {} 不小于单词 {} 的长度，任何地址都会匹配",
+                max_distance, word
+            ));
+        }
+        Ok(Self {
+            word: word.to_string(),
+            max_distance,
+        })
+    }
+
+    pub fn word(&self) -> &str {
+        &self.word
+    }
+
+    pub fn max_distance(&self) -> usize {
+        self.max_distance
+    }
+
+    /// 显示用的名字：`<fuzzy solana 1>`
+    pub fn label(&self) -> String {
+        format!("<fuzzy {} {}>", self.word, self.max_distance)
+    }
+
+    /// 从 [`Fuzzy::label`] 的写法解析，不是模糊前缀时返回 `None`；用于读取文本形式的目标列表，如模糊测试的输入
+    pub fn from_label(label: &str) -> Option<Self> {
+        let rest = label.strip_prefix("<fuzzy ")?.strip_suffix('>')?;
+        let (word, max_distance) = rest.split_once(' ')?;
+        Self::new(word, max_distance.parse().ok()?).ok()
+    }
+
+    /// 地址开头与单词的最小编辑距离及对应的字符数，超过 K 时为 `None`；距离相同时取较短的开头
+    pub fn find(&self, address: &str) -> Option<FuzzyMatch> {
+        let word = self.word.as_bytes();
+        let mut column = [0; MAX_WORD_LEN + 1];
+        for (distance, cell) in column[..=word.len()].iter_mut().enumerate() {
+            *cell = distance;
+        }
+        let column = &mut column[..=word.len()];
+        let mut best: Option<FuzzyMatch> = None;
+        for (index, &byte) in address.as_bytes().iter().take(word.len() + self.max_distance).enumerate() {
+            let lowest = advance(word, column, byte);
+            let distance = column[word.len()];
+            if distance <= self.max_distance && best.is_none_or(|best| distance < best.distance) {
+                best = Some(FuzzyMatch {
+                    distance,
+                    length: index + 1,
+                });
+            }
+            if lowest > self.max_distance {
+                break;
+            }
+        }
+        best
+    }
+
+    /// 检查地址是否符合
+    pub fn matches(&self, address: &str) -> bool {
+        self.find(address).is_some()
+    }
+
+    /// 写入输出文件的前缀列：实际匹配的开头字符和距离，如 `so1ana~1`
+    pub fn matched_text(&self, address: &str) -> String {
+        match self.find(address) {
+            Some(found) => format!("{}~{}", &address[..found.length], found.distance),
+            None => self.label(),
+        }
+    }
+
+    /// 匹配地址中参与比较的开头字符
+    pub fn spans(&self, address: &str) -> Vec<Range<usize>> {
+        self.find(address).map(|found| 0..found.length).into_iter().collect()
+    }
+
+    /// 随机地址符合的概率
+    ///
+    /// 按 [`address_classes`] 把地址分成几类，每一类中逐位推进与 [`Fuzzy::find`] 相同的动态规划：
+    /// 状态是截断到 K+1 的那一列，每一位只需区分单词中出现的字符和“其他字符”，
+    /// 后者的概率是该位其余数字的概率之和。列的最后一格不超过 K 时计入匹配，整列超过 K 时丢弃。
+    pub fn probability(&self) -> f64 {
+        let word = self.word.as_bytes();
+        let cap = self.max_distance + 1;
+        let mut distinct: Vec<u8> = word.to_vec();
+        distinct.sort_unstable();
+        distinct.dedup();
+        address_classes()
+            .iter()
+            .map(|(weight, positions)| {
+                let mut states: BTreeMap<Vec<usize>, f64> = BTreeMap::new();
+                states.insert((0..=word.len()).map(|distance| distance.min(cap)).collect(), 1.0);
+                let mut matched = 0.0;
+                for position in positions.iter().take(word.len() + self.max_distance) {
+                    let mut choices: Vec<(u8, f64)> = distinct
+                        .iter()
+                        .filter_map(|&byte| ALPHABET.bytes().position(|c| c == byte).map(|digit| (byte, position[digit])))
+                        .collect();
+                    let other = 1.0 - choices.iter().map(|(_, probability)| probability).sum::<f64>();
+                    // 0 不在单词中，代表其他任一字符
+                    choices.push((0, other));
+                    let mut next: BTreeMap<Vec<usize>, f64> = BTreeMap::new();
+                    for (column, mass) in &states {
+                        for &(byte, probability) in &choices {
+                            if probability <= 0.0 {
+                                continue;
+                            }
+                            let mut column = column.clone();
+                            let lowest = advance(word, &mut column, byte);
+                            if column[word.len()] <= self.max_distance {
+                                matched += mass * probability;
+                            } else if lowest <= self.max_distance {
+                                for cell in &mut column {
+                                    *cell = (*cell).min(cap);
+                                }
+                                *next.entry(column).or_default() += mass * probability;
+                            }
+                        }
+                    }
+                    states = next;
+                }
+                weight * matched
+            })
+            .sum()
+    }
+}
+
+/// 读入地址的下一个字符，把单词各前缀到地址已读部分的距离这一列更新到下一列，返回列中的最小值
+fn advance(word: &[u8], column: &mut [usize], byte: u8) -> usize {
+    let mut diagonal = column[0];
+    column[0] += 1;
+    let mut lowest = column[0];
+    for (index, &expected) in word.iter().enumerate() {
+        let above = column[index + 1];
+        column[index + 1] = (above + 1)
+            .min(column[index] + 1)
+            .min(diagonal + usize::from(expected != byte));
+        diagonal = above;
+        lowest = lowest.min(column[index + 1]);
+    }
+    lowest
+}
+
+/// 解析 `--max-distance`
+pub fn parse_max_distance(value: &str) -> Result<usize, String> {
+    value
+        .parse::<usize>()
+        .ok()
+        .filter(|distance| *distance < MAX_WORD_LEN)
+        .ok_or_else(|| format!("编辑距离应为 0 到 {} 之间的整数，而不是 {}", MAX_WORD_LEN - 1, value))
+}
@@ -0,0 +1,52 @@
+//! 终端中高亮地址匹配的部分（`--color`）
+//!
+//! 打印匹配时把前缀、回文或镜像的开头和结尾这些真正符合条件的字符标上颜色，一眼就能看出
+//! 为什么匹配。位置由 [`crate::Search::matched_spans`] 给出。颜色由 `console` 输出，
+//! 默认只在终端中着色，重定向到文件或设置了 `NO_COLOR` 时不着色。
+
+use std::ops::Range;
+
+use clap::ValueEnum;
+use console::style;
+
+/// `--color` 的取值
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorMode {
+    /// 输出到终端时着色
+    #[default]
+    Auto,
+    /// 总是着色
+    Always,
+    /// 从不着色
+    Never,
+}
+
+impl ColorMode {
+    /// 设置标准输出和标准错误是否着色，`auto` 时保留 `console` 的检测结果
+    pub fn apply(self) {
+        let enabled = match self {
+            ColorMode::Auto => return,
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        };
+        console::set_colors_enabled(enabled);
+        console::set_colors_enabled_stderr(enabled);
+    }
+}
+
+/// 把 `spans` 范围内的字符高亮，范围须按顺序且互不重叠；不着色时原样返回
+pub fn highlight(address: &str, spans: &[Range<usize>]) -> String {
+    let mut highlighted = String::with_capacity(address.len());
+    let mut position = 0;
+    for span in spans {
+        let (start, end) = (span.start.max(position), span.end.min(address.len()));
+        if start >= end {
+            continue;
+        }
+        highlighted.push_str(&address[position..start]);
+        highlighted.push_str(&style(&address[start..end]).green().bold().to_string());
+        position = end;
+    }
+    highlighted.push_str(&address[position..]);
+    highlighted
+}
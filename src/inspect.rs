@@ -0,0 +1,182 @@
+//! 识别一段粘贴来的私钥（`inspect` 子命令）
+//!
+//! 从别的工具复制来的私钥有多种写法：32 字节种子或 64 字节密钥对的 Base58、
+//! solana CLI 密钥文件中的 JSON 字节数组、十六进制。[`inspect`] 依次尝试这些写法，
+//! 列出每一种说得通的解读和推导出的地址。32 字节的 Base58 或十六进制既可能是种子，
+//! 也可能是公钥本身，无法区分，两种解读都会列出。全程只在内存中处理，不写任何文件。
+
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signer::keypair::keypair_from_seed;
+use solana_sdk::signer::Signer;
+use zeroize::Zeroizing;
+
+use crate::secret::Secret;
+
+/// 输入的编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Base58,
+    /// solana CLI 密钥文件的写法，如 `[12,34,...]`
+    JsonArray,
+    /// 可带 `0x` 前缀，字母大小写均可
+    Hex,
+}
+
+impl Encoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Base58 => "Base58 编码",
+            Encoding::JsonArray => "JSON 字节数组",
+            Encoding::Hex => "十六进制编码",
+        }
+    }
+}
+
+/// 解码出的字节代表什么
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// 32 字节种子
+    Seed,
+    /// 64 字节密钥对：种子 + 公钥
+    Keypair,
+    /// 32 字节公钥，即地址本身
+    PublicKey,
+}
+
+impl Kind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Seed => "32 字节种子",
+            Kind::Keypair => "64 字节密钥对",
+            Kind::PublicKey => "32 字节公钥（地址本身，不是私钥）",
+        }
+    }
+}
+
+/// 一种说得通的解读
+pub struct Interpretation {
+    pub encoding: Encoding,
+    pub kind: Kind,
+    /// 推导出的地址；公钥解读就是它自己
+    pub address: String,
+    /// 由种子重新推导的 64 字节密钥对，公钥解读为 `None`
+    pub keypair: Option<Zeroizing<Vec<u8>>>,
+    /// 解读本身成立但有问题时的说明，如密钥对后半与推导出的公钥不一致
+    pub warning: Option<String>,
+}
+
+impl Interpretation {
+    /// 钱包“导入私钥”接受的 64 字节密钥对 Base58
+    pub fn keypair_base58(&self) -> Option<Secret> {
+        self.keypair
+            .as_ref()
+            .map(|keypair| Secret::new(bs58::encode(&keypair[..]).into_string()))
+    }
+
+    /// solana CLI 密钥文件的 JSON 字节数组
+    pub fn keypair_json(&self) -> Option<Secret> {
+        self.keypair
+            .as_ref()
+            .map(|keypair| Secret::new(serde_json::to_string(&keypair[..]).unwrap_or_default()))
+    }
+}
+
+/// 识别输入，返回所有说得通的解读，一种也没有时返回原因
+pub fn inspect(input: &str) -> Result<Vec<Interpretation>, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("输入为空".to_string());
+    }
+    if input.split_whitespace().count() >= 12 {
+        return Err("这看起来是助记词，不是私钥；助记词请直接在钱包中用“导入助记词”恢复".to_string());
+    }
+
+    if input.starts_with('[') {
+        let bytes: Zeroizing<Vec<u8>> = Zeroizing::new(
+            serde_json::from_str(input).map_err(|err| format!("不是有效的 JSON 字节数组: {}", err))?,
+        );
+        let interpretations = interpret(Encoding::JsonArray, &bytes);
+        if interpretations.is_empty() {
+            return Err(format!("JSON 字节数组有 {} 个字节，私钥应为 32 或 64 个字节", bytes.len()));
+        }
+        return Ok(interpretations);
+    }
+
+    let mut interpretations = Vec::new();
+    let mut lengths = Vec::new();
+    let hex = input.strip_prefix("0x").or_else(|| input.strip_prefix("0X")).unwrap_or(input);
+    if let Some(bytes) = decode_hex(hex) {
+        lengths.push(format!("按十六进制为 {} 字节", bytes.len()));
+        interpretations.extend(interpret(Encoding::Hex, &bytes));
+    }
+    if let Ok(bytes) = bs58::decode(input).into_vec() {
+        let bytes = Zeroizing::new(bytes);
+        lengths.push(format!("按 Base58 为 {} 字节", bytes.len()));
+        interpretations.extend(interpret(Encoding::Base58, &bytes));
+    }
+    if interpretations.is_empty() {
+        return Err(if lengths.is_empty() {
+            "无法识别：不是 Base58、十六进制或 JSON 字节数组".to_string()
+        } else {
+            format!("长度不对：{}，私钥应为 32 或 64 个字节", lengths.join("，"))
+        });
+    }
+    Ok(interpretations)
+}
+
+/// 按字节数给出解读：32 字节是种子或公钥，64 字节是密钥对
+fn interpret(encoding: Encoding, bytes: &[u8]) -> Vec<Interpretation> {
+    match bytes.len() {
+        32 => {
+            let mut interpretations: Vec<Interpretation> = from_seed(encoding, Kind::Seed, bytes).into_iter().collect();
+            // JSON 字节数组只出现在密钥文件中，不会是公钥
+            if encoding != Encoding::JsonArray {
+                interpretations.push(Interpretation {
+                    encoding,
+                    kind: Kind::PublicKey,
+                    address: Pubkey::try_from(bytes).map(|pubkey| pubkey.to_string()).unwrap_or_default(),
+                    keypair: None,
+                    warning: None,
+                });
+            }
+            interpretations
+        }
+        64 => from_seed(encoding, Kind::Keypair, bytes)
+            .map(|mut interpretation| {
+                let derived = interpretation.keypair.as_ref().map(|keypair| keypair[32..].to_vec());
+                if derived.as_deref() != Some(&bytes[32..]) {
+                    interpretation.warning = Some(
+                        "后 32 字节与种子推导出的公钥不一致，钱包会拒绝导入；下面的转换按前 32 字节的种子重新推导".to_string(),
+                    );
+                }
+                interpretation
+            })
+            .into_iter()
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 用前 32 字节作为种子推导密钥对
+fn from_seed(encoding: Encoding, kind: Kind, bytes: &[u8]) -> Option<Interpretation> {
+    let keypair = keypair_from_seed(&bytes[..32]).ok()?;
+    Some(Interpretation {
+        encoding,
+        kind,
+        address: keypair.pubkey().to_string(),
+        keypair: Some(Zeroizing::new(keypair.to_bytes().to_vec())),
+        warning: None,
+    })
+}
+
+/// 解码偶数位的十六进制，含有其他字符时返回 `None`
+fn decode_hex(hex: &str) -> Option<Zeroizing<Vec<u8>>> {
+    if hex.is_empty() || !hex.len().is_multiple_of(2) || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|start| u8::from_str_radix(&hex[start..start + 2], 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    Some(Zeroizing::new(bytes))
+}
@@ -0,0 +1,305 @@
+//! 把匹配到的密钥对安装为 solana CLI 的默认密钥对
+//!
+//! 手动操作需要先把 Base58 私钥转成 JSON 字节数组、放到 `~/.config/solana/id.json`，
+//! 再用 `solana-keygen verify` 核对，容易覆盖掉原有的钱包。`install` 子命令一步完成：
+//! 从匹配文件中找出地址对应的私钥并核对，目标位置已有文件时先改名备份（绝不直接覆盖），
+//! 以 0600 权限写入新文件，最后读回核对地址。
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use solana_sdk::signer::keypair::keypair_from_seed;
+use solana_sdk::signer::Signer;
+use zeroize::Zeroizing;
+
+use crate::integrity;
+
+/// solana CLI 默认密钥对相对于用户主目录的路径
+pub const DEFAULT_RELATIVE_PATH: &str = ".config/solana/id.json";
+
+/// solana CLI 的默认密钥对路径，找不到用户主目录时返回 `None`
+pub fn default_path() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|home| !home.is_empty())
+        .map(|home| PathBuf::from(home).join(DEFAULT_RELATIVE_PATH))
+}
+
+/// 安装的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Installed {
+    /// 目标位置原来没有文件
+    New,
+    /// 原有的文件已改名备份到这里
+    BackedUp(PathBuf),
+    /// 目标位置已经是同一个密钥对，没有改动
+    Unchanged,
+}
+
+/// 从匹配文件中找出地址对应的 64 字节密钥对，并核对私钥确实对应这个地址
+pub fn find_keypair(path: impl AsRef<Path>, address: &str) -> Result<Zeroizing<Vec<u8>>, String> {
+    let path = path.as_ref();
+    let read_error = |err: &dyn std::fmt::Display| format!("无法读取 {}: {}", path.display(), err);
+    let mut records = integrity::open_table(path).map_err(|err| read_error(&err))?;
+    let columns = match records.next() {
+        Some(record) => record.map_err(|err| read_error(&err))?,
+        None => return Err(format!("{} 是空文件", path.display())),
+    };
+    let column = |name: &str| columns.iter().position(|column| column == name);
+    let address_col = column("address").ok_or_else(|| format!("{} 的标题中缺少 address 列", path.display()))?;
+    // --both-secret-forms 的文件没有 private_key 列，keypair64_bs58 与它相同
+    let Some(secret_col) = column("private_key").or_else(|| column("keypair64_bs58")) else {
+        return Err(format!(
+            "{} 中没有 private_key 或 keypair64_bs58 列（Shamir 模式的结果请先用 recover 恢复私钥）",
+            path.display()
+        ));
+    };
+    for record in records {
+        let record = record.map_err(|err| read_error(&err))?;
+        if record.get(address_col) != Some(address) {
+            continue;
+        }
+        let secret = record.get(secret_col).unwrap_or_default();
+        integrity::check_keypair(address, secret).map_err(|err| format!("{} 中 {} 的私钥有误: {}", path.display(), address, err))?;
+        let bytes = Zeroizing::new(bs58::decode(secret).into_vec().map_err(|err| err.to_string())?);
+        return Ok(bytes);
+    }
+    Err(format!("{} 中没有地址 {}", path.display(), address))
+}
+
+/// 原有文件的备份路径：`<文件名>.bak-<时间>`，已存在时再加序号
+pub fn backup_path(path: &Path) -> PathBuf {
+    let stamp = Local::now().format("%Y%m%dT%H%M%S");
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut candidate = path.with_file_name(format!("{}.bak-{}", name, stamp));
+    let mut counter = 1;
+    while candidate.exists() {
+        candidate = path.with_file_name(format!("{}.bak-{}-{}", name, stamp, counter));
+        counter += 1;
+    }
+    candidate
+}
+
+/// 以 solana CLI 的 JSON 字节数组格式把密钥对写到 `path`
+///
+/// 目标位置已有同一个密钥对时不做改动；已有其他文件时先改名备份，备份失败则放弃，
+/// 不是普通文件（如目录）时拒绝。新文件用 `create_new` 创建，权限 0600，写完后读回核对。
+pub fn install(keypair: &[u8], address: &str, path: &Path) -> io::Result<Installed> {
+    let json = Zeroizing::new(serde_json::to_string(keypair).map_err(io::Error::other)?);
+    let installed = match fs::symlink_metadata(path) {
+        Ok(metadata) if !metadata.is_file() => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} 已存在且不是普通文件，拒绝写入", path.display()),
+            ));
+        }
+        Ok(_) => {
+            let existing = Zeroizing::new(fs::read(path)?);
+            if read_keypair(&existing).is_some_and(|bytes| bytes[..] == *keypair) {
+                return Ok(Installed::Unchanged);
+            }
+            let backup = backup_path(path);
+            fs::rename(path, &backup)?;
+            Installed::BackedUp(backup)
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                fs::create_dir_all(dir)?;
+            }
+            Installed::New
+        }
+        Err(err) => return Err(err),
+    };
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(json.as_bytes())?;
+    file.sync_all()?;
+
+    // 与 solana-keygen verify 相同：读回文件，确认其中的密钥对推导出的正是这个地址
+    let written = Zeroizing::new(fs::read(path)?);
+    let verified = read_keypair(&written)
+        .and_then(|bytes| keypair_from_seed(&bytes[..32]).ok())
+        .is_some_and(|keypair| keypair.pubkey().to_string() == address);
+    if !verified {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("读回 {} 核对失败，文件内容与地址 {} 不一致", path.display(), address),
+        ));
+    }
+    Ok(installed)
+}
+
+/// 解析 solana CLI 格式的密钥对文件内容，不是 64 字节的 JSON 数组时返回 `None`
+fn read_keypair(content: &[u8]) -> Option<Zeroizing<Vec<u8>>> {
+    let bytes: Vec<u8> = serde_json::from_slice(content).ok()?;
+    let bytes = Zeroizing::new(bytes);
+    (bytes.len() == 64).then_some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试专用的空临时目录
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sva-install-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 固定种子的地址和 64 字节密钥对
+    fn fixed_keypair(seed: u8) -> (String, Vec<u8>) {
+        let keypair = keypair_from_seed(&[seed; 32]).unwrap();
+        (keypair.pubkey().to_string(), keypair.to_bytes().to_vec())
+    }
+
+    /// 目录中除 `id.json` 之外的备份文件
+    fn backups(dir: &Path) -> Vec<PathBuf> {
+        let mut backups: Vec<PathBuf> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.file_name().unwrap().to_string_lossy().starts_with("id.json.bak-"))
+            .collect();
+        backups.sort();
+        backups
+    }
+
+    #[test]
+    fn installs_into_a_new_directory() {
+        let dir = temp_dir("new");
+        let path = dir.join("nested/solana/id.json");
+        let (address, keypair) = fixed_keypair(1);
+        assert_eq!(install(&keypair, &address, &path).unwrap(), Installed::New);
+        let written: Vec<u8> = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(written, keypair);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn same_keypair_is_left_unchanged() {
+        let dir = temp_dir("unchanged");
+        let path = dir.join("id.json");
+        let (address, keypair) = fixed_keypair(2);
+        install(&keypair, &address, &path).unwrap();
+        assert_eq!(install(&keypair, &address, &path).unwrap(), Installed::Unchanged);
+        assert!(backups(&dir).is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn other_file_is_backed_up_not_overwritten() {
+        let dir = temp_dir("backup");
+        let path = dir.join("id.json");
+        let (old_address, old_keypair) = fixed_keypair(3);
+        let (address, keypair) = fixed_keypair(4);
+        install(&old_keypair, &old_address, &path).unwrap();
+        let old_content = fs::read(&path).unwrap();
+
+        let Installed::BackedUp(backup) = install(&keypair, &address, &path).unwrap() else {
+            panic!("原有的密钥对应当被备份");
+        };
+        assert_eq!(backups(&dir), std::slice::from_ref(&backup));
+        assert_eq!(fs::read(&backup).unwrap(), old_content);
+        let written: Vec<u8> = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(written, keypair);
+
+        // 不是密钥对的文件同样先备份
+        fs::write(&path, "not a keypair").unwrap();
+        assert!(matches!(install(&old_keypair, &old_address, &path).unwrap(), Installed::BackedUp(_)));
+        let contents: Vec<Vec<u8>> = backups(&dir).iter().map(|backup| fs::read(backup).unwrap()).collect();
+        assert!(contents.contains(&old_content));
+        assert!(contents.contains(&b"not a keypair".to_vec()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_a_directory() {
+        let dir = temp_dir("directory");
+        let path = dir.join("id.json");
+        fs::create_dir(&path).unwrap();
+        let (address, keypair) = fixed_keypair(5);
+        let err = install(&keypair, &address, &path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert!(path.is_dir());
+        assert!(backups(&dir).is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_a_symlink() {
+        let dir = temp_dir("symlink");
+        let target = dir.join("elsewhere.json");
+        fs::write(&target, "keep").unwrap();
+        let path = dir.join("id.json");
+        std::os::unix::fs::symlink(&target, &path).unwrap();
+        let (address, keypair) = fixed_keypair(6);
+        assert!(install(&keypair, &address, &path).is_err());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "keep");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn mismatched_address_fails_verification() {
+        let dir = temp_dir("mismatch");
+        let path = dir.join("id.json");
+        let (_, keypair) = fixed_keypair(7);
+        let (other_address, _) = fixed_keypair(8);
+        let err = install(&keypair, &other_address, &path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn backup_path_never_reuses_a_name() {
+        let dir = temp_dir("backup-path");
+        let path = dir.join("id.json");
+        let first = backup_path(&path);
+        fs::write(&first, "").unwrap();
+        let second = backup_path(&path);
+        assert_ne!(first, second);
+        assert!(!second.exists());
+        assert!(second.file_name().unwrap().to_string_lossy().starts_with("id.json.bak-"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn find_keypair_checks_the_secret() {
+        let dir = temp_dir("find");
+        let (address, keypair) = fixed_keypair(9);
+        let (other, other_keypair) = fixed_keypair(10);
+        let secret = bs58::encode(&keypair).into_string();
+        let file = dir.join("matches.csv");
+        fs::write(&file, format!("address,private_key\n{},{}\n", address, secret)).unwrap();
+        assert_eq!(*find_keypair(&file, &address).unwrap(), keypair);
+        assert!(find_keypair(&file, &other).unwrap_err().contains("没有地址"));
+
+        let wrong = dir.join("wrong.csv");
+        fs::write(&wrong, format!("address,private_key\n{},{}\n", address, bs58::encode(&other_keypair).into_string())).unwrap();
+        assert!(find_keypair(&wrong, &address).unwrap_err().contains("私钥有误"));
+
+        let both = dir.join("both.csv");
+        fs::write(&both, format!("address,keypair64_bs58\n{},{}\n", address, secret)).unwrap();
+        assert_eq!(*find_keypair(&both, &address).unwrap(), keypair);
+
+        let shamir = dir.join("shamir.csv");
+        fs::write(&shamir, format!("address,shamir\n{},2-of-3\n", address)).unwrap();
+        assert!(find_keypair(&shamir, &address).unwrap_err().contains("recover"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,394 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signer::keypair::keypair_from_seed;
+use solana_sdk::signer::Signer;
+use zeroize::Zeroizing;
+
+use crate::ata;
+use crate::char_count;
+use crate::format::{self, unescape_formula, ATA_COLUMN_PREFIX, UTF8_BOM};
+
+/// 行哈希取 SHA-256 的前 8 个字节，编码为 16 个十六进制字符
+pub const ROW_HASH_BYTES: usize = 8;
+
+/// 计算一行记录的校验哈希
+///
+/// 算法：把行哈希之外的各列按顺序用逗号连接（与文件本身的分隔符无关），
+/// 对这段 UTF-8 文本做 SHA-256，取前 8 字节的小写十六进制。
+/// 默认列下就是 `地址,私钥`。
+pub fn row_hash(fields: &[&str]) -> String {
+    let digest = Sha256::digest(fields.join(",").as_bytes());
+    digest[..ROW_HASH_BYTES]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// 公钥 32 字节的小写十六进制，即 `pubkey_hex` 列的值
+pub fn pubkey_hex(pubkey: &Pubkey) -> String {
+    pubkey.to_bytes().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// 校验一个 64 字节 Base58 私钥是否确实对应给定地址
+pub fn check_keypair(address: &str, secret: &str) -> Result<(), String> {
+    let bytes = bs58::decode(secret)
+        .into_vec()
+        .map_err(|err| format!("私钥不是有效的 Base58: {}", err))?;
+    if bytes.len() != 64 {
+        return Err(format!("私钥应为 64 字节，实际为 {} 字节", bytes.len()));
+    }
+    let keypair = keypair_from_seed(&bytes[..32]).map_err(|err| err.to_string())?;
+    let pubkey = keypair.pubkey();
+    if pubkey.as_ref() != &bytes[32..] {
+        return Err("私钥后 32 字节与推导出的公钥不一致".to_string());
+    }
+    if pubkey.to_string() != address {
+        return Err(format!("私钥对应的地址是 {}", pubkey));
+    }
+    Ok(())
+}
+
+/// 校验一个 32 字节 Base58 种子是否确实对应给定地址
+pub fn check_seed(address: &str, seed: &str) -> Result<(), String> {
+    let bytes = Zeroizing::new(
+        bs58::decode(seed)
+            .into_vec()
+            .map_err(|err| format!("种子不是有效的 Base58: {}", err))?,
+    );
+    if bytes.len() != 32 {
+        return Err(format!("种子应为 32 字节，实际为 {} 字节", bytes.len()));
+    }
+    let pubkey = keypair_from_seed(&bytes).map_err(|err| err.to_string())?.pubkey();
+    if pubkey.to_string() != address {
+        return Err(format!("种子对应的地址是 {}", pubkey));
+    }
+    Ok(())
+}
+
+/// 按地址校验一个私钥列的函数
+type SecretCheck = fn(&str, &str) -> Result<(), String>;
+
+/// 校验中发现的一个问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyIssue {
+    /// 文件中的行号，从 1 开始
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for VerifyIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "第 {} 行: {}", self.line, self.message)
+    }
+}
+
+/// 一个输出文件的校验结果
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// 校验过的数据行数
+    pub rows: usize,
+    /// 其中带有行哈希的行数
+    pub hashed_rows: usize,
+    pub issues: Vec<VerifyIssue>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// 打开一个地址输出文件，根据标题行自动识别逗号或制表符分隔
+///
+/// 返回的记录从标题行开始，列数不一致的行不会报错，由调用方检查。以 BOM 开头的文件
+/// 按 `--excel-compat` 的写法读取：去掉 BOM，还原每个字段前为防公式加的 `'`。
+/// 以 `{` 开头的文件按 ndjson 读取，见 [`ndjson_table`]。
+pub fn open_table(path: impl AsRef<Path>) -> io::Result<Box<dyn Iterator<Item = csv::Result<csv::StringRecord>>>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    // 先读出标题行判断分隔符，再把它接回去交给 CSV 解析，带引号的字段会被正确还原
+    let mut header = String::new();
+    reader.read_line(&mut header)?;
+    if header.starts_with('{') {
+        return Ok(ndjson_table(header, reader));
+    }
+    let excel_compat = header.starts_with(UTF8_BOM);
+    if excel_compat {
+        header.drain(..UTF8_BOM.len());
+    }
+    let delimiter = if header.contains('\t') { b'\t' } else { b',' };
+    let records = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(io::Cursor::new(header.into_bytes()).chain(reader))
+        .into_records();
+    Ok(Box::new(records.map(move |record| {
+        let record = record?;
+        if !excel_compat {
+            return Ok(record);
+        }
+        let mut unescaped: csv::StringRecord = record.iter().map(unescape_formula).collect();
+        unescaped.set_position(record.position().cloned());
+        Ok(unescaped)
+    })))
+}
+
+/// 把 ndjson 文件读成与 CSV 相同的形式：第一条记录的键作为标题，之后每行按标题的顺序取值
+///
+/// 缺少某个键的行会少一列，由调用方按列数不一致报告；多出的键忽略。行号与文件中的行号一致，
+/// 空行跳过。
+fn ndjson_table(
+    first: String,
+    reader: impl BufRead + 'static,
+) -> Box<dyn Iterator<Item = csv::Result<csv::StringRecord>>> {
+    let parse = |line_number: usize, line: &str| {
+        format::parse_ndjson_line(line)
+            .map_err(|err| csv::Error::from(io::Error::new(io::ErrorKind::InvalidData, format!("第 {} 行: {}", line_number, err))))
+    };
+    let with_line = |mut record: csv::StringRecord, line_number: usize| {
+        let mut position = csv::Position::new();
+        position.set_line(line_number as u64);
+        record.set_position(Some(position));
+        record
+    };
+    let first_fields = match parse(1, &first) {
+        Ok(fields) => fields,
+        Err(err) => return Box::new(std::iter::once(Err(err))),
+    };
+    let keys: Vec<String> = first_fields.iter().map(|(key, _)| key.clone()).collect();
+    let header = with_line(keys.iter().collect(), 1);
+    let row = move |line_number: usize, fields: Vec<(String, String)>| {
+        let values: csv::StringRecord = keys
+            .iter()
+            .filter_map(|key| fields.iter().find(|(field, _)| field == key).map(|(_, value)| value))
+            .collect();
+        with_line(values, line_number)
+    };
+    let first_row = row(1, first_fields);
+    let rest = reader
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 2, line))
+        .filter(|(_, line)| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+        .map(move |(line_number, line)| {
+            let line = line.map_err(csv::Error::from)?;
+            Ok(row(line_number, parse(line_number, &line)?))
+        });
+    Box::new(std::iter::once(Ok(header)).chain(std::iter::once(Ok(first_row))).chain(rest))
+}
+
+/// 校验一个地址输出文件
+///
+/// 每行检查私钥（`private_key`、`keypair64_bs58` 和 `secret32_bs58` 列）是否对应地址；
+/// 如果标题中有 `row_hash` 列或关联代币账户列，同时检查它们。
+/// 没有私钥列的文件（如 Shamir 模式）只检查行哈希。根据标题自动识别逗号或制表符分隔。
+pub fn verify_file(path: impl AsRef<Path>) -> io::Result<VerifyReport> {
+    let mut report = VerifyReport::default();
+    let mut records = open_table(path)?;
+    let columns = match records.next() {
+        Some(record) => record.map_err(io::Error::from)?,
+        None => return Ok(report),
+    };
+    let column = |name: &str| columns.iter().position(|column| column == name);
+    let Some(address_col) = column("address") else {
+        report.issues.push(VerifyIssue {
+            line: 1,
+            message: "标题中缺少 address 列".to_string(),
+        });
+        return Ok(report);
+    };
+    let secret_cols: Vec<(usize, SecretCheck)> = [
+        ("private_key", check_keypair as SecretCheck),
+        ("keypair64_bs58", check_keypair),
+        ("secret32_bs58", check_seed),
+    ]
+    .into_iter()
+    .filter_map(|(name, check)| column(name).map(|index| (index, check)))
+    .collect();
+    let hash_col = column("row_hash");
+    let hex_col = column("pubkey_hex");
+    let mut ata_cols = Vec::new();
+    for (index, name) in columns.iter().enumerate() {
+        if let Some(mint) = name.strip_prefix(ATA_COLUMN_PREFIX) {
+            match ata::parse_mint(mint) {
+                Ok(mint) => ata_cols.push((index, mint)),
+                Err(message) => report.issues.push(VerifyIssue { line: 1, message }),
+            }
+        }
+    }
+
+    // 计数列 count_<字符> 记录字符的出现次数和位置
+    let count_cols: Vec<(usize, char)> = columns
+        .iter()
+        .enumerate()
+        .filter_map(|(index, name)| {
+            let mut chars = name.strip_prefix(char_count::COLUMN_PREFIX)?.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => Some((index, ch)),
+                _ => None,
+            }
+        })
+        .collect();
+
+    for record in records {
+        let record = record.map_err(io::Error::from)?;
+        let line_number = record.position().map_or(0, |position| position.line() as usize);
+        report.rows += 1;
+
+        let fields: Vec<&str> = record.iter().collect();
+        if fields.len() != columns.len() {
+            report.issues.push(VerifyIssue {
+                line: line_number,
+                message: format!("列数不对，期望 {} 列，实际为 {} 列", columns.len(), fields.len()),
+            });
+            continue;
+        }
+        let address = fields[address_col];
+
+        for (secret_col, check) in &secret_cols {
+            if let Err(message) = check(address, fields[*secret_col]) {
+                report.issues.push(VerifyIssue {
+                    line: line_number,
+                    message: format!("{}: {}", &columns[*secret_col], message),
+                });
+            }
+        }
+
+        if let Some(hex_col) = hex_col {
+            match address.parse::<Pubkey>() {
+                Ok(pubkey) if fields[hex_col] != pubkey_hex(&pubkey) => report.issues.push(VerifyIssue {
+                    line: line_number,
+                    message: format!("pubkey_hex 与地址不一致，应为 {}", pubkey_hex(&pubkey)),
+                }),
+                Ok(_) => {}
+                Err(_) => report.issues.push(VerifyIssue {
+                    line: line_number,
+                    message: format!("地址 {} 不是有效的公钥", address),
+                }),
+            }
+        }
+
+        if !ata_cols.is_empty()
+            && let Ok(wallet) = address.parse::<Pubkey>()
+        {
+            for (ata_col, mint) in &ata_cols {
+                let expected = ata::associated_token_address(&wallet, mint);
+                if fields[*ata_col] != expected.to_string() {
+                    report.issues.push(VerifyIssue {
+                        line: line_number,
+                        message: format!("代币 {} 的关联代币账户应为 {}", mint, expected),
+                    });
+                }
+            }
+        }
+
+        for (count_col, ch) in &count_cols {
+            let expected = char_count::describe(*ch, address);
+            if fields[*count_col] != expected {
+                report.issues.push(VerifyIssue {
+                    line: line_number,
+                    message: format!("字符 '{}' 的计数应为 {}", ch, expected),
+                });
+            }
+        }
+
+        if let Some(hash_col) = hash_col {
+            report.hashed_rows += 1;
+            let other: Vec<&str> = fields
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| *index != hash_col)
+                .map(|(_, field)| *field)
+                .collect();
+            if fields[hash_col] != row_hash(&other) {
+                report.issues.push(VerifyIssue {
+                    line: line_number,
+                    message: format!("行哈希不一致: {}", fields[hash_col]),
+                });
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// RFC 8032 第 7.1 节 TEST 1 的私钥种子和公钥
+    const RFC8032_SEED: [u8; 32] = [
+        0x9d, 0x61, 0xb1, 0x9d, 0xef, 0xfd, 0x5a, 0x60, 0xba, 0x84, 0x4a, 0xf4, 0x92, 0xec, 0x2c, 0xc4, 0x44, 0x49,
+        0xc5, 0x69, 0x7b, 0x32, 0x69, 0x19, 0x70, 0x3b, 0xac, 0x03, 0x1c, 0xae, 0x7f, 0x60,
+    ];
+    const RFC8032_PUBKEY_HEX: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+    /// 把 `content` 写入测试专用的临时文件
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sva-integrity-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len()).step_by(2).map(|start| u8::from_str_radix(&hex[start..start + 2], 16).unwrap()).collect()
+    }
+
+    /// 固定种子的地址和 64 字节私钥
+    fn fixed_keypair(seed: &[u8]) -> (String, String) {
+        let keypair = keypair_from_seed(seed).unwrap();
+        (keypair.pubkey().to_string(), keypair.to_base58_string())
+    }
+
+    #[test]
+    fn pubkey_hex_known_vector() {
+        let pubkey = keypair_from_seed(&RFC8032_SEED).unwrap().pubkey();
+        assert_eq!(pubkey_hex(&pubkey), RFC8032_PUBKEY_HEX);
+    }
+
+    #[test]
+    fn pubkey_hex_round_trips_to_the_address() {
+        for seed in 0u8..64 {
+            let pubkey = keypair_from_seed(&[seed; 32]).unwrap().pubkey();
+            let hex = pubkey_hex(&pubkey);
+            assert_eq!(hex.len(), 64);
+            assert!(hex.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f')), "{}", hex);
+            let bytes = from_hex(&hex);
+            assert_eq!(bytes, pubkey.to_bytes());
+            assert_eq!(bs58::encode(&bytes).into_string(), pubkey.to_string());
+        }
+        assert_eq!(pubkey_hex(&Pubkey::default()), "0".repeat(64));
+    }
+
+    #[test]
+    fn verify_checks_the_pubkey_hex_column() {
+        let (address, secret) = fixed_keypair(&RFC8032_SEED);
+        let good = temp_file(
+            "pubkey-hex-good.csv",
+            &format!("address,private_key,pubkey_hex\n{},{},{}\n", address, secret, RFC8032_PUBKEY_HEX),
+        );
+        let report = verify_file(&good).unwrap();
+        assert!(report.is_ok(), "{:?}", report.issues);
+        assert_eq!(report.rows, 1);
+
+        let mut wrong = RFC8032_PUBKEY_HEX.to_string();
+        wrong.replace_range(0..2, "00");
+        let bad = temp_file(
+            "pubkey-hex-bad.csv",
+            &format!("address,private_key,pubkey_hex\n{},{},{}\n", address, secret, wrong),
+        );
+        let report = verify_file(&bad).unwrap();
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].line, 2);
+        assert!(report.issues[0].message.contains(RFC8032_PUBKEY_HEX), "{}", report.issues[0]);
+    }
+}
@@ -0,0 +1,200 @@
+//! 多任务：一个进程内同时运行多个搜索任务
+//!
+//! 同时跑一个急用的简单前缀和一个后台的困难前缀时，开两个进程会盲目地争抢 CPU，
+//! 输出文件也容易互相覆盖。`--jobs` 从配置文件读取多个命名任务，每个任务有自己的前缀、
+//! 输出文件和停止条件，共用一个工作线程池：
+//!
+//! ```json
+//! {
+//!   "jobs": [
+//!     { "name": "urgent", "prefixes": ["Ab"], "threads": "75%", "max_matches": 1 },
+//!     { "name": "background", "prefixes": ["Solana"] }
+//!   ]
+//! }
+//! ```
+//!
+//! 工作线程每批开始前用平滑加权轮询（[`Picker`]）选出一个仍在运行的任务，各任务得到的批数
+//! 与份额成正比。某个任务结束后不再被选中，它的份额按比例分给其余任务。
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::search;
+
+/// 一个任务在线程池中的份额，如 `"25%"`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Share(f64);
+
+impl Share {
+    /// 百分比，0 到 100
+    pub fn percent(&self) -> f64 {
+        self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for Share {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        let percent = value
+            .trim()
+            .strip_suffix('%')
+            .and_then(|number| number.trim().parse::<f64>().ok())
+            .filter(|percent| percent.is_finite() && *percent > 0.0 && *percent <= 100.0)
+            .ok_or_else(|| serde::de::Error::custom(format!("无效的份额 \"{}\"（应为 1% 到 100% 之间，如 \"25%\"）", value)))?;
+        Ok(Share(percent))
+    }
+}
+
+/// 配置文件中的一个任务
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobConfig {
+    /// 任务名，用于状态输出、匹配标记和默认的输出文件名，只能含字母、数字、`-` 和 `_`
+    pub name: String,
+    /// 要查找的前缀，语法与 `-p` 相同
+    pub prefixes: Vec<String>,
+    /// 线程池中的份额；不写时平分其余任务没有占用的部分
+    #[serde(default)]
+    pub threads: Option<Share>,
+    /// 匹配地址的输出文件 [默认: data/<任务名>_matched.csv]
+    #[serde(default)]
+    pub matched_output: Option<String>,
+    /// 非匹配地址的输出文件，多任务模式不采样，不会创建 [默认: data/<任务名>_addresses.csv]
+    #[serde(default)]
+    pub output: Option<String>,
+    /// 找到这么多个匹配后结束这个任务
+    #[serde(default)]
+    pub max_matches: Option<u64>,
+    /// 运行这么多秒后结束这个任务
+    #[serde(default)]
+    pub max_secs: Option<u64>,
+}
+
+impl JobConfig {
+    pub fn matched_output(&self) -> String {
+        self.matched_output
+            .clone()
+            .unwrap_or_else(|| format!("data/{}_matched.csv", self.name))
+    }
+
+    pub fn output(&self) -> String {
+        self.output
+            .clone()
+            .unwrap_or_else(|| format!("data/{}_addresses.csv", self.name))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JobsFile {
+    jobs: Vec<JobConfig>,
+}
+
+/// 读取并检查任务配置
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<JobConfig>, String> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path).map_err(|err| format!("无法读取 {}: {}", path.display(), err))?;
+    let file: JobsFile =
+        serde_json::from_str(&content).map_err(|err| format!("{} 格式有误: {}", path.display(), err))?;
+    validate(&file.jobs)?;
+    Ok(file.jobs)
+}
+
+/// 检查任务名、前缀、停止条件、份额和输出文件
+pub fn validate(jobs: &[JobConfig]) -> Result<(), String> {
+    if jobs.is_empty() {
+        return Err("配置中没有任何任务".to_string());
+    }
+    let mut names = HashSet::new();
+    let mut outputs = HashSet::new();
+    for job in jobs {
+        let valid_name = !job.name.is_empty()
+            && job.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            return Err(format!("任务名 \"{}\" 无效：只能含字母、数字、- 和 _", job.name));
+        }
+        if !names.insert(job.name.as_str()) {
+            return Err(format!("任务名 \"{}\" 重复", job.name));
+        }
+        if job.prefixes.is_empty() {
+            return Err(format!("任务 {} 没有指定前缀", job.name));
+        }
+        for prefix in &job.prefixes {
+            search::validate_prefix(prefix).map_err(|err| format!("任务 {}: {}", job.name, err))?;
+        }
+        if job.max_matches == Some(0) || job.max_secs == Some(0) {
+            return Err(format!("任务 {} 的 max_matches 和 max_secs 必须大于 0", job.name));
+        }
+        for output in [job.matched_output(), job.output()] {
+            if !outputs.insert(output.clone()) {
+                return Err(format!("输出文件 {} 被多个任务使用", output));
+            }
+        }
+    }
+    let claimed: f64 = jobs.iter().filter_map(|job| job.threads).map(|share| share.percent()).sum();
+    if claimed > 100.0 + 1e-9 {
+        return Err(format!("各任务的份额合计 {}%，超过了 100%", claimed));
+    }
+    if claimed >= 100.0 - 1e-9 && jobs.iter().any(|job| job.threads.is_none()) {
+        return Err("份额已经分满 100%，没有写份额的任务分不到任何线程".to_string());
+    }
+    Ok(())
+}
+
+/// 各任务的份额，合计为 1
+///
+/// 写了份额的任务按所写的比例；其余任务平分剩下的部分。全部写了份额但合计不足 100% 时按比例放大。
+pub fn shares(jobs: &[JobConfig]) -> Vec<f64> {
+    let claimed: f64 = jobs.iter().filter_map(|job| job.threads).map(|share| share.percent()).sum();
+    let unclaimed = jobs.iter().filter(|job| job.threads.is_none()).count();
+    let rest = if unclaimed > 0 {
+        (100.0 - claimed).max(0.0) / unclaimed as f64
+    } else {
+        0.0
+    };
+    let percents: Vec<f64> = jobs
+        .iter()
+        .map(|job| job.threads.map_or(rest, |share| share.percent()))
+        .collect();
+    let total: f64 = percents.iter().sum();
+    percents.iter().map(|percent| percent / total).collect()
+}
+
+/// 平滑加权轮询，每个工作线程一个
+///
+/// 每次选择时，每个仍在运行的任务的积分加上它的份额，选积分最高的一个，再从它的积分中减去
+/// 运行中任务的份额之和。长期看各任务被选中的次数与份额成正比，而且分布均匀，不会连续偏向一个任务。
+#[derive(Debug, Clone)]
+pub struct Picker {
+    credits: Vec<f64>,
+}
+
+impl Picker {
+    pub fn new(jobs: usize) -> Self {
+        Self {
+            credits: vec![0.0; jobs],
+        }
+    }
+
+    /// 选出下一批交给哪个任务，`active(i)` 为假的任务跳过；全部结束时返回 `None`
+    pub fn next(&mut self, shares: &[f64], active: impl Fn(usize) -> bool) -> Option<usize> {
+        let mut total = 0.0;
+        let mut best: Option<usize> = None;
+        for (index, share) in shares.iter().enumerate() {
+            if !active(index) {
+                self.credits[index] = 0.0;
+                continue;
+            }
+            self.credits[index] += share;
+            total += share;
+            if best.is_none_or(|best| self.credits[index] > self.credits[best]) {
+                best = Some(index);
+            }
+        }
+        let best = best?;
+        self.credits[best] -= total;
+        Some(best)
+    }
+}
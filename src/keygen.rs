@@ -0,0 +1,373 @@
+//! 密钥对生成与随机数来源
+//!
+//! - `default`：solana-sdk 的 `Keypair::new()`，内部用 rand 0.7 的 `OsRng` 生成 ed25519-dalek 私钥
+//! - `os`：每个密钥直接从操作系统随机数（`OsRng`，即 getrandom）读取 32 字节种子，再由 ed25519-dalek 推导
+//! - `chacha`：每个工作线程一个 ChaCha20 生成器，用操作系统随机数播种，每输出 64 KiB 重新播种一次
+//!
+//! 另有仅供测试的固定种子模式（[`KeyGenerator::seeded`]）：ChaCha20 由给定的 64 位种子初始化，
+//! 整个运行只用一条密钥流，第 p 个密钥的种子是流中第 `32p` 到 `32p + 32` 字节。N 个线程时
+//! 第 i 个线程依次取位置 i、i+N、i+2N……，跳转用 ChaCha20 的计数器直接定位，不需要生成中间的字节。
+//! 每个位置上的密钥只由种子决定，与线程数和线程的快慢无关。生成的密钥可以被预测，不能用于真实资产。
+//!
+//! 三种方式产生的都是 32 字节种子 → Ed25519 密钥对，区别只在种子从哪里来。
+//!
+//! 额外熵（[`ExtraEntropy`]）只用于 `chacha`：用户提供的内容先做 SHA-256 得到摘要 `H`，
+//! 之后每次从操作系统读取播种材料时，都与 `SHA-256(H || 线程序号 || 计数器)` 生成的
+//! 密钥流逐字节异或。额外熵从不单独使用，即使操作系统随机数有问题，种子里仍然保留用户的熵；
+//! 不同线程、每次重新播种用到的密钥流互不相同。
+//!
+//! 启动时的自检（[`self_check`]）从操作系统随机数读取一批字节并做基本的合理性检查，
+//! 再用选定的来源生成几个测试密钥对，确认它们互不相同。自检不能证明随机数是好的，
+//! 只能发现彻底失效的情况（读取失败、全零、重复输出），这时拒绝运行，而不是生成弱密钥。
+
+use std::collections::HashSet;
+use std::fmt;
+
+use clap::ValueEnum;
+use rand::rngs::adapter::ReseedingRng;
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::{ChaCha20Core, ChaCha20Rng};
+use sha2::{Digest, Sha256};
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::keypair::keypair_from_seed;
+use solana_sdk::signer::Signer;
+use zeroize::Zeroizing;
+
+/// ChaCha20 生成器重新播种前输出的字节数
+pub const CHACHA_RESEED_BYTES: u64 = 64 * 1024;
+
+/// 随机数来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum RngSource {
+    /// solana-sdk 的 Keypair::new()
+    #[default]
+    Default,
+    /// 每个密钥直接读取操作系统随机数
+    Os,
+    /// 操作系统随机数播种、定期重新播种的 ChaCha20
+    Chacha,
+}
+
+impl fmt::Display for RngSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            RngSource::Default => "default（solana-sdk Keypair::new，rand 0.7 OsRng）",
+            RngSource::Os => "os（每个密钥直接读取 OsRng 种子）",
+            RngSource::Chacha => "chacha（ChaCha20，OsRng 播种，每 64 KiB 重新播种）",
+        };
+        f.write_str(description)
+    }
+}
+
+/// 自检读取的操作系统随机数块数，每块 32 字节，与一个种子一样大
+pub const SELF_CHECK_BLOCKS: usize = 32;
+
+/// 自检生成的测试密钥对数量
+pub const SELF_CHECK_KEYS: usize = 8;
+
+/// 随机数自检的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EntropyHealth {
+    /// 读取的字节数
+    pub bytes: usize,
+    /// 其中为 1 的比特所占的比例，正常时接近 0.5
+    pub ones_ratio: f64,
+    /// 生成并确认互不相同的测试密钥对数量
+    pub keys: usize,
+}
+
+impl fmt::Display for EntropyHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "从操作系统读取 {} 字节，1 比特占 {:.1}%，{} 个测试密钥对互不相同",
+            self.bytes,
+            self.ones_ratio * 100.0,
+            self.keys
+        )
+    }
+}
+
+/// 启动自检：读取操作系统随机数并检查，再用 `generator` 生成测试密钥对，任何一项异常时返回错误
+///
+/// 检查项：读取不报错；每块不是同一个字节重复；各块互不相同；1 比特的数量偏离一半不超过 6 个标准差
+/// （正常随机数误报的概率低于十亿分之一）。固定种子模式同样检查操作系统随机数，
+/// 因为 solana-sdk 的其他部分仍会用到它。
+pub fn self_check(generator: &mut KeyGenerator) -> Result<EntropyHealth, String> {
+    let mut blocks = vec![[0u8; 32]; SELF_CHECK_BLOCKS];
+    for block in &mut blocks {
+        OsRng
+            .try_fill_bytes(block)
+            .map_err(|err| format!("无法从操作系统读取随机数: {}", err))?;
+        if block.iter().all(|byte| *byte == block[0]) {
+            return Err(format!("操作系统随机数输出了 32 个相同的字节 0x{:02x}", block[0]));
+        }
+    }
+    if blocks.iter().collect::<HashSet<_>>().len() != blocks.len() {
+        return Err("操作系统随机数两次输出了相同的 32 字节".to_string());
+    }
+
+    let bits = (SELF_CHECK_BLOCKS * 32 * 8) as f64;
+    let ones: u32 = blocks.iter().flatten().map(|byte| byte.count_ones()).sum();
+    let deviation = (ones as f64 - bits / 2.0).abs() / (bits / 4.0).sqrt();
+    if deviation > 6.0 {
+        return Err(format!(
+            "操作系统随机数中 1 比特占 {:.1}%，偏离一半 {:.1} 个标准差",
+            ones as f64 / bits * 100.0,
+            deviation
+        ));
+    }
+
+    let pubkeys: HashSet<_> = (0..SELF_CHECK_KEYS).map(|_| generator.generate().pubkey()).collect();
+    if pubkeys.len() != SELF_CHECK_KEYS {
+        return Err(format!("{} 个测试密钥对中出现了相同的公钥", SELF_CHECK_KEYS));
+    }
+
+    Ok(EntropyHealth {
+        bytes: SELF_CHECK_BLOCKS * 32,
+        ones_ratio: ones as f64 / bits,
+        keys: SELF_CHECK_KEYS,
+    })
+}
+
+/// 用户提供的额外熵，只保存其 SHA-256 摘要
+#[derive(Clone)]
+pub struct ExtraEntropy {
+    digest: [u8; 32],
+}
+
+impl ExtraEntropy {
+    pub fn new(data: &[u8]) -> Self {
+        Self {
+            digest: Sha256::digest(data).into(),
+        }
+    }
+
+    /// 用于核对的指纹：`SHA-256(H)` 的前 8 字节，不会泄露额外熵本身
+    pub fn fingerprint(&self) -> String {
+        Sha256::digest(self.digest)[..8]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// 把第 `worker` 个线程从 `counter` 起的密钥流 `SHA-256(H || 线程序号 || 计数器)` 逐字节异或进 `dest`，
+    /// 返回下一个计数器
+    fn mix(&self, worker: u64, mut counter: u64, dest: &mut [u8]) -> u64 {
+        for chunk in dest.chunks_mut(32) {
+            let block = Sha256::new()
+                .chain_update(self.digest)
+                .chain_update(worker.to_le_bytes())
+                .chain_update(counter.to_le_bytes())
+                .finalize();
+            counter += 1;
+            for (byte, mask) in chunk.iter_mut().zip(block) {
+                *byte ^= mask;
+            }
+        }
+        counter
+    }
+}
+
+/// ChaCha20 的播种来源：操作系统随机数，可选地与额外熵的密钥流异或
+struct SeedSource {
+    extra: Option<ExtraEntropy>,
+    worker: u64,
+    counter: u64,
+}
+
+impl RngCore for SeedSource {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        OsRng.fill_bytes(dest);
+        if let Some(extra) = &self.extra {
+            self.counter = extra.mix(self.worker, self.counter, dest);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for SeedSource {}
+
+/// 每个工作线程持有一个密钥生成器
+pub struct KeyGenerator(Generator);
+
+enum Generator {
+    Default,
+    Os,
+    Chacha(Box<ReseedingRng<ChaCha20Core, SeedSource>>),
+    Seeded(Box<SeededStream>),
+}
+
+/// 固定种子模式下一个线程在密钥流中的位置
+struct SeededStream {
+    rng: ChaCha20Rng,
+    /// 下一个密钥的位置
+    next: u64,
+    /// 相邻两个密钥的位置差，即线程数
+    stride: u64,
+}
+
+impl KeyGenerator {
+    pub fn new(source: RngSource) -> Self {
+        Self::with_extra_entropy(source, None, 0)
+    }
+
+    /// 为第 `worker` 个工作线程创建生成器，`chacha` 的每次播种都混入额外熵
+    pub fn with_extra_entropy(source: RngSource, extra: Option<&ExtraEntropy>, worker: u64) -> Self {
+        KeyGenerator(match source {
+            RngSource::Default => Generator::Default,
+            RngSource::Os => Generator::Os,
+            RngSource::Chacha => {
+                let mut seed_source = SeedSource {
+                    extra: extra.cloned(),
+                    worker,
+                    counter: 0,
+                };
+                let core = ChaCha20Core::from_rng(&mut seed_source).expect("无法从操作系统获取随机数");
+                Generator::Chacha(Box::new(ReseedingRng::new(core, CHACHA_RESEED_BYTES, seed_source)))
+            }
+        })
+    }
+
+    /// 仅供测试：由固定种子确定的生成器，`threads` 个线程中的第 `worker` 个从位置 `worker` 开始，
+    /// 每次前进 `threads` 个位置
+    pub fn seeded(seed: u64, worker: u64, threads: u64) -> Self {
+        KeyGenerator(Generator::Seeded(Box::new(SeededStream {
+            rng: ChaCha20Rng::seed_from_u64(seed),
+            next: worker,
+            stride: threads.max(1),
+        })))
+    }
+
+    /// 固定种子模式下最近生成的密钥在密钥流中的位置，其他来源为 `None`
+    pub fn position(&self) -> Option<u64> {
+        match &self.0 {
+            Generator::Seeded(stream) => Some(stream.next.wrapping_sub(stream.stride)),
+            _ => None,
+        }
+    }
+
+    /// 生成一个新的密钥对
+    pub fn generate(&mut self) -> Keypair {
+        match &mut self.0 {
+            Generator::Default => Keypair::new(),
+            Generator::Os => from_rng(&mut OsRng),
+            Generator::Chacha(rng) => from_rng(rng.as_mut()),
+            Generator::Seeded(stream) => {
+                // 一个密钥用 32 字节，即 8 个 32 位字
+                stream.rng.set_word_pos(u128::from(stream.next) * 8);
+                stream.next = stream.next.wrapping_add(stream.stride);
+                from_rng(&mut stream.rng)
+            }
+        }
+    }
+}
+
+fn from_rng(rng: &mut impl RngCore) -> Keypair {
+    let mut seed = Zeroizing::new([0u8; 32]);
+    rng.fill_bytes(seed.as_mut());
+    keypair_from_seed(seed.as_ref()).expect("32 字节种子总是有效的")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    const KEYS: usize = 200;
+
+    /// 模拟一个线程的播种：`os_seed` 代替操作系统随机数给出 32 字节播种材料，
+    /// 与额外熵的密钥流异或后初始化 ChaCha20，再生成 `KEYS` 个公钥
+    fn stream(os_seed: u64, extra: Option<&[u8]>, worker: u64) -> Vec<Pubkey> {
+        let mut material = [0u8; 32];
+        ChaCha20Rng::seed_from_u64(os_seed).fill_bytes(&mut material);
+        if let Some(extra) = extra {
+            ExtraEntropy::new(extra).mix(worker, 0, &mut material);
+        }
+        let mut rng = ChaCha20Rng::from_seed(material);
+        (0..KEYS).map(|_| from_rng(&mut rng).pubkey()).collect()
+    }
+
+    fn disjoint(a: &[Pubkey], b: &[Pubkey]) -> bool {
+        let a: HashSet<_> = a.iter().collect();
+        b.iter().all(|key| !a.contains(key))
+    }
+
+    #[test]
+    fn identical_os_and_extra_entropy_reproduce_the_stream() {
+        let extra = b"4 6 2 1 5 3 3 6 correct horse battery staple";
+        assert_eq!(stream(1, Some(extra), 0), stream(1, Some(extra), 0));
+    }
+
+    #[test]
+    fn different_extra_entropy_gives_disjoint_streams() {
+        let plain = stream(1, None, 0);
+        let dice = stream(1, Some(b"1 2 3 4 5 6"), 0);
+        let other = stream(1, Some(b"1 2 3 4 5 5"), 0);
+        assert!(disjoint(&plain, &dice));
+        assert!(disjoint(&plain, &other));
+        assert!(disjoint(&dice, &other));
+    }
+
+    #[test]
+    fn extra_entropy_is_never_used_alone() {
+        // 额外熵相同、操作系统随机数不同时，密钥流仍然不同
+        let extra = b"same passphrase";
+        assert!(disjoint(&stream(1, Some(extra), 0), &stream(2, Some(extra), 0)));
+    }
+
+    #[test]
+    fn workers_get_different_keystreams() {
+        let extra = b"same passphrase";
+        assert!(disjoint(&stream(1, Some(extra), 0), &stream(1, Some(extra), 1)));
+    }
+
+    #[test]
+    fn mixing_advances_the_counter_per_block() {
+        let extra = ExtraEntropy::new(b"dice");
+        let mut once = [0u8; 96];
+        assert_eq!(extra.mix(0, 0, &mut once), 3);
+        // 分两次异或与一次异或相同
+        let mut twice = [0u8; 96];
+        let next = extra.mix(0, 0, &mut twice[..64]);
+        extra.mix(0, next, &mut twice[64..]);
+        assert_eq!(once, twice);
+        // 异或两次还原
+        extra.mix(0, 0, &mut once);
+        assert_eq!(once, [0u8; 96]);
+    }
+
+    #[test]
+    fn fingerprint_identifies_but_does_not_reveal_the_entropy() {
+        let extra = ExtraEntropy::new(b"dice");
+        assert_eq!(extra.fingerprint(), ExtraEntropy::new(b"dice").fingerprint());
+        assert_ne!(extra.fingerprint(), ExtraEntropy::new(b"dicE").fingerprint());
+        assert_eq!(extra.fingerprint().len(), 16);
+        let digest: String = extra.digest[..8].iter().map(|byte| format!("{:02x}", byte)).collect();
+        assert_ne!(extra.fingerprint(), digest);
+    }
+
+    #[test]
+    fn chacha_generators_with_extra_entropy_produce_distinct_keys() {
+        let extra = ExtraEntropy::new(b"dice");
+        let mut first = KeyGenerator::with_extra_entropy(RngSource::Chacha, Some(&extra), 0);
+        let mut second = KeyGenerator::with_extra_entropy(RngSource::Chacha, Some(&extra), 1);
+        let keys: HashSet<Pubkey> = (0..KEYS)
+            .flat_map(|_| [first.generate().pubkey(), second.generate().pubkey()])
+            .collect();
+        assert_eq!(keys.len(), 2 * KEYS);
+    }
+}
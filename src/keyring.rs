@@ -0,0 +1,140 @@
+//! 把匹配私钥存入操作系统的密钥存储（`--output-append-secret-env`，需要 `keyring` 特性）
+//!
+//! 匹配文件的 `secret_ref` 列只写一个随机的引用（UUID v4），私钥本身以这个引用为账户名
+//! 存进系统的密钥存储，文件可以放心分享或提交，需要时用 `keyring-get` 子命令按引用取回。
+//!
+//! 不链接各平台的库，而是调用系统自带的命令行工具：
+//!
+//! - macOS：`security`（登录钥匙串）。写入时通过 `security -i` 从标准输入传命令，私钥不出现在进程参数中
+//! - Linux 和其他 Unix：libsecret 的 `secret-tool`（GNOME Keyring、KWallet 等 Secret Service 实现），
+//!   私钥从标准输入传入
+//! - Windows：暂不支持
+//!
+//! 引用、地址和私钥都只含字母、数字和 `-`，拼进命令时不需要转义。
+
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use rand::rngs::OsRng;
+use rand::RngCore;
+use zeroize::Zeroizing;
+
+/// 存入密钥存储时使用的服务名
+pub const SERVICE: &str = "solana-vanity-address";
+
+/// 生成一个新的引用：随机的 UUID v4
+pub fn new_reference() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// 检查引用的格式：8-4-4-4-12 个小写十六进制数字
+pub fn parse_reference(value: &str) -> Result<String, String> {
+    let groups: Vec<&str> = value.trim().split('-').collect();
+    let valid = groups.iter().map(|group| group.len()).eq([8, 4, 4, 4, 12])
+        && groups
+            .iter()
+            .all(|group| group.bytes().all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte)));
+    if !valid {
+        return Err(format!("引用应为 secret_ref 列中的 UUID，而不是 {}", value));
+    }
+    Ok(value.trim().to_string())
+}
+
+/// 当前平台使用的命令行工具
+pub fn tool() -> Result<&'static str, String> {
+    if cfg!(target_os = "macos") {
+        Ok("security")
+    } else if cfg!(unix) {
+        Ok("secret-tool")
+    } else {
+        Err("当前平台没有支持的系统密钥存储，--output-append-secret-env 只支持 macOS 和 Linux".to_string())
+    }
+}
+
+/// 把地址 `address` 的私钥以 `reference` 存入密钥存储，同一个引用已存在时覆盖
+pub fn store(reference: &str, address: &str, secret: &str) -> Result<(), String> {
+    let label = format!("{} {}", SERVICE, address);
+    if cfg!(target_os = "macos") {
+        let command = Zeroizing::new(format!(
+            "add-generic-password -U -s {} -a {} -l \"{}\" -w {}\n",
+            SERVICE, reference, label, secret
+        ));
+        run(tool()?, &["-i"], Some(command.as_bytes()))?;
+    } else {
+        let label = format!("--label={}", label);
+        let args = [label.as_str(), "service", SERVICE, "reference", reference, "address", address];
+        run(tool()?, &args, Some(secret.as_bytes()))?;
+    }
+    Ok(())
+}
+
+/// 按引用取回私钥
+pub fn load(reference: &str) -> Result<Zeroizing<String>, String> {
+    let secret = if cfg!(target_os = "macos") {
+        run(tool()?, &["find-generic-password", "-s", SERVICE, "-a", reference, "-w"], None)?
+    } else {
+        run(tool()?, &["lookup", "service", SERVICE, "reference", reference], None)?
+    };
+    if secret.trim().is_empty() {
+        return Err(format!("密钥存储中没有引用 {}", reference));
+    }
+    Ok(Zeroizing::new(secret.trim().to_string()))
+}
+
+/// 按引用删除
+pub fn remove(reference: &str) -> Result<(), String> {
+    if cfg!(target_os = "macos") {
+        run(tool()?, &["delete-generic-password", "-s", SERVICE, "-a", reference], None)?;
+    } else {
+        run(tool()?, &["clear", "service", SERVICE, "reference", reference], None)?;
+    }
+    Ok(())
+}
+
+/// 启动时检查：存入、取回再删除一个测试条目，任何一步失败都返回原因，
+/// 避免找到匹配之后才发现密钥存储不可用（工具没装、Secret Service 没有运行、钥匙串锁定等）
+pub fn self_check() -> Result<(), String> {
+    let reference = new_reference();
+    let probe = new_reference().replace('-', "");
+    store(&reference, "probe", &probe)?;
+    let loaded = load(&reference);
+    remove(&reference)?;
+    if loaded?.as_str() != probe {
+        return Err("取回的内容与存入的不一致".to_string());
+    }
+    Ok(())
+}
+
+/// 运行命令，`input` 从标准输入传入，返回标准输出；启动失败或退出码非零时返回错误
+fn run(tool: &str, args: &[&str], input: Option<&[u8]>) -> Result<Zeroizing<String>, String> {
+    let mut child = Command::new(tool)
+        .args(args)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => format!("找不到 {}，请先安装（Linux 上通常在 libsecret-tools 或 libsecret 包中）", tool),
+            _ => format!("无法运行 {}: {}", tool, err),
+        })?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin
+            .write_all(input)
+            .map_err(|err| format!("无法向 {} 写入: {}", tool, err))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|err| format!("等待 {} 结束失败: {}", tool, err))?;
+    let bytes = Zeroizing::new(output.stdout);
+    let stdout = Zeroizing::new(String::from_utf8_lossy(&bytes).into_owned());
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} 执行失败（{}）: {}", tool, output.status, stderr.trim()));
+    }
+    Ok(stdout)
+}
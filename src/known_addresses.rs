@@ -0,0 +1,431 @@
+//! 已知地址快照的布隆过滤器（`--known-addresses`、`build-filter` 子命令）
+//!
+//! 与 [`crate::watch_list`] 的用途相同，但面向几千万个地址的快照（例如所有有余额的主网地址）：
+//! 这样的名单放进哈希表要占几 GB 内存，布隆过滤器每个地址只需约 2.4 字节（误判率 1e-4 时）。
+//! 只在匹配时检查一次，过滤器说“可能在”的再逐行扫描原始名单文件复核，所以误判只会多花
+//! 一次扫描的时间，不会误报。
+//!
+//! 过滤器可以启动时从地址列表现场构建，也可以用 `build-filter` 预先构建成文件，之后直接读取。
+//! 文件格式（整数均为小端序）：
+//!
+//! | 字段 | 长度 |
+//! |------|------|
+//! | 魔数 `SVKNOWN1` | 8 |
+//! | 哈希函数个数 | 4 |
+//! | 地址数 | 8 |
+//! | 位数组的 64 位字数 | 8 |
+//! | 原始名单路径的字节数 | 4 |
+//! | 原始名单路径（UTF-8，用于复核） | 可变 |
+//! | 位数组 | 字数 × 8 |
+
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::blacklist::for_each_pubkey;
+
+/// 过滤器文件开头的魔数
+pub const MAGIC: &[u8; 8] = b"SVKNOWN1";
+
+/// 默认误判率
+pub const DEFAULT_FALSE_POSITIVE_RATE: f64 = 1e-4;
+
+/// 错误信息中名单的叫法
+const KIND: &str = "已知地址";
+
+/// 已知地址的布隆过滤器
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressFilter {
+    bits: Vec<u64>,
+    hashes: u32,
+    count: u64,
+    /// 原始名单文件的路径，用于复核
+    source: String,
+}
+
+impl AddressFilter {
+    /// 为 `capacity` 个地址创建空过滤器，按误判率选择位数和哈希函数个数
+    pub fn with_capacity(capacity: u64, false_positive_rate: f64, source: &str) -> Self {
+        let capacity = capacity.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let bit_count = (-capacity * false_positive_rate.ln() / (ln2 * ln2)).ceil().max(64.0);
+        let words = (bit_count / 64.0).ceil() as usize;
+        let hashes = ((words * 64) as f64 / capacity * ln2).round().clamp(1.0, 32.0) as u32;
+        Self {
+            bits: vec![0; words],
+            hashes,
+            count: 0,
+            source: source.to_string(),
+        }
+    }
+
+    /// 从每行一个 Base58 地址的文件构建，格式与黑名单相同
+    ///
+    /// 地址数按文件大小估计（每行约 45 字节），实际地址较少时误判率只会更低。
+    pub fn build(path: impl AsRef<Path>, false_positive_rate: f64) -> Result<Self, String> {
+        let path = path.as_ref();
+        let estimated = fs::metadata(path).map(|metadata| metadata.len() / 45).unwrap_or(0);
+        let source = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let mut filter = Self::with_capacity(estimated, false_positive_rate, &source.to_string_lossy());
+        for_each_pubkey(path, KIND, |pubkey| {
+            filter.insert(&pubkey);
+            ControlFlow::Continue(())
+        })?;
+        Ok(filter)
+    }
+
+    /// 加入一个公钥
+    pub fn insert(&mut self, pubkey: &[u8; 32]) {
+        for (word, mask) in self.positions(pubkey) {
+            self.bits[word] |= mask;
+        }
+        self.count += 1;
+    }
+
+    /// 公钥可能在名单中；返回 `false` 时一定不在
+    pub fn contains(&self, pubkey: &[u8; 32]) -> bool {
+        self.positions(pubkey).all(|(word, mask)| self.bits[word] & mask != 0)
+    }
+
+    /// 加入的地址数
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// 位数组的字节数
+    pub fn size_bytes(&self) -> usize {
+        self.bits.len() * 8
+    }
+
+    /// 原始名单文件的路径
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// 按当前地址数计算的误判率
+    pub fn false_positive_rate(&self) -> f64 {
+        let fill = -(self.hashes as f64) * self.count as f64 / (self.bits.len() * 64) as f64;
+        (-fill.exp_m1()).powi(self.hashes as i32)
+    }
+
+    /// 双重哈希得到的各个位置
+    ///
+    /// 已知地址中有系统程序（全零）和各种 sysvar 这样结构明显的公钥，不能像
+    /// [`crate::dup_check`] 那样直接取公钥字节，先做一次 SHA-256。
+    fn positions(&self, pubkey: &[u8; 32]) -> impl Iterator<Item = (usize, u64)> + use<> {
+        let digest = Sha256::digest(pubkey);
+        let h1 = u64::from_le_bytes(digest[..8].try_into().unwrap());
+        let h2 = u64::from_le_bytes(digest[8..16].try_into().unwrap()) | 1;
+        let bit_count = self.bits.len() as u64 * 64;
+        (0..self.hashes as u64).map(move |i| {
+            let bit = h1.wrapping_add(h2.wrapping_mul(i)) % bit_count;
+            ((bit / 64) as usize, 1u64 << (bit % 64))
+        })
+    }
+
+    /// 序列化为文件格式
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(32 + self.source.len() + self.size_bytes());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&self.hashes.to_le_bytes());
+        bytes.extend_from_slice(&self.count.to_le_bytes());
+        bytes.extend_from_slice(&(self.bits.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.source.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(self.source.as_bytes());
+        for word in &self.bits {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// 从 [`AddressFilter::to_bytes`] 的格式解析
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut rest = bytes.strip_prefix(MAGIC.as_slice()).ok_or("不是已知地址过滤器文件（魔数不符）")?;
+        let mut take = |len: usize| -> Result<&[u8], String> {
+            if rest.len() < len {
+                return Err("过滤器文件不完整".to_string());
+            }
+            let (head, tail) = rest.split_at(len);
+            rest = tail;
+            Ok(head)
+        };
+        let hashes = u32::from_le_bytes(take(4)?.try_into().unwrap());
+        let count = u64::from_le_bytes(take(8)?.try_into().unwrap());
+        let words = u64::from_le_bytes(take(8)?.try_into().unwrap()) as usize;
+        let source_len = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+        let source = String::from_utf8(take(source_len)?.to_vec()).map_err(|_| "过滤器文件中的名单路径不是 UTF-8")?;
+        let bits: Vec<u64> = take(words.checked_mul(8).ok_or("过滤器文件不完整")?)?
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        if !rest.is_empty() {
+            return Err("过滤器文件末尾有多余的数据".to_string());
+        }
+        if hashes == 0 || bits.is_empty() {
+            return Err("过滤器文件的参数无效".to_string());
+        }
+        Ok(Self { bits, hashes, count, source })
+    }
+
+    /// 写入过滤器文件
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+        writer.write_all(&self.to_bytes())?;
+        writer.into_inner().map_err(|err| err.into_error())?.sync_all()
+    }
+
+    /// 读取过滤器文件
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|err| format!("无法读取过滤器文件 {}: {}", path.display(), err))?;
+        Self::from_bytes(&bytes).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+}
+
+/// 文件是否以过滤器的魔数开头
+pub fn is_filter_file(path: impl AsRef<Path>) -> bool {
+    let mut magic = [0u8; 8];
+    fs::File::open(path).and_then(|mut file| io::Read::read_exact(&mut file, &mut magic)).is_ok() && &magic == MAGIC
+}
+
+/// 一次检查的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KnownResult {
+    /// 一定不在名单中
+    Absent,
+    /// 过滤器命中，但原始名单中没有：误判
+    FalsePositive,
+    /// 原始名单中确实有这个地址
+    Confirmed,
+    /// 过滤器命中，但无法读取原始名单复核
+    Unverified(String),
+}
+
+impl KnownResult {
+    /// 写入输出文件 `known` 列的标记，误判和不在名单中时为 `None`
+    pub fn mark(&self) -> Option<KnownMark> {
+        match self {
+            KnownResult::Absent | KnownResult::FalsePositive => None,
+            KnownResult::Confirmed => Some(KnownMark::Confirmed),
+            KnownResult::Unverified(_) => Some(KnownMark::Unverified),
+        }
+    }
+}
+
+/// 输出文件中对已知地址命中的标记
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KnownMark {
+    /// 已在原始名单中确认
+    Confirmed,
+    /// 过滤器命中，无法复核
+    Unverified,
+}
+
+impl KnownMark {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KnownMark::Confirmed => "confirmed",
+            KnownMark::Unverified => "unverified",
+        }
+    }
+}
+
+/// `--known-addresses`：过滤器加上用于复核的原始名单
+#[derive(Debug)]
+pub struct KnownAddresses {
+    filter: AddressFilter,
+}
+
+impl KnownAddresses {
+    /// 加载过滤器文件；不是过滤器文件时当作地址列表现场构建
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let filter = if is_filter_file(path) {
+            AddressFilter::load(path)?
+        } else {
+            AddressFilter::build(path, DEFAULT_FALSE_POSITIVE_RATE)?
+        };
+        Ok(Self { filter })
+    }
+
+    pub fn filter(&self) -> &AddressFilter {
+        &self.filter
+    }
+
+    /// 用于复核的原始名单，不存在时为 `None`
+    pub fn source(&self) -> Option<PathBuf> {
+        Some(PathBuf::from(self.filter.source())).filter(|path| path.is_file())
+    }
+
+    /// 检查一个公钥；过滤器命中时逐行扫描原始名单复核
+    pub fn check(&self, pubkey: &[u8; 32]) -> KnownResult {
+        if !self.filter.contains(pubkey) {
+            return KnownResult::Absent;
+        }
+        let Some(source) = self.source() else {
+            return KnownResult::Unverified(format!("原始名单 {} 不存在", self.filter.source()));
+        };
+        let mut found = false;
+        let scanned = for_each_pubkey(&source, KIND, |candidate| {
+            if candidate == *pubkey {
+                found = true;
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        match scanned {
+            Err(err) => KnownResult::Unverified(err),
+            Ok(()) if found => KnownResult::Confirmed,
+            Ok(()) => KnownResult::FalsePositive,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    use super::*;
+
+    /// 测试专用的空临时目录
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sva-known-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn random_pubkeys(seed: u64, count: usize) -> Vec<[u8; 32]> {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        (0..count).map(|_| rng.r#gen()).collect()
+    }
+
+    fn filled(capacity: usize, rate: f64, seed: u64) -> (AddressFilter, Vec<[u8; 32]>) {
+        let pubkeys = random_pubkeys(seed, capacity);
+        let mut filter = AddressFilter::with_capacity(capacity as u64, rate, "/nonexistent/known.txt");
+        for pubkey in &pubkeys {
+            filter.insert(pubkey);
+        }
+        (filter, pubkeys)
+    }
+
+    /// 每行一个地址的名单文件
+    fn write_list(path: &Path, pubkeys: &[[u8; 32]]) {
+        let lines: Vec<String> = pubkeys.iter().map(|pubkey| bs58::encode(pubkey).into_string()).collect();
+        fs::write(path, format!("# 已知地址\n{}\n", lines.join("\n"))).unwrap();
+    }
+
+    #[test]
+    fn no_false_negatives() {
+        let (filter, pubkeys) = filled(20_000, DEFAULT_FALSE_POSITIVE_RATE, 1);
+        assert_eq!(filter.len(), 20_000);
+        assert!(pubkeys.iter().all(|pubkey| filter.contains(pubkey)));
+    }
+
+    #[test]
+    fn false_positive_rate_is_close_to_the_target() {
+        // 每组 20 万次查询，误判次数的期望为 200 和 20，允许两倍的偏差
+        for (rate, seed) in [(1e-3, 2), (1e-4, 3)] {
+            let (filter, _) = filled(20_000, rate, seed);
+            let estimate = filter.false_positive_rate();
+            assert!(estimate < rate * 1.2, "估计误判率 {} 超过目标 {}", estimate, rate);
+            let queries = random_pubkeys(seed + 100, 200_000);
+            let hits = queries.iter().filter(|pubkey| filter.contains(pubkey)).count();
+            let measured = hits as f64 / queries.len() as f64;
+            assert!(measured < rate * 2.0, "实测误判率 {} 超过目标 {} 的两倍", measured, rate);
+        }
+    }
+
+    #[test]
+    fn empty_filter_contains_nothing() {
+        let filter = AddressFilter::with_capacity(0, DEFAULT_FALSE_POSITIVE_RATE, "");
+        assert!(filter.is_empty());
+        assert_eq!(filter.false_positive_rate(), 0.0);
+        assert!(random_pubkeys(4, 1_000).iter().all(|pubkey| !filter.contains(pubkey)));
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let (filter, pubkeys) = filled(5_000, 1e-3, 5);
+        let bytes = filter.to_bytes();
+        assert!(bytes.starts_with(MAGIC));
+        let parsed = AddressFilter::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, filter);
+        assert_eq!(parsed.source(), "/nonexistent/known.txt");
+        assert!(pubkeys.iter().all(|pubkey| parsed.contains(pubkey)));
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let dir = temp_dir("file");
+        let (filter, _) = filled(1_000, 1e-3, 6);
+        let path = dir.join("known.bin");
+        filter.save(&path).unwrap();
+        assert!(is_filter_file(&path));
+        assert_eq!(AddressFilter::load(&path).unwrap(), filter);
+
+        let list = dir.join("known.txt");
+        write_list(&list, &random_pubkeys(7, 3));
+        assert!(!is_filter_file(&list));
+        assert!(!is_filter_file(dir.join("missing.bin")));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_damaged_bytes() {
+        let (filter, _) = filled(100, 1e-3, 8);
+        let bytes = filter.to_bytes();
+        for len in 0..bytes.len() {
+            assert!(AddressFilter::from_bytes(&bytes[..len]).is_err(), "截断到 {} 字节", len);
+        }
+        let mut extra = bytes.clone();
+        extra.push(0);
+        assert!(AddressFilter::from_bytes(&extra).unwrap_err().contains("多余"));
+
+        let mut magic = bytes.clone();
+        magic[7] = b'2';
+        assert!(AddressFilter::from_bytes(&magic).unwrap_err().contains("魔数"));
+
+        let mut no_hashes = bytes.clone();
+        no_hashes[8..12].copy_from_slice(&0u32.to_le_bytes());
+        assert!(AddressFilter::from_bytes(&no_hashes).unwrap_err().contains("参数无效"));
+    }
+
+    #[test]
+    fn check_confirms_and_catches_false_positives() {
+        let dir = temp_dir("check");
+        let listed = random_pubkeys(9, 50);
+        let list = dir.join("known.txt");
+        write_list(&list, &listed);
+
+        let known = KnownAddresses::load(&list).unwrap();
+        assert_eq!(known.filter().len(), 50);
+        assert!(listed.iter().all(|pubkey| known.check(pubkey) == KnownResult::Confirmed));
+        assert!(random_pubkeys(10, 100).iter().all(|pubkey| known.check(pubkey) != KnownResult::Confirmed));
+
+        // 位数组全满的过滤器对任何公钥都说“可能在”，复核后是误判
+        let mut saturated = AddressFilter::with_capacity(1, 0.5, &list.to_string_lossy());
+        for pubkey in random_pubkeys(11, 1_000) {
+            saturated.insert(&pubkey);
+        }
+        let path = dir.join("saturated.bin");
+        saturated.save(&path).unwrap();
+        let known = KnownAddresses::load(&path).unwrap();
+        assert_eq!(known.check(&[0x42; 32]), KnownResult::FalsePositive);
+        assert_eq!(known.check(&listed[0]), KnownResult::Confirmed);
+
+        // 原始名单不在了，只能报告无法复核
+        fs::remove_file(&list).unwrap();
+        assert!(matches!(known.check(&[0x42; 32]), KnownResult::Unverified(_)));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
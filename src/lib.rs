@@ -0,0 +1,72 @@
+//! Solana 靓号地址生成器的核心库
+//!
+//! 命令行程序只是这个库的一层外壳，嵌入方可以直接使用 [`Search`]
+//! 做前缀匹配和计数，并通过 [`Search::stats`] 获取统计快照来渲染自己的界面。
+
+pub mod and_condition;
+pub mod ata;
+pub mod attempts_log;
+pub mod audit;
+pub mod base58;
+pub mod batch;
+pub mod blacklist;
+pub mod byte_pattern;
+pub mod char_class;
+pub mod char_count;
+pub mod char_stats;
+pub mod compare;
+pub mod difficulty;
+pub mod disk_space;
+pub mod dup_check;
+pub mod filter;
+pub mod format;
+pub mod fuzzy;
+pub mod highlight;
+pub mod inspect;
+pub mod install;
+pub mod integrity;
+pub mod jobs;
+pub mod keygen;
+#[cfg(feature = "keyring")]
+pub mod keyring;
+pub mod known_addresses;
+pub mod lockstep;
+pub mod luck;
+pub mod manifest;
+pub mod match_limit;
+pub mod matcher;
+pub mod offline;
+pub mod prefix_quota;
+pub mod profile;
+pub mod rate_log;
+pub mod report;
+pub mod role;
+pub mod schedule;
+pub mod score;
+pub mod search;
+pub mod secret;
+pub mod session;
+pub mod shamir;
+pub mod shape;
+#[cfg(feature = "shm")]
+pub mod shm;
+pub mod simulate;
+pub mod split_key;
+pub mod squads;
+pub mod writer;
+pub mod stats;
+pub mod stop_file;
+pub mod symmetry;
+pub mod tokens;
+pub mod trie;
+pub mod tune;
+pub mod upload;
+pub mod watch_list;
+pub mod watchdog;
+pub mod webhook;
+
+pub use batch::BatchController;
+pub use filter::ByteFilter;
+pub use rate_log::RateLog;
+pub use search::Search;
+pub use stats::Stats;
@@ -0,0 +1,203 @@
+//! 固定种子时的同步轮次（`--insecure-seed`）
+//!
+//! 固定种子让每个位置上的密钥确定下来（见 [`crate::keygen`]），但各线程各跑各的，
+//! 停止时每个线程走到了哪里、匹配以什么顺序交给写入线程，都取决于调度。
+//! 固定种子时工作线程改为按轮推进：每轮每个线程生成同样多的密钥，交出的匹配先放进 [`Lockstep`]；
+//! 所有线程都到达轮末后，由其中一个（领头线程）把这一轮的匹配按密钥位置排序后统一交出，
+//! 并替所有线程决定是否停止。停止只发生在轮与轮之间，所以同样的种子、线程数和批大小
+//! 总是检查同样的密钥、以同样的顺序写出同样的匹配。
+//!
+//! 每轮覆盖一段连续的密钥位置，领头线程按位置顺序为匹配占 `--max-matches` 的名额，
+//! 最后一轮中超出上限的匹配总是位置靠后的那些，所以写出的恰好是密钥流中最前面的 N 个匹配，
+//! 与线程数和批大小都无关。
+//!
+//! 代价是每轮等待最慢的线程，只在固定种子时使用。
+//!
+//! 某个线程 panic 后再也不会到达轮末，它所在的线程调用 [`Lockstep::abort`]：
+//! 正在或将要在轮末等待的线程立即返回 `false` 退出，不会一直卡在轮末等到看门狗强制退出；
+//! 没有交出的匹配由 [`Lockstep::take_pending`] 取出，照常写入文件。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+
+/// 没有指定 `--batch-size` 时每个线程每轮生成的密钥数
+///
+/// 轮越小，达到 `--max-matches` 后多生成的密钥越少，轮末等待的次数越多
+pub const DEFAULT_ROUND_SIZE: u64 = 256;
+
+/// 所有工作线程共享的轮次状态，`T` 是交出的匹配
+#[derive(Debug)]
+pub struct Lockstep<T> {
+    workers: usize,
+    gate: Mutex<Gate>,
+    arrived: Condvar,
+    /// 本轮交出的匹配及其密钥位置
+    pending: Mutex<Vec<(u64, T)>>,
+    /// 领头线程在轮末做出的决定
+    stop: AtomicBool,
+}
+
+impl<T> Lockstep<T> {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            workers,
+            gate: Mutex::new(Gate::default()),
+            arrived: Condvar::new(),
+            pending: Mutex::new(Vec::new()),
+            stop: AtomicBool::new(false),
+        }
+    }
+
+    /// 交出位置 `position` 上的密钥的匹配，轮末再按位置排序
+    pub fn defer(&self, position: u64, item: T) {
+        self.pending().push((position, item));
+    }
+
+    /// 有线程 panic 时调用：所有线程都不再等待，[`end_round`](Self::end_round) 总是返回 `false`
+    pub fn abort(&self) {
+        self.lock_gate().aborted = true;
+        self.arrived.notify_all();
+    }
+
+    /// 中止后取出还没有交出的匹配，按位置排序
+    pub fn take_pending(&self) -> Vec<T> {
+        let mut pending = std::mem::take(&mut *self.pending());
+        pending.sort_unstable_by_key(|(position, _)| *position);
+        pending.into_iter().map(|(_, item)| item).collect()
+    }
+
+    /// 每个线程在轮末调用：等所有线程到齐，领头线程按位置顺序把本轮的匹配交给 `flush`，
+    /// 然后用 `stopped` 判断是否停止；所有线程得到同一个结果，返回 `false` 时结束
+    pub fn end_round(&self, flush: impl FnOnce(Vec<T>), stopped: impl FnOnce() -> bool) -> bool {
+        let Some(leader) = self.wait() else {
+            return false;
+        };
+        if leader {
+            flush(self.take_pending());
+            self.stop.store(stopped(), Ordering::Relaxed);
+        }
+        // 第二次等待保证其他线程读到的是领头线程这一轮的决定
+        self.wait().is_some() && !self.stop.load(Ordering::Relaxed)
+    }
+
+    /// 等所有线程到齐，最后到达的是领头线程，返回 `Some(true)`；已中止时返回 `None`
+    fn wait(&self) -> Option<bool> {
+        let mut gate = self.lock_gate();
+        if gate.aborted {
+            return None;
+        }
+        gate.count += 1;
+        if gate.count == self.workers {
+            gate.count = 0;
+            gate.generation += 1;
+            self.arrived.notify_all();
+            return Some(true);
+        }
+        let generation = gate.generation;
+        let gate = self
+            .arrived
+            .wait_while(gate, |gate| gate.generation == generation && !gate.aborted)
+            .unwrap_or_else(PoisonError::into_inner);
+        (gate.generation != generation).then_some(false)
+    }
+
+    // panic 的线程可能正持有锁，中止后仍然要能取出匹配，所以忽略锁中毒
+    fn lock_gate(&self) -> MutexGuard<'_, Gate> {
+        self.gate.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn pending(&self) -> MutexGuard<'_, Vec<(u64, T)>> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// 轮末的等待状态
+#[derive(Debug, Default)]
+struct Gate {
+    /// 本次等待已到达的线程数
+    count: usize,
+    /// 每次所有线程到齐后加一，等待的线程据此判断是否放行
+    generation: u64,
+    aborted: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn rounds_flush_in_position_order() {
+        let lockstep = Arc::new(Lockstep::new(3));
+        let rounds = Arc::new(Mutex::new(Vec::new()));
+        let handles: Vec<_> = (0..3u64)
+            .map(|worker| {
+                let (lockstep, rounds) = (Arc::clone(&lockstep), Arc::clone(&rounds));
+                thread::spawn(move || {
+                    for round in 0..4u64 {
+                        // 位置大的先交出，轮末仍按位置排序
+                        lockstep.defer(round * 3 + 2 - worker, round * 3 + 2 - worker);
+                        let running = lockstep.end_round(|items| rounds.lock().unwrap().push(items), || round == 2);
+                        assert_eq!(running, round < 2);
+                        if !running {
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(*rounds.lock().unwrap(), vec![vec![0, 1, 2], vec![3, 4, 5], vec![6, 7, 8]]);
+    }
+
+    #[test]
+    fn abort_releases_waiting_workers() {
+        let lockstep = Arc::new(Lockstep::new(3));
+        let waiting: Vec<_> = (0..2u64)
+            .map(|worker| {
+                let lockstep = Arc::clone(&lockstep);
+                thread::spawn(move || {
+                    lockstep.defer(worker, worker);
+                    lockstep.end_round(|_| panic!("没有到齐，不应交出"), || false)
+                })
+            })
+            .collect();
+        // 第三个线程“panic”了，永远不会到达轮末
+        thread::sleep(Duration::from_millis(50));
+        lockstep.abort();
+        for handle in waiting {
+            assert!(!handle.join().unwrap());
+        }
+        assert!(!lockstep.end_round(|_| panic!("已中止"), || false));
+        assert_eq!(lockstep.take_pending(), vec![0, 1]);
+        assert!(lockstep.take_pending().is_empty());
+    }
+
+    #[test]
+    fn abort_during_flush_releases_the_others() {
+        let lockstep = Arc::new(Lockstep::<u64>::new(2));
+        // 两个线程谁是领头线程不确定，领头的那个在交出匹配时 panic，由它所在线程的 panic 处理中止
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let lockstep = Arc::clone(&lockstep);
+                thread::spawn(move || {
+                    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        lockstep.end_round(|_| panic!("注入的 panic"), || false)
+                    }));
+                    if result.is_err() {
+                        lockstep.abort();
+                    }
+                    result.ok()
+                })
+            })
+            .collect();
+        let mut results: Vec<Option<bool>> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+        results.sort();
+        assert_eq!(results, vec![None, Some(false)]);
+    }
+}
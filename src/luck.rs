@@ -0,0 +1,164 @@
+//! 每个匹配的运气统计
+//!
+//! 找到一个匹配所需的尝试次数服从几何分布：每次命中的概率为 p，期望 1/p 次。
+//! 记下每个前缀上一次匹配时的总尝试次数，新的匹配就能算出“距上次匹配（或开始）用了多少次”，
+//! 与期望比较得到运气倍数（期望 / 实际，大于 1 表示比平均快），以及几何分布下的百分位
+//! （随机重来一次，用得更少的概率）。
+//!
+//! 多个线程按批合并计数，匹配时只能用已合并的总数加上本线程在当前批中的位置估算尝试次数，
+//! 误差不超过各线程一批的总和，相对于值得统计运气的前缀可以忽略。
+
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+/// 汇总中至少有这么多个匹配时才画间隔直方图
+pub const MIN_HISTOGRAM_MATCHES: usize = 10;
+
+/// 直方图的分箱：实际尝试次数与期望之比的上界，最后一箱没有上界
+pub const HISTOGRAM_BOUNDS: [f64; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+
+/// 一个匹配的运气
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Luck {
+    /// 距上次匹配（或开始）的尝试次数
+    pub attempts: u64,
+    /// 期望尝试次数 1/p
+    pub expected: f64,
+    /// 运气倍数：期望 / 实际，大于 1 表示比平均快
+    pub factor: f64,
+    /// 几何分布下不超过 `attempts` 次就命中的概率，越小越幸运
+    pub percentile: f64,
+}
+
+impl Luck {
+    /// 按命中概率计算，概率为 0 时返回 `None`
+    pub fn new(attempts: u64, probability: f64) -> Option<Self> {
+        if probability <= 0.0 {
+            return None;
+        }
+        let attempts = attempts.max(1);
+        let expected = 1.0 / probability;
+        Some(Self {
+            attempts,
+            expected,
+            factor: expected / attempts as f64,
+            percentile: -(attempts as f64 * (-probability).ln_1p()).exp_m1(),
+        })
+    }
+}
+
+/// 按前缀记录上一次匹配时的尝试次数和每个匹配的运气，各工作线程共享
+#[derive(Debug)]
+pub struct LuckTracker {
+    state: Mutex<Vec<PatternLuck>>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct PatternLuck {
+    probability: f64,
+    last_match: u64,
+    matches: Vec<Luck>,
+}
+
+impl LuckTracker {
+    /// `probabilities` 按前缀序号给出每个前缀的命中概率
+    pub fn new(probabilities: Vec<f64>) -> Self {
+        let state = probabilities
+            .into_iter()
+            .map(|probability| PatternLuck {
+                probability,
+                ..PatternLuck::default()
+            })
+            .collect();
+        Self { state: Mutex::new(state) }
+    }
+
+    /// 追加运行中添加的前缀，从第 `start` 次尝试开始计算它的第一个匹配
+    pub fn add(&self, probability: f64, start: u64) {
+        self.state.lock().unwrap().push(PatternLuck {
+            probability,
+            last_match: start,
+            matches: Vec::new(),
+        });
+    }
+
+    /// 按前缀序号排列的命中概率
+    pub fn probabilities(&self) -> Vec<f64> {
+        self.state.lock().unwrap().iter().map(|pattern| pattern.probability).collect()
+    }
+
+    /// 记录前缀 `index` 在第 `attempt` 次尝试时匹配，返回这个匹配的运气
+    ///
+    /// 估算的尝试次数可能略有先后颠倒，此时按 1 次计，上次匹配的位置只向前推进。
+    /// 运行中添加的前缀在 [`LuckTracker::add`] 之前就匹配时不记录，返回 `None`。
+    pub fn record(&self, index: usize, attempt: u64) -> Option<Luck> {
+        let mut state = self.state.lock().unwrap();
+        let pattern = state.get_mut(index)?;
+        let luck = Luck::new(attempt.saturating_sub(pattern.last_match), pattern.probability);
+        pattern.last_match = pattern.last_match.max(attempt);
+        if let Some(luck) = luck {
+            pattern.matches.push(luck);
+        }
+        luck
+    }
+
+    /// 每个前缀按匹配顺序的运气
+    pub fn per_pattern(&self) -> Vec<Vec<Luck>> {
+        self.state
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|pattern| pattern.matches.clone())
+            .collect()
+    }
+
+    /// 所有前缀合在一起的间隔直方图，匹配不足 [`MIN_HISTOGRAM_MATCHES`] 个时为 `None`
+    pub fn histogram(&self) -> Option<Histogram> {
+        let all: Vec<Luck> = self.per_pattern().into_iter().flatten().collect();
+        (all.len() >= MIN_HISTOGRAM_MATCHES).then(|| Histogram::new(&all))
+    }
+}
+
+/// 间隔直方图：按实际尝试次数与期望之比分箱，同时给出几何分布（近似为指数分布）下的期望个数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Histogram {
+    pub bins: Vec<HistogramBin>,
+}
+
+/// 直方图的一箱
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistogramBin {
+    /// 比值下界
+    pub low: f64,
+    /// 比值上界，最后一箱为 `None`
+    pub high: Option<f64>,
+    pub observed: usize,
+    pub expected: f64,
+}
+
+impl Histogram {
+    /// 同一前缀内各次间隔互相独立，按期望归一化后不同前缀可以合在一起
+    pub fn new(matches: &[Luck]) -> Self {
+        let total = matches.len() as f64;
+        let lows = std::iter::once(0.0).chain(HISTOGRAM_BOUNDS);
+        let highs = HISTOGRAM_BOUNDS.into_iter().map(Some).chain(std::iter::once(None));
+        let bins = lows
+            .zip(highs)
+            .map(|(low, high)| {
+                let in_bin = |ratio: f64| ratio >= low && high.is_none_or(|high| ratio < high);
+                let survival = |ratio: f64| (-ratio).exp();
+                HistogramBin {
+                    low,
+                    high,
+                    observed: matches
+                        .iter()
+                        .filter(|luck| in_bin(luck.attempts as f64 / luck.expected))
+                        .count(),
+                    expected: total * (survival(low) - high.map_or(0.0, survival)),
+                }
+            })
+            .collect();
+        Self { bins }
+    }
+}
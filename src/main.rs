@@ -1,49 +1,2907 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::OpenOptions;
-use std::io::{self, BufWriter, Write};
-use std::sync::{Arc, Mutex};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 
-use clap::Parser;
+use chrono::{Local, NaiveDateTime};
+use clap::parser::ValueSource;
+use clap::{ArgGroup, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use console::style;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use rayon::prelude::*;
-use solana_sdk::signature::Keypair;
+use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signer::Signer;
+use zeroize::{Zeroize, Zeroizing};
+use solana_vanity_address::split_key::{self, SplitGrinder};
+use solana_vanity_address::squads;
+use solana_vanity_address::and_condition::{parse_and_condition, AndCondition};
+use solana_vanity_address::ata;
+use solana_vanity_address::attempts_log::AttemptsLog;
+use solana_vanity_address::audit::{self, AuditLog, AuditRecord, RunEnd, RunStart};
+use solana_vanity_address::base58::ALPHABET;
+use solana_vanity_address::blacklist::Blacklist;
+use solana_vanity_address::byte_pattern::{parse_byte_prefix, parse_byte_suffix, BytePattern};
+use solana_vanity_address::char_class;
+use solana_vanity_address::char_count::{self, parse_char_count, CharCount};
+use solana_vanity_address::char_stats::{self, CharStats};
+use solana_vanity_address::compare;
+use solana_vanity_address::difficulty;
+use solana_vanity_address::disk_space::{self, DiskGuard};
+use solana_vanity_address::dup_check::{self, DupChecker, DupResult};
+use solana_vanity_address::inspect;
+use solana_vanity_address::highlight::{highlight, ColorMode};
+use solana_vanity_address::install::{self, Installed};
+use solana_vanity_address::integrity;
+use solana_vanity_address::jobs::{self, Picker};
+use solana_vanity_address::manifest::{self, RunInfo};
+use solana_vanity_address::match_limit::{LimitState, MatchLimit, Then};
+use solana_vanity_address::matcher::Matcher;
+use solana_vanity_address::offline;
+use solana_vanity_address::prefix_quota::{self, Loaded, PrefixQuota};
+use solana_vanity_address::profile::{Phase, Profiler};
+use solana_vanity_address::rate_log::HeartbeatLine;
+use solana_vanity_address::role::KeyRole;
+use solana_vanity_address::report::{self, Summary};
+use solana_vanity_address::keygen::{self, ExtraEntropy, KeyGenerator, RngSource};
+#[cfg(feature = "keyring")]
+use solana_vanity_address::keyring;
+use solana_vanity_address::known_addresses::{self, AddressFilter, KnownAddresses, KnownResult};
+use solana_vanity_address::lockstep::{self, Lockstep};
+use solana_vanity_address::luck::{Luck, LuckTracker};
+use solana_vanity_address::shamir::{self, ShamirScheme, ShareFile};
+use solana_vanity_address::shape::Shape;
+use solana_vanity_address::simulate;
+#[cfg(feature = "shm")]
+use solana_vanity_address::shm::{self, ShmRead, ShmReader};
+use solana_vanity_address::filter::{parse_byte, parse_leading_ones, parse_max_length, parse_value_mod};
+use solana_vanity_address::format::{Column, OutputFormat, Schema};
+use solana_vanity_address::fuzzy::{self, parse_max_distance, Fuzzy};
+use solana_vanity_address::schedule::Schedule;
+use solana_vanity_address::search::{self, MatchCounts};
+use solana_vanity_address::secret::{self, Secret};
+use solana_vanity_address::session::{self, SessionStore};
+use solana_vanity_address::stop_file::{StopCommand, StopFile};
+use solana_vanity_address::symmetry::{parse_mirror, parse_palindrome, Symmetry};
+use solana_vanity_address::tokens::{self, TokenValues};
+use solana_vanity_address::tune::{self, TuneMode};
+use solana_vanity_address::upload::{self, S3Config, S3Target, Uploader};
+use solana_vanity_address::watchdog::{self, HeartbeatMonitor, Heartbeats, StalledWorker};
+use solana_vanity_address::watch_list::{self, WatchList};
+use solana_vanity_address::webhook;
+use solana_vanity_address::score::{BeautyScore, Scorer, Threshold};
+use solana_vanity_address::writer::{
+    self, Message, OutputWriter, RankOptions, Record, WriterOptions, WriterOutput,
+};
+use solana_vanity_address::{BatchController, ByteFilter, RateLog, Search, Stats};
 
-#[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+/// 匹配地址的默认输出文件
+const DEFAULT_MATCHED_OUTPUT: &str = "data/matched_addresses.csv";
+
+#[derive(Parser, Debug, Clone)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
+#[command(group(ArgGroup::new("scoring").multiple(true)))]
 struct Args {
-    /// 地址前缀，多个前缀用逗号分隔
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// 地址前缀，多个前缀用逗号分隔；可含 [1-9] 这样的字符类和 {YYYY}、{MM}、{DD}、{RUN} 占位符，启动时展开
     #[arg(short, long, use_value_delimiter = true, value_delimiter = ',')]
     prefixes: Vec<String>,
 
-    /// 要保存的非匹配地址的数量
-    #[arg(short, long, default_value_t = 0)]
-    non_matching_count: usize,
+    /// {RUN} 占位符使用的运行计数器状态文件，每次用到时加一
+    #[arg(long, default_value = tokens::DEFAULT_COUNTER_FILE)]
+    run_counter_file: String,
+
+    /// 要保存的非匹配地址的数量
+    #[arg(short, long, default_value_t = 0)]
+    non_matching_count: usize,
+
+    /// 线程数量
+    #[arg(short, long, default_value_t = 0)]
+    threads: usize,
+
+    /// 开始前测试几个线程数并使用最快的一个；cached 表示有这台机器的缓存结果时直接使用
+    #[arg(long, value_enum, value_name = "MODE", num_args = 0..=1, default_missing_value = "run", conflicts_with = "threads")]
+    tune: Option<TuneMode>,
+
+    /// 只测试线程数并打印结果，不开始搜索
+    #[arg(long, conflicts_with = "threads")]
+    tune_only: bool,
+
+    /// 线程数调优结果的缓存文件 [默认: ~/.solvanity/tune.json]
+    #[arg(long, value_name = "PATH")]
+    tune_cache: Option<String>,
+
+    /// 输出文件
+    #[arg(short, long, default_value = "data/solana_addresses.csv")]
+    output: String,
+
+    /// 匹配地址的输出文件
+    #[arg(short, long, default_value = DEFAULT_MATCHED_OUTPUT)]
+    matched_output: String,
+
+    /// 把匹配私钥单独写入这个文件（地址加私钥列），匹配文件只留地址、前缀、时间等公开的列，
+    /// 可以分享或提交，私钥文件离线保管
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["output_none", "no_secret", "shamir", "rotate_every"])]
+    split_output: Option<String>,
+
+    /// 每隔这么多秒把匹配文件改名为带时间戳的文件名并重新开始，供按文件读取的下游流水线使用
+    #[arg(long, value_name = "SECONDS", conflicts_with_all = ["output_none", "no_secret", "s3_upload"])]
+    rotate_every: Option<u64>,
+
+    /// 找到这么多个匹配后自动结束（或按 --then 只继续采样）；匹配先占名额再写入，恰好写出这么多个
+    #[arg(long, value_name = "N", alias = "max-matched")]
+    max_matches: Option<u64>,
+
+    /// 达到 --max-matches 之后：stop 直接结束，continue-sample 不再匹配、继续采集 -n 个非匹配地址后结束
+    #[arg(long, value_enum, default_value = "stop", requires = "max_matches")]
+    then: Then,
+
+    /// 找到 --max-matches 个匹配后再继续搜索这么多秒，只保留美观度分数最高的 N 个匹配，结束时按分数写入
+    #[arg(
+        long,
+        value_name = "SECS",
+        requires = "max_matches",
+        group = "scoring",
+        conflicts_with_all = ["output_none", "no_secret"]
+    )]
+    keep_searching_secs: Option<u64>,
+
+    /// 每个前缀找到这么多个匹配后不再搜索它，所有前缀都找够后结束
+    #[arg(long, value_name = "N", conflicts_with = "keep_searching_secs")]
+    per_prefix: Option<u64>,
+
+    /// 匹配文件已存在时追加写入而不是覆盖；与 --per-prefix 一起使用时先统计已有的匹配，只搜索还没找够的前缀
+    #[arg(long, conflicts_with_all = ["output_none", "no_secret", "rotate_every"])]
+    append: bool,
+
+    /// 匹配私钥只存入系统密钥存储（macOS 钥匙串、Linux Secret Service），匹配文件的 secret_ref 列
+    /// 只写一个随机引用，可以分享；用 keyring-get 子命令按引用取回私钥
+    #[cfg(feature = "keyring")]
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "output_none", "no_secret", "non_matching_count", "keypair_dir", "shamir", "split_output", "rank",
+            "save_near_misses", "both_secret_forms",
+        ]
+    )]
+    output_append_secret_env: bool,
+
+    /// 把匹配实时写入共享内存环形缓冲区，Linux 上位于 /dev/shm/NAME，含 / 时按路径处理
+    #[cfg(feature = "shm")]
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["output_none", "no_secret", "shamir"])]
+    shm: Option<String>,
+
+    /// 共享内存缓冲区的槽位数，每个槽位 256 字节，写满后覆盖最旧的记录
+    #[cfg(feature = "shm")]
+    #[arg(long, default_value_t = shm::DEFAULT_SLOTS, requires = "shm")]
+    shm_slots: usize,
+
+    /// 分阶段统计工作循环的耗时，结束时打印百分比并写入 --report
+    #[arg(long)]
+    profile: bool,
+
+    /// 多任务配置文件（JSON），在一个进程内按份额同时运行其中的多个任务
+    #[arg(long, value_name = "FILE")]
+    jobs: Option<String>,
+
+    /// 分析模式：运行一段时间，报告生成的地址与文件中每个目标地址开头最多重合几个字符
+    #[arg(long, value_name = "FILE")]
+    compare_addresses: Option<String>,
+
+    /// --compare-addresses 的运行时长（秒）
+    #[arg(long, value_name = "SECONDS", default_value_t = 60, requires = "compare_addresses")]
+    compare_secs: u64,
+
+    /// 公钥原始字节的前缀（十六进制，如 0xdead），不经 Base58 直接比较；与 -p 任一命中即匹配，多个用逗号分隔
+    #[arg(long, value_parser = parse_byte_prefix, value_delimiter = ',', value_name = "HEX")]
+    byte_prefix: Vec<BytePattern>,
+
+    /// 公钥原始字节的后缀（十六进制，如 0xbeef），规则同 --byte-prefix
+    #[arg(long, value_parser = parse_byte_suffix, value_delimiter = ',', value_name = "HEX")]
+    byte_suffix: Vec<BytePattern>,
+
+    /// 匹配开头 N 个字符是回文的地址（如 AbCbA），与 -p 任一命中即匹配
+    #[arg(long, value_parser = parse_palindrome, value_name = "N")]
+    palindrome: Option<Symmetry>,
+
+    /// 匹配开头 N 个字符倒过来等于结尾 N 个字符的地址（如 AbC…CbA），与 -p 任一命中即匹配
+    #[arg(long, value_parser = parse_mirror, value_name = "N")]
+    mirror: Option<Symmetry>,
+
+    /// 匹配开头若干字符与 WORD 的编辑距离不超过 --max-distance 的地址（如 so1ana 之于 solana），
+    /// 与 -p 任一命中即匹配，匹配时记录实际距离
+    #[arg(long, value_name = "WORD")]
+    fuzzy: Option<String>,
+
+    /// --fuzzy 允许的最大编辑距离，插入、删除、替换各算 1
+    #[arg(long, value_parser = parse_max_distance, default_value_t = fuzzy::DEFAULT_MAX_DISTANCE, requires = "fuzzy", value_name = "K")]
+    max_distance: usize,
+
+    /// 公钥第一个字节的上限（如 0x0f），在 Base58 编码前过滤
+    #[arg(long, value_parser = parse_byte)]
+    first_byte_max: Option<u8>,
+
+    /// 公钥作为 256 位大端整数的取模条件，如 1000=777 表示十进制末三位为 777，可重复指定
+    #[arg(long, value_parser = parse_value_mod, value_name = "DIVISOR=REMAINDER")]
+    value_mod: Vec<ByteFilter>,
+
+    /// 地址至少以 N 个 1 开头（公钥前 N 个字节为零），在 Base58 编码前过滤；-p 前缀接在这些 1 之后
+    #[arg(long, value_parser = parse_leading_ones, value_name = "N")]
+    leading_ones: Option<u8>,
+
+    /// 地址不超过 N 个字符（32 到 44，通常为 44），在 Base58 编码前按公钥字节判断
+    #[arg(long, value_parser = parse_max_length, value_name = "N")]
+    max_length: Option<u8>,
+
+    /// 地址中某个字符至少出现的次数，如 8x8 表示任意位置至少八个 8，可重复指定
+    #[arg(long, value_parser = parse_char_count, value_name = "CHARxN")]
+    count_char: Vec<CharCount>,
+
+    /// 必须同时满足的条件：prefix:TEXT、suffix:TEXT 或 contains:TEXT，与前缀等目标取“与”，可重复指定
+    #[arg(long = "and", value_parser = parse_and_condition, value_name = "KIND:TEXT")]
+    and_conditions: Vec<AndCondition>,
+
+    /// 地址开头的形状：? 任意字符，小写字母为必须相同的变量，大写字母和数字为字面字符，\ 转义小写字面字符
+    #[arg(long, value_name = "PATTERN")]
+    pattern: Option<Shape>,
+
+    /// 在每行末尾追加行哈希列（SHA-256 前 8 字节），供 verify 子命令校验
+    #[arg(long)]
+    row_hash: bool,
+
+    /// 在输出文件中增加 pubkey_hex 列（32 字节公钥的十六进制），省去下游做 Base58 解码
+    #[arg(long)]
+    include_pubkey_bytes: bool,
+
+    /// 在匹配文件中增加 attempts、luck、luck_percentile 列：距上次匹配的尝试次数、运气倍数和百分位
+    #[arg(long)]
+    luck: bool,
+
+    /// 来源标记：匹配文件和非匹配文件的每行增加 source 列，值为这个字符串，用于汇总多台机器的结果
+    #[arg(long, value_name = "STRING")]
+    tag: Option<String>,
+
+    /// 私钥同时写成两列：secret32_bs58（32 字节种子）和 keypair64_bs58（64 字节密钥对），取代 private_key 列
+    #[arg(long, conflicts_with_all = ["shamir", "output_none", "no_secret"])]
+    both_secret_forms: bool,
+
+    /// 写成 Excel 能直接打开的文件：UTF-8 BOM、CRLF 换行，可能被当作公式的字段前加 '
+    #[arg(long)]
+    excel_compat: bool,
+
+    /// 输出格式，匹配文件和非匹配文件共用，可分别覆盖；ndjson 每行一个带 schema_version 的 JSON 对象
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv, alias = "output-format")]
+    format: OutputFormat,
+
+    /// 输出列，逗号分隔，可选 address、private_key、secret32_bs58、keypair64_bs58、prefix、pubkey_hex、source、index、row_hash
+    #[arg(long, default_value = Schema::DEFAULT_COLUMNS)]
+    columns: String,
+
+    /// 匹配文件的输出格式，覆盖 --format
+    #[arg(long, value_enum)]
+    matched_format: Option<OutputFormat>,
+
+    /// 匹配文件的输出列，覆盖 --columns
+    #[arg(long)]
+    matched_columns: Option<String>,
+
+    /// 非匹配文件的输出格式，覆盖 --format
+    #[arg(long, value_enum)]
+    sample_format: Option<OutputFormat>,
+
+    /// 非匹配文件的输出列，覆盖 --columns
+    #[arg(long)]
+    sample_columns: Option<String>,
+
+    /// 为每个匹配地址写一个 solana CLI 格式的密钥文件（<地址>.json）到该目录
+    #[arg(long)]
+    keypair_dir: Option<String>,
+
+    /// 密钥的用途，改变匹配文件名和密钥文件目录的默认值（mint: 代币 mint 账户，token-account: 非关联代币账户）
+    #[arg(long, value_enum, default_value = "signer")]
+    role: KeyRole,
+
+    /// 匹配时执行的命令模板，支持 {address}、{prefix}、{keypair_file} 占位符
+    #[arg(long)]
+    on_match: Option<String>,
+
+    /// 匹配时向该地址 POST 一个只含地址和前缀的 JSON 对象
+    #[arg(long, value_name = "URL", value_parser = webhook::parse_url)]
+    webhook_url: Option<String>,
+
+    /// 把匹配私钥拆分成 Shamir 分享（如 2-of-3），不写入明文私钥
+    #[arg(long)]
+    shamir: Option<ShamirScheme>,
+
+    /// Shamir 分享的输出目录，第 i 份写入 <目录>/<i>/<地址>.txt
+    #[arg(long, default_value = "data/shares")]
+    shamir_dir: String,
+
+    /// 离线模式：拒绝所有可能访问网络的选项
+    #[arg(long)]
+    offline: bool,
+
+    /// 每批生成的地址数量，0 表示自适应调整
+    #[arg(long, default_value_t = 0)]
+    batch_size: u64,
+
+    /// 自适应批大小的目标：每个线程大约每隔多少毫秒更新一次共享计数器，与 --batch-size 冲突 [默认: 50]
+    #[arg(long, value_name = "MS")]
+    update_interval_ms: Option<u64>,
+
+    /// 限制总生成速率（个/秒），与其他任务共用机器时避免占满所有核心
+    #[arg(long, value_name = "KEYS_PER_SEC")]
+    limit_rate: Option<f64>,
+
+    /// 随机数来源：default 为 solana-sdk 默认，os 直接读取操作系统随机数，chacha 为定期重新播种的 ChaCha20
+    /// [默认: default，指定了 --extra-entropy-file 时为 chacha]
+    #[arg(long, value_enum)]
+    rng: Option<RngSource>,
+
+    /// 额外熵文件（掷骰结果、长口令等），混入每个工作线程的种子；"-" 表示从标准输入读取
+    #[arg(long)]
+    extra_entropy_file: Option<String>,
+
+    /// 仅供测试：用固定种子确定地生成密钥，结果可被预测，绝不能用于真实资产
+    #[arg(long, conflicts_with_all = ["rng", "extra_entropy_file"])]
+    insecure_seed: Option<u64>,
+
+    /// 给每个生成的地址打美观度分，结束时写出分数最高的地址
+    #[arg(long, group = "scoring")]
+    rank: bool,
+
+    /// 排行榜保留的地址数量
+    #[arg(long, default_value_t = 20, requires = "rank")]
+    top_n: usize,
+
+    /// 评分权重，如 repeat=1,run=3,palindrome=2，未给出的项使用默认值
+    #[arg(long, default_value = "repeat=1,run=3,palindrome=2", requires = "scoring")]
+    score_weights: BeautyScore,
+
+    /// 排行榜的输出文件
+    #[arg(long, default_value = "data/top_addresses.csv", requires = "rank")]
+    rank_output: String,
+
+    /// 运行时间窗口，如 "22:00-07:00,Sat,Sun"，窗口外自动暂停，窗口内自动恢复
+    #[arg(long)]
+    schedule: Option<Schedule>,
+
+    /// 同时保存近似命中（开头与前缀重合最多的几个地址）的私钥，默认只保留地址
+    #[arg(long, num_args = 0..=1, default_missing_value = "data/near_misses.csv", value_name = "PATH")]
+    save_near_misses: Option<String>,
+
+    /// 控制文件：出现时优雅退出（与 Ctrl+C 相同），内容为 pause/resume 时暂停/恢复
+    #[arg(long)]
+    stop_file: Option<String>,
+
+    /// 读取控制文件后将其删除，每条指令只生效一次
+    #[arg(long, requires = "stop_file")]
+    consume_stop_file: bool,
+
+    /// 某个工作线程连续这么多秒没有心跳（卡住或死锁）时报错并以退出码 4 结束，0 表示不检查；panic 总会报告
+    #[arg(long, value_name = "SECS", default_value_t = watchdog::DEFAULT_STALL_TIMEOUT_SECS)]
+    stall_timeout: u64,
+
+    /// 工作线程 panic 后在原地重新开始（每个线程最多 3 次），而不是结束整个运行
+    #[arg(long)]
+    restart_stalled_workers: bool,
+
+    /// 仅供测试：第 0 个工作线程完成这么多批后 panic，用来检查 panic 后的收尾；只存在于调试构建中
+    #[cfg(debug_assertions)]
+    #[arg(long, value_name = "BATCHES", hide = true)]
+    debug_panic_after: Option<u64>,
+
+    /// 输出所在磁盘的剩余空间低于这个值时停止写非匹配地址并暂停搜索，释放后自动恢复，0 表示不检查
+    #[arg(long, value_name = "SIZE", value_parser = disk_space::parse_size, default_value = disk_space::DEFAULT_MIN_FREE)]
+    min_free_disk: u64,
+
+    /// 不保存任何结果：不创建输出文件和写入线程，匹配只打印到终端，适合测量搜索耗时
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "non_matching_count", "output", "matched_output", "keypair_dir", "on_match", "webhook_url",
+            "shamir", "rank", "save_near_misses", "rate_log", "row_hash",
+        ]
+    )]
+    output_none: bool,
+
+    /// 会话模式：匹配的私钥只保存在内存中，不写盘也不打印，结束时选择要导出的记录
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "output_none", "non_matching_count", "output", "keypair_dir", "on_match", "webhook_url", "shamir",
+            "rank", "save_near_misses",
+        ]
+    )]
+    no_secret: bool,
+
+    /// 会话模式最多在内存中保存的匹配数量，超出后新匹配的私钥直接丢弃
+    #[arg(long, default_value_t = session::DEFAULT_CAPACITY, requires = "no_secret")]
+    session_capacity: usize,
+
+    /// 终端输出中的私钥显示前 4 个字符，默认完全隐藏（写入文件的私钥不受影响）
+    #[arg(long)]
+    reveal_partial: bool,
+
+    /// 终端输出是否着色，打印匹配时高亮地址中匹配的部分
+    #[arg(long, value_enum, default_value = "auto")]
+    color: ColorMode,
+
+    /// 审计日志文件，每次运行追加开始和结束记录 [默认: ~/.solvanity/runs.jsonl]
+    #[arg(long)]
+    audit_log: Option<String>,
+
+    /// 不读取终端命令（默认在终端中运行时接受 s/p/q 命令）
+    #[arg(long)]
+    no_interactive: bool,
+
+    /// 检测重复生成的公钥，确认重复时中止运行（会降低速度）
+    #[arg(long)]
+    dup_check: bool,
+
+    /// 重复检测使用的布隆过滤器总内存（MB），平均分给各个线程
+    #[arg(long, default_value_t = 64, requires = "dup_check")]
+    dup_check_mb: usize,
+
+    /// 为每个匹配地址推导这些代币的关联代币账户（ATA），作为匹配文件的额外列；Token-2022 的代币写成 MINT:token-2022
+    #[arg(long, value_parser = ata::parse_mint, value_delimiter = ',', value_name = "MINT")]
+    derive_ata: Vec<ata::Mint>,
+
+    /// 地址黑名单文件，每行一个地址；黑名单中的地址不会写入任何输出
+    #[arg(long)]
+    blacklist_file: Option<String>,
+
+    /// 观察名单文件，每行一个完整地址；生成的地址与名单中的地址完全相同时单独报告
+    #[arg(long, value_name = "FILE")]
+    watch_list: Option<String>,
+
+    /// 已知地址快照：每行一个地址的列表，或 build-filter 生成的过滤器文件；每个匹配都检查是否在其中，命中的行在匹配文件的 known 列中标出
+    #[arg(long, value_name = "PATH")]
+    known_addresses: Option<String>,
+
+    /// 抽样统计地址的字符分布，结束时与理论分布做卡方检验
+    #[arg(long)]
+    char_stats: bool,
+
+    /// 吞吐量日志文件，每个统计周期追加一行 CSV
+    #[arg(long)]
+    rate_log: Option<String>,
+
+    /// 吞吐量日志的统计周期（秒）
+    #[arg(long, default_value_t = 10)]
+    rate_log_interval: u64,
+
+    /// 每隔这么多秒输出一行纯文本进度（[时间] generated=X matched=Y rate=Z/s），不覆盖之前的内容，
+    /// 适合 journald 等日志采集
+    #[arg(long, value_name = "SECONDS")]
+    heartbeat: Option<u64>,
+
+    /// 尝试次数日志文件，每个匹配追加一行 CSV：前缀、匹配时的全局尝试次数、运行毫秒数
+    #[arg(long, value_name = "FILE")]
+    attempts_log: Option<String>,
+
+    /// 运行摘要（JSON）的输出文件，结束时写入统计、各前缀匹配数和结束原因
+    #[arg(long)]
+    report: Option<String>,
+
+    /// 每隔多少秒把当前摘要写入 --report，长时间运行中途崩溃时也能留下进度记录
+    #[arg(long, value_name = "SECONDS", requires = "report")]
+    checkpoint_every: Option<u64>,
+
+    /// 定期把匹配文件和 --report 摘要上传到 S3 兼容的存储，如 s3://bucket/prefix；
+    /// 凭据、区域和服务地址按 AWS 的惯例从环境变量和 ~/.aws 读取
+    #[arg(long, value_name = "URL", conflicts_with_all = ["output_none", "no_secret"])]
+    s3_upload: Option<S3Target>,
+
+    /// 上传周期（秒），结束时总会再上传一次
+    #[arg(long, value_name = "SECONDS", default_value_t = upload::DEFAULT_INTERVAL_SECS, requires = "s3_upload")]
+    s3_upload_interval: u64,
+
+    /// 允许 --s3-upload 上传含明文私钥的匹配文件；默认拒绝，应改用 --split-output 或 --shamir
+    #[arg(long, requires = "s3_upload")]
+    s3_upload_plaintext: bool,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// 校验输出文件：私钥是否对应地址，以及行哈希是否一致；目录则按 MANIFEST.json 校验
+    Verify {
+        /// 要校验的 CSV 文件，或带有 MANIFEST.json 的输出目录
+        #[arg(required = true)]
+        files: Vec<String>,
+
+        /// 同时检查 CSV 文件中有没有观察名单上的地址
+        #[arg(long, value_name = "FILE")]
+        watch_list: Option<String>,
+    },
+
+    /// 分离密钥搜索：为对方公钥搜索附加标量，全程不接触完整私钥
+    GrindSplit {
+        /// 对方（密钥所有者）的公钥地址
+        #[arg(long)]
+        partner_pubkey: String,
+
+        /// 地址前缀，多个前缀用逗号分隔
+        #[arg(short, long, required = true, use_value_delimiter = true, value_delimiter = ',')]
+        prefixes: Vec<String>,
+
+        /// 线程数量
+        #[arg(short, long, default_value_t = 0)]
+        threads: usize,
+
+        /// 结果输出文件，每行为地址和 tweak
+        #[arg(short, long, default_value = "data/split_tweaks.csv")]
+        output: String,
+    },
+
+    /// Squads v4 靓号金库：随机生成 create_key，按 Squads 的种子推导多签和金库 PDA，匹配金库地址
+    GrindSquads {
+        /// Squads v4 程序地址
+        #[arg(long, value_parser = squads::parse_program, default_value_t = squads::PROGRAM_ID)]
+        program: Pubkey,
+
+        /// 金库编号，0 为默认金库
+        #[arg(long, default_value_t = 0)]
+        vault_index: u8,
+
+        /// 金库地址前缀，多个前缀用逗号分隔
+        #[arg(short, long, required = true, use_value_delimiter = true, value_delimiter = ',')]
+        prefixes: Vec<String>,
+
+        /// 线程数量
+        #[arg(short, long, default_value_t = 0)]
+        threads: usize,
+
+        /// 结果输出文件，每行为金库、多签、create_key 及其私钥和两个 bump
+        #[arg(short, long, default_value = "data/squads_vaults.csv")]
+        output: String,
+    },
+
+    /// 蒙特卡洛模拟：反复运行真实的生成和匹配，把首次命中所需的尝试次数与解析估计比较
+    Simulate {
+        /// 地址前缀，多个前缀用逗号分隔，语法与主命令的 -p 相同
+        #[arg(short, long, required = true, use_value_delimiter = true, value_delimiter = ',')]
+        prefixes: Vec<String>,
+
+        /// 试验次数
+        #[arg(long, default_value_t = 1000)]
+        trials: usize,
+
+        /// 每次试验最多的尝试次数，达到后按未命中截断
+        #[arg(long, default_value_t = 10_000_000)]
+        max_attempts: u64,
+
+        /// 线程数量，0 表示使用所有可用线程
+        #[arg(short, long, default_value_t = 0)]
+        threads: usize,
+
+        /// 随机数来源
+        #[arg(long, value_enum, default_value = "default")]
+        rng: RngSource,
+
+        /// 判定通过的容差：命中数偏离期望的标准差个数
+        #[arg(long, default_value_t = 3.0)]
+        tolerance: f64,
+    },
+
+    /// 用至少 K 份 Shamir 分享文件恢复私钥并核对地址
+    Recover {
+        /// 分享文件
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+
+    /// 用自己的私钥和代算方返回的 tweak 合并出最终私钥
+    Combine {
+        /// 自己的 64 字节 Base58 密钥对（即 grind-split 使用的公钥对应的私钥）
+        #[arg(long)]
+        my_secret: String,
+
+        /// 代算方返回的 tweak
+        #[arg(long)]
+        tweak: String,
+
+        /// 期望得到的地址，给出时会核对
+        #[arg(long)]
+        address: Option<String>,
+    },
+
+    /// 把匹配文件中的一个密钥对安装为 solana CLI 的默认密钥对，原有文件先备份
+    Install {
+        /// 要安装的地址
+        #[arg(long)]
+        address: String,
+
+        /// 目标路径 [默认: ~/.config/solana/id.json]
+        #[arg(long)]
+        path: Option<String>,
+
+        /// 含有该地址私钥的匹配文件（CSV、TSV 或 ndjson）
+        file: String,
+    },
+
+    /// 识别一段私钥的格式，打印对应的地址和各钱包需要的格式，不写任何文件
+    Inspect {
+        /// 私钥字符串；省略或为 - 时从标准输入读取，不会留在 shell 历史中
+        secret: Option<String>,
+    },
+
+    /// 把每行一个地址的列表转换成 --known-addresses 使用的布隆过滤器文件
+    BuildFilter {
+        /// 地址列表文件，每行一个 Base58 地址
+        input: String,
+
+        /// 输出的过滤器文件
+        output: String,
+
+        /// 目标误判率
+        #[arg(long, default_value_t = known_addresses::DEFAULT_FALSE_POSITIVE_RATE)]
+        fp_rate: f64,
+    },
+
+    /// 查看审计日志中记录的历次运行
+    Runs {
+        #[command(subcommand)]
+        command: RunsCommand,
+    },
+
+    /// 按 --output-append-secret-env 写入的引用从系统密钥存储取回私钥
+    #[cfg(feature = "keyring")]
+    KeyringGet {
+        /// 匹配文件 secret_ref 列中的引用
+        #[arg(value_parser = keyring::parse_reference)]
+        reference: String,
+
+        /// 期望的地址，给出时会核对
+        #[arg(long)]
+        address: Option<String>,
+    },
+
+    /// 读取 --shm 的共享内存缓冲区，持续打印新写入的匹配
+    #[cfg(feature = "shm")]
+    ShmTail {
+        /// 与 --shm 相同的名字或路径
+        name: String,
+
+        /// 从缓冲区中最早的记录开始，而不是只打印之后写入的
+        #[arg(long)]
+        from_start: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum RunsCommand {
+    /// 按时间顺序列出每次运行的参数摘要和结果
+    List {
+        /// 审计日志文件 [默认: ~/.solvanity/runs.jsonl]
+        #[arg(long)]
+        audit_log: Option<String>,
+    },
+}
+
+/// 记录到审计日志时隐去值的参数：固定种子可以重现所有密钥
+const SECRET_ARGS: [&str; 1] = ["insecure_seed"];
+
+/// 搜索结束的原因，由第一个发出停止请求的地方设置
+static STOP_REASON: OnceLock<&'static str> = OnceLock::new();
+
+/// 请求停止搜索并记下原因
+fn request_stop(search: &Search, reason: &'static str) {
+    let _ = STOP_REASON.set(reason);
+    search.stop();
+}
+
+/// 启动前检查输出所在磁盘的剩余空间，不足或放不下 `-n` 个非匹配地址时警告
+fn check_disk_space(guard: &DiskGuard, non_matching_count: usize) {
+    let low = guard.check();
+    let Some(lowest) = guard.lowest() else {
+        println!("警告：无法检查输出目录的剩余空间，--min-free-disk 不起作用");
+        return;
+    };
+    let free = disk_space::format_size(lowest.available);
+    let min_free = disk_space::format_size(guard.min_free());
+    if low {
+        eprintln!(
+            "警告：{} 所在磁盘只剩 {}，低于 --min-free-disk {}，搜索会暂停，直到空间释放",
+            lowest.path.display(),
+            free,
+            min_free
+        );
+        return;
+    }
+    let estimated = non_matching_count as u64 * disk_space::SAMPLE_ROW_BYTES;
+    if estimated.saturating_add(guard.min_free()) > lowest.available {
+        eprintln!(
+            "警告：{} 个非匹配地址约需 {}，{} 所在磁盘只剩 {}，空间低于 {} 后将不再写入非匹配地址",
+            non_matching_count,
+            disk_space::format_size(estimated),
+            lowest.path.display(),
+            free,
+            min_free
+        );
+    }
+}
+
+/// 报告从已有匹配文件中读到的匹配，以及还要搜索的前缀
+fn report_loaded(path: &str, loaded: &Loaded, prefixes: &[String], target: u64) {
+    println!("已有匹配文件 {}：{} 行，确认 {} 个匹配", path, loaded.rows, loaded.counts.values().sum::<u64>());
+    for prefix in prefixes.iter().collect::<BTreeSet<_>>() {
+        let count = loaded.counts.get(prefix).copied().unwrap_or(0);
+        let state = if count >= target { "已找够，不再搜索" } else { "继续搜索" };
+        println!("  {}: {} / {}（{}）", prefix, count, target, state);
+    }
+    for (line, reason) in loaded.skipped.iter().take(10) {
+        eprintln!("  警告：第 {} 行没有计入：{}", line, reason);
+    }
+    if loaded.skipped.len() > 10 {
+        eprintln!("  警告：另有 {} 行没有计入", loaded.skipped.len() - 10);
+    }
+}
+
+/// 看门狗发现工作线程卡住或 panic：逐个说明后请求优雅退出，
+/// 工作线程在宽限时间内没有结束就直接以退出码 4 退出
+fn stall_exit(search: &Search, stalled: &[StalledWorker]) {
+    eprintln!();
+    for worker in stalled {
+        let restarts = match worker.restarts {
+            0 => String::new(),
+            restarts => format!("，已重新开始 {} 次", restarts),
+        };
+        match &worker.panic {
+            Some(message) => eprintln!(
+                "错误：工作线程 {} panic：{}（已完成 {} 批、生成 {} 个{}）",
+                worker.worker, message, worker.batches, worker.generated, restarts
+            ),
+            None => eprintln!(
+                "错误：工作线程 {} 已经 {} 秒没有心跳，可能卡住或死锁（已完成 {} 批、生成 {} 个{}）",
+                worker.worker,
+                worker.stalled.as_secs(),
+                worker.batches,
+                worker.generated,
+                restarts
+            ),
+        }
+    }
+    eprintln!("错误：工作线程已崩溃或卡住，正在结束");
+    request_stop(search, "stalled");
+    std::thread::spawn(|| {
+        std::thread::sleep(watchdog::EXIT_GRACE);
+        eprintln!("错误：工作线程在 {} 秒内没有结束，强制退出", watchdog::EXIT_GRACE.as_secs());
+        std::process::exit(4);
+    });
+}
+
+/// --profile 时把上一个计时点到现在的时间记到 `phase` 上
+fn lap(profiler: &mut Option<Profiler>, mark: &mut Option<Instant>, phase: Phase) {
+    if let Some(profiler) = profiler {
+        profiler.lap(mark, phase);
+    }
+}
+
+/// --profile 时给 `work` 计时并记到 `phase` 上
+fn timed<T>(profiler: &mut Option<Profiler>, phase: Phase, work: impl FnOnce() -> T) -> T {
+    let Some(profiler) = profiler else {
+        return work();
+    };
+    let start = Instant::now();
+    let result = work();
+    profiler.add(phase, start.elapsed());
+    result
+}
+
+/// 工作线程找到的一个匹配，还没有占名额、计数和编号
+///
+/// `record` 中的前缀、运气和编号在 `handle_match` 中填入。
+struct Found {
+    record: Record,
+    /// 满足的前缀序号，从小到大
+    indices: Vec<usize>,
+    /// 匹配时全局的尝试次数
+    attempt: u64,
+}
+
+/// 把记录交给写入线程；写入线程出错退出后通道已关闭，此时请求停止，
+/// 错误本身在汇合写入线程时报告
+fn send_to_writer(sender: &mpsc::SyncSender<Message>, search: &Search, message: Message) {
+    if sender.send(message).is_err() {
+        request_stop(search, "write_error");
+    }
+}
+
+/// 生效的参数（含默认值），用于审计日志；敏感参数的值替换为占位符
+fn audit_config(matches: &ArgMatches) -> BTreeMap<String, Vec<String>> {
+    // 派生宏会生成一个与结构体同名的参数组，只记录真正的参数
+    let command = Args::command();
+    matches
+        .ids()
+        .filter(|id| command.get_arguments().any(|arg| arg.get_id() == *id))
+        .filter_map(|id| {
+            let name = id.as_str();
+            let values = matches.try_get_raw(name).ok()??;
+            let values = if SECRET_ARGS.contains(&name) {
+                vec![audit::REDACTED.to_string()]
+            } else {
+                values.map(|value| value.to_string_lossy().into_owned()).collect()
+            };
+            Some((name.to_string(), values))
+        })
+        .collect()
+}
+
+/// 追加一条审计记录，失败时只打印警告
+fn append_audit(log: &AuditLog, record: &AuditRecord) {
+    if let Err(err) = log.append(record) {
+        eprintln!("警告：无法写入审计日志 {}: {}", log.path().display(), err);
+    }
+}
+
+/// runs list 子命令：按开始记录逐次列出运行，配上对应的结束记录
+fn list_runs(path: Option<&str>) -> io::Result<()> {
+    let Some(path) = path.map(PathBuf::from).or_else(audit::default_path) else {
+        exit_with_error("找不到用户主目录，请用 --audit-log 指定审计日志");
+    };
+    let log = AuditLog::new(&path);
+    let (records, invalid) = match log.read() {
+        Ok(read) => read,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            println!("{} 中还没有任何运行记录", path.display());
+            return Ok(());
+        }
+        Err(err) => return Err(err),
+    };
+
+    let ends: BTreeMap<&str, &RunEnd> = records
+        .iter()
+        .filter_map(|record| match record {
+            AuditRecord::End(end) => Some((end.run_id.as_str(), end)),
+            AuditRecord::Start(_) => None,
+        })
+        .collect();
+    let mut runs = 0;
+    for record in &records {
+        let AuditRecord::Start(start) = record else {
+            continue;
+        };
+        runs += 1;
+        let config = |name: &str| start.config.get(name).map(|values| values.join(",")).unwrap_or_default();
+        println!(
+            "{}  {}  {}  v{}{}",
+            start.run_id,
+            start.timestamp,
+            start.host.as_deref().unwrap_or("-"),
+            start.version,
+            start.git_hash.as_deref().map(|hash| format!(" ({})", hash)).unwrap_or_default()
+        );
+        println!("  前缀: {}  线程: {}  随机数: {}", config("prefixes"), config("threads"), config("rng"));
+        match ends.get(start.run_id.as_str()) {
+            Some(end) => {
+                let matches: Vec<String> = end
+                    .matches
+                    .iter()
+                    .map(|(prefix, count)| format!("{}={}", prefix, count))
+                    .collect();
+                println!(
+                    "  结束: {}，用时 {:.1} 秒，尝试 {} 次，匹配 {}",
+                    end.exit_reason,
+                    end.duration_secs,
+                    end.attempts,
+                    matches.join(" ")
+                );
+                for output in &end.outputs {
+                    println!("    {}  {} 字节  sha256:{}", output.path, output.bytes, output.sha256);
+                }
+            }
+            None => println!("  没有结束记录（仍在运行或异常退出）"),
+        }
+    }
+    println!("共 {} 次运行，记录在 {}", runs, path.display());
+    if !invalid.is_empty() {
+        let lines: Vec<String> = invalid.iter().map(usize::to_string).collect();
+        eprintln!("警告：第 {} 行无法解析，已跳过", lines.join(", "));
+    }
+    Ok(())
+}
+
+/// 打印错误信息并退出，用于参数校验等无法继续的情况
+fn exit_with_error(message: impl std::fmt::Display) -> ! {
+    eprintln!("错误: {}", message);
+    std::process::exit(2);
+}
+
+/// 是否指定了 `--output-append-secret-env`，没有启用 `keyring` 特性时总是 `false`
+#[cfg(feature = "keyring")]
+fn secret_store(args: &Args) -> bool {
+    args.output_append_secret_env
+}
+
+#[cfg(not(feature = "keyring"))]
+fn secret_store(_args: &Args) -> bool {
+    false
+}
+
+/// 按 `--role` 填入没有显式给出的匹配文件和密钥文件目录，`explicit_matched_output` 表示匹配文件已显式指定
+///
+/// 与会话模式、`--output-none`、`--shamir` 的冲突留给 [`validate_args`] 报告。
+fn apply_role_defaults(args: &mut Args, explicit_matched_output: bool) {
+    if !explicit_matched_output
+        && let Some(path) = args.role.default_matched_output()
+    {
+        args.matched_output = path.to_string();
+    }
+    if args.keypair_dir.is_none()
+        && !(args.output_none || args.no_secret || args.shamir.is_some() || secret_store(args))
+    {
+        args.keypair_dir = args.role.default_keypair_dir().map(str::to_string);
+    }
+}
+
+/// 在开始任何工作之前检查选项组合
+///
+/// clap 能表达的简单冲突已经写在参数定义上，这里检查依赖取值的组合，
+/// 错误信息中列出互相冲突的选项，避免某个选项被悄悄忽略。
+fn validate_args(args: &Args) -> Result<(), String> {
+    if args.shamir.is_some() {
+        let plaintext: Vec<&str> = [
+            (args.keypair_dir.is_some(), "--keypair-dir"),
+            (args.rank, "--rank"),
+        ]
+        .into_iter()
+        .filter_map(|(used, flag)| used.then_some(flag))
+        .collect();
+        if !plaintext.is_empty() {
+            return Err(format!("--shamir 模式不写明文私钥，不能与 {} 同时使用", plaintext.join("、")));
+        }
+    }
+    if let Some(rng) = args.rng
+        && rng != RngSource::Chacha
+        && args.extra_entropy_file.is_some()
+    {
+        let name = rng.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or_default();
+        return Err(format!(
+            "--extra-entropy-file 只能与 --rng chacha 一起使用，不能与 --rng {} 同时使用",
+            name
+        ));
+    }
+    if let Some(rate) = args.limit_rate
+        && !(rate.is_finite() && rate > 0.0)
+    {
+        return Err("--limit-rate 必须是正数".to_string());
+    }
+    if args.max_matches == Some(0) {
+        return Err("--max-matches 必须大于 0".to_string());
+    }
+    if args.per_prefix == Some(0) {
+        return Err("--per-prefix 必须大于 0".to_string());
+    }
+    if args.role != KeyRole::Signer {
+        let conflicting: Vec<&str> = [
+            (args.output_none, "--output-none"),
+            (args.no_secret, "--no-secret"),
+            (args.shamir.is_some(), "--shamir"),
+            (secret_store(args), "--output-append-secret-env"),
+            (!args.derive_ata.is_empty(), "--derive-ata"),
+        ]
+        .into_iter()
+        .filter_map(|(used, flag)| used.then_some(flag))
+        .collect();
+        if !conflicting.is_empty() {
+            return Err(format!(
+                "--role {} 需要为每个匹配写出密钥文件，不能与 {} 同时使用",
+                args.role.name(),
+                conflicting.join("、")
+            ));
+        }
+    }
+    if let Some(tag) = &args.tag
+        && (tag.trim().is_empty() || tag.chars().any(char::is_control))
+    {
+        return Err("--tag 不能为空，也不能含有换行等控制字符".to_string());
+    }
+    if args.then == Then::ContinueSample {
+        if args.non_matching_count == 0 {
+            return Err("--then continue-sample 需要用 -n 指定要采集的非匹配地址数量".to_string());
+        }
+        if args.keep_searching_secs.is_some() {
+            return Err("--then continue-sample 不能与 --keep-searching-secs 同时使用".to_string());
+        }
+    }
+    if let Some(interval) = args.update_interval_ms {
+        if args.batch_size != 0 {
+            return Err("--update-interval-ms 只用于自适应批大小，不能与 --batch-size 同时使用".to_string());
+        }
+        if !(1..=60_000).contains(&interval) {
+            return Err("--update-interval-ms 必须在 1 到 60000 之间".to_string());
+        }
+    }
+    #[cfg(feature = "shm")]
+    if args.shm_slots == 0 {
+        return Err("--shm-slots 必须大于 0".to_string());
+    }
+    #[cfg(all(feature = "shm", feature = "keyring"))]
+    if args.shm.is_some() && args.output_append_secret_env {
+        return Err("--output-append-secret-env 不写明文私钥，不能与 --shm 同时使用".to_string());
+    }
+    if args.rotate_every == Some(0) {
+        return Err("--rotate-every 必须大于 0 秒".to_string());
+    }
+    if args.checkpoint_every == Some(0) {
+        return Err("--checkpoint-every 必须大于 0 秒".to_string());
+    }
+    if args.heartbeat == Some(0) {
+        return Err("--heartbeat 必须大于 0 秒".to_string());
+    }
+    if args.s3_upload.is_some() && args.s3_upload_interval == 0 {
+        return Err("--s3-upload-interval 必须大于 0 秒".to_string());
+    }
+    if args.no_secret && args.session_capacity == 0 {
+        return Err("--session-capacity 为 0 时 --no-secret 无法保存任何匹配".to_string());
+    }
+    for (index, condition) in args.count_char.iter().enumerate() {
+        if let Some(earlier) = args.count_char[..index].iter().find(|earlier| earlier.ch == condition.ch) {
+            return Err(format!(
+                "字符 '{}' 指定了两次 --count-char（{} 和 {}），请只保留一个",
+                condition.ch, earlier, condition
+            ));
+        }
+    }
+    if let Some(word) = &args.fuzzy {
+        Fuzzy::new(word, args.max_distance)?;
+    }
+    let has_target = !args.prefixes.is_empty()
+        || !args.byte_prefix.is_empty()
+        || !args.byte_suffix.is_empty()
+        || args.palindrome.is_some()
+        || args.mirror.is_some()
+        || args.fuzzy.is_some()
+        || args.first_byte_max.is_some()
+        || !args.value_mod.is_empty()
+        || args.leading_ones.is_some()
+        || args.max_length.is_some()
+        || !args.count_char.is_empty()
+        || !args.and_conditions.is_empty()
+        || args.pattern.is_some();
+    if !has_target && args.non_matching_count == 0 && !args.rank && args.watch_list.is_none() {
+        return Err(
+            "没有任何搜索目标：请用 -p 指定前缀，或指定 --byte-prefix、--byte-suffix、--palindrome、--mirror、--fuzzy、--first-byte-max、--value-mod、--leading-ones、--max-length、--count-char、--and、--pattern、-n、--rank、--watch-list 之一"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// 解析输出列并校验，`extra` 中尚未列出的附加列（公钥十六进制、关联代币账户、字符计数）
+/// 插在行哈希之前，`--row-hash` 在末尾追加行哈希列
+fn build_schema(format: OutputFormat, columns: &str, row_hash: bool, extra: &[Column]) -> Schema {
+    let mut columns = Schema::parse_columns(columns).unwrap_or_else(|err| exit_with_error(err));
+    let position = columns
+        .iter()
+        .position(|column| *column == Column::RowHash)
+        .unwrap_or(columns.len());
+    let extra: Vec<Column> = extra.iter().copied().filter(|column| !columns.contains(column)).collect();
+    columns.splice(position..position, extra);
+    if row_hash && !columns.contains(&Column::RowHash) {
+        columns.push(Column::RowHash);
+    }
+    Schema::new(format, columns).unwrap_or_else(|err| exit_with_error(err))
+}
+
+/// 检查输出列与其他选项是否冲突
+///
+/// Shamir 模式下匹配文件的 private_key 列换成 shamir 列；
+/// 其他情况下匹配私钥必须至少写到匹配文件、私钥文件或密钥文件之一，否则会丢失。
+fn check_schemas(options: &mut WriterOptions) {
+    if options.sample_schema.contains(Column::Shamir) {
+        exit_with_error("非匹配文件不能包含 shamir 列");
+    }
+    if options.sample_schema.contains(Column::SecretRef) {
+        exit_with_error("非匹配文件不能包含 secret_ref 列");
+    }
+    if options.matched_schema.contains(Column::SecretRef) && !options.secret_store {
+        exit_with_error("secret_ref 列需要同时指定 --output-append-secret-env（需要启用 keyring 特性）");
+    }
+    let matched = &mut options.matched_schema.columns;
+    if options.shamir.is_some() {
+        for column in matched.iter_mut() {
+            if *column == Column::PrivateKey {
+                *column = Column::Shamir;
+            }
+        }
+        if !matched.contains(&Column::Shamir) {
+            matched.insert(1, Column::Shamir);
+        }
+        // 原本同时写了 private_key 和 shamir 时去掉重复
+        let mut seen = Vec::new();
+        matched.retain(|column| {
+            let first = !seen.contains(column);
+            seen.push(*column);
+            first
+        });
+    } else if matched.contains(&Column::Shamir) {
+        exit_with_error("shamir 列需要同时指定 --shamir");
+    } else if !options.matched_schema.has_secret()
+        && options.keypair_dir.is_none()
+        && options.split_output.is_none()
+        && !options.secret_store
+    {
+        exit_with_error("匹配文件不含私钥列时必须指定 --keypair-dir，否则匹配私钥会丢失");
+    }
+}
+
+/// 恢复时间的显示：当天只显示时刻，否则带上星期
+fn format_wake_time(now: NaiveDateTime, next: NaiveDateTime) -> String {
+    if next.date() == now.date() {
+        next.format("%H:%M").to_string()
+    } else {
+        next.format("%a %H:%M").to_string()
+    }
+}
+
+/// 检查所有前缀，有非 Base58 字符时报错退出
+fn validate_prefixes(prefixes: &[String]) {
+    for prefix in prefixes {
+        search::validate_prefix(prefix).unwrap_or_else(|err| exit_with_error(err));
+    }
+}
+
+/// 展开前缀中的占位符并校验展开后的前缀，用到 {RUN} 时计数器加一并保存
+fn expand_prefixes(patterns: &[String], counter_file: &str) -> Vec<String> {
+    let run = patterns.iter().any(|pattern| tokens::uses_run_counter(pattern)).then(|| {
+        let current = tokens::read_counter(counter_file)
+            .unwrap_or_else(|err| exit_with_error(format!("无法读取运行计数器 {}: {}", counter_file, err)));
+        current + 1
+    });
+    let values = TokenValues {
+        date: Local::now().date_naive(),
+        run,
+    };
+    let prefixes: Vec<String> = patterns
+        .iter()
+        .map(|pattern| {
+            let prefix = tokens::expand(pattern, &values).unwrap_or_else(|err| exit_with_error(err));
+            if let Err(err) = search::validate_prefix(&prefix) {
+                if prefix != *pattern {
+                    exit_with_error(format!("{}（由 \"{}\" 展开）", err, pattern));
+                }
+                exit_with_error(err);
+            }
+            if prefix != *pattern {
+                println!("前缀 {} 展开为 {}", pattern, prefix);
+            }
+            if char_class::has_class(&prefix) {
+                let count = char_class::expand(&prefix).map_or(0, |literals| literals.len());
+                println!("前缀 {} 含字符类，展开为 {} 个具体前缀", prefix, count);
+            }
+            prefix
+        })
+        .collect();
+
+    // 所有前缀都通过校验后才保存计数器，报错退出的运行不占用计数值
+    if let Some(run) = run {
+        tokens::write_counter(counter_file, run)
+            .unwrap_or_else(|err| exit_with_error(format!("无法保存运行计数器 {}: {}", counter_file, err)));
+        println!("运行计数器: {}（保存在 {}）", run, counter_file);
+    }
+    prefixes
+}
+
+/// 把各前缀的近似命中写入 CSV：前缀、重合字符数、地址、私钥
+///
+/// 与匹配文件一样用 csv 写入，文件只有所有者可读写，编码后的私钥写完即清零。
+fn write_near_misses(path: &str, search: &Search) -> io::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer::create_secret_file(Path::new(path))?);
+    writer.write_record(["prefix", "matched", "address", "private_key"])?;
+    for (prefix, board) in search.prefixes().iter().zip(search.near_misses()) {
+        for entry in board {
+            if let Some(keypair) = entry.keypair.map(Zeroizing::new) {
+                let secret = Zeroizing::new(bs58::encode(keypair.as_ref()).into_string());
+                let matched = entry.matched.to_string();
+                writer.write_record([prefix.as_str(), &matched, &entry.address, &secret])?;
+            }
+        }
+    }
+    writer.flush()
+}
+
+/// 写入线程结束后写出近似命中文件和完整性清单，返回本次的所有输出文件
+fn write_run_files(
+    args: &Args,
+    search: &Search,
+    stats: &Stats,
+    num_threads: usize,
+    rng: RngSource,
+    written: WriterOutput,
+) -> io::Result<Vec<PathBuf>> {
+    // 要求保存近似命中时，写出最终榜上的地址和私钥
+    let mut files: Vec<PathBuf> = vec![args.matched_output.clone().into()];
+    files.extend(written.sample_file.clone());
+    if let Some(path) = &args.save_near_misses {
+        write_near_misses(path, search)?;
+        println!("近似命中的地址和私钥已写入 {}", path);
+        files.push(path.into());
+    }
+
+    // 所有文件都已刷新，最后写入完整性清单
+    files.extend(args.rate_log.iter().chain(&args.attempts_log).map(PathBuf::from));
+    files.extend(written.files);
+    let run = RunInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        finished_at: Local::now().to_rfc3339(),
+        prefixes: search.prefixes().to_vec(),
+        threads: num_threads,
+        rng: match args.insecure_seed {
+            Some(seed) => format!("insecure-seed:{}", seed),
+            None => rng.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or_default(),
+        },
+        generated: stats.generated,
+        matched: stats.matched,
+        elapsed_secs: stats.elapsed.as_secs_f64(),
+    };
+    let manifest_dir = Path::new(&args.matched_output)
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let manifest_path = manifest::update(
+        manifest_dir,
+        &files,
+        run,
+        Path::new(&args.matched_output),
+        &written.matches,
+    )?;
+    println!("完整性清单已写入 {}", manifest_path.display());
+    files.push(manifest_path);
+    Ok(files)
+}
+
+/// 限速：本线程已生成 `generated` 个地址，按每线程 `rate` 个/秒计算应有的时间，
+/// 比实际（不含暂停的）运行时间超前多少就睡多久；分段睡眠以便及时响应停止
+fn throttle(search: &Search, generated: u64, rate: f64) {
+    let due = Duration::from_secs_f64(generated as f64 / rate);
+    while !search.is_stopped() {
+        let ahead = due.saturating_sub(search.active_elapsed());
+        if ahead.is_zero() {
+            break;
+        }
+        std::thread::sleep(ahead.min(Duration::from_millis(100)));
+    }
+}
+
+/// 读取终端命令的线程：`s` 打印状态，`p` 切换暂停，`a PATTERN[:N]` 添加前缀，`q` 与 Ctrl+C 相同地优雅结束
+///
+/// 输出通过 MultiProgress 暂停进度条后打印，不会与进度条混在一起。
+/// 搜索结束后读到的行不再当作命令，而是转发到返回的通道，供结束时的提问使用。
+fn spawn_command_reader(
+    search: Arc<Search>,
+    manual_pause: Arc<AtomicBool>,
+    multi_progress: MultiProgress,
+    luck_tracker: Arc<LuckTracker>,
+    leading_ones: String,
+    default_target: Option<u64>,
+) -> mpsc::Receiver<String> {
+    let (lines, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match io::stdin().read_line(&mut line) {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
+            }
+            if search.is_stopped() {
+                if lines.send(line.trim().to_string()).is_err() {
+                    return;
+                }
+                continue;
+            }
+            let command = line.trim();
+            let message = match command {
+                "" => continue,
+                "s" => status_report(&search.stats(), &luck_tracker.probabilities()),
+                "p" => {
+                    let paused = !manual_pause.fetch_xor(true, Ordering::Relaxed);
+                    if paused { "已暂停，再输入 p 恢复" } else { "已恢复" }.to_string()
+                }
+                "q" => {
+                    request_stop(&search, "command");
+                    "正在结束：等待工作线程完成当前批并写入剩余记录".to_string()
+                }
+                _ => match command.strip_prefix("a ") {
+                    Some(argument) => add_prefix(&search, &luck_tracker, argument.trim(), &leading_ones, default_target),
+                    None => format!("未知命令: {}（s 查看状态, p 暂停/恢复, a PATTERN[:N] 添加前缀, q 结束）", command),
+                },
+            };
+            multi_progress.suspend(|| println!("{}", message));
+        }
+    });
+    receiver
+}
+
+/// 交互命令 `a PATTERN[:N]`：把前缀加进正在进行的搜索，返回要打印的结果
+///
+/// 与命令行上的前缀一样接在 `--leading-ones` 的 1 之后。没有 `:N` 时用 `--per-prefix` 的数量，
+/// 两者都没有时不限数量。
+fn add_prefix(
+    search: &Search,
+    luck_tracker: &LuckTracker,
+    argument: &str,
+    leading_ones: &str,
+    default_target: Option<u64>,
+) -> String {
+    let (prefix, target) = match search::parse_added_prefix(argument) {
+        Ok(parsed) => parsed,
+        Err(err) => return err,
+    };
+    let matcher = Matcher::Prefix(format!("{}{}", leading_ones, prefix));
+    let target = target.or(default_target);
+    let start = search.generated();
+    let index = match search.add(matcher.clone(), target) {
+        Ok(index) => index,
+        Err(err) => return err,
+    };
+    let probability = difficulty::effective_probabilities(search)[index];
+    luck_tracker.add(probability, start);
+    let count = target.map_or("不限数量".to_string(), |target| format!("找 {} 个", target));
+    if probability == 0.0 {
+        format!("已添加前缀 {}（{}），但在当前的过滤条件下不可能出现，永远不会匹配", matcher, count)
+    } else {
+        format!("已添加前缀 {}（{}），期望尝试次数约 {:.0} 次", matcher, count, 1.0 / probability)
+    }
+}
+
+/// 在终端中提问并读取一行回答；不在终端中运行时返回 `None`
+fn ask(question: &str, lines: Option<&mpsc::Receiver<String>>) -> Option<String> {
+    if lines.is_none() && !io::stdin().is_terminal() {
+        return None;
+    }
+    print!("{}", question);
+    io::stdout().flush().ok()?;
+    match lines {
+        Some(lines) => lines.recv().ok(),
+        None => {
+            let mut line = String::new();
+            match io::stdin().read_line(&mut line) {
+                Ok(0) | Err(_) => None,
+                Ok(_) => Some(line.trim().to_string()),
+            }
+        }
+    }
+}
+
+/// 解析要导出的编号：`all` 或逗号分隔的编号（从 1 开始），返回从 0 开始的下标
+fn parse_selection(answer: &str, count: usize) -> Result<Vec<usize>, String> {
+    if answer.eq_ignore_ascii_case("all") {
+        return Ok((0..count).collect());
+    }
+    let mut selected = Vec::new();
+    for item in answer.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        match item.parse::<usize>() {
+            Ok(number) if (1..=count).contains(&number) => {
+                if !selected.contains(&(number - 1)) {
+                    selected.push(number - 1);
+                }
+            }
+            _ => return Err(format!("无效的编号: {}（应为 1 到 {}）", item, count)),
+        }
+    }
+    Ok(selected)
+}
+
+/// 会话模式结束：列出内存中的匹配，让用户选择导出哪些，其余的私钥全部清除
+fn finish_session(
+    session: &SessionStore,
+    schema: &Schema,
+    default_path: &str,
+    lines: Option<&mpsc::Receiver<String>>,
+) {
+    if session.dropped() > 0 {
+        eprintln!("警告：会话已满，搜索中有 {} 个匹配的私钥被丢弃", session.dropped());
+    }
+    let addresses = session.addresses();
+    if addresses.is_empty() {
+        return;
+    }
+    println!("会话中共有 {} 个匹配：", addresses.len());
+    for (index, (address, prefix, secret)) in addresses.iter().enumerate() {
+        println!("  [{}] {}（前缀 {}）私钥 {}", index + 1, address, prefix, secret);
+    }
+
+    let mut exported = 0;
+    'export: loop {
+        let Some(answer) = ask("要导出哪些？输入编号（如 1,3）或 all，直接回车则全部放弃: ", lines) else {
+            eprintln!("不在终端中运行，无法选择导出");
+            break;
+        };
+        let selected = match parse_selection(&answer, addresses.len()) {
+            Ok(selected) if selected.is_empty() => break,
+            Ok(selected) => selected,
+            Err(err) => {
+                eprintln!("{}", err);
+                continue;
+            }
+        };
+
+        let default_format = schema.format.to_possible_value().unwrap().get_name().to_string();
+        let format = loop {
+            let question = format!("格式 csv/tsv/ndjson [{}]: ", default_format);
+            let Some(answer) = ask(&question, lines) else { break 'export };
+            let answer = if answer.is_empty() { default_format.clone() } else { answer };
+            match OutputFormat::from_str(&answer, true) {
+                Ok(format) => break format,
+                Err(_) => eprintln!("未知的格式: {}", answer),
+            }
+        };
+        let schema = Schema {
+            format,
+            ..schema.clone()
+        };
+
+        loop {
+            let Some(path) = ask(&format!("导出到文件 [{}]: ", default_path), lines) else { break 'export };
+            let path = if path.is_empty() { default_path.to_string() } else { path };
+            if Path::new(&path).exists() {
+                let Some(answer) = ask(&format!("{} 已存在，覆盖？(y/N): ", path), lines) else { break 'export };
+                if !answer.eq_ignore_ascii_case("y") {
+                    continue;
+                }
+            }
+            match session.export(&selected, &schema, &path) {
+                Ok(count) => {
+                    println!("已导出 {} 个匹配到 {}", count, path);
+                    exported = count;
+                    break 'export;
+                }
+                Err(err) => eprintln!("导出失败: {}，请换一个路径", err),
+            }
+        }
+    }
+
+    let remaining = session.wipe() - exported;
+    if remaining > 0 {
+        eprintln!("警告：{} 个未导出的私钥已从内存中清除，永远无法找回", remaining);
+    }
+}
+
+/// 交互命令 `s` 打印的详细状态
+fn status_report(stats: &Stats, probabilities: &[f64]) -> String {
+    let mut lines = vec![format!(
+        "已生成 {} 个地址，匹配 {} 个，运行 {:.1} 秒，平均速率 {:.2}/秒，批大小 {}",
+        stats.generated,
+        stats.matched,
+        stats.elapsed.as_secs_f64(),
+        stats.rate,
+        stats.batch_size
+    )];
+    for (index, (((prefix, count), near_misses), probability)) in stats
+        .per_prefix
+        .iter()
+        .zip(&stats.near_misses)
+        .zip(probabilities)
+        .enumerate()
+    {
+        let chance = difficulty::found_by_now(stats.attempts_for(index), *probability);
+        let mut line = format!(
+            "  {}: {} 个，到现在应该找到的概率 {:.1}%",
+            prefix,
+            count,
+            chance * 100.0
+        );
+        if let Some((address, matched)) = near_misses.first() {
+            line.push_str(&format!("，最接近 {}/{}: {}", matched, char_class::len(prefix), address));
+        }
+        lines.push(line);
+    }
+    if stats.sample_target > 0 {
+        lines.push(format!("  非匹配地址采样: {} / {}", stats.samples_taken, stats.sample_target));
+    }
+    lines.join("\n")
+}
+
+/// 打印搜索结束时的汇总
+fn print_summary(stats: &Stats) {
+    println!(
+        "搜索结束：共生成 {} 个地址，匹配 {} 个，用时 {:.1} 秒，平均速率 {:.2}/秒",
+        stats.generated,
+        stats.matched,
+        stats.elapsed.as_secs_f64(),
+        stats.rate
+    );
+    for ((prefix, count), near_misses) in stats.per_prefix.iter().zip(&stats.near_misses) {
+        println!("  {}: {} 个", prefix, count);
+        for (address, matched) in near_misses {
+            println!("    近似命中 {}/{}: {}", matched, char_class::len(prefix), address);
+        }
+    }
+    if stats.sample_target > 0 {
+        println!("非匹配地址采样: {} / {}", stats.samples_taken, stats.sample_target);
+    }
+}
+
+/// 一个匹配的运气，如 `运气 ×1.80：距上次 1862 次，期望 3364 次，百分位 42.5%`
+fn describe_luck(luck: &Luck) -> String {
+    format!(
+        "运气 ×{:.2}：距上次 {} 次，期望 {:.0} 次，百分位 {:.1}%",
+        luck.factor,
+        luck.attempts,
+        luck.expected,
+        luck.percentile * 100.0
+    )
+}
+
+/// 结束时每个前缀最多列出的运气倍数个数
+const LUCK_LISTED: usize = 10;
+
+/// 打印每个前缀各次匹配的运气倍数，匹配足够多时画出间隔直方图
+fn print_luck(search: &Search, tracker: &LuckTracker) {
+    let per_pattern = tracker.per_pattern();
+    if per_pattern.iter().all(Vec::is_empty) {
+        return;
+    }
+    println!("运气（期望尝试次数 / 实际尝试次数，大于 1 表示比平均快）：");
+    for (prefix, matches) in search.prefixes().iter().zip(&per_pattern) {
+        if matches.is_empty() {
+            continue;
+        }
+        let mut factors: Vec<String> = matches
+            .iter()
+            .take(LUCK_LISTED)
+            .map(|luck| format!("×{:.1}", luck.factor))
+            .collect();
+        if matches.len() > LUCK_LISTED {
+            factors.push("…".to_string());
+        }
+        println!("  {} 找到 {} 次，运气 {}", prefix, matches.len(), factors.join(" / "));
+    }
+    let Some(histogram) = tracker.histogram() else {
+        return;
+    };
+    println!("匹配间隔分布（实际 / 期望尝试次数，# 为实际个数，括号中为理论期望）：");
+    let widest = histogram.bins.iter().map(|bin| bin.observed).max().unwrap_or(0).max(1);
+    for bin in &histogram.bins {
+        let range = match bin.high {
+            Some(high) => format!("{:>4}–{:<4}", bin.low, high),
+            None => format!("{:>4}+    ", bin.low),
+        };
+        let bar = "#".repeat((bin.observed * 30).div_ceil(widest));
+        println!("  {} {:<30} {} ({:.1})", range, bar, bin.observed, bin.expected);
+    }
+}
+
+/// 前缀首字符的实际概率与“每个字符 1/58”相差一倍以上时提示，说明期望尝试次数为何与 58^长度 不同
+fn print_first_char_hint(search: &Search) {
+    let distribution = difficulty::first_char_distribution();
+    let mut firsts: Vec<char> = search.patterns().literals().filter_map(|(literal, _)| literal.chars().next()).collect();
+    firsts.sort_unstable();
+    firsts.dedup();
+    let notes: Vec<String> = firsts
+        .iter()
+        .filter_map(|&c| {
+            let probability = distribution[ALPHABET.find(c)?];
+            let ratio = probability * 58.0;
+            (probability > 0.0 && !(0.5..=2.0).contains(&ratio)).then(|| format!("'{}' 约 1/{:.0}", c, 1.0 / probability))
+        })
+        .collect();
+    if !notes.is_empty() {
+        println!(
+            "首字符在随机地址中并不均匀（每个字符 1/58 只是平均）：{}，期望尝试次数已按实际分布计算",
+            notes.join("，")
+        );
+    }
+}
+
+/// 打印字符频率检验结果
+fn print_char_stats(stats: &CharStats) {
+    let report = stats.analyze();
+    println!("字符分布检验：抽样 {} 个地址，总体偏离分数 z = {:.2}", report.samples, report.z);
+    if report.samples < 1000 {
+        println!("  样本太少，结果仅供参考");
+    }
+    if report.invalid > 0 {
+        println!("  警告：{} 个地址含有非 Base58 字符或长度异常", report.invalid);
+    }
+    for position in report.anomalies() {
+        println!(
+            "  警告：第 {} 位偏离显著（卡方 {:.1}，z = {:.2}，不可能出现的字符 {} 个）",
+            position.position, position.chi_square, position.z, position.impossible
+        );
+    }
+    if !report.is_anomalous() {
+        println!("  未发现异常");
+    }
+}
+
+/// 读取额外熵文件，"-" 表示标准输入；内容为空时退出
+fn read_extra_entropy(path: &str) -> ExtraEntropy {
+    let data = if path == "-" {
+        println!("请输入额外熵（掷骰结果、口令等），以 Ctrl+D 结束：");
+        let mut data = Vec::new();
+        io::Read::read_to_end(&mut io::stdin(), &mut data).map(|_| data)
+    } else {
+        std::fs::read(path)
+    };
+    let data = data.unwrap_or_else(|err| exit_with_error(format!("无法读取额外熵 {}: {}", path, err)));
+    if data.iter().all(u8::is_ascii_whitespace) {
+        exit_with_error("额外熵为空");
+    }
+    ExtraEntropy::new(&data)
+}
+
+/// verify 子命令：逐个校验文件，有任何问题时以非零状态退出
+fn verify(files: &[String], watch_list: Option<&str>) -> io::Result<()> {
+    let watch_list = watch_list.map(|path| {
+        let watch_list = WatchList::load(path).unwrap_or_else(|err| exit_with_error(err));
+        println!("已加载观察名单 {}：{} 个地址", path, watch_list.len());
+        watch_list
+    });
+    let mut all_ok = true;
+    for file in files {
+        if Path::new(file).is_dir() {
+            let report = manifest::verify_dir(file)?;
+            for issue in &report.issues {
+                println!("{}: {}", file, issue);
+            }
+            if report.is_ok() {
+                println!("{}: 与 {} 一致，共 {} 个文件", file, manifest::FILE_NAME, report.files);
+            } else {
+                println!("{}: 发现 {} 个问题，清单中共 {} 个文件", file, report.issues.len(), report.files);
+                all_ok = false;
+            }
+            continue;
+        }
+        let report = integrity::verify_file(file)?;
+        for issue in &report.issues {
+            println!("{}: {}", file, issue);
+        }
+        if report.is_ok() {
+            println!("{}: 校验通过，共 {} 行（{} 行带行哈希）", file, report.rows, report.hashed_rows);
+        } else {
+            println!("{}: 发现 {} 个问题，共 {} 行", file, report.issues.len(), report.rows);
+            all_ok = false;
+        }
+        if let Some(watch_list) = &watch_list {
+            let hits = watch_list.scan_file(file).unwrap_or_else(|err| exit_with_error(err));
+            for (line, address) in &hits {
+                println!("{}: 第 {} 行的地址 {} 在观察名单中", file, line, address);
+            }
+            if hits.is_empty() {
+                println!("{}: 没有观察名单上的地址", file);
+            } else {
+                all_ok = false;
+            }
+        }
+    }
+
+    if !all_ok {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// recover 子命令：合并分享文件，打印地址和 64 字节私钥
+fn recover(files: &[String]) -> io::Result<()> {
+    let mut shares = Vec::with_capacity(files.len());
+    for file in files {
+        let content = std::fs::read_to_string(file)?;
+        shares.push(ShareFile::parse(&content).unwrap_or_else(|err| exit_with_error(format!("{}: {}", file, err))));
+    }
+    let (address, keypair) = shamir::recover(&shares).unwrap_or_else(|err| exit_with_error(err));
+
+    // 这个子命令的用途就是显示私钥，直接取出明文
+    let secret = Secret::new(bs58::encode(keypair).into_string());
+    println!("地址: {}", address);
+    println!("私钥: {}", secret.expose());
+    Ok(())
+}
+
+/// keyring-get 子命令：按引用从系统密钥存储取回私钥，核对后打印地址和 64 字节私钥
+#[cfg(feature = "keyring")]
+fn keyring_get(reference: &str, address: Option<&str>) -> io::Result<()> {
+    let secret = keyring::load(reference).unwrap_or_else(|err| exit_with_error(err));
+    let bytes = Zeroizing::new(
+        bs58::decode(secret.as_str())
+            .into_vec()
+            .unwrap_or_else(|err| exit_with_error(format!("取回的私钥不是有效的 Base58: {}", err))),
+    );
+    if bytes.len() != 64 {
+        exit_with_error(format!("取回的私钥应为 64 字节，实际为 {} 字节", bytes.len()));
+    }
+    let derived = Pubkey::new_from_array(bytes[32..].try_into().unwrap()).to_string();
+    let address = address.unwrap_or(&derived);
+    integrity::check_keypair(address, &secret).unwrap_or_else(|err| exit_with_error(err));
+
+    // 这个子命令的用途就是显示私钥，直接取出明文
+    let secret = Secret::new(secret.to_string());
+    println!("地址: {}", address);
+    println!("私钥: {}", secret.expose());
+    Ok(())
+}
+
+/// 这次运行到达终点的期望尝试次数，用于缩减线程数
+///
+/// `--max-matches` 按所有前缀的概率之和，`--per-prefix` 按各前缀还差的匹配数除以概率，取最大的一个，
+/// 两者都给时取先到的一个；没有终点，或到达后还要继续搜索、采样时为无穷大。
+fn expected_run_attempts(args: &Args, probabilities: &[f64], prefix_quota: Option<&PrefixQuota>) -> f64 {
+    if args.keep_searching_secs.is_some() || args.then == Then::ContinueSample {
+        return f64::INFINITY;
+    }
+    let by_matches = args
+        .max_matches
+        .map_or(f64::INFINITY, |max| max as f64 / probabilities.iter().sum::<f64>());
+    let by_quota = prefix_quota.map_or(f64::INFINITY, |quota| {
+        probabilities
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| quota.remaining(*index) > 0)
+            .map(|(index, probability)| quota.remaining(index) as f64 / probability)
+            .fold(0.0, f64::max)
+    });
+    by_matches.min(by_quota)
+}
+
+/// --tune：有缓存且允许使用时直接返回缓存的线程数，否则逐个测试候选线程数并更新缓存
+fn tuned_threads(mode: TuneMode, cache_path: Option<&str>, rng: RngSource) -> usize {
+    let logical = std::thread::available_parallelism().map_or(1, |count| count.get());
+    let key = tune::machine_key(logical);
+    let cache_path = cache_path.map(PathBuf::from).or_else(tune::default_cache_path);
+    if mode == TuneMode::Cached
+        && let Some(path) = &cache_path
+    {
+        match tune::load_cache(path) {
+            Ok(cache) => match cache.get(&key) {
+                Some(entry) => {
+                    println!(
+                        "使用缓存的调优结果：{} 个线程（{} 测得 {:.0}/秒）",
+                        entry.threads, entry.measured_at, entry.rate
+                    );
+                    return entry.threads;
+                }
+                None => println!("调优缓存中没有这台机器的结果，重新测试"),
+            },
+            Err(err) => eprintln!("警告：无法读取调优缓存 {}: {}", path.display(), err),
+        }
+    }
+
+    let candidates = tune::candidates(logical);
+    println!(
+        "线程数调优：{}，依次测试 {:?} 个线程，每个约 {} 秒",
+        key,
+        candidates,
+        tune::TRIAL_DURATION.as_secs()
+    );
+    let trials = tune::run(&candidates, rng, |trial| {
+        println!("  {:>4} 个线程: {:>12.0}/秒", trial.threads, trial.rate)
+    });
+    let best = tune::best(&trials).expect("候选线程数不会为空");
+    let all = trials.iter().find(|trial| trial.threads == logical).copied();
+    match all {
+        Some(all) if all.threads != best.threads => println!(
+            "最快的是 {} 个线程（{:.0}/秒），比用满 {} 个逻辑核心快 {:.1}%",
+            best.threads,
+            best.rate,
+            logical,
+            (best.rate / all.rate - 1.0) * 100.0
+        ),
+        _ => println!("最快的是 {} 个线程（{:.0}/秒）", best.threads, best.rate),
+    }
+
+    if let Some(path) = &cache_path {
+        let entry = tune::CachedTune {
+            threads: best.threads,
+            rate: best.rate,
+            measured_at: Local::now().to_rfc3339(),
+        };
+        match tune::save_cache(path, &key, entry) {
+            Ok(()) => println!("调优结果已保存到 {}，之后可以用 --tune cached 直接使用", path.display()),
+            Err(err) => eprintln!("警告：无法保存调优结果到 {}: {}", path.display(), err),
+        }
+    }
+    best.threads
+}
+
+/// install 子命令：把匹配文件中的密钥对写成 solana CLI 的密钥对文件并打印后续设置命令
+fn install_keypair(address: &str, path: Option<&str>, file: &str) -> io::Result<()> {
+    let keypair = install::find_keypair(file, address).unwrap_or_else(|err| exit_with_error(err));
+    let path = path
+        .map(PathBuf::from)
+        .or_else(install::default_path)
+        .unwrap_or_else(|| exit_with_error("找不到用户主目录，请用 --path 指定目标路径"));
+    let installed = install::install(&keypair, address, &path)
+        .unwrap_or_else(|err| exit_with_error(format!("无法安装到 {}: {}", path.display(), err)));
+    match installed {
+        Installed::Unchanged => println!("{} 已经是地址 {} 的密钥对，没有改动", path.display(), address),
+        installed => {
+            if let Installed::BackedUp(backup) = &installed {
+                println!("原有的密钥对已备份到 {}", backup.display());
+            }
+            println!("已写入 {}（权限 0600），核对地址 {} 通过", path.display(), address);
+        }
+    }
+    let quoted = writer::shell_quote(&path.to_string_lossy());
+    println!("完成设置：");
+    println!("  solana config set --keypair {}", quoted);
+    println!("  solana-keygen verify {} {}", address, quoted);
+    println!("  solana address    # 应显示 {}", address);
+    Ok(())
+}
+
+/// inspect 子命令：识别私钥的格式，列出每种解读的地址和钱包导入建议
+fn inspect_secret(secret: Option<&str>) -> io::Result<()> {
+    let input = match secret {
+        Some(secret) if secret != "-" => {
+            eprintln!("提示：命令行参数会留在 shell 历史中，下次可以省略参数，从标准输入粘贴");
+            Zeroizing::new(secret.to_string())
+        }
+        _ => {
+            if io::stdin().is_terminal() {
+                eprint!("粘贴私钥后回车: ");
+            }
+            let mut line = Zeroizing::new(String::new());
+            io::stdin().read_line(&mut line)?;
+            line
+        }
+    };
+    let interpretations = inspect::inspect(&input).unwrap_or_else(|err| exit_with_error(err));
+    if interpretations.len() > 1 {
+        println!("输入有 {} 种可能的解读，请按你预期的地址确认：", interpretations.len());
+    }
+    for (index, interpretation) in interpretations.iter().enumerate() {
+        if interpretations.len() > 1 {
+            println!();
+            println!("解读 {}：", index + 1);
+        }
+        println!("格式: {}，{}", interpretation.kind.name(), interpretation.encoding.name());
+        println!("地址: {}", interpretation.address);
+        if let Some(warning) = &interpretation.warning {
+            println!("警告: {}", warning);
+        }
+        let (Some(base58), Some(json)) = (interpretation.keypair_base58(), interpretation.keypair_json()) else {
+            println!("这是地址本身，不能导入钱包");
+            continue;
+        };
+        let ready = interpretation.kind == inspect::Kind::Keypair && interpretation.warning.is_none();
+        if ready && interpretation.encoding == inspect::Encoding::Base58 {
+            println!("Phantom / Solflare / Backpack（导入私钥）: 可以直接使用");
+        } else {
+            println!("Phantom / Solflare / Backpack（导入私钥）: 需要 64 字节密钥对的 Base58：");
+            println!("  {}", base58.expose());
+        }
+        if ready && interpretation.encoding == inspect::Encoding::JsonArray {
+            println!("solana CLI: 已经是密钥文件的格式，保存为文件后 solana config set --keypair <文件>");
+        } else {
+            println!("solana CLI: 把下面的 JSON 数组保存为文件，再 solana config set --keypair <文件>：");
+            println!("  {}", json.expose());
+        }
+    }
+    Ok(())
+}
+
+/// build-filter 子命令：构建已知地址过滤器并写入文件
+fn build_filter(input: &str, output: &str, fp_rate: f64) -> io::Result<()> {
+    if !(fp_rate > 0.0 && fp_rate < 1.0) {
+        exit_with_error(format!("--fp-rate 应在 0 和 1 之间，而不是 {}", fp_rate));
+    }
+    let started = Instant::now();
+    let filter = AddressFilter::build(input, fp_rate).unwrap_or_else(|err| exit_with_error(err));
+    filter.save(output)?;
+    println!(
+        "已写入 {}：{} 个地址，{:.1} MB，误判率约 {:.1e}，用时 {:.1} 秒",
+        output,
+        filter.len(),
+        filter.size_bytes() as f64 / 1e6,
+        filter.false_positive_rate(),
+        started.elapsed().as_secs_f64()
+    );
+    println!("复核时会读取原始名单 {}，请不要移动或删除它", filter.source());
+    Ok(())
+}
+
+/// grind-split 子命令：搜索附加标量，只输出地址和 tweak
+fn grind_split(partner_pubkey: &str, prefixes: &[String], threads: usize, output: &str) -> io::Result<()> {
+    let partner = split_key::decode_point(partner_pubkey).unwrap_or_else(|err| exit_with_error(err));
+    let num_threads = if threads == 0 { rayon::current_num_threads() } else { threads };
+    validate_prefixes(prefixes);
+    let search = Search::new(prefixes.iter().cloned());
+    println!("使用 {} 个线程，为 {} 查找以下前缀: {:?}", num_threads, partner_pubkey, search.prefixes());
+
+    if let Some(dir) = std::path::Path::new(output).parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let output_file = OpenOptions::new().write(true).create(true).truncate(true).open(output)?;
+    let writer = Mutex::new(BufWriter::new(output_file));
+    writeln!(writer.lock().unwrap(), "address,tweak")?;
+
+    let thread_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()
+        .unwrap();
+    thread_pool.install(|| {
+        (0..num_threads).into_par_iter().for_each(|_| {
+            let mut grinder = SplitGrinder::new(&partner);
+            let patterns = search.patterns();
+            let mut matches = MatchCounts::new(patterns.len());
+            loop {
+                for _ in 0..1024 {
+                    let address = grinder.address();
+                    let indices = patterns.find_all(&address);
+                    if !indices.is_empty() {
+                        matches.credit(&indices);
+                        let tweak = grinder.tweak();
+                        println!("找到 {}，tweak: {}", highlight(&address, &patterns.matched_spans(&indices, &address)), tweak);
+                        let mut writer = writer.lock().unwrap();
+                        writeln!(writer, "{},{}", address, tweak).unwrap();
+                        writer.flush().unwrap();
+                    }
+                    grinder.advance();
+                }
+                search.record_batch(1024, &mut matches);
+            }
+        });
+    });
+
+    Ok(())
+}
+
+/// grind-squads 子命令：搜索金库地址符合前缀的 create_key，输出其私钥和推导出的地址
+fn grind_squads(program: &Pubkey, vault_index: u8, prefixes: &[String], threads: usize, output: &str) -> io::Result<()> {
+    let num_threads = if threads == 0 { rayon::current_num_threads() } else { threads };
+    validate_prefixes(prefixes);
+    let search = Search::new(prefixes.iter().cloned());
+    // 输出文件含 create_key 的私钥：只有所有者可读写，已存在时拒绝覆盖
+    let output_file = match writer::create_new_secret_file(Path::new(output)) {
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+            exit_with_error(format!("{} 已存在，其中可能有私钥，拒绝覆盖；请用 -o 指定新的文件或先移走它", output))
+        }
+        result => result?,
+    };
+    println!(
+        "使用 {} 个线程，在 Squads 程序 {} 下为金库 {} 查找以下前缀: {:?}",
+        num_threads,
+        program,
+        vault_index,
+        search.prefixes()
+    );
+    println!("每次尝试要推导两个 PDA，速率远低于普通搜索；Ctrl+C 结束");
+
+    let writer = Mutex::new(BufWriter::new(output_file));
+    writeln!(
+        writer.lock().unwrap(),
+        "vault,vault_bump,multisig,multisig_bump,create_key,create_key_secret"
+    )?;
+
+    let thread_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()
+        .unwrap();
+    // 写入失败时停止所有工作线程并返回错误，不再继续生成写不出去的私钥
+    thread_pool.install(|| {
+        (0..num_threads).into_par_iter().try_for_each(|_| {
+            let mut keygen = KeyGenerator::new(RngSource::Default);
+            let patterns = search.patterns();
+            let mut matches = MatchCounts::new(patterns.len());
+            while !search.is_stopped() {
+                for _ in 0..256 {
+                    let create_key = keygen.generate();
+                    let derived = squads::derive(program, &create_key.pubkey(), vault_index);
+                    let vault = derived.vault.to_string();
+                    let indices = patterns.find_all(&vault);
+                    if !indices.is_empty() {
+                        matches.credit(&indices);
+                        println!(
+                            "找到金库 {}（多签 {}，create_key {}）",
+                            highlight(&vault, &patterns.matched_spans(&indices, &vault)),
+                            derived.multisig,
+                            create_key.pubkey()
+                        );
+                        let secret = Zeroizing::new(create_key.to_base58_string());
+                        let mut writer = writer.lock().unwrap();
+                        let written = writeln!(
+                            writer,
+                            "{},{},{},{},{},{}",
+                            vault,
+                            derived.vault_bump,
+                            derived.multisig,
+                            derived.multisig_bump,
+                            create_key.pubkey(),
+                            secret.as_str()
+                        )
+                        .and_then(|()| writer.flush());
+                        if let Err(err) = written {
+                            search.stop();
+                            return Err(io::Error::new(err.kind(), format!("写入 {} 失败: {}", output, err)));
+                        }
+                    }
+                }
+                search.record_batch(256, &mut matches);
+            }
+            Ok(())
+        })
+    })
+}
+
+/// 命令行上显式指定了、但不在 `allowed` 中的参数，用于拒绝在独立模式下不起作用的选项
+fn explicit_args_except(matches: &ArgMatches, allowed: &[&str]) -> Vec<String> {
+    Args::command()
+        .get_arguments()
+        .filter(|arg| !allowed.contains(&arg.get_id().as_str()))
+        .filter(|arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine))
+        .filter_map(|arg| arg.get_long().map(|long| format!("--{}", long)))
+        .collect()
+}
+
+/// --compare-addresses 模式下可以在命令行上指定的参数
+const COMPARE_ARGS: [&str; 7] = [
+    "compare_addresses",
+    "compare_secs",
+    "threads",
+    "rng",
+    "extra_entropy_file",
+    "insecure_seed",
+    "offline",
+];
+
+/// --compare-addresses 模式每批生成的密钥数
+const COMPARE_BATCH_SIZE: u64 = 1024;
+
+/// --compare-addresses：运行固定时长，打印每个目标地址的最长重合和对应的期望尝试次数
+///
+/// 目标地址当作前缀交给 [`Search`]，每个地址都要编码并沿前缀树比较，速率比普通搜索低一些。
+fn run_compare(args: &Args, matches: &ArgMatches, path: &str) -> io::Result<()> {
+    let conflicting = explicit_args_except(matches, &COMPARE_ARGS);
+    if !conflicting.is_empty() {
+        exit_with_error(format!(
+            "--compare-addresses 是单独的分析模式，不能与 {} 同时使用",
+            conflicting.join("、")
+        ));
+    }
+    if args.compare_secs == 0 {
+        exit_with_error("--compare-secs 必须大于 0");
+    }
+    let targets = compare::load_targets(path).unwrap_or_else(|err| exit_with_error(err));
+    let (rng, extra_entropy) = setup_rng(args);
+    let num_threads = if args.threads == 0 { rayon::current_num_threads() } else { args.threads };
+    let duration = Duration::from_secs(args.compare_secs);
+    println!(
+        "使用 {} 个线程与 {} 个目标地址比较，运行 {} 秒（Ctrl+C 提前结束）",
+        num_threads,
+        targets.len(),
+        args.compare_secs
+    );
+    let search = Search::new(targets);
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let interrupt = Arc::clone(&interrupted);
+    ctrlc::set_handler(move || {
+        if interrupt.swap(true, Ordering::Relaxed) {
+            std::process::exit(130);
+        }
+    })
+    .expect("无法注册 Ctrl+C 处理函数");
+
+    let progress = ProgressBar::new_spinner();
+    progress.set_style(ProgressStyle::default_spinner().template("{spinner:.green} {msg}").unwrap());
+    std::thread::scope(|scope| {
+        // 状态显示，同时检查运行时长和 Ctrl+C
+        scope.spawn(|| {
+            while !search.is_stopped() {
+                if interrupted.load(Ordering::Relaxed) || search.active_elapsed() >= duration {
+                    search.stop();
+                }
+                let stats = search.stats();
+                let best = stats.best_partial.iter().max().copied().unwrap_or(0);
+                progress.set_message(format!(
+                    "已生成: {} | 速率: {:.2}/秒 | 最长重合: {} 个字符 | 剩余 {:.0} 秒",
+                    stats.generated,
+                    stats.rate,
+                    best,
+                    duration.saturating_sub(stats.elapsed).as_secs_f64()
+                ));
+                progress.tick();
+                std::thread::sleep(Duration::from_millis(200));
+            }
+        });
+
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .unwrap();
+        thread_pool.install(|| {
+            (0..num_threads).into_par_iter().for_each(|worker| {
+                let mut keygen = worker_generator(args, rng, extra_entropy.as_ref(), worker as u64, num_threads);
+                let patterns = search.patterns();
+                while !search.is_stopped() {
+                    for _ in 0..COMPARE_BATCH_SIZE {
+                        let address = keygen.generate().pubkey().to_string();
+                        patterns.track_partial(&address, None);
+                    }
+                    search.record_batch(COMPARE_BATCH_SIZE, &mut MatchCounts::default());
+                }
+            });
+        });
+    });
+    progress.finish_and_clear();
+
+    let stats = search.stats();
+    println!(
+        "共生成 {} 个地址，用时 {:.1} 秒，平均速率 {:.2}/秒",
+        stats.generated,
+        stats.elapsed.as_secs_f64(),
+        stats.rate
+    );
+    // 表头的汉字在终端中占两列，宽度按显示宽度扣掉
+    println!("{:<40}  {:>2}  {:>8}  {:>8}  最接近的地址", "目标地址", "重合", "期望尝试", "再多一个");
+    for closest in compare::closest(&search) {
+        println!(
+            "{:<44}  {:>4}  {:>12.3e}  {:>12.3e}  {}",
+            closest.target,
+            closest.matched,
+            closest.expected_attempts,
+            closest.next_attempts,
+            closest.address.as_deref().unwrap_or("-")
+        );
+    }
+    println!("期望尝试：随机地址开头与目标重合这么多个字符平均需要的尝试次数；再多一个：多重合一个字符需要的次数");
+    Ok(())
+}
+
+/// 多任务模式下可以在命令行上指定的参数，其余设置写在各任务的配置中
+const JOBS_ARGS: [&str; 7] = ["jobs", "threads", "rng", "extra_entropy_file", "insecure_seed", "offline", "role"];
+
+/// 多任务模式每次分给一个任务的密钥数
+const JOB_BATCH_SIZE: u64 = 256;
+
+/// --jobs：在一个工作线程池上按份额同时运行配置文件中的多个任务
+///
+/// 工作线程每批开始前选出一个仍在运行的任务，匹配交给该任务自己的写入线程；
+/// 任务达到 max_matches 或 max_secs 后结束，份额分给其余任务，全部结束或按 Ctrl+C 时退出。
+fn run_jobs(args: &Args, matches: &ArgMatches, path: &str) -> io::Result<()> {
+    let conflicting = explicit_args_except(matches, &JOBS_ARGS);
+    if !conflicting.is_empty() {
+        exit_with_error(format!(
+            "--jobs 模式下各任务的前缀、输出文件和停止条件写在配置文件中，不能与 {} 同时使用",
+            conflicting.join("、")
+        ));
+    }
+    let jobs = jobs::load(path).unwrap_or_else(|err| exit_with_error(err));
+    // 每个任务的配置与命令行合并后，与单任务一样填入角色默认值并检查选项组合；
+    // 匹配文件总是按任务区分，不用角色的默认文件，否则几个任务会写进同一个文件
+    let job_args: Vec<Args> = jobs
+        .iter()
+        .map(|job| {
+            let mut job_args = args.clone();
+            job_args.jobs = None;
+            job_args.prefixes = job.prefixes.clone();
+            job_args.matched_output = job.matched_output();
+            job_args.output = job.output();
+            job_args.max_matches = job.max_matches;
+            apply_role_defaults(&mut job_args, true);
+            validate_args(&job_args).unwrap_or_else(|err| exit_with_error(format!("任务 {}: {}", job.name, err)));
+            job_args
+        })
+        .collect();
+    let shares = jobs::shares(&jobs);
+    let (rng, extra_entropy) = setup_rng(args);
+    let num_threads = if args.threads == 0 { rayon::current_num_threads() } else { args.threads };
+    println!("使用 {} 个线程运行 {} 个任务:", num_threads, jobs.len());
+
+    // 每个任务有自己的搜索状态和写入线程
+    let mut searches = Vec::with_capacity(jobs.len());
+    let mut senders = Vec::with_capacity(jobs.len());
+    let mut writer_handles = Vec::with_capacity(jobs.len());
+    for ((job, job_args), share) in jobs.iter().zip(&job_args).zip(&shares) {
+        let search = Search::new(job_args.prefixes.iter().cloned());
+        let matched_output = &job_args.matched_output;
+        let options = WriterOptions {
+            keypair_dir: job_args.keypair_dir.as_ref().map(Into::into),
+            ..WriterOptions::default()
+        };
+        let output_writer = OutputWriter::create(matched_output, &job_args.output, options)?;
+        let (sender, receiver) = mpsc::sync_channel(writer::CHANNEL_CAPACITY);
+        writer_handles.push(output_writer.spawn(receiver));
+        senders.push(sender);
+        let limits: Vec<String> = job
+            .max_matches
+            .map(|max| format!("找到 {} 个匹配", max))
+            .into_iter()
+            .chain(job.max_secs.map(|secs| format!("运行 {} 秒", secs)))
+            .collect();
+        let limits = if limits.is_empty() {
+            "不自动结束".to_string()
+        } else {
+            format!("{}后结束", limits.join("或"))
+        };
+        println!(
+            "  [{}] 份额 {:.0}%，前缀 {:?}，写入 {}，{}",
+            job.name,
+            share * 100.0,
+            search.prefixes(),
+            matched_output,
+            limits
+        );
+        searches.push(search);
+    }
+
+    // 第一次 Ctrl+C 结束所有任务，第二次立即退出
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let interrupt = Arc::clone(&interrupted);
+    ctrlc::set_handler(move || {
+        if interrupt.swap(true, Ordering::Relaxed) {
+            std::process::exit(130);
+        }
+    })
+    .expect("无法注册 Ctrl+C 处理函数");
+
+    let multi_progress = MultiProgress::new();
+    let job_progress: Vec<ProgressBar> = jobs
+        .iter()
+        .map(|_| {
+            let bar = multi_progress.add(ProgressBar::new_spinner());
+            bar.set_style(ProgressStyle::default_spinner().template("{spinner:.green} {msg}").unwrap());
+            bar
+        })
+        .collect();
+    let finished: Vec<AtomicBool> = jobs.iter().map(|_| AtomicBool::new(false)).collect();
+    // 结束一个任务：停止计时并不再分配批次，只有第一次调用会打印
+    let finish = |index: usize, reason: &str| {
+        if !finished[index].swap(true, Ordering::Relaxed) {
+            searches[index].stop();
+            searches[index].pause();
+            multi_progress.suspend(|| println!("[{}] 任务结束：{}", jobs[index].name, reason));
+        }
+    };
+    let active = |index: usize| !interrupted.load(Ordering::Relaxed) && !searches[index].is_stopped();
+
+    let workers_done = AtomicBool::new(false);
+    // 每个任务的匹配各自按发现顺序编号，写入 index 列
+    let match_indices: Vec<AtomicU64> = jobs.iter().map(|_| AtomicU64::new(0)).collect();
+    // 每个任务的 max_matches 名额，计数和写入之前先占，多个线程同时找到匹配时也不会超出
+    let limits: Vec<Option<MatchLimit>> =
+        jobs.iter().map(|job| job.max_matches.map(|max| MatchLimit::new(max, Then::Stop))).collect();
+    std::thread::scope(|scope| {
+        // 状态显示：每个任务一行，同时检查 max_secs
+        scope.spawn(|| {
+            while !workers_done.load(Ordering::Relaxed) {
+                let active_share: f64 = (0..jobs.len()).filter(|index| active(*index)).map(|index| shares[index]).sum();
+                for (index, job) in jobs.iter().enumerate() {
+                    let search = &searches[index];
+                    if let Some(secs) = job.max_secs
+                        && search.active_elapsed() >= Duration::from_secs(secs)
+                    {
+                        finish(index, "已到运行时长");
+                    }
+                    let stats = search.stats();
+                    let matched = match job.max_matches {
+                        Some(max) => format!("{} / {}", stats.matched, max),
+                        None => stats.matched.to_string(),
+                    };
+                    let state = if active(index) {
+                        format!("当前份额 {:.0}%", shares[index] / active_share * 100.0)
+                    } else {
+                        "已结束".to_string()
+                    };
+                    job_progress[index].set_message(format!(
+                        "[{}] {} | 已生成: {} | 速率: {:.2}/秒 | 匹配: {} | 用时 {:.0} 秒",
+                        job.name,
+                        state,
+                        stats.generated,
+                        stats.rate,
+                        matched,
+                        stats.elapsed.as_secs_f64()
+                    ));
+                    job_progress[index].tick();
+                }
+                std::thread::sleep(Duration::from_millis(200));
+            }
+        });
+
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .unwrap();
+        thread_pool.install(|| {
+            (0..num_threads).into_par_iter().for_each(|worker| {
+                let mut keygen = worker_generator(args, rng, extra_entropy.as_ref(), worker as u64, num_threads);
+                let mut picker = Picker::new(jobs.len());
+                let patterns: Vec<_> = searches.iter().map(Search::patterns).collect();
+                let mut matches: Vec<MatchCounts> = patterns.iter().map(|patterns| MatchCounts::new(patterns.len())).collect();
+                while let Some(index) = picker.next(&shares, active) {
+                    let search = &searches[index];
+                    let patterns = &patterns[index];
+                    for _ in 0..JOB_BATCH_SIZE {
+                        let keypair = keygen.generate();
+                        let pubkey = keypair.pubkey();
+                        let address = pubkey.to_string();
+                        let indices = patterns.find_all(&address);
+                        if indices.is_empty() {
+                            continue;
+                        }
+                        if let Some(limit) = &limits[index] {
+                            if !limit.claim() {
+                                continue;
+                            }
+                            if limit.update(false).is_some() {
+                                finish(index, "已找到足够的匹配");
+                            }
+                        }
+                        matches[index].credit(&indices);
+                        let prefix = patterns.matched_text(&indices, &address);
+                        let shown = highlight(&address, &patterns.matched_spans(&indices, &address));
+                        multi_progress.suspend(|| println!("[{}] 找到 {}（前缀 {}）", jobs[index].name, shown, prefix));
+                        let mut keypair_bytes = [0u8; 64];
+                        keypair_bytes[..32].copy_from_slice(keypair.secret().as_ref());
+                        keypair_bytes[32..].copy_from_slice(pubkey.as_ref());
+                        let record = Record {
+                            address,
+                            prefix,
+                            keypair: keypair_bytes,
+                            luck: None,
+                            index: Some(match_indices[index].fetch_add(1, Ordering::Relaxed) + 1),
+                            known: None,
+                        };
+                        keypair_bytes.zeroize();
+                        if senders[index].send(Message::Matched(record)).is_err() {
+                            finish(index, "写入失败");
+                        }
+                    }
+                    search.record_batch(JOB_BATCH_SIZE, &mut matches[index]);
+                }
+            });
+        });
+        workers_done.store(true, Ordering::Relaxed);
+    });
+    for bar in &job_progress {
+        bar.finish();
+    }
+    if interrupted.load(Ordering::Relaxed) {
+        println!("已中断，正在保存各任务的结果");
+    }
+
+    // 与单任务模式相同：工作线程都已退出，丢弃发送端后各写入线程写完排队的记录才结束
+    drop(senders);
+    for ((job, search), writer_handle) in jobs.iter().zip(&searches).zip(writer_handles) {
+        let stats = search.stats();
+        let written = writer_handle.join().unwrap()?;
+        println!("[{}] 结果已写入 {}", job.name, job.matched_output());
+        if written.matches.len() as u64 != stats.matched {
+            eprintln!(
+                "错误：任务 {} 统计到 {} 个匹配，但只写入了 {} 个，请检查输出文件",
+                job.name,
+                stats.matched,
+                written.matches.len()
+            );
+        }
+        print_summary(&stats);
+    }
+    Ok(())
+}
+
+/// shm-tail 子命令：按顺序读取共享内存缓冲区中的记录，只打印序号、地址和前缀
+#[cfg(feature = "shm")]
+fn shm_tail(name: &str, from_start: bool) -> io::Result<()> {
+    let path = shm::resolve_path(name);
+    let reader = ShmReader::open(&path).unwrap_or_else(|err| exit_with_error(format!("{}: {}", path.display(), err)));
+    let written = reader.write_sequence();
+    let mut next = if from_start { written.saturating_sub(reader.slots()) } else { written };
+    println!("正在读取 {}（{} 个槽位，已写入 {} 条），Ctrl+C 退出", path.display(), reader.slots(), written);
+    loop {
+        match reader.read(next) {
+            ShmRead::Entry(entry) => {
+                println!("{}\t{}\t{}", entry.sequence, entry.address, entry.prefix);
+                next += 1;
+            }
+            ShmRead::Pending => std::thread::sleep(Duration::from_millis(50)),
+            ShmRead::Overwritten => {
+                // 跳到仍在缓冲区中的最早一条
+                let oldest = reader.write_sequence().saturating_sub(reader.slots()).max(next + 1);
+                eprintln!("读取落后太多，第 {} 到 {} 条记录已被覆盖", next, oldest - 1);
+                next = oldest;
+            }
+        }
+    }
+}
+
+/// simulate 子命令：并行运行各次试验，打印经验分布与解析预测的对比，不通过时退出码为 1
+fn simulate(
+    prefixes: &[String],
+    trials: usize,
+    max_attempts: u64,
+    threads: usize,
+    rng: RngSource,
+    tolerance: f64,
+) -> io::Result<()> {
+    validate_prefixes(prefixes);
+    if trials == 0 || max_attempts == 0 {
+        exit_with_error("--trials 和 --max-attempts 必须大于 0");
+    }
+    let search = Search::new(prefixes.iter().cloned());
+    let probability: f64 = difficulty::effective_probabilities(&search).iter().sum();
+    if probability == 0.0 {
+        exit_with_error("这些前缀的解析概率为 0，无法模拟");
+    }
+    let num_threads = if threads == 0 { rayon::current_num_threads() } else { threads };
+    // 截断在上限处的几何分布的均值：(1 - (1-p)^上限) / p
+    let per_trial = -(max_attempts as f64 * (-probability).ln_1p()).exp_m1() / probability;
+    println!(
+        "前缀 {:?}：解析概率 {:.4e}，期望每次试验 {:.0} 次尝试；{} 次试验，每次最多 {} 次，预计共约 {:.3e} 次尝试，使用 {} 个线程",
+        search.prefixes(),
+        probability,
+        1.0 / probability,
+        trials,
+        max_attempts,
+        per_trial * trials as f64,
+        num_threads
+    );
+
+    let progress = ProgressBar::new(trials as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{bar:40.cyan/blue} {pos}/{len} 次试验 [{elapsed_precise}]")
+            .unwrap(),
+    );
+    let thread_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()
+        .unwrap();
+    let outcomes: Vec<Option<u64>> = thread_pool.install(|| {
+        (0..trials)
+            .into_par_iter()
+            .map_init(
+                || KeyGenerator::new(rng),
+                |keygen, _| {
+                    let outcome = simulate::run_trial(&search, keygen, max_attempts);
+                    progress.inc(1);
+                    outcome
+                },
+            )
+            .collect()
+    });
+    progress.finish_and_clear();
+
+    let report = simulate::Report::new(&outcomes, max_attempts, probability);
+    println!(
+        "命中 {} / {} 次试验（{} 次达到上限），共 {} 次尝试",
+        report.hits,
+        report.trials,
+        report.trials as u64 - report.hits,
+        report.total_attempts
+    );
+    println!(
+        "概率：经验 {:.4e}，解析 {:.4e}，相对偏差 {:+.2}%",
+        report.empirical_probability,
+        report.probability,
+        (report.empirical_probability / report.probability - 1.0) * 100.0
+    );
+    if let Some(mean) = report.mean {
+        println!("平均尝试次数：经验 {:.0}，解析 {:.0}", mean, 1.0 / report.probability);
+    }
+    println!("首次命中所需尝试次数的分位点：");
+    for quantile in &report.quantiles {
+        let empirical = quantile
+            .empirical
+            .map_or_else(|| format!("> {}", max_attempts), |attempts| attempts.to_string());
+        println!("  {:>3.0}%  经验 {:>12}  解析 {:>12.0}", quantile.q * 100.0, empirical, quantile.analytic);
+    }
+    if report.passes(tolerance) {
+        println!("通过：命中数偏离期望 {:+.2} 个标准差（容差 {}）", report.z, tolerance);
+        Ok(())
+    } else {
+        println!("不通过：命中数偏离期望 {:+.2} 个标准差，超过容差 {}", report.z, tolerance);
+        std::process::exit(1);
+    }
+}
+
+/// combine 子命令：合并出最终扩展私钥并核对地址
+fn combine(my_secret: &str, tweak: &str, address: Option<&str>) -> io::Result<()> {
+    let combined = split_key::combine(my_secret, tweak).unwrap_or_else(|err| exit_with_error(err));
+    if let Some(expected) = address
+        && expected != combined.address
+    {
+        eprintln!("合并结果 {} 与期望的地址 {} 不一致", combined.address, expected);
+        std::process::exit(1);
+    }
+
+    println!("地址: {}", combined.address);
+    let secret = Secret::new(bs58::encode(combined.expanded_secret).into_string());
+    println!("扩展私钥: {}", secret.expose());
+    println!("注意：这是“标量 || nonce 前缀”格式的扩展私钥，不能导入 Solana 钱包或 solana-keygen");
+    Ok(())
+}
+
+/// 不带任何参数在终端中启动时的设置向导，返回等价的命令行参数；其他情况返回 `None`
+///
+/// 前缀用与 `-p` 相同的校验和难度估计，结束时打印等价的命令行，之后按这些参数正常启动。
+fn wizard_args() -> Option<Vec<String>> {
+    if std::env::args_os().len() > 1 || !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return None;
+    }
+    let program = std::env::args().next().unwrap_or_else(|| env!("CARGO_PKG_NAME").to_string());
+    // 在终端中读到文件结尾（Ctrl+D）说明用户放弃了
+    let answer = |question: &str| ask(question, None).unwrap_or_else(|| std::process::exit(0));
+    println!("没有指定任何参数，进入设置向导（Ctrl+C 退出）");
+
+    let prefixes = loop {
+        let prefixes: Vec<String> = answer("要查找的地址前缀，多个用逗号分隔: ")
+            .split(',')
+            .map(str::trim)
+            .filter(|prefix| !prefix.is_empty())
+            .map(str::to_string)
+            .collect();
+        if prefixes.is_empty() {
+            println!("至少需要一个前缀");
+            continue;
+        }
+        if let Err(err) = prefixes.iter().try_for_each(|prefix| search::validate_prefix(prefix)) {
+            println!("{}", err);
+            continue;
+        }
+        let mut possible = true;
+        for prefix in &prefixes {
+            let attempts = difficulty::expected_attempts(prefix, false);
+            if attempts.is_finite() {
+                println!(
+                    "  {}: 匹配概率约 {:.3e}（期望尝试次数约 {:.0} 次）",
+                    prefix,
+                    1.0 / attempts,
+                    attempts
+                );
+            } else {
+                println!("  {}: 任何地址都不可能以它开头", prefix);
+                possible = false;
+            }
+        }
+        if possible {
+            break prefixes;
+        }
+    };
+
+    let max_matches = loop {
+        let count = answer("找到几个后结束（留空则一直运行到按 Ctrl+C）: ");
+        if count.is_empty() {
+            break None;
+        }
+        match count.parse::<u64>() {
+            Ok(count) if count > 0 => break Some(count),
+            _ => println!("请输入正整数，或者直接回车"),
+        }
+    };
+    let output = answer(&format!("匹配结果保存到（留空使用 {}）: ", DEFAULT_MATCHED_OUTPUT));
+
+    let mut argv = vec![program, "-p".to_string(), prefixes.join(",")];
+    if let Some(count) = max_matches {
+        argv.extend(["--max-matches".to_string(), count.to_string()]);
+    }
+    if !output.is_empty() {
+        argv.extend(["--matched-output".to_string(), output]);
+    }
+    let command: Vec<String> = argv.iter().map(|arg| quote_arg(arg)).collect();
+    println!("等价的命令行: {}", command.join(" "));
+    Some(argv)
+}
+
+/// 命令行参数只含安全字符时原样显示，否则加引号
+fn quote_arg(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "-_./,:=".contains(c)) {
+        value.to_string()
+    } else {
+        writer::shell_quote(value)
+    }
+}
+
+/// 确定随机数来源并打印，然后做启动自检，自检失败时拒绝运行，不生成可能很弱的密钥
+fn setup_rng(args: &Args) -> (RngSource, Option<ExtraEntropy>) {
+    // 额外熵只能与 chacha 组合，未指定 --rng 时自动使用 chacha
+    let extra_entropy = args.extra_entropy_file.as_deref().map(read_extra_entropy);
+    let rng = match (args.rng, &extra_entropy) {
+        (None, Some(_)) => RngSource::Chacha,
+        (rng, _) => rng.unwrap_or_default(),
+    };
+
+    match args.insecure_seed {
+        Some(seed) => println!("警告：使用固定种子 {}，生成的密钥可被预测，仅供测试，切勿用于真实资产", seed),
+        None => println!("随机数来源: {}", rng),
+    }
+    if let Some(extra) = &extra_entropy {
+        println!("已混入额外熵，指纹: {}", extra.fingerprint());
+    }
+    let mut check_generator = worker_generator(args, rng, extra_entropy.as_ref(), u64::MAX, 1);
+    match keygen::self_check(&mut check_generator) {
+        Ok(health) => println!("随机数自检通过：{}", health),
+        Err(err) => exit_with_error(format!("随机数自检失败，拒绝生成密钥: {}", err)),
+    }
+    (rng, extra_entropy)
+}
+
+/// `threads` 个工作线程中第 `worker` 个的密钥生成器
+fn worker_generator(
+    args: &Args,
+    rng: RngSource,
+    extra: Option<&ExtraEntropy>,
+    worker: u64,
+    threads: usize,
+) -> KeyGenerator {
+    match args.insecure_seed {
+        Some(seed) => KeyGenerator::seeded(seed, worker, threads as u64),
+        None => KeyGenerator::with_extra_entropy(rng, extra, worker),
+    }
+}
+
+/// 实际使用了的可能访问网络的选项，交给 [`offline::validate`]；新增网络功能时必须加在这里。
+/// --on-match 执行任意外部命令，无法保证不联网，一并列入
+fn network_options(args: &Args) -> Vec<&'static str> {
+    [
+        args.on_match.is_some().then_some("--on-match"),
+        args.webhook_url.is_some().then_some("--webhook-url"),
+        args.s3_upload.is_some().then_some("--s3-upload"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+fn main() -> io::Result<()> {
+    let matches = match wizard_args() {
+        Some(argv) => Args::command().get_matches_from(argv),
+        None => Args::command().get_matches(),
+    };
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    secret::set_reveal_partial(args.reveal_partial);
+    args.color.apply();
+
+    if args.offline {
+        offline::validate(&network_options(&args)).unwrap_or_else(|err| exit_with_error(err));
+        offline::enable();
+        println!("OFFLINE MODE：已禁用所有网络功能");
+    }
+
+    match &args.command {
+        Some(Command::Verify { files, watch_list }) => return verify(files, watch_list.as_deref()),
+        Some(Command::GrindSplit { partner_pubkey, prefixes, threads, output }) => {
+            return grind_split(partner_pubkey, prefixes, *threads, output);
+        }
+        Some(Command::GrindSquads { program, vault_index, prefixes, threads, output }) => {
+            return grind_squads(program, *vault_index, prefixes, *threads, output);
+        }
+        Some(Command::Simulate { prefixes, trials, max_attempts, threads, rng, tolerance }) => {
+            return simulate(prefixes, *trials, *max_attempts, *threads, *rng, *tolerance);
+        }
+        Some(Command::Recover { files }) => return recover(files),
+        Some(Command::Combine { my_secret, tweak, address }) => {
+            return combine(my_secret, tweak, address.as_deref());
+        }
+        Some(Command::Install { address, path, file }) => {
+            return install_keypair(address, path.as_deref(), file);
+        }
+        Some(Command::Inspect { secret }) => return inspect_secret(secret.as_deref()),
+        Some(Command::BuildFilter { input, output, fp_rate }) => return build_filter(input, output, *fp_rate),
+        Some(Command::Runs { command: RunsCommand::List { audit_log } }) => {
+            return list_runs(audit_log.as_deref());
+        }
+        #[cfg(feature = "keyring")]
+        Some(Command::KeyringGet { reference, address }) => return keyring_get(reference, address.as_deref()),
+        #[cfg(feature = "shm")]
+        Some(Command::ShmTail { name, from_start }) => return shm_tail(name, *from_start),
+        None => {}
+    }
+
+    if let Some(path) = &args.jobs {
+        return run_jobs(&args, &matches, path);
+    }
 
-    /// 线程数量
-    #[arg(short, long, default_value_t = 0)]
-    threads: usize,
+    if let Some(path) = &args.compare_addresses {
+        return run_compare(&args, &matches, path);
+    }
 
-    /// 输出文件
-    #[arg(short, long, default_value = "data/solana_addresses.csv")]
-    output: String,
+    if args.tune_only {
+        tuned_threads(TuneMode::Run, args.tune_cache.as_deref(), args.rng.unwrap_or_default());
+        return Ok(());
+    }
 
-    /// 匹配地址的输出文件
-    #[arg(short, long, default_value = "data/matched_addresses.csv")]
-    matched_output: String,
-}
+    apply_role_defaults(&mut args, matches.value_source("matched_output") == Some(ValueSource::CommandLine));
+    validate_args(&args).unwrap_or_else(|err| exit_with_error(err));
 
-fn main() -> io::Result<()> {
-    let args = Args::parse();
+    let (rng, extra_entropy) = setup_rng(&args);
+
+    // 占位符在校验之前展开，之后一律使用展开后的前缀
+    let expanded_prefixes = expand_prefixes(&args.prefixes, &args.run_counter_file);
+
+    // 准备搜索目标，计数器由 Search 统一管理
+    // 只给了字节过滤、字符计数或形状条件时，用空前缀表示不限制前缀
+    // 原始字节模式、回文和镜像匹配器、模糊前缀与文本前缀放在一起，是“任一命中即匹配”的关系
+    // --leading-ones N 时文本前缀接在 N 个 1 之后
+    let ones = "1".repeat(args.leading_ones.unwrap_or(0) as usize);
+    let mut prefixes: Vec<Matcher> =
+        expanded_prefixes.iter().map(|prefix| Matcher::Prefix(format!("{}{}", ones, prefix))).collect();
+    prefixes.extend(args.byte_prefix.iter().chain(&args.byte_suffix).cloned().map(Matcher::Bytes));
+    prefixes.extend(args.palindrome.iter().chain(&args.mirror).copied().map(Matcher::Symmetry));
+    if let Some(word) = &args.fuzzy {
+        let fuzzy = Fuzzy::new(word, args.max_distance).unwrap_or_else(|message| exit_with_error(message));
+        prefixes.push(Matcher::Fuzzy(fuzzy));
+    }
+    let has_condition = args.first_byte_max.is_some()
+        || !args.value_mod.is_empty()
+        || args.leading_ones.is_some()
+        || args.max_length.is_some()
+        || !args.count_char.is_empty()
+        || !args.and_conditions.is_empty()
+        || args.pattern.is_some();
+    if prefixes.is_empty() && has_condition {
+        prefixes.push(Matcher::Prefix(ones.clone()));
+    }
+    // --per-prefix 与 --append：已有匹配文件中找够的前缀不再搜索
+    let mut loaded_counts = BTreeMap::new();
+    if let Some(target) = args.per_prefix
+        && args.append
+        && Path::new(&args.matched_output).exists()
+    {
+        let loaded = prefix_quota::load_matches(&args.matched_output, &Search::new(prefixes.iter().cloned()))
+            .unwrap_or_else(|err| exit_with_error(err));
+        let labels: Vec<String> = prefixes.iter().map(Matcher::to_string).collect();
+        report_loaded(&args.matched_output, &loaded, &labels, target);
+        prefixes.retain(|prefix| loaded.counts.get(&prefix.to_string()).copied().unwrap_or(0) < target);
+        if prefixes.is_empty() {
+            println!("所有前缀都已找够 {} 个匹配，无需搜索", target);
+            return Ok(());
+        }
+        loaded_counts = loaded.counts;
+    }
+
+    let mut search = Search::new(prefixes);
+    println!("查找以下前缀: {:?}", search.prefixes());
+    print_first_char_hint(&search);
+
+    if let Some(max) = args.first_byte_max {
+        let filter = ByteFilter::FirstByteMax(max);
+        println!(
+            "公钥首字节不大于 0x{:02x}，通过概率 {:.4}（期望尝试次数约为原来的 {:.1} 倍）",
+            max,
+            filter.probability(),
+            1.0 / filter.probability()
+        );
+        search = search.with_filter(filter);
+    }
+    for filter in &args.value_mod {
+        if let ByteFilter::ValueMod { divisor, remainder } = filter {
+            println!(
+                "公钥整数除以 {} 余 {}，通过概率约 {:.3e}（期望尝试次数约为原来的 {} 倍）",
+                divisor,
+                remainder,
+                filter.probability(),
+                divisor
+            );
+        }
+        search = search.with_filter(filter.clone());
+    }
+    if let Some(count) = args.leading_ones {
+        let filter = ByteFilter::LeadingZeros(count);
+        println!(
+            "地址至少以 {} 个 1 开头（公钥前 {} 个字节为零），通过概率 {:.3e}（期望尝试次数约为原来的 {:.0} 倍）",
+            count,
+            count,
+            filter.probability(),
+            1.0 / filter.probability()
+        );
+        search = search.with_filter(filter);
+    }
+    if let Some(length) = args.max_length {
+        let filter = ByteFilter::MaxLength(length);
+        println!(
+            "地址不超过 {} 个字符，通过概率 {:.3e}（期望尝试次数约为原来的 {:.1} 倍）",
+            length,
+            filter.probability(),
+            1.0 / filter.probability()
+        );
+        search = search.with_filter(filter);
+    }
+    for condition in &args.count_char {
+        let probability = condition.probability("");
+        println!(
+            "地址中至少 {} 个 '{}'，单独满足的概率约 {:.3e}（期望尝试次数约 {:.0} 次）",
+            condition.min,
+            condition.ch,
+            probability,
+            1.0 / probability
+        );
+        search = search.with_char_count(*condition);
+    }
+    for condition in &args.and_conditions {
+        let probability = condition.probability("");
+        println!(
+            "同时要求 {}，单独满足的概率约 {:.3e}（期望尝试次数约 {:.0} 次）",
+            condition,
+            probability,
+            1.0 / probability
+        );
+        search = search.with_and_condition(condition.clone());
+    }
+    if let Some(shape) = &args.pattern {
+        let probability = shape.probability("");
+        println!(
+            "地址开头的形状为 {}，单独符合的概率约 {:.3e}（期望尝试次数约 {:.0} 次）",
+            shape,
+            probability,
+            1.0 / probability
+        );
+        search = search.with_shape(shape.clone());
+    }
+    let search = Arc::new(search.with_sample_target(args.non_matching_count));
+    let prefix_quota = args.per_prefix.map(|target| {
+        println!("每个前缀找到 {} 个匹配后不再搜索它，所有前缀都找够后结束", target);
+        let initial = search.prefixes().into_iter().map(|prefix| loaded_counts.get(&prefix).copied().unwrap_or(0));
+        PrefixQuota::new(target, initial)
+    });
+    match (args.max_matches, args.keep_searching_secs) {
+        (Some(max), Some(secs)) => {
+            let weights = args.score_weights;
+            println!(
+                "找到 {} 个匹配后再继续搜索 {} 秒，只保留美观度分数最高的 {} 个（权重 repeat={} run={} palindrome={}）",
+                max, secs, max, weights.repeat, weights.run, weights.palindrome
+            );
+        }
+        (Some(max), None) if args.then == Then::ContinueSample => println!(
+            "找到 {} 个匹配后不再匹配，继续采集非匹配地址直到 {} 个后结束",
+            max, args.non_matching_count
+        ),
+        (Some(max), None) => println!("找到 {} 个匹配后自动结束", max),
+        _ => {}
+    }
 
-    // 设置线程数，默认使用所有可用线程
+    // 各前缀的有效匹配概率，用于估计到现在应该找到的概率
+    let probabilities = difficulty::effective_probabilities(&search);
+
+    // 设置线程数，默认使用所有可用线程，--tune 时按测试结果；没有用 --threads 指定时，
+    // 有明确终点的简单搜索按期望尝试次数缩减线程数
+    let available_threads = match args.tune {
+        Some(mode) => tuned_threads(mode, args.tune_cache.as_deref(), rng),
+        None if args.threads == 0 => rayon::current_num_threads(),
+        None => args.threads,
+    };
+    let expected_attempts = expected_run_attempts(&args, &probabilities, prefix_quota.as_ref());
     let num_threads = if args.threads == 0 {
-        rayon::current_num_threads()
+        tune::scaled_threads(expected_attempts, available_threads)
     } else {
-        args.threads
+        available_threads
     };
-    println!("使用 {} 个线程", num_threads);
+    if num_threads < available_threads {
+        println!(
+            "使用 {} 个线程（共 {} 个可用，预计约 {:.0} 次尝试即可结束，用 --threads 指定可覆盖）",
+            num_threads, available_threads, expected_attempts
+        );
+    } else {
+        println!("使用 {} 个线程", num_threads);
+    }
+
+    // 审计日志：开始和结束各追加一条，写入失败只警告
+    let audit_log = args
+        .audit_log
+        .as_ref()
+        .map(PathBuf::from)
+        .or_else(audit::default_path)
+        .map(AuditLog::new);
+    let run_id = format!("{}-{}", Local::now().format("%Y%m%dT%H%M%S"), std::process::id());
+    if let Some(audit_log) = &audit_log {
+        let mut config = audit_config(&matches);
+        config.insert("threads".to_string(), vec![num_threads.to_string()]);
+        let rng_name = match args.insecure_seed {
+            Some(_) => "insecure-seed".to_string(),
+            None => rng.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or_default(),
+        };
+        config.insert("rng".to_string(), vec![rng_name]);
+        if !expanded_prefixes.is_empty() {
+            config.insert("prefixes".to_string(), expanded_prefixes.clone());
+        }
+        let start = RunStart {
+            run_id: run_id.clone(),
+            timestamp: Local::now().to_rfc3339(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_hash: audit::git_hash().map(str::to_string),
+            host: audit::host_name(),
+            config,
+        };
+        append_audit(audit_log, &AuditRecord::Start(start));
+    }
     
     // 创建本地线程池，而不是使用全局线程池
     let thread_pool = rayon::ThreadPoolBuilder::new()
@@ -51,49 +2909,237 @@ fn main() -> io::Result<()> {
         .build()
         .unwrap();
 
-    // 准备前缀集合
-    let prefixes: HashSet<String> = args.prefixes.into_iter().collect();
-    println!("查找以下前缀: {:?}", prefixes);
+    // 黑名单在所有输出之前检查，命中的地址只计数和记录，不写入任何文件
+    let blacklist = args.blacklist_file.as_ref().map(|path| {
+        let blacklist = Blacklist::load(path).unwrap_or_else(|err| exit_with_error(err));
+        println!("已加载黑名单 {}：{} 个地址", path, blacklist.len());
+        blacklist
+    });
+    let blacklisted = AtomicU64::new(0);
+
+    // 匹配的发现顺序，交给写入线程之前领取，写入 index 列
+    let match_index = AtomicU64::new(0);
+    // 固定种子时工作线程按轮同步，匹配按密钥位置排序后交出
+    let lockstep = args.insecure_seed.map(|_| Lockstep::<Found>::new(num_threads));
 
-    // 初始化计数器和文件
-    let generated = Arc::new(Mutex::new(0u64));
-    let matched = Arc::new(Mutex::new(0u64));
-    
-    // 保存前N个非匹配地址
-    let non_matching_addresses = Arc::new(Mutex::new(VecDeque::with_capacity(args.non_matching_count)));
+    // 观察名单：只比较 32 字节公钥，命中单独计数和报告
+    let watch_list = args.watch_list.as_ref().map(|path| {
+        let watch_list = WatchList::load(path).unwrap_or_else(|err| exit_with_error(err));
+        println!(
+            "已加载观察名单 {}：{} 个地址。Ed25519 公钥约有 2^{} 种，命中其中任意一个的期望尝试次数约 {:.1e} 次",
+            path,
+            watch_list.len(),
+            watch_list::PUBKEY_SPACE_LOG2,
+            watch_list.expected_attempts()
+        );
+        watch_list
+    });
+    let watch_hits = AtomicU64::new(0);
+
+    // 已知地址快照：只在匹配时检查，过滤器命中后扫描原始名单复核
+    let known_addresses = args.known_addresses.as_ref().map(|path| {
+        let started = Instant::now();
+        let known = KnownAddresses::load(path).unwrap_or_else(|err| exit_with_error(err));
+        let filter = known.filter();
+        println!(
+            "已加载已知地址 {}：{} 个地址，过滤器 {:.1} MB，误判率约 {:.1e}，用时 {:.1} 秒",
+            path,
+            filter.len(),
+            filter.size_bytes() as f64 / 1e6,
+            filter.false_positive_rate(),
+            started.elapsed().as_secs_f64()
+        );
+        if known.source().is_none() {
+            eprintln!("警告：原始名单 {} 不存在，过滤器命中将无法复核", filter.source());
+        }
+        known
+    });
+    let known_hits = Mutex::new(Vec::new());
+    let known_false_positives = AtomicU64::new(0);
 
-    // 在创建输出文件之前，确保目录存在
-    let output_dir = std::path::Path::new(&args.output).parent().unwrap_or_else(|| std::path::Path::new("."));
-    let matched_output_dir = std::path::Path::new(&args.matched_output).parent().unwrap_or_else(|| std::path::Path::new("."));
+    // 限速：总速率平均分给各个工作线程
+    if let Some(rate) = args.limit_rate {
+        println!("生成速率限制为 {} 个/秒，预计耗时会相应变长", rate);
+    }
+    let worker_rate = args.limit_rate.map(|rate| rate / num_threads as f64);
 
-    if !output_dir.exists() {
-        std::fs::create_dir_all(output_dir)?;
+    // 创建输出文件，之后由写入线程独占
+    let pubkey_column = args.include_pubkey_bytes.then_some(Column::PubkeyHex);
+    let extra_columns: Vec<Column> = pubkey_column
+        .into_iter()
+        .chain(args.derive_ata.iter().map(|mint| Column::Ata(*mint)))
+        .chain(args.count_char.iter().map(|condition| Column::CharCount(condition.ch)))
+        .chain(
+            args.luck
+                .then_some([Column::Attempts, Column::Luck, Column::LuckPercentile])
+                .into_iter()
+                .flatten(),
+        )
+        .chain(args.known_addresses.is_some().then_some(Column::Known))
+        .collect();
+    let matched_schema = build_schema(
+        args.matched_format.unwrap_or(args.format),
+        args.matched_columns.as_deref().unwrap_or(&args.columns),
+        args.row_hash,
+        &extra_columns,
+    );
+    let sample_schema = build_schema(
+        args.sample_format.unwrap_or(args.format),
+        args.sample_columns.as_deref().unwrap_or(&args.columns),
+        args.row_hash,
+        pubkey_column.as_slice(),
+    );
+    let (matched_schema, sample_schema) = if args.both_secret_forms {
+        (matched_schema.with_both_secret_forms(), sample_schema.with_both_secret_forms())
+    } else {
+        (matched_schema, sample_schema)
+    };
+    let (matched_schema, sample_schema) = match &args.tag {
+        Some(tag) => (matched_schema.with_source(tag), sample_schema.with_source(tag)),
+        None => (matched_schema, sample_schema),
+    };
+    let (matched_schema, sample_schema) = if args.excel_compat {
+        (matched_schema.with_excel_compat(), sample_schema.with_excel_compat())
+    } else {
+        (matched_schema, sample_schema)
+    };
+    // --split-output：私钥列移到单独的文件，匹配文件成为公开索引
+    let (matched_schema, split_output) = match &args.split_output {
+        Some(path) => {
+            let (public, secrets) = matched_schema.split_secrets();
+            (public, Some((PathBuf::from(path), secrets)))
+        }
+        None => (matched_schema, None),
+    };
+    // --output-append-secret-env：私钥列换成 secret_ref 列，私钥只存入系统密钥存储
+    let matched_schema = if secret_store(&args) { matched_schema.with_secret_ref() } else { matched_schema };
+    let disk_guard = (args.min_free_disk > 0 && !args.output_none && !args.no_secret).then(|| {
+        let mut paths = vec![PathBuf::from(&args.matched_output), PathBuf::from(&args.output)];
+        paths.extend(args.keypair_dir.iter().chain(&args.split_output).map(PathBuf::from));
+        if args.shamir.is_some() {
+            paths.push(PathBuf::from(&args.shamir_dir));
+        }
+        Arc::new(DiskGuard::new(paths, args.min_free_disk))
+    });
+    if let Some(guard) = &disk_guard {
+        check_disk_space(guard, args.non_matching_count);
     }
-    if !matched_output_dir.exists() && matched_output_dir != output_dir {
-        std::fs::create_dir_all(matched_output_dir)?;
+    let mut writer_options = WriterOptions {
+        matched_schema,
+        sample_schema,
+        keypair_dir: args.keypair_dir.as_ref().map(Into::into),
+        on_match: args.on_match.clone(),
+        webhook_url: args.webhook_url.clone(),
+        shamir: args.shamir.map(|scheme| (scheme, args.shamir_dir.clone().into())),
+        rank: args.rank.then(|| RankOptions {
+            top_n: args.top_n.max(1),
+            path: args.rank_output.clone().into(),
+            threshold: Arc::new(Threshold::default()),
+        }),
+        keep_best: args
+            .keep_searching_secs
+            .and(args.max_matches)
+            .map(|max| (max as usize, args.score_weights)),
+        #[cfg(feature = "shm")]
+        shm: args.shm.as_deref().map(|name| (shm::resolve_path(name), args.shm_slots)),
+        rotate_every: args.rotate_every.map(Duration::from_secs),
+        disk_guard: disk_guard.clone(),
+        append: args.append,
+        split_output,
+        secret_store: secret_store(&args),
+    };
+    check_schemas(&mut writer_options);
+    let plaintext_upload = args.s3_upload.is_some() && writer_options.matched_schema.has_secret();
+    if plaintext_upload && !args.s3_upload_plaintext {
+        exit_with_error(format!(
+            "{} 含有明文私钥，--s3-upload 默认不上传：请用 --split-output 把私钥留在本机，或用 --shamir 只写分享；\
+             确认桶不可公开访问并已加密后，可以加 --s3-upload-plaintext 强制上传",
+            args.matched_output
+        ));
     }
-    
-    // 创建输出文件
-    let output_file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(&args.output)?;
-    let output_writer = Arc::new(Mutex::new(BufWriter::new(output_file)));
-    
-    // 匹配地址输出文件
-    let matched_output_file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(&args.matched_output)?;
-    let matched_output_writer = Arc::new(Mutex::new(BufWriter::new(matched_output_file)));
-    
-    // 写入CSV标题
+    if let Some(dir) = &writer_options.keypair_dir
+        && let Some(usage) = args.role.usage(&format!("{}/<地址>.json", dir.display()))
     {
-        writeln!(output_writer.lock().unwrap(), "address,private_key")?;
-        writeln!(matched_output_writer.lock().unwrap(), "address,private_key")?;
+        println!("用途 {}：匹配写入 {}，创建账户时使用：{}", args.role.name(), args.matched_output, usage);
+    }
+    if !args.derive_ata.is_empty() {
+        let mints: Vec<String> = args.derive_ata.iter().map(ata::Mint::to_string).collect();
+        println!("匹配地址将附带以下代币的关联代币账户: {}", mints.join(", "));
+    }
+    if let Some((scheme, dir)) = &writer_options.shamir {
+        println!("匹配私钥将拆分为 {} 份 Shamir 分享写入 {}，不保存明文私钥", scheme, dir.display());
     }
+    if let Some((path, secrets)) = &writer_options.split_output {
+        println!(
+            "匹配私钥单独写入 {}（{}），{} 只含公开的列（{}），私钥文件请离线保管",
+            path.display(),
+            secrets.header().join(","),
+            args.matched_output,
+            writer_options.matched_schema.header().join(",")
+        );
+    }
+    #[cfg(feature = "keyring")]
+    if writer_options.secret_store {
+        let tool = keyring::tool().unwrap_or_else(|err| exit_with_error(err));
+        keyring::self_check().unwrap_or_else(|err| exit_with_error(format!("系统密钥存储不可用: {}", err)));
+        println!(
+            "匹配私钥只存入系统密钥存储（{}，服务名 {}），{} 的 secret_ref 列只写引用，用 keyring-get 子命令取回",
+            tool,
+            keyring::SERVICE,
+            args.matched_output
+        );
+    }
+    if let Some(template) = &writer_options.on_match {
+        writer::validate_hook(template, writer_options.keypair_dir.as_deref())
+            .unwrap_or_else(|err| exit_with_error(err));
+    }
+    #[cfg(feature = "shm")]
+    if let Some((path, slots)) = &writer_options.shm {
+        println!(
+            "匹配将实时写入共享内存 {}（{} 个槽位，每个 {} 字节，含明文私钥），可用 shm-tail 子命令查看",
+            path.display(),
+            slots,
+            shm::SLOT_SIZE
+        );
+    }
+    let rank_threshold = writer_options.rank.as_ref().map(|rank| Arc::clone(&rank.threshold));
+    if let Some(rank) = &writer_options.rank {
+        let weights = args.score_weights;
+        println!(
+            "排行榜保留分数最高的 {} 个地址，结束时写入 {}（权重 repeat={} run={} palindrome={}）",
+            rank.top_n,
+            rank.path.display(),
+            weights.repeat,
+            weights.run,
+            weights.palindrome
+        );
+    }
+    // --output-none 和 --no-secret 时不创建写入线程和任何文件，匹配只打印地址
+    let session = args.no_secret.then(|| Arc::new(SessionStore::new(args.session_capacity)));
+    let export_schema = writer_options.matched_schema.clone();
+    let (sender, writer_handle) = if args.output_none {
+        println!("不保存任何结果：不创建输出文件，匹配的地址只打印到终端");
+        (None, None)
+    } else if args.no_secret {
+        println!(
+            "会话模式：私钥只保存在内存中（最多 {} 个），不写盘也不打印，结束时选择要导出的记录",
+            args.session_capacity
+        );
+        (None, None)
+    } else {
+        if let Some(seconds) = args.rotate_every {
+            let example = writer::rotated_path(Path::new(&args.matched_output), Local::now());
+            println!(
+                "匹配文件每 {} 秒轮换一次：完成的一段改名为 {} 这样带开始时间的文件名",
+                seconds,
+                example.display()
+            );
+        }
+        let output_writer = OutputWriter::create(&args.matched_output, &args.output, writer_options)
+            .unwrap_or_else(|err| exit_with_error(err.to_string()));
+        let (sender, receiver) = mpsc::sync_channel(writer::CHANNEL_CAPACITY);
+        (Some(sender), Some(output_writer.spawn(receiver)))
+    };
 
     // 设置进度条
     let multi_progress = MultiProgress::new();
@@ -111,94 +3157,893 @@ fn main() -> io::Result<()> {
             .unwrap(),
     );
 
+    let partial_progress = multi_progress.add(ProgressBar::new_spinner());
+    partial_progress.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.yellow} {msg}")
+            .unwrap(),
+    );
+
+    let probability_progress = multi_progress.add(ProgressBar::new_spinner());
+    probability_progress.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.magenta} {msg}")
+            .unwrap(),
+    );
+
+    // 吞吐量日志，独立于主输出文件
+    let mut rate_log = match &args.rate_log {
+        Some(path) => Some(RateLog::open(path, num_threads)?),
+        None => None,
+    };
+    let rate_log_interval = Duration::from_secs(args.rate_log_interval.max(1));
+    // --heartbeat 的纯文本进度行，与进度条互不影响
+    let mut heartbeat_line = args
+        .heartbeat
+        .map(|seconds| (Duration::from_secs(seconds), HeartbeatLine::new()));
+    // 尝试次数日志，各工作线程在匹配时直接追加
+    let attempts_log = match &args.attempts_log {
+        Some(path) => Some(AttemptsLog::open(path)?),
+        None => None,
+    };
+
+    // 摘要报告：上次运行停在运行中的快照说明它没有正常结束
+    if let Some(path) = &args.report {
+        match Summary::load(path) {
+            Ok(Some(previous)) if previous.is_running() => println!(
+                "上次运行 {} 没有正常结束，最后的快照（{}）：已生成 {} 个地址，匹配 {} 个",
+                previous.run_id, previous.updated_at, previous.generated, previous.matched
+            ),
+            Ok(_) => {}
+            Err(err) => eprintln!("警告：无法读取上次的摘要报告: {}", err),
+        }
+        match args.checkpoint_every {
+            Some(seconds) => println!("每 {} 秒把当前摘要写入 {}", seconds, path),
+            None => println!("结束时把摘要写入 {}", path),
+        }
+    }
+    let checkpoint = args
+        .checkpoint_every
+        .zip(args.report.clone())
+        .map(|(seconds, path)| (Duration::from_secs(seconds), path));
+    let checkpoint_run_id = run_id.clone();
+
+    // 上传线程：凭据有问题时在开始搜索前报错，上传本身的失败只打印警告
+    let uploader = args.s3_upload.clone().map(|target| {
+        let config = S3Config::from_env().unwrap_or_else(|err| exit_with_error(err));
+        let mut files = vec![PathBuf::from(&args.matched_output)];
+        files.extend(args.report.iter().map(PathBuf::from));
+        println!(
+            "每 {} 秒把 {} 上传到 {}/{}/（{}），结束时再上传一次",
+            args.s3_upload_interval,
+            files.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join("、"),
+            target,
+            run_id,
+            config.endpoint
+        );
+        match &args.split_output {
+            Some(path) => println!("匹配文件只含公开的列，私钥文件 {} 不会上传", path),
+            None if secret_store(&args) => println!("匹配文件只含私钥的引用，私钥保存在本机的系统密钥存储中，不会上传"),
+            None if plaintext_upload => {
+                println!("注意：按 --s3-upload-plaintext 上传含明文私钥的匹配文件，请确认桶的访问权限和加密设置")
+            }
+            None => println!("匹配文件不含明文私钥"),
+        }
+        Uploader::spawn(config, target, run_id.clone(), files, Duration::from_secs(args.s3_upload_interval))
+    });
+
+    // Ctrl+C 时优雅退出：工作线程结束当前批，写入线程写完剩余记录；再按一次立即退出
+    // 会话模式下立即退出前先清除内存中的私钥
+    let stop_search = Arc::clone(&search);
+    let stop_session = session.clone();
+    ctrlc::set_handler(move || {
+        if stop_search.is_stopped() {
+            if let Some(session) = &stop_session {
+                let wiped = session.wipe();
+                if wiped > 0 {
+                    eprintln!("\n{} 个未导出的私钥已从内存中清除，无法找回", wiped);
+                }
+            }
+            std::process::exit(130);
+        }
+        request_stop(&stop_search, "interrupted");
+    })
+    .expect("无法注册 Ctrl+C 处理函数");
+
+    // 在时间窗口外启动时先暂停等待，不退出
+    if let Some(schedule) = &args.schedule {
+        println!("运行时间窗口: {}", schedule);
+        let now = Local::now().naive_local();
+        if let Some(next) = schedule.next_start(now) {
+            search.pause();
+            println!("当前不在时间窗口内，将于 {} 开始", format_wake_time(now, next));
+        }
+    }
+
+    let luck_tracker = Arc::new(LuckTracker::new(probabilities.clone()));
+    let save_near_misses = args.save_near_misses.is_some();
+    for ((prefix, matcher), probability) in search.prefixes().iter().zip(search.matchers()).zip(&probabilities) {
+        if !prefix.is_empty() && *probability == 0.0 {
+            let conflicts = |shape: &&Shape| {
+                let literals = char_class::expand(prefix).unwrap_or_default();
+                literals.iter().all(|literal| shape.probability(literal) == 0.0)
+            };
+            if matches!(matcher, Matcher::Bytes(_)) {
+                println!("警告：字节前缀 {} 与 --first-byte-max 冲突，永远不会匹配", prefix);
+                continue;
+            }
+            match search.shape().filter(conflicts) {
+                Some(shape) => println!("警告：前缀 {} 与形状 {} 冲突，永远不会匹配", prefix, shape),
+                None => println!("警告：前缀 {} 在当前的过滤条件下不可能出现，永远不会匹配", prefix),
+            }
+        }
+    }
+
     // 启动进度条更新线程
-    let generated_clone = Arc::clone(&generated);
-    let matched_clone = Arc::clone(&matched);
-    let start_time = Instant::now();
-    
-    std::thread::spawn(move || {
-        loop {
-            let generation_count = *generated_clone.lock().unwrap();
-            let matches = *matched_clone.lock().unwrap();
-            let elapsed = start_time.elapsed().as_secs();
-            
-            if elapsed > 0 {
-                let rate = generation_count as f64 / elapsed as f64;
-                total_progress.set_message(format!(
-                    "已生成: {} | 速率: {:.2}/秒 | 匹配: {}",
-                    generation_count, rate, matches
-                ));
-                
-                if matches > 0 {
+    let progress_search = Arc::clone(&search);
+    let progress_luck = Arc::clone(&luck_tracker);
+    let schedule = args.schedule.clone();
+    let stop_file = args
+        .stop_file
+        .as_ref()
+        .map(|path| StopFile::new(path, args.consume_stop_file));
+    if let Some(stop_file) = &stop_file {
+        println!("控制文件: {}（出现即优雅退出，内容为 pause/resume 时暂停/恢复）", stop_file.path().display());
+    }
+
+    // 手动暂停由控制文件和终端命令共同控制
+    let manual_pause = Arc::new(AtomicBool::new(false));
+    let progress_pause = Arc::clone(&manual_pause);
+
+    // 在终端中运行时接受简单的命令
+    let stdin_lines = if !args.no_interactive && io::stdin().is_terminal() {
+        println!("交互命令: s 查看状态, p 暂停/恢复, a PATTERN[:N] 添加前缀, q 结束（输入后按回车）");
+        Some(spawn_command_reader(
+            Arc::clone(&search),
+            Arc::clone(&manual_pause),
+            multi_progress.clone(),
+            Arc::clone(&luck_tracker),
+            ones.clone(),
+            args.per_prefix,
+        ))
+    } else {
+        None
+    };
+
+    let progress_disk_guard = disk_guard.clone();
+    let min_free_disk = args.min_free_disk;
+    let mut disk_was_low = false;
+    let heartbeats = Arc::new(Heartbeats::new(num_threads));
+    let progress_heartbeats = Arc::clone(&heartbeats);
+    let mut monitor = HeartbeatMonitor::new(
+        (args.stall_timeout > 0).then(|| Duration::from_secs(args.stall_timeout)),
+        num_threads,
+        Instant::now(),
+    );
+    let progress_handle = std::thread::spawn(move || {
+        let mut last_rate_log = Instant::now();
+        let mut last_heartbeat = Instant::now();
+        let mut last_checkpoint = Instant::now();
+        while !progress_search.is_stopped() {
+            // 控制文件：与 Ctrl+C 相同的优雅退出，或者手动暂停、恢复
+            if let Some(stop_file) = &stop_file {
+                match stop_file.poll() {
+                    Ok(Some(StopCommand::Stop)) => {
+                        request_stop(&progress_search, "stop_file");
+                        break;
+                    }
+                    Ok(Some(StopCommand::Pause)) => progress_pause.store(true, Ordering::Relaxed),
+                    Ok(Some(StopCommand::Resume)) => progress_pause.store(false, Ordering::Relaxed),
+                    Ok(None) => {}
+                    Err(err) => eprintln!("读取控制文件失败: {}", err),
+                }
+            }
+
+            // 纯文本进度行在暂停期间照常输出，日志中不会出现空档
+            if let Some((interval, heartbeat)) = heartbeat_line.as_mut()
+                && last_heartbeat.elapsed() >= *interval
+            {
+                last_heartbeat = Instant::now();
+                let line = heartbeat.line(&progress_search.stats());
+                total_progress.suspend(|| println!("{}", line));
+            }
+
+            // 手动暂停或在时间窗口外时暂停，否则恢复；与当前状态一致时不做任何操作
+            let now = Local::now().naive_local();
+            let wake = schedule.as_ref().and_then(|schedule| schedule.next_start(now));
+            let manually_paused = progress_pause.load(Ordering::Relaxed);
+            let disk_low = progress_disk_guard.as_ref().filter(|guard| guard.is_low());
+            if disk_low.is_some() != disk_was_low {
+                disk_was_low = disk_low.is_some();
+                let message = match disk_low.and_then(|guard| guard.lowest()) {
+                    Some(lowest) => format!(
+                        "磁盘空间不足：{} 所在磁盘只剩 {}，停止写入非匹配地址并暂停搜索，释放空间后自动恢复",
+                        lowest.path.display(),
+                        disk_space::format_size(lowest.available)
+                    ),
+                    None => "磁盘空间已释放，恢复搜索".to_string(),
+                };
+                total_progress.suspend(|| eprintln!("{}", message));
+            }
+            if manually_paused || wake.is_some() || disk_low.is_some() {
+                progress_search.pause();
+                let message = match (wake, disk_low.and_then(|guard| guard.lowest())) {
+                    _ if manually_paused => "已手动暂停".to_string(),
+                    (_, Some(lowest)) => format!(
+                        "磁盘空间不足（剩余 {}，需要 {}），已暂停",
+                        disk_space::format_size(lowest.available),
+                        disk_space::format_size(min_free_disk)
+                    ),
+                    (Some(next), None) => format!("休眠中，将于 {} 恢复", format_wake_time(now, next)),
+                    (None, None) => "已暂停".to_string(),
+                };
+                total_progress.set_message(message);
+                monitor.reset(Instant::now());
+                std::thread::sleep(Duration::from_millis(200));
+                continue;
+            }
+            progress_search.resume();
+
+            let stats = progress_search.stats();
+
+            let stalled = monitor.observe(&progress_heartbeats, Instant::now());
+            if !stalled.is_empty() {
+                stall_exit(&progress_search, &stalled);
+                break;
+            }
+
+            if let Some(log) = rate_log.as_mut()
+                && last_rate_log.elapsed() >= rate_log_interval
+            {
+                last_rate_log = Instant::now();
+                if let Err(err) = log.record(&stats) {
+                    eprintln!("写入吞吐量日志失败: {}", err);
+                }
+            }
+
+            if let Some((interval, path)) = &checkpoint
+                && last_checkpoint.elapsed() >= *interval
+            {
+                last_checkpoint = Instant::now();
+                let summary = Summary::new(&checkpoint_run_id, &stats, report::STATE_RUNNING);
+                if let Err(err) = summary.save(path) {
+                    eprintln!("写入摘要报告失败: {}", err);
+                }
+            }
+
+            if stats.elapsed.as_secs() > 0 {
+                let mut message = format!(
+                    "已生成: {} | 速率: {:.2}/秒 | 匹配: {} | 批大小: {}",
+                    stats.generated, stats.rate, stats.matched, stats.batch_size
+                );
+                if stats.sample_target > 0 {
+                    message.push_str(&format!(" | 采样: {} / {}", stats.samples_taken, stats.sample_target));
+                    if stats.samples_taken == stats.sample_target {
+                        message.push_str("（已完成）");
+                    }
+                }
+                total_progress.set_message(message);
+
+                if let Some(attempts_per_match) = stats.attempts_per_match() {
                     matched_progress.set_message(format!(
                         "找到 {} 个匹配的地址! 当前概率: 1/{}",
-                        matches, if matches > 0 { generation_count / matches } else { 0 }
+                        stats.matched, attempts_per_match
                     ));
                 }
+
+                let partial: Vec<String> = stats
+                    .best_partial_matches()
+                    .filter(|(prefix, _)| !prefix.is_empty())
+                    .map(|(prefix, best)| format!("{}: {}… ({}/{})", prefix, best, best.len(), char_class::len(prefix)))
+                    .collect();
+                if !partial.is_empty() {
+                    partial_progress.set_message(format!("最长部分匹配: {}", partial.join(" | ")));
+                }
+
+                let found: Vec<String> = stats
+                    .per_prefix
+                    .iter()
+                    .zip(progress_luck.probabilities())
+                    .enumerate()
+                    .filter(|(_, ((prefix, _), _))| !prefix.is_empty())
+                    .map(|(index, ((prefix, count), probability))| {
+                        let chance = difficulty::found_by_now(stats.attempts_for(index), probability);
+                        let text = format!("{}: {:.1}%", prefix, chance * 100.0);
+                        if *count == 0 && (probability == 0.0 || chance > difficulty::SUSPICIOUS_PROBABILITY) {
+                            style(text).red().to_string()
+                        } else {
+                            text
+                        }
+                    })
+                    .collect();
+                if !found.is_empty() {
+                    probability_progress.set_message(format!("到现在应该找到的概率: {}", found.join(" | ")));
+                }
             }
             
             std::thread::sleep(Duration::from_millis(200));
         }
+        total_progress.finish();
+        matched_progress.finish();
+        partial_progress.finish();
+        probability_progress.finish();
     });
 
-    // 使用本地线程池执行并行任务
-    thread_pool.install(|| {
-        (0..num_threads).into_par_iter().for_each(|_| {
-            loop {
+    // 重复检测：布隆过滤器的内存平均分给各个工作线程
+    let dup_check_bytes = args.dup_check_mb * 1024 * 1024 / num_threads;
+    let duplicate_found = AtomicBool::new(false);
+    let dup_totals = Mutex::new((0u64, 0f64));
+    let profile_totals = Mutex::new(Profiler::default());
+    // --keep-searching-secs：首次达到 --max-matches 的时间
+    let count_reached = OnceLock::new();
+    // 其他情况下按 --then 推进的状态，见 match_limit 模块
+    let match_limit = args
+        .max_matches
+        .filter(|_| args.keep_searching_secs.is_none())
+        .map(|max| MatchLimit::new(max, args.then));
+    // 占到名额后和每批结束时推进 --max-matches 的状态，拿到转换的线程负责停止或打印提示
+    let advance_limit = || {
+        let Some(limit) = &match_limit else {
+            return;
+        };
+        let Some(transition) = limit.update(search.wants_samples()) else {
+            return;
+        };
+        match transition.stop_reason() {
+            Some(reason) => request_stop(&search, reason),
+            None => {
+                let stats = search.stats();
+                multi_progress.suspend(|| {
+                    println!(
+                        "已找到 {} 个匹配，不再匹配，继续采集非匹配地址（{} / {}）",
+                        limit.max(),
+                        stats.samples_taken,
+                        stats.sample_target
+                    )
+                });
+            }
+        }
+    };
+
+    // 处理一个匹配：先占 --max-matches 和 --per-prefix 的名额，占不到就丢弃，
+    // 占到后计数、记录运气，编号后交给写入线程或打印。名额在计数和写入之前原子地占用，
+    // 多个线程同时找到匹配时也不会超出上限。同步轮次中由领头线程在轮末按密钥位置依次调用，
+    // 哪些匹配占到名额、编号是多少都是确定的
+    let handle_match = |found: Found, matches: &mut MatchCounts, profiler: &mut Option<Profiler>| {
+        let Found {
+            mut record,
+            mut indices,
+            attempt,
+        } = found;
+        if let Some(limit) = &match_limit
+            && !limit.claim()
+        {
+            return;
+        }
+        // 只计入还没找够的前缀；都已找够时这个匹配既不计数也不写入。
+        // 运行中添加的前缀由 Search 按添加时给出的数量占名额
+        indices.retain(|&index| search.claim(index) && prefix_quota.as_ref().is_none_or(|quota| quota.claim(index)));
+        if indices.is_empty() {
+            if let Some(limit) = &match_limit {
+                limit.release();
+            }
+            return;
+        }
+        if prefix_quota.as_ref().is_some_and(PrefixQuota::is_done) && search.targets_done() {
+            request_stop(&search, "per_prefix_done");
+        }
+        advance_limit();
+        matches.credit(&indices);
+        // 每个前缀各记一次运气，写入的记录带序号最小的前缀的运气
+        let mut luck = None;
+        for &index in &indices {
+            luck = luck.or(luck_tracker.record(index, attempt));
+            if let Some(log) = &attempts_log
+                && let Err(err) = log.record(&search.patterns().prefixes()[index], attempt, search.active_elapsed())
+            {
+                multi_progress.suspend(|| eprintln!("写入尝试次数日志失败: {}", err));
+            }
+        }
+
+        let pubkey = record.pubkey();
+        let address = record.address.clone();
+        if let Some(known) = &known_addresses {
+            let result = known.check(&pubkey.to_bytes());
+            record.known = result.mark();
+            match result {
+                KnownResult::Absent => {}
+                KnownResult::FalsePositive => {
+                    known_false_positives.fetch_add(1, Ordering::Relaxed);
+                }
+                KnownResult::Confirmed => {
+                    known_hits.lock().unwrap().push(address.clone());
+                    multi_progress.suspend(|| {
+                        eprintln!("!!! 已知地址命中：匹配的地址 {} 在已知地址快照中，已在原始名单中确认 !!!", address)
+                    });
+                }
+                KnownResult::Unverified(reason) => {
+                    known_hits.lock().unwrap().push(address.clone());
+                    multi_progress.suspend(|| {
+                        eprintln!("!!! 已知地址可能命中：匹配的地址 {} 通过了过滤器，但无法复核（{}）!!!", address, reason)
+                    });
+                }
+            }
+        }
+
+        // 交给写入线程写入匹配的地址，回文和镜像记录实际对称的字符串
+        record.prefix = search.matched_text(&indices, &address);
+        record.luck = luck;
+        record.index = Some(match_index.fetch_add(1, Ordering::Relaxed) + 1);
+        match &sender {
+            Some(sender) => timed(profiler, Phase::Write, || {
+                send_to_writer(sender, &search, Message::Matched(record))
+            }),
+            None => multi_progress.suspend(|| {
+                let shown = highlight(&address, &search.matched_spans(&indices, &address));
+                println!("找到 {}（前缀 {}）", shown, record.prefix);
+                if let Some(luck) = luck {
+                    println!("  {}", describe_luck(&luck));
+                }
+                if let Some(mark) = record.known {
+                    println!("  !!! 已知地址快照中的地址（known={}）", mark.as_str());
+                }
+                for condition in &args.count_char {
+                    let positions = char_count::positions(condition.ch, &address);
+                    println!("  '{}' 出现 {} 次，位置 {:?}", condition.ch, positions.len(), positions);
+                }
+                for mint in &args.derive_ata {
+                    println!("  ATA {}: {}", mint, ata::associated_token_address(&pubkey, mint));
+                }
+                if let Some(session) = &session
+                    && !session.push(record)
+                {
+                    eprintln!("  会话已满，这个匹配的私钥已丢弃");
+                }
+            }),
+        }
+    };
+
+    if args.dup_check {
+        println!(
+            "重复检测已开启：共 {} MB 布隆过滤器，每个线程另外精确保存最近 {} 个公钥",
+            args.dup_check_mb,
+            dup_check::RECENT_WINDOW
+        );
+    }
+
+    // 一个工作线程的主体，返回它的字符统计；panic 时整个主体连同它的状态一起丢弃
+    let run_worker = |worker: usize| {
+        let mut batch = if lockstep.is_some() {
+            BatchController::fixed(if args.batch_size == 0 { lockstep::DEFAULT_ROUND_SIZE } else { args.batch_size })
+        } else if args.batch_size == 0 {
+            BatchController::adaptive(
+                args.update_interval_ms
+                    .map_or(BatchController::DEFAULT_TARGET, Duration::from_millis),
+            )
+        } else {
+            BatchController::fixed(args.batch_size)
+        };
+
+        let mut matches = MatchCounts::new(search.prefixes().len());
+        let mut char_stats = args.char_stats.then(CharStats::new);
+        let mut dup_checker = args.dup_check.then(|| DupChecker::new(dup_check_bytes));
+        let mut keygen = worker_generator(&args, rng, extra_entropy.as_ref(), worker as u64, num_threads);
+        let mut sample_counter = 0u64;
+        let mut worker_generated = 0u64;
+        let mut profiler = args.profile.then(Profiler::default);
+        #[cfg(debug_assertions)]
+        let mut batches = 0u64;
+
+        // 同步轮次中只在轮末由领头线程统一决定是否停止，各线程不能提前退出
+        while lockstep.is_some() || !search.is_stopped() {
+            search.wait_while_paused();
+            if lockstep.is_none() && search.is_stopped() {
+                break;
+            }
+            #[cfg(debug_assertions)]
+            {
+                if worker == 0 && args.debug_panic_after == Some(batches) {
+                    panic!("--debug-panic-after 注入的 panic");
+                }
+                batches += 1;
+            }
+            let batch_start = Instant::now();
+            let batch_size = batch.size();
+            let sampling = match_limit.as_ref().is_some_and(|limit| limit.state() == LimitState::Sampling);
+            // 运行中用 a 命令添加的前缀从下一批开始参与匹配
+            let patterns = search.patterns();
+
+            for position in 0..batch_size {
+                // --profile 时抽样的密钥从这里开始计时
+                let mut mark = profiler.as_mut().and_then(Profiler::start_key);
+
                 // 生成新的密钥对
-                let keypair = Keypair::new();
-                let address = keypair.pubkey().to_string();
-                // let secret_key = bs58::encode(keypair.secret().as_ref()).into_string();
+                let keypair = keygen.generate();
+                let pubkey = keypair.pubkey();
+                lap(&mut profiler, &mut mark, Phase::Keygen);
+
+                // 重复检测：只有精确比较确认相同才中止
+                if let Some(checker) = dup_checker.as_mut()
+                    && checker.check(&pubkey.to_bytes()) == DupResult::Duplicate
+                {
+                    multi_progress.suspend(|| {
+                        eprintln!("严重错误：生成了完全相同的公钥 {}，随机数来源可能已经失效，正在中止", pubkey)
+                    });
+                    duplicate_found.store(true, Ordering::Relaxed);
+                    request_stop(&search, "duplicate_key");
+                }
+
+                if let Some(watch_list) = &watch_list
+                    && watch_list.contains(&pubkey.to_bytes())
+                {
+                    watch_hits.fetch_add(1, Ordering::Relaxed);
+                    multi_progress.suspend(|| eprintln!("观察名单命中：生成的地址 {} 与名单中的地址完全相同", pubkey));
+                }
+
+                if let Some(blacklist) = &blacklist
+                    && blacklist.contains(&pubkey.to_bytes())
+                {
+                    blacklisted.fetch_add(1, Ordering::Relaxed);
+                    multi_progress.suspend(|| eprintln!("跳过黑名单中的地址 {}", pubkey));
+                    continue;
+                }
+
+                // 字符统计按固定间隔抽样，与是否匹配无关
+                let sample_due = char_stats.is_some() && {
+                    sample_counter += 1;
+                    sample_counter.is_multiple_of(char_stats::SAMPLE_INTERVAL)
+                };
+
+                // 字节过滤不通过且不需要采样时，直接跳过 Base58 编码；
+                // 只有原始字节模式时，不符合任何字节模式的密钥同样不需要编码
+                let passes_filters = search.accepts_bytes(pubkey.as_ref());
+                let byte_match = if passes_filters { patterns.find_bytes(pubkey.as_ref()) } else { Vec::new() };
+                let can_match = passes_filters && (!byte_match.is_empty() || !patterns.bytes_only());
+                if !can_match && !search.wants_samples() && !sample_due && !args.rank {
+                    lap(&mut profiler, &mut mark, Phase::Match);
+                    continue;
+                }
+                lap(&mut profiler, &mut mark, Phase::Match);
+                let address = pubkey.to_string();
+                lap(&mut profiler, &mut mark, Phase::Encode);
+                if sample_due && let Some(char_stats) = char_stats.as_mut() {
+                    char_stats.record(&address);
+                }
+                // 字符计数条件只能在编码之后检查
+                let passes_filters = passes_filters && search.accepts_address(&address);
                 let mut keypair_bytes = [0u8; 64];
                 keypair_bytes[..32].copy_from_slice(keypair.secret().as_ref());
-                keypair_bytes[32..].copy_from_slice(keypair.pubkey().as_ref());
-                let secret_key = bs58::encode(&keypair_bytes).into_string();
-                
-                // 更新计数器
-                let mut gen_lock = generated.lock().unwrap();
-                *gen_lock += 1;
-                let current_count = *gen_lock;
-                drop(gen_lock);
-                
-                // 检查是否匹配任何前缀
-                let mut is_match = false;
-                for prefix in &prefixes {
-                    if address.starts_with(prefix) {
-                        is_match = true;
-                        
-                        // 更新匹配计数
-                        let mut match_lock = matched.lock().unwrap();
-                        *match_lock += 1;
-                        drop(match_lock);
-                        
-                        // 写入匹配的地址
-                        let mut writer = matched_output_writer.lock().unwrap();
-                        writeln!(writer, "{},{}", address, secret_key).unwrap();
-                        writer.flush().unwrap();
-                        break;
+                keypair_bytes[32..].copy_from_slice(pubkey.as_ref());
+
+                // 分数超过入榜门槛时交给写入线程的排行榜
+                if let (Some(threshold), Some(sender)) = (&rank_threshold, &sender) {
+                    let score = args.score_weights.score(&address);
+                    if threshold.admits(score) {
+                        let record = Record {
+                            address: address.clone(),
+                            prefix: String::new(),
+                            keypair: keypair_bytes,
+                            luck: None,
+                            index: None,
+                            known: None,
+                        };
+                        timed(&mut profiler, Phase::Write, || {
+                            send_to_writer(sender, &search, Message::Ranked(record, score))
+                        });
                     }
                 }
-                
-                // 如果不匹配但在前N个，保存它
-                if !is_match {
-                    let mut addresses = non_matching_addresses.lock().unwrap();
-                    if addresses.len() < args.non_matching_count {
-                        addresses.push_back((address.clone(), secret_key.clone()));
-                        
-                        // 写入非匹配地址
-                        let mut writer = output_writer.lock().unwrap();
-                        writeln!(writer, "{},{}", address, secret_key).unwrap();
+
+                // 检查满足哪些前缀，满足几个时每个都计入
+                let matched_indices = if passes_filters {
+                    let mut indices = byte_match;
+                    indices.extend(patterns.find_all(&address));
+                    indices.sort_unstable();
+                    indices
+                } else {
+                    Vec::new()
+                };
+                lap(&mut profiler, &mut mark, Phase::Match);
+                if !matched_indices.is_empty() && sampling {
+                    // 匹配已够，只采集非匹配地址：匹配的地址既不写入也不作为样本
+                    keypair_bytes.zeroize();
+                    continue;
+                }
+                if !matched_indices.is_empty() {
+                    // 已合并的总数加上各线程在这一批中大致相同的进度，估算全局的尝试次数；
+                    // 固定种子时密钥的位置就是确切的尝试次数
+                    let attempt = keygen
+                        .position()
+                        .map_or(search.generated() + (position + 1) * num_threads as u64, |position| position + 1);
+                    let found = Found {
+                        record: Record {
+                            address,
+                            prefix: String::new(),
+                            keypair: keypair_bytes,
+                            luck: None,
+                            index: None,
+                            known: None,
+                        },
+                        indices: matched_indices,
+                        attempt,
+                    };
+                    keypair_bytes.zeroize();
+                    // 同步轮次中先交给 Lockstep，轮末由领头线程按位置顺序处理
+                    match &lockstep {
+                        Some(lockstep) => lockstep.defer(keygen.position().unwrap_or_default(), found),
+                        None => handle_match(found, &mut matches, &mut profiler),
                     }
+                    continue;
+                }
+
+                if passes_filters {
+                    patterns.track_partial(&address, save_near_misses.then_some(&keypair_bytes));
                 }
-                
-                // 每生成100万个地址刷新一次输出文件
-                if current_count % 1_000_000 == 0 {
-                    output_writer.lock().unwrap().flush().unwrap();
-                    matched_output_writer.lock().unwrap().flush().unwrap();
+                if let Some(sender) = &sender
+                    && search.take_sample()
+                {
+                    // 不匹配但在前N个，保存它
+                    timed(&mut profiler, Phase::Write, || {
+                        send_to_writer(
+                            sender,
+                            &search,
+                            Message::Sample(Record {
+                                address,
+                                prefix: String::new(),
+                                keypair: keypair_bytes,
+                                luck: None,
+                                index: None,
+                                known: None,
+                            }),
+                        )
+                    });
                 }
             }
-        });
+
+            // 每批更新一次计数器
+            let shared_start = profiler.is_some().then(Instant::now);
+            search.record_batch(batch_size, &mut matches);
+            heartbeats.beat(worker, batch_size);
+            if let (Some(max), Some(secs)) = (args.max_matches, args.keep_searching_secs)
+                && search.matched() >= max
+            {
+                // 第一个达到数量的线程记下时间，之后各线程到时间才停止
+                let reached = *count_reached.get_or_init(|| {
+                    multi_progress.suspend(|| {
+                        println!("已找到 {} 个匹配，继续搜索 {} 秒寻找分数更高的地址", max, secs)
+                    });
+                    Instant::now()
+                });
+                if reached.elapsed() >= Duration::from_secs(secs) {
+                    request_stop(&search, "max_matches");
+                }
+            }
+            // 采样在匹配找够之后完成时，在这里结束
+            advance_limit();
+            batch.record(batch_start.elapsed());
+            search.report_batch_size(batch.size());
+            if let (Some(profiler), Some(start)) = (profiler.as_mut(), shared_start) {
+                profiler.add(Phase::Shared, start.elapsed());
+            }
+
+            if let Some(rate) = worker_rate {
+                worker_generated += batch_size;
+                throttle(&search, worker_generated, rate);
+            }
+
+            if let Some(lockstep) = &lockstep {
+                // 领头线程按位置顺序处理本轮的匹配，最后一轮多出的匹配因此总是位置靠后的那些；
+                // 计数立即合并，不等下一批
+                let flush = |found: Vec<Found>| {
+                    for found in found {
+                        handle_match(found, &mut matches, &mut profiler);
+                    }
+                    search.record_batch(0, &mut matches);
+                };
+                if !lockstep.end_round(flush, || search.is_stopped()) {
+                    break;
+                }
+            }
+        }
+
+        if let Some(checker) = &dup_checker {
+            let mut totals = dup_totals.lock().unwrap();
+            totals.0 += checker.unconfirmed();
+            totals.1 += checker.expected_false_positives();
+        }
+        if let Some(profiler) = &profiler {
+            profile_totals.lock().unwrap().merge(profiler);
+        }
+        char_stats
+    };
+
+    // 使用本地线程池执行并行任务，每个工作线程各自累积字符统计，结束时合并；
+    // panic 只影响所在的线程，按 --restart-stalled-workers 重新开始或交给看门狗报告。
+    // 同步轮次中重新开始的线程会从头生成自己的位置，破坏确定性，所以不重新开始，
+    // 而是中止轮次，让其他线程不再在轮末等待它
+    let char_stats = thread_pool.install(|| {
+        (0..num_threads).into_par_iter().map(|worker| loop {
+            match panic::catch_unwind(AssertUnwindSafe(|| run_worker(worker))) {
+                Ok(char_stats) => {
+                    heartbeats.exited(worker);
+                    break char_stats;
+                }
+                Err(payload) => {
+                    let message = watchdog::panic_message(payload.as_ref());
+                    let restart = args.restart_stalled_workers && lockstep.is_none() && !search.is_stopped();
+                    if !heartbeats.panicked(worker, message.clone(), restart) {
+                        if let Some(lockstep) = &lockstep {
+                            lockstep.abort();
+                        }
+                        break None;
+                    }
+                    multi_progress.suspend(|| eprintln!("工作线程 {} panic：{}，以全新的状态重新开始", worker, message));
+                }
+            }
+        })
+        .reduce(|| None, |a, b| match (a, b) {
+            (Some(a), Some(b)) => Some(a.merge(b)),
+            (a, b) => a.or(b),
+        })
     });
+    progress_handle.join().unwrap();
+    // 轮次中止时最后一轮的匹配还没有交出，照常处理，不丢弃已经找到的密钥
+    if let Some(lockstep) = &lockstep {
+        let mut matches = MatchCounts::new(search.prefixes().len());
+        for found in lockstep.take_pending() {
+            handle_match(found, &mut matches, &mut None);
+        }
+        search.record_batch(0, &mut matches);
+    }
+    let restarts = heartbeats.total_restarts();
+    if restarts > 0 {
+        eprintln!("警告：工作线程共 panic 后重新开始 {} 次，panic 时所在批的计数没有计入", restarts);
+    }
+
+    // 停止的顺序：工作线程都已退出，不会再发送；丢弃最后一个发送端后通道关闭，
+    // 写入线程把通道中排队的记录全部写完、刷新文件后才结束
+    drop(sender);
+    let stats = search.stats();
+    let mut output_files = Vec::new();
+    if let Some(writer_handle) = writer_handle {
+        let written = writer_handle.join().unwrap()?;
+        if let Some(guard) = &disk_guard
+            && guard.dropped_samples() > 0
+        {
+            eprintln!(
+                "警告：磁盘空间不足期间丢弃了 {} 个非匹配地址，非匹配文件中的地址少于 -n",
+                guard.dropped_samples()
+            );
+        }
+        // 每个计入统计的匹配都发给了写入线程，数量不一致说明有匹配丢失；
+        // --keep-searching-secs 时写入线程只保留分数最高的 --max-matches 个
+        let expected = match (args.keep_searching_secs, args.max_matches) {
+            (Some(_), Some(max)) => stats.matched.min(max),
+            _ => stats.matched,
+        };
+        if written.matches.len() as u64 != expected {
+            eprintln!(
+                "错误：统计到 {} 个匹配，但只写入了 {} 个，请检查输出文件",
+                expected,
+                written.matches.len()
+            );
+        }
+        if let Some(seconds) = args.rotate_every {
+            println!(
+                "匹配文件每 {} 秒轮换一次，共 {} 个已完成的文件{}",
+                seconds,
+                written.rotated.len(),
+                written.rotated.last().map(|path| format!("，最后一个为 {}", path.display())).unwrap_or_default()
+            );
+        }
+        if args.keep_searching_secs.is_some() && !written.matches.is_empty() {
+            println!("共找到 {} 个匹配，保留分数最高的 {} 个：", stats.matched, written.matches.len());
+            for record in &written.matches {
+                println!("  {}  分数 {}", record.address, args.score_weights.score(&record.address));
+            }
+        }
+        output_files = write_run_files(&args, &search, &stats, num_threads, rng, written)?;
+    }
+
+    print_summary(&stats);
+    print_luck(&search, &luck_tracker);
+    let profile = args.profile.then(|| profile_totals.lock().unwrap().summary());
+    if let Some(profile) = &profile {
+        println!(
+            "阶段耗时（每 {} 个密钥抽样计时一次）：{}",
+            profile.sample_interval,
+            profile.breakdown()
+        );
+    }
+    if offline::is_offline() {
+        println!("OFFLINE MODE：本次运行未启用任何网络功能");
+    }
+    if let Some(char_stats) = &char_stats {
+        print_char_stats(char_stats);
+    }
+    if blacklist.is_some() {
+        println!("黑名单命中 {} 次，均未写入输出", blacklisted.load(Ordering::Relaxed));
+    }
+    if let Some(watch_list) = &watch_list {
+        let hits = watch_hits.load(Ordering::Relaxed);
+        println!("观察名单命中 {} 次（期望 0 次）", hits);
+        if stats.rate > 0.0 {
+            let years = watch_list.expected_years(stats.rate);
+            println!(
+                "  按本次平均速率 {:.0}/秒，命中名单中任意一个地址的期望时间约 {:.1e} 年，是宇宙年龄的 {:.1e} 倍",
+                stats.rate,
+                years,
+                years / watch_list::UNIVERSE_AGE_YEARS
+            );
+        }
+        if hits > 0 {
+            eprintln!("警告：观察名单命中几乎不可能发生，这更可能说明随机数来源已经失效，而不是运气");
+        }
+    }
+    if let Some(known) = &known_addresses {
+        let hits = known_hits.lock().unwrap();
+        println!(
+            "已知地址检查：{} 个匹配中命中 {} 个，过滤器误判 {} 次",
+            stats.matched,
+            hits.len(),
+            known_false_positives.load(Ordering::Relaxed)
+        );
+        for address in hits.iter() {
+            eprintln!("!!! 已知地址命中: {}", address);
+        }
+        if !hits.is_empty() && known.source().is_some() {
+            eprintln!("警告：匹配的私钥控制着快照中已有的地址，这几乎不可能是巧合，请检查随机数来源");
+        }
+    }
+    if args.dup_check {
+        let (unconfirmed, expected) = *dup_totals.lock().unwrap();
+        println!(
+            "重复检测：布隆过滤器未确认的命中 {} 次（随机数正常时期望约 {:.1} 次误判）",
+            unconfirmed, expected
+        );
+        if unconfirmed as f64 > expected * 10.0 + 10.0 {
+            eprintln!("警告：未确认的命中远多于期望的误判次数，随机数来源可能有问题");
+        }
+    }
+    if let Some(session) = &session {
+        finish_session(session, &export_schema, &args.matched_output, stdin_lines.as_ref());
+    }
+    let exit_reason = if duplicate_found.load(Ordering::Relaxed) {
+        "duplicate_key"
+    } else {
+        STOP_REASON.get().copied().unwrap_or("finished")
+    };
+    if let Some(path) = &args.report {
+        let summary = Summary::new(&run_id, &stats, exit_reason)
+            .with_profile(profile)
+            .with_known_hits(known_hits.lock().unwrap().clone())
+            .with_luck(&search.prefixes(), &luck_tracker);
+        match summary.save(path) {
+            Ok(()) => println!("摘要报告已写入 {}", path),
+            Err(err) => eprintln!("写入摘要报告失败: {}", err),
+        }
+    }
+    if let Some(uploader) = uploader {
+        uploader.finish();
+    }
+    if let Some(audit_log) = &audit_log {
+        let end = RunEnd {
+            run_id: run_id.clone(),
+            timestamp: Local::now().to_rfc3339(),
+            duration_secs: stats.elapsed.as_secs_f64(),
+            attempts: stats.generated,
+            matches: stats.per_prefix.iter().cloned().collect(),
+            exit_reason: exit_reason.to_string(),
+            outputs: audit::output_entries(&output_files),
+        };
+        append_audit(audit_log, &AuditRecord::End(end));
+    }
+    if duplicate_found.load(Ordering::Relaxed) {
+        eprintln!("错误：检测到重复的密钥对，本次运行的结果不可信");
+        std::process::exit(3);
+    }
+    if exit_reason == "stalled" {
+        std::process::exit(4);
+    }
 
     Ok(())
 }
\ No newline at end of file
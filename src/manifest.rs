@@ -0,0 +1,260 @@
+//! 输出文件的完整性清单
+//!
+//! 运行结束、所有文件刷新之后，在匹配文件旁写入 `MANIFEST.json`，记录每个输出文件的
+//! 字节数和 SHA-256 以及本次运行的参数。把结果复制到其他机器后，可以用
+//! `verify <目录>` 确认文件没有被截断或修改。
+//!
+//! 清单同时按前缀索引最近一次运行的匹配：每个前缀的匹配数量、首末匹配时间，
+//! 以及每个匹配地址写入了哪些文件（匹配文件、密钥文件、Shamir 分享），方便下游自动化。
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::search::PATTERN_SEPARATOR;
+
+/// 清单文件名
+pub const FILE_NAME: &str = "MANIFEST.json";
+
+/// 一个输出文件的记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEntry {
+    /// 相对于清单所在目录的路径
+    pub path: String,
+    pub bytes: u64,
+    /// 小写十六进制
+    pub sha256: String,
+}
+
+/// 最近一次运行的参数和结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunInfo {
+    pub version: String,
+    pub finished_at: String,
+    pub prefixes: Vec<String>,
+    pub threads: usize,
+    pub rng: String,
+    pub generated: u64,
+    pub matched: u64,
+    pub elapsed_secs: f64,
+}
+
+/// 写入线程记录的一个匹配及其产物
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchRecord {
+    pub address: String,
+    pub prefix: String,
+    /// 写入时的本地时间，RFC 3339
+    pub found_at: String,
+    /// 除匹配文件外为这个地址单独写入的文件
+    pub files: Vec<PathBuf>,
+}
+
+/// 清单中的一个匹配地址
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchEntry {
+    pub address: String,
+    pub found_at: String,
+    /// 相对于清单所在目录的路径
+    pub files: Vec<String>,
+}
+
+/// 一个前缀在最近一次运行中的产物
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrefixEntry {
+    pub count: usize,
+    pub first_found_at: Option<String>,
+    pub last_found_at: Option<String>,
+    /// 匹配文件，相对于清单所在目录
+    pub output: String,
+    pub matches: Vec<MatchEntry>,
+}
+
+/// `MANIFEST.json` 的内容
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub run: RunInfo,
+    /// 按前缀索引的最近一次运行的匹配，随 `run` 一起替换
+    #[serde(default)]
+    pub prefixes: BTreeMap<String, PrefixEntry>,
+    pub files: Vec<FileEntry>,
+}
+
+impl Manifest {
+    /// 读取目录中的清单，不存在时返回 `None`
+    pub fn load(dir: impl AsRef<Path>) -> io::Result<Option<Self>> {
+        let path = dir.as_ref().join(FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), err)))
+    }
+
+    /// 写入或替换一个文件的记录，同一路径只保留一条
+    pub fn record(&mut self, entry: FileEntry) {
+        match self.files.iter_mut().find(|existing| existing.path == entry.path) {
+            Some(existing) => *existing = entry,
+            None => self.files.push(entry),
+        }
+    }
+
+    /// 先写临时文件再改名，避免中途退出留下半个清单
+    pub fn save(&self, dir: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let path = dir.as_ref().join(FILE_NAME);
+        let temp = dir.as_ref().join(format!("{}.tmp", FILE_NAME));
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        std::fs::write(&temp, json + "\n")?;
+        std::fs::rename(&temp, &path)?;
+        Ok(path)
+    }
+}
+
+/// 更新目录中的清单：重新计算给定文件的记录，保留清单里其他文件的记录
+///
+/// 以追加方式写入的文件（如吞吐量日志）每次运行都会替换原有记录，不会重复出现。
+/// 前缀索引整体替换为本次运行的匹配，`matched_file` 是本次的匹配文件。
+pub fn update(
+    dir: impl AsRef<Path>,
+    files: &[PathBuf],
+    run: RunInfo,
+    matched_file: &Path,
+    matches: &[MatchRecord],
+) -> io::Result<PathBuf> {
+    let dir = dir.as_ref();
+    let mut manifest = Manifest::load(dir)?.unwrap_or_default();
+
+    let output = relative_path(dir, matched_file)?;
+    let mut prefixes: BTreeMap<String, PrefixEntry> = run
+        .prefixes
+        .iter()
+        .map(|prefix| {
+            let entry = PrefixEntry {
+                output: output.clone(),
+                ..PrefixEntry::default()
+            };
+            (prefix.clone(), entry)
+        })
+        .collect();
+    for record in matches {
+        let files = record
+            .files
+            .iter()
+            .map(|file| relative_path(dir, file))
+            .collect::<io::Result<Vec<String>>>()?;
+        // 同时满足多个前缀的地址在每个前缀下各记一次
+        for prefix in record.prefix.split(PATTERN_SEPARATOR) {
+            let entry = prefixes.entry(prefix.to_string()).or_default();
+            entry.count += 1;
+            entry.first_found_at.get_or_insert_with(|| record.found_at.clone());
+            entry.last_found_at = Some(record.found_at.clone());
+            entry.matches.push(MatchEntry {
+                address: record.address.clone(),
+                found_at: record.found_at.clone(),
+                files: files.clone(),
+            });
+        }
+    }
+    manifest.prefixes = prefixes;
+    manifest.run = run;
+
+    for file in files {
+        manifest.record(FileEntry {
+            path: relative_path(dir, file)?,
+            bytes: std::fs::metadata(file)?.len(),
+            sha256: sha256_file(file)?,
+        });
+    }
+    manifest.save(dir)
+}
+
+/// 目录清单的校验结果
+#[derive(Debug, Default)]
+pub struct ManifestReport {
+    /// 清单中的文件数
+    pub files: usize,
+    pub issues: Vec<String>,
+}
+
+impl ManifestReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// 按清单逐个检查目录中文件的字节数和 SHA-256
+pub fn verify_dir(dir: impl AsRef<Path>) -> io::Result<ManifestReport> {
+    let dir = dir.as_ref();
+    let Some(manifest) = Manifest::load(dir)? else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} 中没有 {}", dir.display(), FILE_NAME),
+        ));
+    };
+
+    let mut report = ManifestReport {
+        files: manifest.files.len(),
+        ..ManifestReport::default()
+    };
+    for entry in &manifest.files {
+        let path = dir.join(&entry.path);
+        let bytes = match std::fs::metadata(&path) {
+            Ok(metadata) => metadata.len(),
+            Err(err) => {
+                report.issues.push(format!("{}: 无法读取 ({})", entry.path, err));
+                continue;
+            }
+        };
+        if bytes != entry.bytes {
+            report
+                .issues
+                .push(format!("{}: 长度不一致，清单为 {} 字节，实际为 {} 字节", entry.path, entry.bytes, bytes));
+        } else if sha256_file(&path)? != entry.sha256 {
+            report.issues.push(format!("{}: SHA-256 不一致", entry.path));
+        }
+    }
+    Ok(report)
+}
+
+/// 流式计算文件的 SHA-256
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// 计算文件相对于清单目录的路径，不在目录下时用 `..` 表示，统一使用 `/` 分隔
+fn relative_path(dir: &Path, file: &Path) -> io::Result<String> {
+    let dir = dir.canonicalize()?;
+    let file = file.canonicalize()?;
+    let dir: Vec<Component> = dir.components().collect();
+    let file: Vec<Component> = file.components().collect();
+    let common = dir.iter().zip(&file).take_while(|(a, b)| a == b).count();
+
+    let parts: Vec<String> = std::iter::repeat_n("..".to_string(), dir.len() - common)
+        .chain(
+            file[common..]
+                .iter()
+                .map(|component| component.as_os_str().to_string_lossy().into_owned()),
+        )
+        .collect();
+    Ok(parts.join("/"))
+}
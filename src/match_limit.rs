@@ -0,0 +1,211 @@
+//! 达到 `--max-matches` 之后的去向（`--then`）
+//!
+//! 有些流程既要几个靓号，又要一份非匹配地址的统计样本。`--then continue-sample` 时，
+//! 找够匹配后不再匹配，只继续采集 `-n` 要求的非匹配地址，采够后结束。状态转换：
+//!
+//! ```text
+//! Matching ──匹配数达到 N──▶ Sampling ──采样完成──▶ Done
+//!     │                                            ▲
+//!     └──匹配数达到 N，且 --then stop 或采样已完成──┘
+//! ```
+//!
+//! 每个匹配在计数和写入之前先用 [`MatchLimit::claim`] 占一个名额，名额用完后找到的匹配直接丢弃，
+//! 所以无论多少个线程同时找到匹配，计数和写入的都恰好不超过 N 个。
+//!
+//! 占到名额后和各工作线程每批结束时调用 [`MatchLimit::update`]，状态用一个原子量保存，
+//! 每次转换只有一个线程拿到，由它负责打印提示或请求停止。
+
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use clap::ValueEnum;
+
+/// `--then` 的取值
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Then {
+    /// 直接结束
+    Stop,
+    /// 不再匹配，继续采集非匹配地址直到达到 -n
+    ContinueSample,
+}
+
+/// 当前所处的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum LimitState {
+    /// 正常搜索匹配
+    Matching,
+    /// 匹配已够，只采集非匹配地址
+    Sampling,
+    /// 应当结束
+    Done,
+}
+
+impl LimitState {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => LimitState::Matching,
+            1 => LimitState::Sampling,
+            _ => LimitState::Done,
+        }
+    }
+}
+
+/// 一次状态转换
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// 匹配已够，开始只采样
+    StartSampling,
+    /// 匹配已够，直接结束
+    StopAtMax,
+    /// 采样也已完成，结束
+    SamplesDone,
+}
+
+impl Transition {
+    /// 对应的结束原因，开始采样时为 `None`
+    pub fn stop_reason(&self) -> Option<&'static str> {
+        match self {
+            Transition::StartSampling => None,
+            Transition::StopAtMax => Some("max_matches"),
+            Transition::SamplesDone => Some("samples_done"),
+        }
+    }
+}
+
+/// 各工作线程共享的匹配数上限和状态
+#[derive(Debug)]
+pub struct MatchLimit {
+    max: u64,
+    then: Then,
+    state: AtomicU8,
+    /// 已占用的名额
+    claimed: AtomicU64,
+}
+
+impl MatchLimit {
+    pub fn new(max: u64, then: Then) -> Self {
+        Self {
+            max,
+            then,
+            state: AtomicU8::new(LimitState::Matching as u8),
+            claimed: AtomicU64::new(0),
+        }
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    pub fn state(&self) -> LimitState {
+        LimitState::from_u8(self.state.load(Ordering::Relaxed))
+    }
+
+    /// 为一个新匹配占一个名额，名额已经用完时返回 `false`，这个匹配应当丢弃
+    pub fn claim(&self) -> bool {
+        self.claimed
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                (count < self.max).then_some(count + 1)
+            })
+            .is_ok()
+    }
+
+    /// 退回占到的名额：匹配占到名额后又因为其他原因（如 `--per-prefix` 已找够）被丢弃时调用
+    pub fn release(&self) {
+        self.claimed.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// 已占用的名额
+    pub fn claimed(&self) -> u64 {
+        self.claimed.load(Ordering::Relaxed)
+    }
+
+    /// 按已占用的名额和是否还需要采样推进状态，返回这次调用完成的转换
+    ///
+    /// 多个线程同时满足条件时只有一个拿到转换，其余返回 `None`。
+    pub fn update(&self, wants_samples: bool) -> Option<Transition> {
+        let current = self.state();
+        let (next, transition) = match current {
+            LimitState::Matching if self.claimed() >= self.max => {
+                if self.then == Then::ContinueSample && wants_samples {
+                    (LimitState::Sampling, Transition::StartSampling)
+                } else {
+                    (LimitState::Done, Transition::StopAtMax)
+                }
+            }
+            LimitState::Sampling if !wants_samples => (LimitState::Done, Transition::SamplesDone),
+            _ => return None,
+        };
+        self.state
+            .compare_exchange(current as u8, next as u8, Ordering::Relaxed, Ordering::Relaxed)
+            .ok()
+            .map(|_| transition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Barrier;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn burst_of_claims_never_overshoots() {
+        // 8 个线程同时各抢 1000 次，成功的恰好是 max 次
+        const THREADS: usize = 8;
+        let limit = MatchLimit::new(37, Then::Stop);
+        let start = Barrier::new(THREADS);
+        let won: usize = thread::scope(|scope| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    scope.spawn(|| {
+                        start.wait();
+                        (0..1000).filter(|_| limit.claim()).count()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).sum()
+        });
+        assert_eq!(won, 37);
+        assert_eq!(limit.claimed(), 37);
+        assert!(!limit.claim());
+    }
+
+    #[test]
+    fn released_slots_can_be_claimed_again() {
+        let limit = MatchLimit::new(1, Then::Stop);
+        assert!(limit.claim());
+        assert!(!limit.claim());
+        limit.release();
+        assert_eq!(limit.claimed(), 0);
+        assert!(limit.claim());
+    }
+
+    #[test]
+    fn stop_when_full() {
+        let limit = MatchLimit::new(2, Then::Stop);
+        assert!(limit.claim());
+        assert_eq!(limit.update(true), None);
+        assert!(limit.claim());
+        assert_eq!(limit.update(true), Some(Transition::StopAtMax));
+        // 只有一个调用拿到转换
+        assert_eq!(limit.update(true), None);
+        assert_eq!(limit.state(), LimitState::Done);
+    }
+
+    #[test]
+    fn continue_sample_then_done() {
+        let limit = MatchLimit::new(1, Then::ContinueSample);
+        assert!(limit.claim());
+        assert_eq!(limit.update(true), Some(Transition::StartSampling));
+        assert_eq!(limit.state(), LimitState::Sampling);
+        assert_eq!(limit.update(true), None);
+        assert_eq!(limit.update(false), Some(Transition::SamplesDone));
+        assert_eq!(Transition::SamplesDone.stop_reason(), Some("samples_done"));
+
+        // 采样在匹配找够之前已经完成时直接结束
+        let limit = MatchLimit::new(1, Then::ContinueSample);
+        assert!(limit.claim());
+        assert_eq!(limit.update(false), Some(Transition::StopAtMax));
+    }
+}
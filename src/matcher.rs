@@ -0,0 +1,153 @@
+//! 搜索目标的类型
+//!
+//! `-p` 的文本前缀、`--byte-prefix` / `--byte-suffix` 的原始字节模式、`--palindrome` / `--mirror`
+//! 的对称匹配器和 `--fuzzy` 的模糊前缀一起放进 [`crate::Search`]，是“任一命中即匹配”的关系。
+//! 每种目标是 [`Matcher`] 的一个变体，[`Search`](crate::Search) 按变体分派匹配，
+//! 不从名字里猜类型，所以任何文本前缀都不会被当成别的匹配器。
+//!
+//! [`Matcher`] 的 [`Display`](fmt::Display) 是它的名字，用于计数、统计和输出文件的前缀列：
+//! 文本前缀就是它本身，其余分别为 [`BytePattern::label`]、[`Symmetry::label`] 和 [`Fuzzy::label`]。
+
+use std::fmt;
+
+use crate::byte_pattern::BytePattern;
+use crate::fuzzy::Fuzzy;
+use crate::symmetry::Symmetry;
+
+/// 一个搜索目标
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Matcher {
+    /// 地址的文本前缀，可以含字符类
+    Prefix(String),
+    /// 公钥原始字节的前缀或后缀
+    Bytes(BytePattern),
+    /// 回文或镜像
+    Symmetry(Symmetry),
+    /// 模糊前缀
+    Fuzzy(Fuzzy),
+}
+
+impl Matcher {
+    /// 文本前缀本身，其余变体为 `None`
+    pub fn as_prefix(&self) -> Option<&str> {
+        match self {
+            Matcher::Prefix(prefix) => Some(prefix),
+            _ => None,
+        }
+    }
+
+    /// 名字相同时的排列顺序，保证 [`crate::Search`] 中的顺序与输入顺序无关
+    pub(crate) fn rank(&self) -> u8 {
+        match self {
+            Matcher::Prefix(_) => 0,
+            Matcher::Bytes(_) => 1,
+            Matcher::Symmetry(_) => 2,
+            Matcher::Fuzzy(_) => 3,
+        }
+    }
+}
+
+impl fmt::Display for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Matcher::Prefix(prefix) => f.write_str(prefix),
+            Matcher::Bytes(pattern) => f.write_str(&pattern.label()),
+            Matcher::Symmetry(symmetry) => f.write_str(&symmetry.label()),
+            Matcher::Fuzzy(fuzzy) => f.write_str(&fuzzy.label()),
+        }
+    }
+}
+
+impl From<String> for Matcher {
+    fn from(prefix: String) -> Self {
+        Matcher::Prefix(prefix)
+    }
+}
+
+impl From<&str> for Matcher {
+    fn from(prefix: &str) -> Self {
+        Matcher::Prefix(prefix.to_string())
+    }
+}
+
+impl From<&String> for Matcher {
+    fn from(prefix: &String) -> Self {
+        Matcher::Prefix(prefix.clone())
+    }
+}
+
+impl From<BytePattern> for Matcher {
+    fn from(pattern: BytePattern) -> Self {
+        Matcher::Bytes(pattern)
+    }
+}
+
+impl From<Symmetry> for Matcher {
+    fn from(symmetry: Symmetry) -> Self {
+        Matcher::Symmetry(symmetry)
+    }
+}
+
+impl From<Fuzzy> for Matcher {
+    fn from(fuzzy: Fuzzy) -> Self {
+        Matcher::Fuzzy(fuzzy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Search;
+
+    #[test]
+    fn display_is_the_label() {
+        assert_eq!(Matcher::from("Sol").to_string(), "Sol");
+        assert_eq!(Matcher::Bytes(BytePattern::Prefix(vec![0xde, 0xad])).to_string(), "0xdead…");
+        assert_eq!(Matcher::Bytes(BytePattern::Suffix(vec![0xbe, 0xef])).to_string(), "…0xbeef");
+        assert_eq!(Matcher::Symmetry(Symmetry::Palindrome(5)).to_string(), "<palindrome 5>");
+        assert_eq!(Matcher::Symmetry(Symmetry::Mirror(3)).to_string(), "<mirror 3>");
+        assert_eq!(Matcher::Fuzzy(Fuzzy::new("solana", 1).unwrap()).to_string(), "<fuzzy solana 1>");
+    }
+
+    #[test]
+    fn text_that_looks_like_a_label_stays_a_prefix() {
+        // 与回文匹配器同名的字符串仍然只是文本前缀，不会被当成回文
+        let search = Search::new(["<palindrome 3>"]);
+        assert!(search.symmetries().is_empty());
+        assert!(search.byte_patterns().is_empty());
+        assert_eq!(search.find("ABAxyz"), None);
+        assert_eq!(search.find("<palindrome 3>xyz"), Some(0));
+
+        let search = Search::new(["0xdead…", "<fuzzy abc 1>"]);
+        assert!(search.byte_patterns().is_empty());
+        assert!(search.fuzzies().is_empty());
+        assert_eq!(search.find("abd"), None);
+    }
+
+    #[test]
+    fn same_label_different_kinds_are_kept_apart() {
+        let palindrome = Matcher::Symmetry(Symmetry::Palindrome(3));
+        let search = Search::new([Matcher::from("<palindrome 3>"), palindrome.clone()]);
+        assert_eq!(search.matchers(), &[Matcher::from("<palindrome 3>"), palindrome]);
+        assert_eq!(search.find_all("ABAxyz"), vec![1]);
+    }
+
+    #[test]
+    fn duplicates_are_removed_and_order_is_fixed() {
+        let a = Search::new([
+            Matcher::Symmetry(Symmetry::Mirror(2)),
+            Matcher::from("So"),
+            Matcher::from("So"),
+            Matcher::Symmetry(Symmetry::Mirror(2)),
+        ]);
+        let b = Search::new([Matcher::from("So"), Matcher::Symmetry(Symmetry::Mirror(2))]);
+        assert_eq!(a.matchers(), b.matchers());
+        assert_eq!(a.prefixes(), &["<mirror 2>".to_string(), "So".to_string()]);
+    }
+
+    #[test]
+    fn as_prefix_only_for_text() {
+        assert_eq!(Matcher::from("So").as_prefix(), Some("So"));
+        assert_eq!(Matcher::Symmetry(Symmetry::Palindrome(3)).as_prefix(), None);
+    }
+}
@@ -0,0 +1,36 @@
+//! 离线模式
+//!
+//! 参数校验阶段拒绝所有可能访问网络的选项；此外所有可能访问网络的代码路径在执行前
+//! 都会检查这里的全局标志，作为第二道保险。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// 进入离线模式，之后无法退出
+pub fn enable() {
+    OFFLINE.store(true, Ordering::SeqCst);
+}
+
+/// 是否处于离线模式
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::SeqCst)
+}
+
+/// 可能访问网络的代码在执行前调用，离线模式下返回错误
+pub fn ensure_online(feature: &str) -> Result<(), String> {
+    if is_offline() {
+        Err(format!("离线模式下禁止{}", feature))
+    } else {
+        Ok(())
+    }
+}
+
+/// 检查离线模式与已使用的选项是否冲突，`used` 为实际使用了的网络相关选项
+pub fn validate(used: &[&str]) -> Result<(), String> {
+    if used.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("--offline 不能与以下可能访问网络的选项一起使用: {}", used.join(", ")))
+    }
+}
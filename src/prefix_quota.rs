@@ -0,0 +1,134 @@
+//! 每个前缀的匹配数量（`--per-prefix`），以及从已有匹配文件继续（`--append`）
+//!
+//! 多个前缀各要几个时，先找够的前缀之后的匹配既不计数也不写入，所有前缀都找够后结束。
+//! 一个地址同时满足几个前缀时，每个还没找够的前缀各占一个名额。
+//! 与 `--append` 一起使用时，启动前读取已有的匹配文件，用与搜索相同的前缀匹配逻辑
+//! 重新确认每一行并按前缀计数，已经找够的前缀不再参与搜索，重启时只找剩下的。
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::integrity;
+use crate::search::Search;
+
+/// 各工作线程共享的每个前缀的剩余名额
+#[derive(Debug)]
+pub struct PrefixQuota {
+    target: u64,
+    counts: Vec<AtomicU64>,
+}
+
+impl PrefixQuota {
+    /// `initial` 按前缀序号给出已有的匹配数
+    pub fn new(target: u64, initial: impl IntoIterator<Item = u64>) -> Self {
+        Self {
+            target,
+            counts: initial.into_iter().map(AtomicU64::new).collect(),
+        }
+    }
+
+    pub fn target(&self) -> u64 {
+        self.target
+    }
+
+    /// 为前缀 `index` 的一个新匹配占一个名额，已经找够时返回 `false`
+    ///
+    /// 运行中添加的前缀不在这里计数（见 [`Search::claim`]），总是返回 `true`。
+    pub fn claim(&self, index: usize) -> bool {
+        let Some(count) = self.counts.get(index) else {
+            return true;
+        };
+        count
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                (count < self.target).then_some(count + 1)
+            })
+            .is_ok()
+    }
+
+    /// 前缀 `index` 还差的匹配数
+    pub fn remaining(&self, index: usize) -> u64 {
+        self.target.saturating_sub(self.counts[index].load(Ordering::Relaxed))
+    }
+
+    /// 所有前缀是否都已找够
+    pub fn is_done(&self) -> bool {
+        self.counts
+            .iter()
+            .all(|count| count.load(Ordering::Relaxed) >= self.target)
+    }
+}
+
+/// 从已有匹配文件读出的结果
+#[derive(Debug, Default)]
+pub struct Loaded {
+    /// 数据行数
+    pub rows: usize,
+    /// 每个前缀确认过的匹配数
+    pub counts: BTreeMap<String, u64>,
+    /// 没有计入的行：行号和原因
+    pub skipped: Vec<(usize, String)>,
+}
+
+/// 读取已有的匹配文件，按 `search` 的前缀重新匹配每一行的地址并计数
+///
+/// 地址无效、不匹配任何前缀，或私钥列（`private_key`、`keypair64_bs58`、`secret32_bs58`）
+/// 与地址不对应的行不计入。`search` 只用来匹配，不修改它的计数。
+pub fn load_matches(path: impl AsRef<Path>, search: &Search) -> Result<Loaded, String> {
+    let path = path.as_ref();
+    let read_error = |err: &dyn std::fmt::Display| format!("无法读取 {}: {}", path.display(), err);
+    let mut records = integrity::open_table(path).map_err(|err| read_error(&err))?;
+    let mut loaded = Loaded::default();
+    let Some(columns) = records.next() else {
+        return Ok(loaded);
+    };
+    let columns = columns.map_err(|err| read_error(&err))?;
+    let column = |name: &str| columns.iter().position(|column| column == name);
+    let address_col = column("address").ok_or_else(|| format!("{} 的标题中缺少 address 列", path.display()))?;
+    let keypair_col = column("private_key").or_else(|| column("keypair64_bs58"));
+    let seed_col = column("secret32_bs58");
+
+    for record in records {
+        let record = record.map_err(|err| read_error(&err))?;
+        let line = record.position().map_or(0, |position| position.line() as usize);
+        loaded.rows += 1;
+        let address = record.get(address_col).unwrap_or_default();
+        let verdict = match_row(search, address).and_then(|indices| {
+            if let Some(secret) = keypair_col.and_then(|col| record.get(col)) {
+                integrity::check_keypair(address, secret)?;
+            }
+            if let Some(seed) = seed_col.and_then(|col| record.get(col)) {
+                integrity::check_seed(address, seed)?;
+            }
+            Ok(indices)
+        });
+        match verdict {
+            Ok(indices) => {
+                for index in indices {
+                    *loaded.counts.entry(search.prefixes()[index].clone()).or_default() += 1;
+                }
+            }
+            Err(reason) => loaded.skipped.push((line, reason)),
+        }
+    }
+    Ok(loaded)
+}
+
+/// 与搜索时相同的检查：字节过滤、原始字节模式、前缀树和对称匹配器，最后是字符条件，
+/// 返回地址满足的全部前缀序号
+fn match_row(search: &Search, address: &str) -> Result<Vec<usize>, String> {
+    let pubkey: Pubkey = address.parse().map_err(|_| format!("无效的地址: {}", address))?;
+    let bytes = pubkey.to_bytes();
+    let mut indices = Vec::new();
+    if search.accepts_bytes(&bytes) && search.accepts_address(address) {
+        indices.extend(search.find_bytes(&bytes));
+        indices.extend(search.find_all(address));
+        indices.sort_unstable();
+    }
+    if indices.is_empty() {
+        return Err(format!("{} 不匹配当前的任何前缀", address));
+    }
+    Ok(indices)
+}
@@ -0,0 +1,151 @@
+//! 工作循环的分阶段计时（`--profile`）
+//!
+//! 每个工作线程持有一个 [`Profiler`]，结束时合并。逐个密钥的阶段（密钥生成、Base58 编码、匹配）
+//! 每 [`SAMPLE_INTERVAL`] 个密钥抽样计时一次，估计值为抽样耗时乘以间隔，平时不调用计时器；
+//! 每批一次的共享状态更新和把结果交给写入线程（含通道满时的等待）每次都计时，它们本身就不频繁。
+//! 暂停、限速的睡眠和写入线程自身的耗时不属于任何阶段，百分比相对于各阶段之和。
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// 逐个密钥的阶段每隔多少个密钥抽样计时一次
+pub const SAMPLE_INTERVAL: u64 = 64;
+
+/// 工作循环的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// 随机数和密钥对生成
+    Keygen,
+    /// 公钥的 Base58 编码
+    Encode,
+    /// 过滤条件、名单检查和前缀匹配
+    Match,
+    /// 每批合并计数器等共享状态更新
+    Shared,
+    /// 把结果交给写入线程
+    Write,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 5] = [Phase::Keygen, Phase::Encode, Phase::Match, Phase::Shared, Phase::Write];
+
+    /// 摘要 JSON 中使用的名字
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Keygen => "keygen",
+            Phase::Encode => "encode",
+            Phase::Match => "match",
+            Phase::Shared => "shared",
+            Phase::Write => "write",
+        }
+    }
+
+    /// 终端中显示的名字
+    pub fn label(self) -> &'static str {
+        match self {
+            Phase::Keygen => "密钥生成",
+            Phase::Encode => "Base58 编码",
+            Phase::Match => "匹配",
+            Phase::Shared => "共享状态",
+            Phase::Write => "交给写入线程",
+        }
+    }
+
+    /// 是否按抽样计时
+    fn sampled(self) -> bool {
+        matches!(self, Phase::Keygen | Phase::Encode | Phase::Match)
+    }
+}
+
+/// 一个工作线程的累计耗时
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    totals: [Duration; 5],
+    keys: u64,
+}
+
+impl Profiler {
+    /// 开始处理一个新密钥，轮到抽样时返回计时起点
+    pub fn start_key(&mut self) -> Option<Instant> {
+        self.keys += 1;
+        self.keys.is_multiple_of(SAMPLE_INTERVAL).then(Instant::now)
+    }
+
+    /// 把上一个计时点到现在的时间记到 `phase` 上，并把计时点移到现在；没有计时点时什么也不做
+    pub fn lap(&mut self, mark: &mut Option<Instant>, phase: Phase) {
+        if let Some(last) = mark {
+            let now = Instant::now();
+            self.totals[phase as usize] += now - *last;
+            *last = now;
+        }
+    }
+
+    /// 给每次都计时的阶段记上一段时间
+    pub fn add(&mut self, phase: Phase, elapsed: Duration) {
+        self.totals[phase as usize] += elapsed;
+    }
+
+    pub fn merge(&mut self, other: &Profiler) {
+        for (total, other) in self.totals.iter_mut().zip(other.totals) {
+            *total += other;
+        }
+        self.keys += other.keys;
+    }
+
+    /// 各阶段的估计耗时和百分比
+    pub fn summary(&self) -> ProfileSummary {
+        let estimates: Vec<f64> = Phase::ALL
+            .iter()
+            .map(|phase| {
+                let secs = self.totals[*phase as usize].as_secs_f64();
+                if phase.sampled() { secs * SAMPLE_INTERVAL as f64 } else { secs }
+            })
+            .collect();
+        let total: f64 = estimates.iter().sum();
+        let phases = Phase::ALL
+            .iter()
+            .zip(estimates)
+            .map(|(phase, secs)| {
+                let percent = if total > 0.0 { secs / total * 100.0 } else { 0.0 };
+                (phase.name().to_string(), PhaseTime { secs, percent })
+            })
+            .collect();
+        ProfileSummary {
+            sample_interval: SAMPLE_INTERVAL,
+            phases,
+        }
+    }
+}
+
+/// 一个阶段的估计耗时（所有工作线程之和）和所占百分比
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PhaseTime {
+    pub secs: f64,
+    pub percent: f64,
+}
+
+/// 写入摘要报告的分阶段耗时
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileSummary {
+    /// 逐个密钥的阶段的抽样间隔
+    pub sample_interval: u64,
+    /// 按阶段名（keygen、encode、match、shared、write）
+    pub phases: BTreeMap<String, PhaseTime>,
+}
+
+impl ProfileSummary {
+    /// 单行的百分比分布，按阶段的固定顺序
+    pub fn breakdown(&self) -> String {
+        Phase::ALL
+            .iter()
+            .filter_map(|phase| {
+                self.phases
+                    .get(phase.name())
+                    .map(|time| format!("{} {:.1}%", phase.label(), time.percent))
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
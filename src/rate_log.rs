@@ -0,0 +1,106 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::Local;
+
+use crate::stats::Stats;
+
+/// 吞吐量时间序列日志
+///
+/// 每个统计周期追加一行 CSV，每行写完立即刷新，便于长时间运行时对比硬件或观察降频。
+/// 文件以追加方式打开，与主输出文件互不影响。
+pub struct RateLog {
+    file: File,
+    threads: usize,
+    rate: IntervalRate,
+}
+
+impl RateLog {
+    pub const HEADER: &'static str =
+        "timestamp,interval_keys_per_sec,cumulative_keys,matches,threads";
+
+    /// 打开（或创建）日志文件，空文件会先写入标题行
+    pub fn open(path: impl AsRef<Path>, threads: usize) -> io::Result<Self> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if file.metadata()?.len() == 0 {
+            writeln!(file, "{}", Self::HEADER)?;
+            file.flush()?;
+        }
+
+        Ok(Self {
+            file,
+            threads,
+            rate: IntervalRate::default(),
+        })
+    }
+
+    /// 根据最新的统计快照追加一行，速率按与上一行之间的区间计算
+    pub fn record(&mut self, stats: &Stats) -> io::Result<()> {
+        let interval_rate = self.rate.update(stats);
+        writeln!(
+            self.file,
+            "{},{:.2},{},{},{}",
+            Local::now().to_rfc3339(),
+            interval_rate,
+            stats.generated,
+            stats.matched,
+            self.threads
+        )?;
+        self.file.flush()
+    }
+}
+
+/// `--heartbeat` 的纯文本进度行
+///
+/// 进度条用回车原地刷新，写进 journald 等日志后只剩一串控制字符。这里每个周期输出完整的一行
+/// `[时间] generated=X matched=Y rate=Z/s`，不覆盖之前的内容，便于日志采集和 grep。
+/// 速率与 [`RateLog`] 相同，按与上一行之间的区间计算。
+#[derive(Debug, Default)]
+pub struct HeartbeatLine {
+    rate: IntervalRate,
+}
+
+impl HeartbeatLine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 根据最新的统计快照生成一行，不含换行符
+    pub fn line(&mut self, stats: &Stats) -> String {
+        let rate = self.rate.update(stats);
+        format!(
+            "[{}] generated={} matched={} rate={:.2}/s",
+            Local::now().format("%Y-%m-%dT%H:%M:%S%:z"),
+            stats.generated,
+            stats.matched,
+            rate
+        )
+    }
+}
+
+/// 相邻两次快照之间的速率，暂停的时间不计入运行时间，也就不计入区间
+#[derive(Debug, Default)]
+struct IntervalRate {
+    last_generated: u64,
+    last_elapsed: Duration,
+}
+
+impl IntervalRate {
+    fn update(&mut self, stats: &Stats) -> f64 {
+        let interval = stats.elapsed.saturating_sub(self.last_elapsed).as_secs_f64();
+        let rate = if interval > 0.0 {
+            stats.generated.saturating_sub(self.last_generated) as f64 / interval
+        } else {
+            0.0
+        };
+        self.last_generated = stats.generated;
+        self.last_elapsed = stats.elapsed;
+        rate
+    }
+}
@@ -0,0 +1,126 @@
+//! 运行摘要报告
+//!
+//! `--report` 指定的 JSON 文件在运行结束时写入最终的统计摘要；配合 `--checkpoint-every`
+//! 还会在运行期间定期覆盖写入当前的摘要，多日运行中途崩溃时也能留下进度记录。
+//! 摘要只包含计数和地址开头，不含任何私钥。
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::luck::{Histogram, Luck, LuckTracker};
+use crate::profile::ProfileSummary;
+use crate::stats::Stats;
+
+/// 运行期间写入的快照的状态，结束时替换为结束原因
+pub const STATE_RUNNING: &str = "running";
+
+/// 某一时刻的运行摘要
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub run_id: String,
+    /// 写入时的本地时间，RFC 3339
+    pub updated_at: String,
+    /// 运行中为 `running`，结束后为结束原因
+    pub state: String,
+    pub elapsed_secs: f64,
+    pub generated: u64,
+    pub matched: u64,
+    pub rate: f64,
+    /// 每个前缀的匹配数
+    pub matches: BTreeMap<String, u64>,
+    /// 每个前缀目前为止最长的部分匹配
+    pub best_partial: BTreeMap<String, String>,
+    /// `--profile` 的分阶段耗时，只在结束时写入
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<ProfileSummary>,
+    /// `--known-addresses` 命中的匹配地址
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub known_hits: Vec<String>,
+    /// 每个前缀各次匹配的运气，只在结束时写入
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub luck: BTreeMap<String, Vec<Luck>>,
+    /// 匹配间隔直方图，匹配足够多时才有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub luck_histogram: Option<Histogram>,
+}
+
+impl Summary {
+    pub fn new(run_id: &str, stats: &Stats, state: &str) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            updated_at: Local::now().to_rfc3339(),
+            state: state.to_string(),
+            elapsed_secs: stats.elapsed.as_secs_f64(),
+            generated: stats.generated,
+            matched: stats.matched,
+            rate: stats.rate,
+            matches: stats.per_prefix.iter().cloned().collect(),
+            best_partial: stats
+                .best_partial_matches()
+                .map(|(prefix, best)| (prefix.to_string(), best.to_string()))
+                .collect(),
+            profile: None,
+            known_hits: Vec::new(),
+            luck: BTreeMap::new(),
+            luck_histogram: None,
+        }
+    }
+
+    /// 附上分阶段耗时
+    pub fn with_profile(mut self, profile: Option<ProfileSummary>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// 附上已知地址命中的匹配地址
+    pub fn with_known_hits(mut self, known_hits: Vec<String>) -> Self {
+        self.known_hits = known_hits;
+        self
+    }
+
+    /// 附上每个匹配的运气和间隔直方图
+    pub fn with_luck(mut self, prefixes: &[String], tracker: &LuckTracker) -> Self {
+        self.luck = prefixes
+            .iter()
+            .cloned()
+            .zip(tracker.per_pattern())
+            .filter(|(_, matches)| !matches.is_empty())
+            .collect();
+        self.luck_histogram = tracker.histogram();
+        self
+    }
+
+    /// 是否是运行期间的快照（对应的运行没有正常结束时，文件会停留在这个状态）
+    pub fn is_running(&self) -> bool {
+        self.state == STATE_RUNNING
+    }
+
+    /// 读取报告，不存在时返回 `None`
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), err)))
+    }
+
+    /// 先写临时文件再改名，写入途中崩溃不会留下半个报告
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut temp = PathBuf::from(path).into_os_string();
+        temp.push(".tmp");
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        std::fs::write(&temp, json + "\n")?;
+        std::fs::rename(&temp, path)
+    }
+}
@@ -0,0 +1,61 @@
+//! 密钥的用途（`--role`）
+//!
+//! 除了钱包地址，Solana 上还有几种地址也由调用方自己生成的 Ed25519 密钥对决定，
+//! 同样可以搜索靓号：代币 mint 账户、非关联代币账户。它们的搜索过程完全相同，区别只在
+//! 结果怎么用，所以 [`KeyRole`] 只改变默认值：匹配文件名、是否默认为每个匹配写密钥文件，
+//! 以及结束时提示的用法。命令行显式给出的选项总是优先。
+//!
+//! 这几种用途都要求地址在曲线上（创建账户时它要签名），搜索本来就只生成普通密钥对，
+//! 结果都满足；PDA 和关联代币账户（ATA）不在曲线上，没有私钥，不能这样搜索，
+//! 见 `--derive-ata` 和 `grind-squads`。
+
+use clap::ValueEnum;
+
+/// `--role` 的取值
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum KeyRole {
+    /// 普通钱包或签名者，与不指定时相同
+    #[default]
+    Signer,
+    /// 代币 mint 账户，用于 spl-token create-token --mint-keypair
+    Mint,
+    /// 非关联代币账户，用于 spl-token create-account <MINT> <密钥文件>
+    TokenAccount,
+}
+
+impl KeyRole {
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyRole::Signer => "signer",
+            KeyRole::Mint => "mint",
+            KeyRole::TokenAccount => "token-account",
+        }
+    }
+
+    /// 默认的匹配文件，`None` 表示沿用 `-m` 的默认值
+    pub fn default_matched_output(&self) -> Option<&'static str> {
+        match self {
+            KeyRole::Signer => None,
+            KeyRole::Mint => Some("data/mint_matched.csv"),
+            KeyRole::TokenAccount => Some("data/token_account_matched.csv"),
+        }
+    }
+
+    /// 默认的密钥文件目录；使用这个地址时必须用密钥文件签名，所以除 signer 外都默认写出
+    pub fn default_keypair_dir(&self) -> Option<&'static str> {
+        match self {
+            KeyRole::Signer => None,
+            KeyRole::Mint => Some("data/mint_keypairs"),
+            KeyRole::TokenAccount => Some("data/token_account_keypairs"),
+        }
+    }
+
+    /// 用匹配到的密钥文件创建账户的命令
+    pub fn usage(&self, keypair_file: &str) -> Option<String> {
+        match self {
+            KeyRole::Signer => None,
+            KeyRole::Mint => Some(format!("spl-token create-token --mint-keypair {}", keypair_file)),
+            KeyRole::TokenAccount => Some(format!("spl-token create-account <MINT> {}", keypair_file)),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! 运行时间窗口
+//!
+//! `--schedule` 的语法是逗号分隔的若干项，当前时间满足任意一项即处于窗口内：
+//!
+//! - `HH:MM-HH:MM`：每天的时间段，起点大于终点时跨越午夜，如 `22:00-07:00`；终点可以写 `24:00`
+//! - `Mon`…`Sun`：整天，不区分大小写
+//!
+//! 例如 `22:00-07:00,Sat,Sun` 表示每晚 22 点到次日 7 点以及整个周末。
+//! 时间一律按本地墙上时钟判断，夏令时切换时窗口仍按钟面时间开始和结束。
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Timelike, Weekday};
+
+/// 每天的一个时间段，单位为从零点开始的分钟数，`end` 为 1440 表示到午夜
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TimeRange {
+    start: u32,
+    end: u32,
+}
+
+impl TimeRange {
+    fn contains(&self, minute: u32) -> bool {
+        if self.start < self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+}
+
+/// 一组重复的运行时间窗口
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    ranges: Vec<TimeRange>,
+    days: Vec<Weekday>,
+}
+
+impl Schedule {
+    /// 给定的本地时间是否处于窗口内
+    pub fn is_active(&self, time: NaiveDateTime) -> bool {
+        let minute = time.hour() * 60 + time.minute();
+        self.days.contains(&time.weekday()) || self.ranges.iter().any(|range| range.contains(minute))
+    }
+
+    /// 从给定时间起下一次进入窗口的时刻（精确到分钟），已在窗口内时返回 `None`
+    pub fn next_start(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        if self.is_active(time) {
+            return None;
+        }
+        let mut minute = time.with_second(0)?.with_nanosecond(0)?;
+        // 窗口每周重复，最多向后找一周
+        for _ in 0..7 * 24 * 60 {
+            minute += Duration::minutes(1);
+            if self.is_active(minute) {
+                return Some(minute);
+            }
+        }
+        None
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut schedule = Schedule {
+            ranges: Vec::new(),
+            days: Vec::new(),
+        };
+        for item in value.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            if let Some((start, end)) = item.split_once('-') {
+                let range = TimeRange {
+                    start: parse_minute(start)?,
+                    end: parse_minute(end)?,
+                };
+                if range.start == range.end || range.start == 24 * 60 {
+                    return Err(format!("无效的时间段: {}（起点和终点不能相同）", item));
+                }
+                schedule.ranges.push(range);
+            } else {
+                let day = item
+                    .parse::<Weekday>()
+                    .map_err(|_| format!("无法识别的时间窗口: {}（应为 HH:MM-HH:MM 或 Mon…Sun）", item))?;
+                schedule.days.push(day);
+            }
+        }
+        if schedule.ranges.is_empty() && schedule.days.is_empty() {
+            return Err("时间窗口不能为空".to_string());
+        }
+        Ok(schedule)
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ranges = self.ranges.iter().map(|range| {
+            format!(
+                "{:02}:{:02}-{:02}:{:02}",
+                range.start / 60,
+                range.start % 60,
+                range.end / 60,
+                range.end % 60
+            )
+        });
+        let days = self.days.iter().map(|day| day.to_string());
+        let items: Vec<String> = ranges.chain(days).collect();
+        f.write_str(&items.join(","))
+    }
+}
+
+/// 解析 `HH:MM`，允许 `24:00` 表示午夜
+fn parse_minute(value: &str) -> Result<u32, String> {
+    let value = value.trim();
+    if value == "24:00" {
+        return Ok(24 * 60);
+    }
+    let time = NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| format!("无效的时间: {}（应为 HH:MM）", value))?;
+    Ok(time.hour() * 60 + time.minute())
+}
@@ -0,0 +1,202 @@
+//! 地址“美观度”评分与排行榜
+//!
+//! 使用 `--rank` 时，每个生成的地址都会打分，写入线程用一个容量为 N 的最小堆保留分数最高的
+//! N 个地址，结束时按分数从高到低写出。工作线程只在分数超过当前入榜门槛时才把地址发给写入线程，
+//! 堆满之后几乎不产生额外的通道消息。
+//!
+//! 评分规则可以替换：实现 [`Scorer`] 即可，命令行使用的是 [`BeautyScore`]。
+
+use std::cmp::{Ordering as CmpOrdering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// 地址评分规则，分数越高越好
+pub trait Scorer: Send + Sync {
+    fn score(&self, address: &str) -> u32;
+}
+
+/// 默认的美观度评分：三项指标的加权和
+///
+/// - `repeat`：重复字符数，即地址长度减去不同字符的个数
+/// - `run`：连续相同字符，每段长度为 L 的连续字符计 (L-1)²，例如 `aaaa` 计 9
+/// - `palindrome`：最长回文子串的长度，短于 3 时计 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BeautyScore {
+    pub repeat: u32,
+    pub run: u32,
+    pub palindrome: u32,
+}
+
+impl Default for BeautyScore {
+    fn default() -> Self {
+        Self {
+            repeat: 1,
+            run: 3,
+            palindrome: 2,
+        }
+    }
+}
+
+impl BeautyScore {
+    /// 三项指标的原始值：(重复字符数, 连续字符得分, 最长回文长度)
+    pub fn components(address: &str) -> (u32, u32, u32) {
+        let bytes = address.as_bytes();
+
+        let distinct = bytes.iter().collect::<HashSet<_>>().len();
+        let repeat = (bytes.len() - distinct) as u32;
+
+        let run = bytes
+            .chunk_by(|a, b| a == b)
+            .map(|chunk| (chunk.len() as u32 - 1).pow(2))
+            .sum();
+
+        let palindrome = longest_palindrome(bytes) as u32;
+        let palindrome = if palindrome >= 3 { palindrome } else { 0 };
+
+        (repeat, run, palindrome)
+    }
+}
+
+impl Scorer for BeautyScore {
+    fn score(&self, address: &str) -> u32 {
+        let (repeat, run, palindrome) = Self::components(address);
+        self.repeat * repeat + self.run * run + self.palindrome * palindrome
+    }
+}
+
+impl FromStr for BeautyScore {
+    type Err = String;
+
+    /// 解析 `repeat=1,run=3,palindrome=2`，未给出的项保持默认权重
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut score = Self::default();
+        for part in value.split(',').filter(|part| !part.trim().is_empty()) {
+            let (name, weight) = part
+                .split_once('=')
+                .ok_or_else(|| format!("无效的权重: {}（应为 名称=数值）", part))?;
+            let weight: u32 = weight
+                .trim()
+                .parse()
+                .map_err(|_| format!("无效的权重数值: {}", weight))?;
+            match name.trim() {
+                "repeat" => score.repeat = weight,
+                "run" => score.run = weight,
+                "palindrome" => score.palindrome = weight,
+                other => return Err(format!("未知的评分项: {}（可选: repeat, run, palindrome）", other)),
+            }
+        }
+        Ok(score)
+    }
+}
+
+/// 中心扩展法求最长回文子串的长度
+fn longest_palindrome(bytes: &[u8]) -> usize {
+    let expand = |mut left: usize, mut right: usize| {
+        // 返回 [left, right) 向两侧扩展后的最大长度
+        while left > 0 && right < bytes.len() && bytes[left - 1] == bytes[right] {
+            left -= 1;
+            right += 1;
+        }
+        right - left
+    };
+    (0..bytes.len())
+        .map(|center| expand(center, center + 1).max(expand(center, center)))
+        .max()
+        .unwrap_or(0)
+}
+
+/// 排行榜的入榜门槛，由写入线程更新，工作线程读取
+#[derive(Debug, Default)]
+pub struct Threshold(AtomicU32);
+
+impl Threshold {
+    /// 分数是否有可能进入排行榜
+    pub fn admits(&self, score: u32) -> bool {
+        score > self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn set(&self, score: u32) {
+        self.0.store(score, Ordering::Relaxed);
+    }
+}
+
+struct Entry<T> {
+    score: u32,
+    /// 加入顺序，分数相同时先找到的排在前面
+    sequence: u64,
+    item: T,
+}
+
+impl<T> Entry<T> {
+    fn key(&self) -> (u32, Reverse<u64>) {
+        (self.score, Reverse(self.sequence))
+    }
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// 保留分数最高的 N 项
+pub struct TopN<T> {
+    capacity: usize,
+    sequence: u64,
+    heap: BinaryHeap<Reverse<Entry<T>>>,
+}
+
+impl<T> TopN<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            sequence: 0,
+            heap: BinaryHeap::with_capacity(capacity + 1),
+        }
+    }
+
+    /// 加入一项，超出容量时丢弃分数最低的一项
+    pub fn push(&mut self, score: u32, item: T) {
+        self.sequence += 1;
+        self.heap.push(Reverse(Entry {
+            score,
+            sequence: self.sequence,
+            item,
+        }));
+        if self.heap.len() > self.capacity {
+            self.heap.pop();
+        }
+    }
+
+    /// 已满时返回榜上的最低分，新项必须高于它才能入榜
+    pub fn min_score(&self) -> Option<u32> {
+        if self.heap.len() < self.capacity {
+            return None;
+        }
+        self.heap.peek().map(|Reverse(entry)| entry.score)
+    }
+
+    /// 按分数从高到低排列
+    pub fn into_sorted(self) -> Vec<(u32, T)> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(entry)| (entry.score, entry.item))
+            .collect()
+    }
+}
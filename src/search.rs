@@ -0,0 +1,1109 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::and_condition::AndCondition;
+use crate::base58::Alphabet;
+use crate::byte_pattern::BytePattern;
+use crate::char_class;
+use crate::char_count::CharCount;
+use crate::filter::ByteFilter;
+use crate::fuzzy::Fuzzy;
+use crate::matcher::Matcher;
+use crate::shape::Shape;
+use crate::stats::Stats;
+use crate::symmetry::Symmetry;
+use crate::trie::PrefixTrie;
+
+pub use crate::base58::ALPHABET as BASE58_ALPHABET;
+
+/// 检查前缀只含 Base58 字符，否则指出第一个有问题的字符及其位置（从 1 开始）
+///
+/// 含有非 ASCII 或非 Base58 字符的前缀永远不会匹配，提前报错比静默地一直找不到更好。
+/// 全角字符会提示对应的半角字符。含字符类的前缀按 [`char_class`] 的语法检查，并且必须能展开。
+pub fn validate_prefix(prefix: &str) -> Result<(), String> {
+    if char_class::has_class(prefix) {
+        return char_class::expand(prefix).map(|_| ());
+    }
+    validate_prefix_with(prefix, Alphabet::BITCOIN)
+}
+
+/// 与 [`validate_prefix`] 相同，但使用给定的字母表
+pub fn validate_prefix_with(prefix: &str, alphabet: Alphabet) -> Result<(), String> {
+    let Err(c) = alphabet.validate(prefix) else {
+        return Ok(());
+    };
+    let position = prefix.chars().position(|other| other == c).unwrap_or_default() + 1;
+
+    if c.is_ascii() {
+        return Err(format!(
+            "前缀 \"{}\" 的第 {} 个字符 '{}' 不在 Base58 字母表中（Base58 不含 0、O、I、l 和符号）",
+            prefix, position, c
+        ));
+    }
+    // 全角 ASCII（U+FF01–U+FF5E）与半角字符一一对应
+    let halfwidth = ('\u{FF01}'..='\u{FF5E}')
+        .contains(&c)
+        .then(|| char::from_u32(c as u32 - 0xFEE0))
+        .flatten();
+    let hint = match halfwidth {
+        Some(ascii) if alphabet.contains(ascii) => format!("，是否想输入半角的 '{}'？", ascii),
+        _ => String::new(),
+    };
+    Err(format!(
+        "前缀 \"{}\" 的第 {} 个字符 '{}'（U+{:04X}）不是 ASCII 字符，可能是误输入的全角或带重音的字符{}",
+        prefix, position, c, c as u32, hint
+    ))
+}
+
+/// 解析交互命令 `a PATTERN[:N]` 的参数，返回要添加的前缀和要找的匹配数量，没有 `:N` 时数量为 `None`
+///
+/// 前缀按 [`validate_prefix`] 检查；`:` 不在 Base58 字母表中，所以最后一个 `:` 之后总是数量。
+pub fn parse_added_prefix(argument: &str) -> Result<(String, Option<u64>), String> {
+    let (prefix, target) = match argument.rsplit_once(':') {
+        Some((prefix, count)) => {
+            let count: u64 = count
+                .parse()
+                .ok()
+                .filter(|count| *count > 0)
+                .ok_or_else(|| format!("匹配数量 \"{}\" 不是正整数", count))?;
+            (prefix, Some(count))
+        }
+        None => (argument, None),
+    };
+    if prefix.is_empty() {
+        return Err("缺少要添加的前缀，用法: a PATTERN[:N]".to_string());
+    }
+    validate_prefix(prefix)?;
+    Ok((prefix.to_string(), target))
+}
+
+/// 每个前缀保留的近似命中数量
+pub const NEAR_MISS_COUNT: usize = 5;
+
+/// 一个地址同时满足几个前缀时，输出文件前缀列中各前缀之间的分隔符
+pub const PATTERN_SEPARATOR: &str = "|";
+
+/// 工作线程一批的本地匹配计数，由 [`Search::record_batch`] 合并
+///
+/// 一个地址同时满足几个前缀（如 `So` 和 `Sol`）时，每个前缀各记一次，地址数只记一次。
+#[derive(Debug, Clone, Default)]
+pub struct MatchCounts {
+    addresses: u64,
+    per_prefix: Vec<u64>,
+}
+
+impl MatchCounts {
+    /// `prefixes` 个前缀的空计数
+    pub fn new(prefixes: usize) -> Self {
+        Self {
+            addresses: 0,
+            per_prefix: vec![0; prefixes],
+        }
+    }
+
+    /// 记一个匹配的地址，`indices` 是它满足的全部前缀序号
+    pub fn credit(&mut self, indices: &[usize]) {
+        if indices.is_empty() {
+            return;
+        }
+        self.addresses += 1;
+        for &index in indices {
+            // 运行中追加的目标序号可能超出创建时的数量
+            if index >= self.per_prefix.len() {
+                self.per_prefix.resize(index + 1, 0);
+            }
+            self.per_prefix[index] += 1;
+        }
+    }
+}
+
+/// 近似命中：开头与前缀重合最多的地址之一
+#[derive(Clone)]
+pub struct NearMiss {
+    pub address: String,
+    /// 与前缀重合的字符数
+    pub matched: usize,
+    /// 只有要求保存时才保留密钥对
+    pub keypair: Option<[u8; 64]>,
+}
+
+/// 一个目标的计数和近似命中榜，添加目标后重建 [`PatternSet`] 时原样带过去
+#[derive(Default)]
+struct PatternState {
+    count: AtomicU64,
+    best_partial: AtomicUsize,
+    near_misses: Mutex<Vec<NearMiss>>,
+    /// 近似命中榜满后的最短重合长度，超过它才需要加锁
+    near_miss_floor: AtomicUsize,
+    /// 开始参与搜索时的生成总数，启动时就有的目标为 0
+    added_at: u64,
+    /// 运行中添加时指定的匹配数量，`None` 表示不限
+    target: Option<u64>,
+    /// 已经为 `target` 占用的名额
+    claimed: AtomicU64,
+}
+
+impl PatternState {
+    fn new(added_at: u64, target: Option<u64>) -> Self {
+        Self {
+            added_at,
+            target,
+            ..Self::default()
+        }
+    }
+}
+
+/// 某一时刻参与匹配的全部目标：前缀树和各类匹配器，创建后不再改变
+///
+/// 运行中添加目标时 [`Search::add`] 构造一个新的 `PatternSet` 整体替换旧的，已有目标的序号和计数不变。
+/// 工作线程每批开始时用 [`Search::patterns`] 取一次，批内的匹配不加锁。
+pub struct PatternSet {
+    matchers: Vec<Matcher>,
+    /// 各目标的名字，即 [`Matcher`] 的 `Display`，与 `matchers` 一一对应
+    prefixes: Vec<String>,
+    /// 展开字符类后的具体前缀，按字典序排列，前缀树的下标指向这里
+    literals: Vec<String>,
+    /// 每个具体前缀所属的前缀序号，几个含字符类的前缀展开出相同的具体前缀时有多个
+    owners: Vec<Vec<usize>>,
+    /// 是否有前缀含字符类，此时一个前缀对应多个具体前缀
+    has_classes: bool,
+    trie: PrefixTrie,
+    /// 原始字节模式及其前缀序号，不进前缀树
+    byte_patterns: Vec<(usize, BytePattern)>,
+    /// 回文和镜像匹配器及其前缀序号，不进前缀树
+    symmetries: Vec<(usize, Symmetry)>,
+    /// 模糊前缀及其前缀序号，不进前缀树
+    fuzzies: Vec<(usize, Fuzzy)>,
+    states: Vec<Arc<PatternState>>,
+}
+
+impl PatternSet {
+    /// 按给定的顺序编号，`states` 与 `matchers` 一一对应
+    fn build(matchers: Vec<Matcher>, states: Vec<Arc<PatternState>>) -> Self {
+        let prefixes: Vec<String> = matchers.iter().map(Matcher::to_string).collect();
+        let text_prefixes = || {
+            matchers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, matcher)| matcher.as_prefix().map(|prefix| (index, prefix)))
+        };
+        let has_classes = text_prefixes().any(|(_, prefix)| char_class::has_class(prefix));
+        let byte_patterns: Vec<(usize, BytePattern)> = matchers
+            .iter()
+            .enumerate()
+            .filter_map(|(index, matcher)| match matcher {
+                Matcher::Bytes(pattern) => Some((index, pattern.clone())),
+                _ => None,
+            })
+            .collect();
+        let symmetries: Vec<(usize, Symmetry)> = matchers
+            .iter()
+            .enumerate()
+            .filter_map(|(index, matcher)| match matcher {
+                Matcher::Symmetry(symmetry) => Some((index, *symmetry)),
+                _ => None,
+            })
+            .collect();
+        let fuzzies: Vec<(usize, Fuzzy)> = matchers
+            .iter()
+            .enumerate()
+            .filter_map(|(index, matcher)| match matcher {
+                Matcher::Fuzzy(fuzzy) => Some((index, fuzzy.clone())),
+                _ => None,
+            })
+            .collect();
+        let mut expanded: Vec<(String, usize)> = text_prefixes()
+            .flat_map(|(index, prefix)| {
+                char_class::expand(prefix)
+                    .unwrap_or_else(|_| vec![prefix.to_string()])
+                    .into_iter()
+                    .map(move |literal| (literal, index))
+            })
+            .collect();
+        expanded.sort();
+        expanded.dedup();
+        let mut literals: Vec<String> = Vec::new();
+        let mut owners: Vec<Vec<usize>> = Vec::new();
+        for (literal, index) in expanded {
+            match owners.last_mut() {
+                Some(last) if literals.last() == Some(&literal) => last.push(index),
+                _ => {
+                    literals.push(literal);
+                    owners.push(vec![index]);
+                }
+            }
+        }
+
+        Self {
+            trie: PrefixTrie::new(&literals),
+            literals,
+            owners,
+            has_classes,
+            byte_patterns,
+            symmetries,
+            fuzzies,
+            matchers,
+            prefixes,
+            states,
+        }
+    }
+
+    /// 目标数
+    pub fn len(&self) -> usize {
+        self.matchers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.matchers.is_empty()
+    }
+
+    /// 各目标的名字，即 [`Matcher`] 的 `Display`
+    pub fn prefixes(&self) -> &[String] {
+        &self.prefixes
+    }
+
+    /// 参与匹配的目标，与 [`PatternSet::prefixes`] 顺序一致
+    pub fn matchers(&self) -> &[Matcher] {
+        &self.matchers
+    }
+
+    /// 查找地址满足的全部前缀序号，按序号排列，不修改任何计数
+    ///
+    /// 通过前缀树查找，开销只与地址长度有关，与前缀数量无关；之后再检查回文、镜像匹配器和模糊前缀。
+    /// 同时满足 `So` 和 `Sol` 的地址两个都返回，每个前缀的计数因此与前缀的顺序无关。
+    pub fn find_all(&self, address: &str) -> Vec<usize> {
+        let mut found: Vec<usize> = self
+            .trie
+            .find_all(address)
+            .into_iter()
+            .flat_map(|literal| self.owners[literal].iter().copied())
+            .chain(
+                self.symmetries
+                    .iter()
+                    .filter(|(_, symmetry)| symmetry.matches(address))
+                    .map(|(index, _)| *index),
+            )
+            .chain(
+                self.fuzzies
+                    .iter()
+                    .filter(|(_, fuzzy)| fuzzy.matches(address))
+                    .map(|(index, _)| *index),
+            )
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    /// 地址满足的序号最小的前缀，只关心是否匹配时使用
+    pub fn find(&self, address: &str) -> Option<usize> {
+        self.find_all(address).first().copied()
+    }
+
+    /// 回文和镜像匹配器及其前缀序号
+    pub fn symmetries(&self) -> &[(usize, Symmetry)] {
+        &self.symmetries
+    }
+
+    /// 模糊前缀及其前缀序号
+    pub fn fuzzies(&self) -> &[(usize, Fuzzy)] {
+        &self.fuzzies
+    }
+
+    /// 写入输出文件的前缀列：回文和镜像匹配器记录实际对称的字符串，模糊前缀记录实际匹配的字符串和距离，
+    /// 其余为前缀本身；
+    /// 满足几个前缀时按序号用 [`PATTERN_SEPARATOR`] 连接
+    pub fn matched_text(&self, indices: &[usize], address: &str) -> String {
+        let texts: Vec<String> = indices
+            .iter()
+            .map(|index| match &self.matchers[*index] {
+                Matcher::Symmetry(symmetry) => symmetry.matched_text(address),
+                Matcher::Fuzzy(fuzzy) => fuzzy.matched_text(address),
+                _ => self.prefixes[*index].clone(),
+            })
+            .collect();
+        texts.join(PATTERN_SEPARATOR)
+    }
+
+    /// 地址中与前缀序号 `indices` 匹配的字符位置，用于终端高亮，按起点排列，可能互相重叠
+    ///
+    /// 文本前缀（含字符类）是开头的若干字符，回文和镜像见 [`Symmetry::spans`]，模糊前缀见 [`Fuzzy::spans`]；
+    /// 原始字节模式和只有条件的空前缀没有对应的字符。
+    pub fn matched_spans(&self, indices: &[usize], address: &str) -> Vec<Range<usize>> {
+        let mut spans: Vec<Range<usize>> = indices
+            .iter()
+            .flat_map(|index| match &self.matchers[*index] {
+                Matcher::Symmetry(symmetry) => symmetry.spans(address),
+                Matcher::Fuzzy(fuzzy) => fuzzy.spans(address),
+                Matcher::Bytes(_) => Vec::new(),
+                Matcher::Prefix(prefix) => {
+                    let length = char_class::len(prefix).min(address.len());
+                    (length > 0).then_some(0..length).into_iter().collect()
+                }
+            })
+            .collect();
+        spans.sort_by_key(|span| span.start);
+        spans
+    }
+
+    /// 查找公钥原始字节符合的全部字节模式的前缀序号，不需要 Base58 编码
+    pub fn find_bytes(&self, pubkey: &[u8]) -> Vec<usize> {
+        self.byte_patterns
+            .iter()
+            .filter(|(_, pattern)| pattern.matches(pubkey))
+            .map(|(index, _)| *index)
+            .collect()
+    }
+
+    /// 原始字节模式及其前缀序号
+    pub fn byte_patterns(&self) -> &[(usize, BytePattern)] {
+        &self.byte_patterns
+    }
+
+    /// 是否只有原始字节模式，此时不符合字节模式的密钥不需要编码就能确定不匹配
+    pub fn bytes_only(&self) -> bool {
+        !self.byte_patterns.is_empty() && self.literals.is_empty() && self.symmetries.is_empty() && self.fuzzies.is_empty()
+    }
+
+    /// 全部具体前缀及其所属的前缀序号，属于几个前缀的具体前缀出现几次
+    pub fn literals(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.literals
+            .iter()
+            .zip(&self.owners)
+            .flat_map(|(literal, owners)| owners.iter().map(move |owner| (literal.as_str(), *owner)))
+    }
+
+    /// 记录地址与各前缀的最长公共前缀长度，用于展示部分匹配的进度和近似命中榜
+    ///
+    /// 先用普通读取比较，只有超过当前最好成绩或近似命中榜的门槛时才写共享变量。
+    /// `keypair` 只在需要保存近似命中的私钥时传入。
+    /// 通过前缀树只访问与地址至少重合一个字符的前缀。含字符类的前缀取各个具体前缀中
+    /// 重合最长的一个；前缀树先回调较长的重合，所以同一前缀只需处理第一次回调。
+    pub fn track_partial(&self, address: &str, keypair: Option<&[u8; 64]>) {
+        let mut visited = Vec::new();
+        self.trie.for_each_common_prefix(address, |literal, length| {
+            for &index in &self.owners[literal] {
+                if self.has_classes {
+                    if visited.contains(&index) {
+                        continue;
+                    }
+                    visited.push(index);
+                }
+                let state = &self.states[index];
+                if length <= state.near_miss_floor.load(Ordering::Relaxed) {
+                    continue;
+                }
+                if length > state.best_partial.load(Ordering::Relaxed) {
+                    state.best_partial.fetch_max(length, Ordering::Relaxed);
+                }
+                state.record_near_miss(address, length, keypair);
+            }
+        });
+    }
+}
+
+impl PatternState {
+    /// 把地址放进近似命中榜，按重合长度从长到短排列，长度相同时先到的在前
+    fn record_near_miss(&self, address: &str, matched: usize, keypair: Option<&[u8; 64]>) {
+        let mut board = self.near_misses.lock().unwrap();
+        if board.len() == NEAR_MISS_COUNT {
+            if board.last().is_some_and(|last| last.matched >= matched) {
+                return;
+            }
+            board.pop();
+        }
+        let position = board.partition_point(|entry| entry.matched >= matched);
+        board.insert(
+            position,
+            NearMiss {
+                address: address.to_string(),
+                matched,
+                keypair: keypair.copied(),
+            },
+        );
+        if board.len() == NEAR_MISS_COUNT {
+            let floor = board.last().map_or(0, |last| last.matched);
+            self.near_miss_floor.store(floor, Ordering::Relaxed);
+        }
+    }
+}
+
+/// 一次前缀搜索的共享状态
+///
+/// 所有计数器都是原子变量，可以在多个工作线程之间通过 `&Search` 直接共享。
+/// 参与匹配的目标放在 [`PatternSet`] 中，运行中可以用 [`Search::add`] 追加。
+pub struct Search {
+    patterns: RwLock<Arc<PatternSet>>,
+    filters: Vec<ByteFilter>,
+    char_counts: Vec<CharCount>,
+    and_conditions: Vec<AndCondition>,
+    shape: Option<Shape>,
+    generated: AtomicU64,
+    /// 匹配的地址数，满足几个前缀的地址只算一次
+    matched: AtomicU64,
+    batch_size: AtomicU64,
+    sample_target: usize,
+    samples_taken: AtomicUsize,
+    stopped: AtomicBool,
+    paused: AtomicBool,
+    start_time: Instant,
+    pause_clock: Mutex<PauseClock>,
+}
+
+/// 暂停时间的累计，用于从总时长中扣除
+#[derive(Default)]
+struct PauseClock {
+    since: Option<Instant>,
+    total: Duration,
+}
+
+/// 暂停时工作线程检查恢复的间隔
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+impl Search {
+    /// 用给定的目标创建搜索，重复的目标会被去掉，顺序固定为名字的字典序
+    ///
+    /// 字符串是文本前缀。含字符类的前缀展开成具体前缀后放进同一棵前缀树；几个前缀展开出相同的具体前缀时，
+    /// 同时属于这几个前缀。无法展开的前缀按字面处理，永远不会匹配。
+    /// [`Matcher::Bytes`] 用 [`Search::find_bytes`] 匹配；[`Matcher::Symmetry`] 和 [`Matcher::Fuzzy`]
+    /// 在前缀树之后检查。
+    pub fn new<I, M>(matchers: I) -> Self
+    where
+        I: IntoIterator<Item = M>,
+        M: Into<Matcher>,
+    {
+        let mut keyed: Vec<(String, Matcher)> = matchers
+            .into_iter()
+            .map(Into::into)
+            .map(|matcher| (matcher.to_string(), matcher))
+            .collect();
+        keyed.sort_by(|(a_label, a), (b_label, b)| a_label.cmp(b_label).then(a.rank().cmp(&b.rank())));
+        keyed.dedup_by(|(_, a), (_, b)| a == b);
+        let matchers: Vec<Matcher> = keyed.into_iter().map(|(_, matcher)| matcher).collect();
+        let states = matchers.iter().map(|_| Arc::default()).collect();
+
+        Self {
+            patterns: RwLock::new(Arc::new(PatternSet::build(matchers, states))),
+            filters: Vec::new(),
+            char_counts: Vec::new(),
+            and_conditions: Vec::new(),
+            shape: None,
+            generated: AtomicU64::new(0),
+            matched: AtomicU64::new(0),
+            batch_size: AtomicU64::new(0),
+            sample_target: 0,
+            samples_taken: AtomicUsize::new(0),
+            stopped: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            start_time: Instant::now(),
+            pause_clock: Mutex::new(PauseClock::default()),
+        }
+    }
+
+    /// 增加一个公钥字节过滤条件，匹配要求同时满足前缀和所有过滤条件
+    pub fn with_filter(mut self, filter: ByteFilter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// 增加一个字符计数条件，匹配要求同时满足前缀和所有计数条件
+    pub fn with_char_count(mut self, condition: CharCount) -> Self {
+        self.char_counts.push(condition);
+        self
+    }
+
+    /// 增加一个与条件（`--and`），匹配要求同时满足前缀和所有与条件
+    pub fn with_and_condition(mut self, condition: AndCondition) -> Self {
+        self.and_conditions.push(condition);
+        self
+    }
+
+    /// 设置地址开头的形状，匹配要求同时满足前缀和形状
+    pub fn with_shape(mut self, shape: Shape) -> Self {
+        self.shape = Some(shape);
+        self
+    }
+
+    /// 设置要保存的非匹配地址数量
+    pub fn with_sample_target(mut self, target: usize) -> Self {
+        self.sample_target = target;
+        self
+    }
+
+    /// 当前参与匹配的全部目标
+    ///
+    /// 返回的是此刻的快照，之后 [`Search::add`] 追加的目标不在其中；
+    /// 工作线程每批取一次，批内用它匹配，不必每个地址都加锁。
+    pub fn patterns(&self) -> Arc<PatternSet> {
+        Arc::clone(&self.patterns.read().unwrap())
+    }
+
+    /// 在运行中追加一个目标，返回它的序号
+    ///
+    /// 新目标排在已有目标之后，已有目标的序号和计数不变；工作线程从下一批开始匹配它。
+    /// `target` 是这个目标要找的匹配数量，用 [`Search::claim`] 占用名额，`None` 表示不限。
+    /// 已经在查找同一个目标时返回错误。
+    pub fn add(&self, matcher: Matcher, target: Option<u64>) -> Result<usize, String> {
+        let mut patterns = self.patterns.write().unwrap();
+        if patterns.matchers.contains(&matcher) {
+            return Err(format!("已经在查找 {}", matcher));
+        }
+        let mut matchers = patterns.matchers.clone();
+        matchers.push(matcher);
+        let mut states = patterns.states.clone();
+        states.push(Arc::new(PatternState::new(self.generated(), target)));
+        let index = states.len() - 1;
+        *patterns = Arc::new(PatternSet::build(matchers, states));
+        Ok(index)
+    }
+
+    /// 为目标 `index` 的一个新匹配占一个名额，目标没有指定数量时总是成功，已经找够时返回 `false`
+    pub fn claim(&self, index: usize) -> bool {
+        let patterns = self.patterns();
+        let state = &patterns.states[index];
+        match state.target {
+            None => true,
+            Some(target) => state
+                .claimed
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |claimed| {
+                    (claimed < target).then_some(claimed + 1)
+                })
+                .is_ok(),
+        }
+    }
+
+    /// 指定了数量的目标是否都已找够，没有这样的目标时为 `true`
+    pub fn targets_done(&self) -> bool {
+        self.patterns().states.iter().all(|state| {
+            state
+                .target
+                .is_none_or(|target| state.claimed.load(Ordering::Relaxed) >= target)
+        })
+    }
+
+    /// 是否还需要保存非匹配地址，达到目标后工作线程不再为采样做任何额外工作
+    pub fn wants_samples(&self) -> bool {
+        self.samples_taken.load(Ordering::Relaxed) < self.sample_target
+    }
+
+    /// 申请保存一个非匹配地址，达到目标后返回 `false`
+    pub fn take_sample(&self) -> bool {
+        self.wants_samples() && self.samples_taken.fetch_add(1, Ordering::Relaxed) < self.sample_target
+    }
+
+    /// 公钥字节过滤条件
+    pub fn filters(&self) -> &[ByteFilter] {
+        &self.filters
+    }
+
+    /// 检查公钥原始字节是否满足所有过滤条件
+    pub fn accepts_bytes(&self, pubkey: &[u8]) -> bool {
+        self.filters.iter().all(|filter| filter.accepts(pubkey))
+    }
+
+    /// 字符计数条件
+    pub fn char_counts(&self) -> &[CharCount] {
+        &self.char_counts
+    }
+
+    /// 与条件
+    pub fn and_conditions(&self) -> &[AndCondition] {
+        &self.and_conditions
+    }
+
+    /// 地址开头的形状
+    pub fn shape(&self) -> Option<&Shape> {
+        self.shape.as_ref()
+    }
+
+    /// 检查编码后的地址是否满足所有字符计数条件、与条件和形状
+    pub fn accepts_address(&self, address: &str) -> bool {
+        self.char_counts.iter().all(|condition| condition.accepts(address))
+            && self.and_conditions.iter().all(|condition| condition.accepts(address))
+            && self.shape.as_ref().is_none_or(|shape| shape.matches(address))
+    }
+
+    /// 参与匹配的各目标的名字，见 [`PatternSet::prefixes`]
+    pub fn prefixes(&self) -> Vec<String> {
+        self.patterns().prefixes.clone()
+    }
+
+    /// 参与匹配的目标，见 [`PatternSet::matchers`]
+    pub fn matchers(&self) -> Vec<Matcher> {
+        self.patterns().matchers.clone()
+    }
+
+    /// 见 [`PatternSet::find_all`]；同一批中匹配许多地址时先取 [`Search::patterns`]
+    pub fn find_all(&self, address: &str) -> Vec<usize> {
+        self.patterns().find_all(address)
+    }
+
+    /// 见 [`PatternSet::find`]
+    pub fn find(&self, address: &str) -> Option<usize> {
+        self.patterns().find(address)
+    }
+
+    /// 见 [`PatternSet::symmetries`]
+    pub fn symmetries(&self) -> Vec<(usize, Symmetry)> {
+        self.patterns().symmetries.clone()
+    }
+
+    /// 见 [`PatternSet::fuzzies`]
+    pub fn fuzzies(&self) -> Vec<(usize, Fuzzy)> {
+        self.patterns().fuzzies.clone()
+    }
+
+    /// 见 [`PatternSet::matched_text`]
+    pub fn matched_text(&self, indices: &[usize], address: &str) -> String {
+        self.patterns().matched_text(indices, address)
+    }
+
+    /// 见 [`PatternSet::matched_spans`]
+    pub fn matched_spans(&self, indices: &[usize], address: &str) -> Vec<Range<usize>> {
+        self.patterns().matched_spans(indices, address)
+    }
+
+    /// 见 [`PatternSet::find_bytes`]
+    pub fn find_bytes(&self, pubkey: &[u8]) -> Vec<usize> {
+        self.patterns().find_bytes(pubkey)
+    }
+
+    /// 见 [`PatternSet::byte_patterns`]
+    pub fn byte_patterns(&self) -> Vec<(usize, BytePattern)> {
+        self.patterns().byte_patterns.clone()
+    }
+
+    /// 见 [`PatternSet::bytes_only`]
+    pub fn bytes_only(&self) -> bool {
+        self.patterns().bytes_only()
+    }
+
+    /// 见 [`PatternSet::literals`]
+    pub fn literals(&self) -> Vec<(String, usize)> {
+        self.patterns()
+            .literals()
+            .map(|(literal, owner)| (literal.to_string(), owner))
+            .collect()
+    }
+
+    /// 见 [`PatternSet::track_partial`]
+    pub fn track_partial(&self, address: &str, keypair: Option<&[u8; 64]>) {
+        self.patterns().track_partial(address, keypair);
+    }
+
+    /// 每个前缀的近似命中榜，与 [`Search::prefixes`] 顺序一致
+    pub fn near_misses(&self) -> Vec<Vec<NearMiss>> {
+        self.patterns()
+            .states
+            .iter()
+            .map(|state| state.near_misses.lock().unwrap().clone())
+            .collect()
+    }
+
+    /// 合并工作线程一批的本地计数，返回合并后的生成总数
+    ///
+    /// `matches` 记录这一批的匹配，合并后清零以便复用。
+    /// 先加生成数再加匹配数，配合 [`Search::stats`] 的读取顺序保证快照一致。
+    pub fn record_batch(&self, generated: u64, matches: &mut MatchCounts) -> u64 {
+        let total = self.generated.fetch_add(generated, Ordering::Release) + generated;
+        let patterns = self.patterns();
+        for (state, local) in patterns.states.iter().zip(matches.per_prefix.iter_mut()) {
+            if *local > 0 {
+                state.count.fetch_add(*local, Ordering::Release);
+                *local = 0;
+            }
+        }
+        if matches.addresses > 0 {
+            self.matched.fetch_add(matches.addresses, Ordering::Release);
+            matches.addresses = 0;
+        }
+        total
+    }
+
+    /// 目前为止已合并的生成总数
+    pub fn generated(&self) -> u64 {
+        self.generated.load(Ordering::Acquire)
+    }
+
+    /// 目前为止已合并的匹配地址数，满足几个前缀的地址只算一次
+    pub fn matched(&self) -> u64 {
+        self.matched.load(Ordering::Acquire)
+    }
+
+    /// 检查单个地址并立即计数，返回它满足的全部前缀，字符计数条件和形状同样生效
+    pub fn check(&self, address: &str) -> Vec<String> {
+        let patterns = self.patterns();
+        let indices = if self.accepts_address(address) { patterns.find_all(address) } else { Vec::new() };
+        let mut matches = MatchCounts::new(patterns.len());
+        matches.credit(&indices);
+        self.record_batch(1, &mut matches);
+        indices.iter().map(|index| patterns.prefixes[*index].clone()).collect()
+    }
+
+    /// 记录工作线程最近使用的批大小，供统计快照展示
+    pub fn report_batch_size(&self, size: u64) {
+        self.batch_size.store(size, Ordering::Relaxed);
+    }
+
+    /// 请求停止搜索，工作线程会在当前批结束后退出
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Release);
+    }
+
+    /// 是否已经请求停止
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Acquire)
+    }
+
+    /// 暂停搜索，工作线程会在当前批结束后等待，暂停期间不计入用时
+    pub fn pause(&self) {
+        let mut clock = self.pause_clock.lock().unwrap();
+        if clock.since.is_none() {
+            clock.since = Some(Instant::now());
+        }
+        self.paused.store(true, Ordering::Release);
+    }
+
+    /// 恢复搜索
+    pub fn resume(&self) {
+        let mut clock = self.pause_clock.lock().unwrap();
+        if let Some(since) = clock.since.take() {
+            clock.total += since.elapsed();
+        }
+        self.paused.store(false, Ordering::Release);
+    }
+
+    /// 是否处于暂停状态
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// 暂停期间阻塞当前工作线程，恢复或请求停止后返回
+    pub fn wait_while_paused(&self) {
+        while self.is_paused() && !self.is_stopped() {
+            std::thread::sleep(PAUSE_POLL_INTERVAL);
+        }
+    }
+
+    /// 不含暂停时间的运行时长
+    pub fn active_elapsed(&self) -> Duration {
+        let clock = self.pause_clock.lock().unwrap();
+        let paused = clock.total + clock.since.map_or(Duration::ZERO, |since| since.elapsed());
+        self.start_time.elapsed().saturating_sub(paused)
+    }
+
+    /// 读取当前的统计快照
+    ///
+    /// 先读匹配计数再读生成计数，保证快照里 `matched <= generated`。
+    pub fn stats(&self) -> Stats {
+        let patterns = self.patterns();
+        let per_prefix: Vec<(String, u64)> = patterns
+            .prefixes
+            .iter()
+            .zip(&patterns.states)
+            .map(|(prefix, state)| (prefix.clone(), state.count.load(Ordering::Acquire)))
+            .collect();
+        let matched = self.matched.load(Ordering::Acquire);
+        let generated = self.generated.load(Ordering::Acquire);
+        let elapsed = self.active_elapsed();
+        let rate = if elapsed.as_secs_f64() > 0.0 {
+            generated as f64 / elapsed.as_secs_f64()
+        } else {
+            0.0
+        };
+
+        Stats {
+            generated,
+            matched,
+            per_prefix,
+            rate,
+            elapsed,
+            batch_size: self.batch_size.load(Ordering::Relaxed),
+            near_misses: patterns
+                .states
+                .iter()
+                .map(|state| {
+                    state
+                        .near_misses
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .map(|entry| (entry.address.clone(), entry.matched))
+                        .collect()
+                })
+                .collect(),
+            samples_taken: self.samples_taken.load(Ordering::Relaxed).min(self.sample_target),
+            sample_target: self.sample_target,
+            best_partial: patterns
+                .states
+                .iter()
+                .map(|state| state.best_partial.load(Ordering::Relaxed))
+                .collect(),
+            added_at: patterns.states.iter().map(|state| state.added_at).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 默认字母表把 `1` 换成 `0`
+    const ZERO_ALPHABET: &str = "023456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    #[test]
+    fn accepts_base58_prefixes() {
+        for prefix in ["", "1", "So1", "z", "123456789", "ABCDEFGHJKLMNPQRSTUVWXYZ", "abcdefghijkmnopqrstuvwxyz"] {
+            assert_eq!(validate_prefix(prefix), Ok(()), "{}", prefix);
+        }
+    }
+
+    #[test]
+    fn reports_excluded_ascii_with_position() {
+        for (prefix, position, c) in [("So0", 3, '0'), ("O", 1, 'O'), ("AbI", 3, 'I'), ("Sol", 3, 'l'), ("S-x", 2, '-')] {
+            let message = validate_prefix(prefix).unwrap_err();
+            assert!(message.contains(&format!("第 {} 个字符 '{}'", position, c)), "{}", message);
+            assert!(message.contains("不在 Base58 字母表中"), "{}", message);
+        }
+    }
+
+    #[test]
+    fn fullwidth_characters_get_a_halfwidth_hint() {
+        let message = validate_prefix("Ｓol").unwrap_err();
+        assert!(message.contains("第 1 个字符 'Ｓ'（U+FF33）"), "{}", message);
+        assert!(message.contains("半角的 'S'"), "{}", message);
+
+        let message = validate_prefix("So２").unwrap_err();
+        assert!(message.contains("第 3 个字符"), "{}", message);
+        assert!(message.contains("半角的 '2'"), "{}", message);
+
+        // 对应的半角字符本身也不在字母表中时不给提示
+        let message = validate_prefix("Ｏ").unwrap_err();
+        assert!(message.contains("U+FF2F"), "{}", message);
+        assert!(!message.contains("半角"), "{}", message);
+    }
+
+    #[test]
+    fn positions_count_characters_not_bytes() {
+        let message = validate_prefix("Soxé").unwrap_err();
+        assert!(message.contains("第 4 个字符 'é'（U+00E9）"), "{}", message);
+        assert!(message.contains("不是 ASCII 字符"), "{}", message);
+
+        // 前面的多字节字符只算一个位置，只报第一个有问题的字符
+        let message = validate_prefix("日本").unwrap_err();
+        assert!(message.contains("第 1 个字符 '日'（U+65E5）"), "{}", message);
+
+        let message = validate_prefix("So🚀").unwrap_err();
+        assert!(message.contains("第 3 个字符 '🚀'（U+1F680）"), "{}", message);
+
+        // 组合用重音符号是单独的字符
+        let message = validate_prefix("Se\u{301}").unwrap_err();
+        assert!(message.contains("第 3 个字符"), "{}", message);
+        assert!(message.contains("U+0301"), "{}", message);
+    }
+
+    #[test]
+    fn character_classes_are_checked_by_their_own_syntax() {
+        assert_eq!(validate_prefix("S[1-9]"), Ok(()));
+        assert_eq!(validate_prefix("[^1]x"), Ok(()));
+        assert!(validate_prefix("S[é]").is_err());
+        assert!(validate_prefix("S[1-9").is_err());
+        assert!(validate_prefix("S]").is_err());
+    }
+
+    #[test]
+    fn custom_alphabet() {
+        let alphabet = Alphabet::new(ZERO_ALPHABET).unwrap();
+        assert_eq!(validate_prefix_with("S0l", alphabet).map_err(|_| ()), Err(()));
+        assert_eq!(validate_prefix_with("S0", alphabet), Ok(()));
+        let message = validate_prefix_with("S1", alphabet).unwrap_err();
+        assert!(message.contains("第 2 个字符 '1'"), "{}", message);
+        // 全角提示只在半角字符属于该字母表时给出
+        assert!(validate_prefix_with("０", alphabet).unwrap_err().contains("半角的 '0'"));
+        assert!(!validate_prefix_with("１", alphabet).unwrap_err().contains("半角"));
+    }
+
+    #[test]
+    fn nested_prefixes_are_each_credited_once() {
+        let search = Search::new(["ABC", "A", "AB"]);
+        assert_eq!(search.check("ABCxyz"), ["A", "AB", "ABC"]);
+        assert_eq!(search.check("ABxyz"), ["A", "AB"]);
+        assert_eq!(search.check("Axyz"), ["A"]);
+        assert!(search.check("xyz").is_empty());
+
+        let stats = search.stats();
+        assert_eq!(stats.generated, 4);
+        // 满足几个前缀的地址只算一次，每个前缀各记一次
+        assert_eq!(stats.matched, 3);
+        let per_prefix: Vec<(&str, u64)> = stats.per_prefix.iter().map(|(prefix, count)| (prefix.as_str(), *count)).collect();
+        assert_eq!(per_prefix, [("A", 3), ("AB", 2), ("ABC", 1)]);
+    }
+
+    #[test]
+    fn counts_do_not_depend_on_prefix_order() {
+        let addresses = ["ABCD", "ABD", "AC", "BCD", "ABCA"];
+        let counts = |prefixes: &[&str]| {
+            let search = Search::new(prefixes.iter().copied());
+            for address in addresses {
+                search.check(address);
+            }
+            search.stats().per_prefix
+        };
+        assert_eq!(counts(&["A", "AB", "ABC", "B"]), counts(&["B", "ABC", "AB", "A"]));
+    }
+
+    #[test]
+    fn nested_prefixes_in_text_and_spans() {
+        let search = Search::new(["AB", "ABC"]);
+        let indices = search.find_all("ABCxyz");
+        assert_eq!(search.matched_text(&indices, "ABCxyz"), "AB|ABC");
+        assert_eq!(search.matched_spans(&indices, "ABCxyz"), [0..2, 0..3]);
+    }
+
+    #[test]
+    fn classes_expanding_to_the_same_literal_credit_both() {
+        let search = Search::new(["S[12]", "S[1-2]", "S1"]);
+        assert_eq!(search.check("S1x"), ["S1", "S[1-2]", "S[12]"]);
+        assert_eq!(search.check("S2x"), ["S[1-2]", "S[12]"]);
+        assert_eq!(search.stats().matched, 2);
+        // 具体前缀只有 S1 和 S2，各属于几个前缀
+        let patterns = search.patterns();
+        let literals: Vec<(&str, usize)> = patterns.literals().collect();
+        assert_eq!(literals, [("S1", 0), ("S1", 1), ("S1", 2), ("S2", 1), ("S2", 2)]);
+    }
+
+    #[test]
+    fn prefix_overlapping_a_mirror() {
+        let address = "ABCxyzCBA";
+        let search = Search::new([Matcher::from("AB"), Matcher::Symmetry(Symmetry::Mirror(3))]);
+        let indices = search.find_all(address);
+        assert_eq!(indices, [0, 1]);
+        assert_eq!(search.matched_text(&indices, address), "ABC…CBA|AB");
+        assert_eq!(search.matched_spans(&indices, address), [0..3, 0..2, 6..9]);
+    }
+
+    #[test]
+    fn mirror_whose_start_and_end_overlap() {
+        // 开头两个字符与结尾两个字符在短地址中共用中间的字符
+        let search = Search::new([Matcher::from("A"), Matcher::Symmetry(Symmetry::Mirror(2))]);
+        let indices = search.find_all("ABA");
+        assert_eq!(indices, [0, 1]);
+        assert_eq!(search.matched_spans(&indices, "ABA"), [0..2, 0..1, 1..3]);
+        assert_eq!(search.find_all("ABC"), [1]);
+    }
+
+    #[test]
+    fn palindrome_and_prefix_over_the_same_characters() {
+        let search = Search::new([Matcher::from("ABA"), Matcher::Symmetry(Symmetry::Palindrome(3))]);
+        assert_eq!(search.check("ABAx"), ["<palindrome 3>", "ABA"]);
+        assert_eq!(search.check("BCBx"), ["<palindrome 3>"]);
+        assert_eq!(search.stats().matched, 2);
+    }
+
+    #[test]
+    fn find_all_with_classes_matches_naive() {
+        use rand::{Rng, SeedableRng};
+        use rand_chacha::ChaCha20Rng;
+
+        // 窄字母表让前缀和字符类更容易互相嵌套、展开出相同的具体前缀
+        const CHARS: &[u8] = b"12A";
+        const PIECES: [&str; 5] = ["1", "2", "A", "[12]", "[^2-9B-Za-z]"];
+        let mut rng = ChaCha20Rng::seed_from_u64(1692);
+        for _ in 0..300 {
+            let prefixes: Vec<String> = (0..rng.gen_range(1..6))
+                .map(|_| (0..rng.gen_range(0..4)).map(|_| PIECES[rng.gen_range(0..PIECES.len())]).collect())
+                .collect();
+            let search = Search::new(prefixes.iter().cloned());
+            for _ in 0..30 {
+                let address: String = (0..rng.gen_range(0..6)).map(|_| CHARS[rng.gen_range(0..CHARS.len())] as char).collect();
+                let expected: Vec<usize> = search
+                    .prefixes()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, prefix)| {
+                        char_class::expand(prefix).unwrap().iter().any(|literal| address.starts_with(literal.as_str()))
+                    })
+                    .map(|(index, _)| index)
+                    .collect();
+                assert_eq!(search.find_all(&address), expected, "{:?} {:?}", search.prefixes(), address);
+                assert_eq!(search.find(&address), expected.first().copied());
+            }
+        }
+    }
+
+    #[test]
+    fn added_prefix_keeps_existing_indices_and_counts() {
+        let search = Search::new(["D", "B"]);
+        assert_eq!(search.check("Bx"), ["B"]);
+        assert_eq!(search.add(Matcher::from("A"), None), Ok(2));
+        assert_eq!(search.check("Ax"), ["A"]);
+        assert_eq!(search.check("Bx"), ["B"]);
+
+        let stats = search.stats();
+        let counts: Vec<(&str, u64)> = stats.per_prefix.iter().map(|(prefix, count)| (prefix.as_str(), *count)).collect();
+        assert_eq!(counts, [("B", 2), ("D", 0), ("A", 1)]);
+        assert_eq!(stats.added_at, [0, 0, 1]);
+        assert_eq!(stats.attempts_for(0), 3);
+        assert_eq!(stats.attempts_for(2), 2);
+    }
+
+    #[test]
+    fn snapshot_taken_before_add_does_not_see_it() {
+        let search = Search::new(["B"]);
+        let before = search.patterns();
+        let mut matches = MatchCounts::new(before.len());
+        search.add(Matcher::from("A"), None).unwrap();
+        assert!(before.find_all("Ax").is_empty());
+
+        // 工作线程下一批取到的快照才包含新前缀，本地计数按需扩展
+        let after = search.patterns();
+        let indices = after.find_all("Ax");
+        assert_eq!(indices, [1]);
+        matches.credit(&indices);
+        search.record_batch(1, &mut matches);
+        assert_eq!(search.stats().per_prefix[1], ("A".to_string(), 1));
+    }
+
+    #[test]
+    fn added_prefix_tracks_partial_matches() {
+        let search = Search::new(["B"]);
+        let index = search.add(Matcher::from("A[BC]D"), None).unwrap();
+        search.track_partial("ACx", None);
+        let stats = search.stats();
+        assert_eq!(stats.best_partial[index], 2);
+        assert_eq!(stats.near_misses[index], [("ACx".to_string(), 2)]);
+    }
+
+    #[test]
+    fn adding_a_duplicate_is_rejected() {
+        let search = Search::new(["B"]);
+        assert!(search.add(Matcher::from("B"), Some(1)).is_err());
+        assert_eq!(search.prefixes(), ["B"]);
+    }
+
+    #[test]
+    fn claim_stops_at_the_target_of_an_added_prefix() {
+        let search = Search::new(["B"]);
+        assert!(search.targets_done());
+        let index = search.add(Matcher::from("A"), Some(2)).unwrap();
+        assert!(!search.targets_done());
+        assert!(search.claim(index));
+        assert!(search.claim(index));
+        assert!(!search.claim(index));
+        assert!(search.targets_done());
+        // 启动时的前缀不受限制
+        assert!((0..5).all(|_| search.claim(0)));
+    }
+
+    #[test]
+    fn parses_added_prefixes() {
+        assert_eq!(parse_added_prefix("Moon"), Ok(("Moon".to_string(), None)));
+        assert_eq!(parse_added_prefix("Moon:3"), Ok(("Moon".to_string(), Some(3))));
+        assert_eq!(parse_added_prefix("S[1-9]:1"), Ok(("S[1-9]".to_string(), Some(1))));
+        for argument in ["Moon:0", "Moon:x", "Moon:", ":2", "", "M0on"] {
+            assert!(parse_added_prefix(argument).is_err(), "{}", argument);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! 打印时自动隐藏的私钥（`--reveal-partial`）
+//!
+//! 通知、日志和调试输出越来越多，私钥就越可能出现在终端回滚、journald 或贴出来的错误
+//! 报告里。所有私钥的文本形式都包装成 [`Secret`]：`Display` 和 `Debug` 只输出
+//! `…redacted…`，指定 `--reveal-partial` 时输出前 4 个字符，方便核对是哪一把。
+//! 只有写文件、导出和专门用来显示私钥的子命令（`inspect`、`recover`、`combine`）
+//! 调用 [`Secret::expose`] 取出明文，`format!("{}", secret)` 永远拿不到完整私钥。
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use zeroize::Zeroizing;
+
+/// 完全隐藏时的占位文本
+pub const REDACTED: &str = "…redacted…";
+
+/// `--reveal-partial` 时显示的字符数
+pub const PARTIAL_CHARS: usize = 4;
+
+static REVEAL_PARTIAL: AtomicBool = AtomicBool::new(false);
+
+/// 打印私钥时显示前几个字符，进程内全局生效
+pub fn set_reveal_partial(reveal: bool) {
+    REVEAL_PARTIAL.store(reveal, Ordering::Relaxed);
+}
+
+/// 私钥的文本形式，丢弃时清零
+pub struct Secret(Zeroizing<String>);
+
+impl Secret {
+    pub fn new(text: String) -> Self {
+        Self(Zeroizing::new(text))
+    }
+
+    /// 取出明文，只用于写文件、导出和显式显示私钥的子命令
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(text: String) -> Self {
+        Self::new(text)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if REVEAL_PARTIAL.load(Ordering::Relaxed) {
+            let head: String = self.0.chars().take(PARTIAL_CHARS).collect();
+            write!(f, "{}…", head)
+        } else {
+            f.write_str(REDACTED)
+        }
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({})", self)
+    }
+}
@@ -0,0 +1,83 @@
+//! `--no-secret` 会话模式的内存存储
+//!
+//! 演示或共享屏幕时，匹配的私钥既不写盘也不打印，只保存在内存里；结束时由用户选择
+//! 要导出哪些。[`Record`] 在丢弃时会清零私钥，所以没有导出的记录随存储一起被清除。
+//! 存储有容量上限，满了之后新匹配的私钥直接丢弃。
+
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::format::Schema;
+use crate::secret::Secret;
+use crate::writer::{self, Record};
+
+/// 会话中默认最多保存的匹配数量
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// 内存中的匹配记录
+pub struct SessionStore {
+    records: Mutex<Vec<Record>>,
+    capacity: usize,
+    /// 存储已满而被丢弃的匹配数量
+    dropped: AtomicU64,
+}
+
+impl SessionStore {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            records: Mutex::new(Vec::new()),
+            capacity,
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// 保存一条匹配，存储已满时丢弃（私钥随之清零）并返回 `false`
+    pub fn push(&self, record: Record) -> bool {
+        let mut records = self.records.lock().unwrap();
+        if records.len() >= self.capacity {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        records.push(record);
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// 按保存顺序列出地址、匹配的前缀和打印时隐藏的私钥
+    pub fn addresses(&self) -> Vec<(String, String, Secret)> {
+        let records = self.records.lock().unwrap();
+        records
+            .iter()
+            .map(|record| (record.address.clone(), record.prefix.clone(), record.secret_key()))
+            .collect()
+    }
+
+    /// 把选中的记录（下标从 0 开始）按输出格式写入文件，返回写入的数量
+    pub fn export(&self, selected: &[usize], schema: &Schema, path: impl AsRef<Path>) -> io::Result<usize> {
+        let records = self.records.lock().unwrap();
+        let chosen: Vec<&Record> = selected.iter().filter_map(|index| records.get(*index)).collect();
+        writer::export_records(path.as_ref(), schema, &chosen)?;
+        Ok(chosen.len())
+    }
+
+    /// 清除所有记录，返回清除的数量
+    pub fn wipe(&self) -> usize {
+        let mut records = self.records.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let count = records.len();
+        records.clear();
+        count
+    }
+}
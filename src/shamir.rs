@@ -0,0 +1,257 @@
+//! Shamir 秘密分享
+//!
+//! 匹配到的私钥立即拆分成 N 份，任意 K 份即可恢复，少于 K 份得不到任何信息。
+//! 分享的是 32 字节种子，恢复时重新推导公钥并与地址核对。
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use sharks::{Share, Sharks};
+use solana_sdk::signer::keypair::keypair_from_seed;
+use solana_sdk::signer::Signer;
+
+use crate::secret::REDACTED;
+
+/// 门限方案，如 `2-of-3`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShamirScheme {
+    pub threshold: u8,
+    pub shares: u8,
+}
+
+impl FromStr for ShamirScheme {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("无效的门限方案: {}（应为 K-of-N，如 2-of-3）", value);
+        let (threshold, shares) = value.split_once("-of-").ok_or_else(invalid)?;
+        let threshold: u8 = threshold.parse().map_err(|_| invalid())?;
+        let shares: u8 = shares.parse().map_err(|_| invalid())?;
+        if threshold < 2 || threshold > shares {
+            return Err(format!("门限方案要求 2 <= K <= N，实际为 {}", value));
+        }
+        Ok(Self { threshold, shares })
+    }
+}
+
+impl fmt::Display for ShamirScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-of-{}", self.threshold, self.shares)
+    }
+}
+
+/// 一份分享文件的内容
+#[derive(Clone, PartialEq, Eq)]
+pub struct ShareFile {
+    pub address: String,
+    pub scheme: ShamirScheme,
+    /// 分享数据的 Base58 编码
+    pub share: String,
+}
+
+impl ShareFile {
+    /// 解析 `key=value` 格式的分享文件
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let field = |key: &str| {
+            content
+                .lines()
+                .find_map(|line| line.trim().strip_prefix(key)?.strip_prefix('='))
+                .map(str::to_string)
+                .ok_or_else(|| format!("分享文件缺少 {} 字段", key))
+        };
+        Ok(Self {
+            address: field("address")?,
+            scheme: field("scheme")?.parse()?,
+            share: field("share")?,
+        })
+    }
+}
+
+/// 分享数据与私钥同样敏感，调试输出中隐藏
+impl fmt::Debug for ShareFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShareFile")
+            .field("address", &self.address)
+            .field("scheme", &self.scheme)
+            .field("share", &format_args!("{}", REDACTED))
+            .finish()
+    }
+}
+
+impl fmt::Display for ShareFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "address={}", self.address)?;
+        writeln!(f, "scheme={}", self.scheme)?;
+        writeln!(f, "share={}", self.share)
+    }
+}
+
+impl ShamirScheme {
+    /// 把种子拆分成 N 份分享
+    pub fn split(&self, address: &str, seed: &[u8]) -> Vec<ShareFile> {
+        Sharks(self.threshold)
+            .dealer(seed)
+            .take(self.shares as usize)
+            .map(|share| ShareFile {
+                address: address.to_string(),
+                scheme: *self,
+                share: bs58::encode(Vec::from(&share)).into_string(),
+            })
+            .collect()
+    }
+
+    /// 第 `index` 份（从 1 开始）分享的文件路径：`<目录>/<index>/<地址>.txt`
+    pub fn share_path(dir: &Path, index: usize, address: &str) -> PathBuf {
+        dir.join(index.to_string()).join(format!("{}.txt", address))
+    }
+}
+
+/// 用至少 K 份分享恢复 64 字节密钥对，并核对地址
+pub fn recover(files: &[ShareFile]) -> Result<(String, [u8; 64]), String> {
+    let first = files.first().ok_or("没有提供分享文件")?;
+    if let Some(other) = files
+        .iter()
+        .find(|file| file.address != first.address || file.scheme != first.scheme)
+    {
+        return Err(format!("分享文件不属于同一个密钥: {} / {}", first.address, other.address));
+    }
+    if files.len() < first.scheme.threshold as usize {
+        return Err(format!(
+            "{} 方案至少需要 {} 份分享，只提供了 {} 份",
+            first.scheme,
+            first.scheme.threshold,
+            files.len()
+        ));
+    }
+
+    let shares = files
+        .iter()
+        .map(|file| {
+            let bytes = bs58::decode(&file.share)
+                .into_vec()
+                .map_err(|err| format!("分享数据不是有效的 Base58: {}", err))?;
+            Share::try_from(bytes.as_slice()).map_err(str::to_string)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let seed = Sharks(first.scheme.threshold)
+        .recover(&shares)
+        .map_err(str::to_string)?;
+
+    let keypair = keypair_from_seed(&seed).map_err(|err| err.to_string())?;
+    let pubkey = keypair.pubkey();
+    if pubkey.to_string() != first.address {
+        return Err(format!("恢复出的地址 {} 与分享文件中的地址 {} 不一致", pubkey, first.address));
+    }
+
+    let mut keypair_bytes = [0u8; 64];
+    keypair_bytes[..32].copy_from_slice(&seed);
+    keypair_bytes[32..].copy_from_slice(pubkey.as_ref());
+    Ok((first.address.clone(), keypair_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Keypair;
+
+    const SCHEMES: [&str; 5] = ["2-of-2", "2-of-3", "3-of-5", "4-of-7", "5-of-5"];
+
+    /// `items` 中所有大小为 `size` 的组合
+    fn combinations<T: Clone>(items: &[T], size: usize) -> Vec<Vec<T>> {
+        if size == 0 {
+            return vec![Vec::new()];
+        }
+        let mut result = Vec::new();
+        for (index, item) in items.iter().enumerate() {
+            for mut rest in combinations(&items[index + 1..], size - 1) {
+                rest.insert(0, item.clone());
+                result.push(rest);
+            }
+        }
+        result
+    }
+
+    fn split_random(scheme: ShamirScheme) -> (Keypair, Vec<ShareFile>) {
+        let keypair = Keypair::new();
+        let files = scheme.split(&keypair.pubkey().to_string(), keypair.secret().as_bytes());
+        (keypair, files)
+    }
+
+    #[test]
+    fn any_threshold_subset_recovers_the_keypair() {
+        for scheme in SCHEMES {
+            let scheme: ShamirScheme = scheme.parse().unwrap();
+            for _ in 0..20 {
+                let (keypair, files) = split_random(scheme);
+                assert_eq!(files.len(), scheme.shares as usize);
+                for subset in combinations(&files, scheme.threshold as usize) {
+                    let (address, bytes) = recover(&subset).unwrap();
+                    assert_eq!(address, keypair.pubkey().to_string());
+                    assert_eq!(bytes, keypair.to_bytes());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn more_than_threshold_shares_also_recover() {
+        let scheme: ShamirScheme = "3-of-5".parse().unwrap();
+        for _ in 0..50 {
+            let (keypair, files) = split_random(scheme);
+            assert_eq!(recover(&files).unwrap().1, keypair.to_bytes());
+        }
+    }
+
+    #[test]
+    fn fewer_than_threshold_shares_fail() {
+        for scheme in SCHEMES {
+            let scheme: ShamirScheme = scheme.parse().unwrap();
+            let (_, files) = split_random(scheme);
+            for subset in combinations(&files, scheme.threshold as usize - 1) {
+                let err = recover(&subset).unwrap_err();
+                assert!(err.contains("至少需要"), "{}", err);
+            }
+        }
+    }
+
+    #[test]
+    fn shares_of_different_keys_are_rejected() {
+        let scheme: ShamirScheme = "2-of-3".parse().unwrap();
+        let (_, first) = split_random(scheme);
+        let (_, second) = split_random(scheme);
+        let err = recover(&[first[0].clone(), second[1].clone()]).unwrap_err();
+        assert!(err.contains("不属于同一个密钥"), "{}", err);
+    }
+
+    #[test]
+    fn shares_of_two_splits_of_the_same_key_are_rejected() {
+        let scheme: ShamirScheme = "2-of-3".parse().unwrap();
+        let keypair = Keypair::new();
+        let address = keypair.pubkey().to_string();
+        for _ in 0..20 {
+            let first = scheme.split(&address, keypair.secret().as_bytes());
+            let second = scheme.split(&address, keypair.secret().as_bytes());
+            // 两次拆分的多项式不同，混用得到的是另一个种子，地址核对不通过
+            let err = recover(&[first[0].clone(), second[1].clone()]).unwrap_err();
+            assert!(err.contains("不一致"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn share_files_round_trip_through_text() {
+        let scheme: ShamirScheme = "2-of-3".parse().unwrap();
+        let (keypair, files) = split_random(scheme);
+        let parsed: Vec<ShareFile> = files.iter().map(|file| ShareFile::parse(&file.to_string()).unwrap()).collect();
+        assert_eq!(parsed, files);
+        assert_eq!(recover(&parsed[1..]).unwrap().1, keypair.to_bytes());
+    }
+
+    #[test]
+    fn scheme_parsing() {
+        assert_eq!("2-of-3".parse::<ShamirScheme>().unwrap(), ShamirScheme { threshold: 2, shares: 3 });
+        for invalid in ["1-of-3", "4-of-3", "2of3", "a-of-3", "2-of-"] {
+            assert!(invalid.parse::<ShamirScheme>().is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! 地址开头的形状
+//!
+//! `--pattern` 用一个小语法描述地址开头的形状，例如开头几个字符相同或者左右对称，
+//! 这类条件无法用有限个前缀表达。语法逐个字符对应地址开头的各位：
+//!
+//! - `?`：任意字符
+//! - 小写字母 `a`–`z`：变量，同一个字母出现的各位必须是同一个字符（不同字母可以相同）
+//! - 大写字母和数字：必须是这个字符本身
+//! - `\` 加一个字符：必须是这个字符本身，用来写小写的字面字符，如 `\o`
+//!
+//! 例如 `aaaa` 表示开头四个字符相同，`abcba` 表示开头五个字符左右对称，
+//! `xxS\oL` 表示两个相同的字符后接 `SoL`。
+//!
+//! 启动时编译成逐位的检查：每个变量第一次出现的位置不做检查，之后出现的位置只需与
+//! 第一次出现的位置比较一个字节。
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::base58::Alphabet;
+use crate::char_stats::POSITIONS;
+use crate::difficulty::address_classes;
+
+/// 编译后的一位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Element {
+    /// 任意字符，也用于变量第一次出现的位置
+    Any,
+    /// 必须是这个字符
+    Literal(u8),
+    /// 必须与前面某一位相同
+    Same(usize),
+}
+
+/// 编译后的形状
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shape {
+    source: String,
+    elements: Vec<Element>,
+}
+
+impl Shape {
+    /// 形状覆盖的长度
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// 检查地址开头是否符合形状
+    pub fn matches(&self, address: &str) -> bool {
+        let bytes = address.as_bytes();
+        bytes.len() >= self.elements.len()
+            && self.elements.iter().enumerate().all(|(position, element)| match element {
+                Element::Any => true,
+                Element::Literal(byte) => bytes[position] == *byte,
+                Element::Same(earlier) => bytes[position] == bytes[*earlier],
+            })
+    }
+
+    /// 随机地址以 `prefix` 开头时符合形状的概率，`prefix` 为空时即单独符合的概率
+    ///
+    /// 按 [`address_classes`] 把地址分成几类，每一类中各位独立，分别计算再加权。
+    /// 同一个变量的各位合成一组：组内全部相同的概率为
+    /// 对每个字符求各位概率之积再求和。落在前缀范围内的位置已经确定。
+    pub fn probability(&self, prefix: &str) -> f64 {
+        address_classes()
+            .iter()
+            .map(|(weight, positions)| weight * self.probability_with(prefix, positions))
+            .sum()
+    }
+
+    /// 给定每一位上各数字的概率时符合形状的概率
+    fn probability_with(&self, prefix: &str, positions: &[[f64; 58]]) -> f64 {
+        let fixed = prefix.as_bytes();
+        let distribution = |position: usize| positions.get(position).copied().unwrap_or([0.0; 58]);
+        let mut probability = 1.0;
+        for (position, element) in self.elements.iter().enumerate() {
+            let group: Vec<usize> = match element {
+                Element::Same(_) => continue,
+                Element::Literal(_) => vec![position],
+                Element::Any => std::iter::once(position)
+                    .chain(
+                        (position + 1..self.elements.len())
+                            .filter(|later| self.elements[*later] == Element::Same(position)),
+                    )
+                    .collect(),
+            };
+            if group.len() == 1 && *element == Element::Any {
+                continue;
+            }
+            // 组内已被前缀确定的字符，必须彼此一致，也必须与字面字符一致
+            let mut required = match element {
+                Element::Literal(byte) => Some(*byte),
+                _ => None,
+            };
+            for known in group.iter().filter_map(|position| fixed.get(*position)) {
+                match required {
+                    Some(byte) if byte != *known => return 0.0,
+                    _ => required = Some(*known),
+                }
+            }
+            let free: Vec<[f64; 58]> = group
+                .iter()
+                .filter(|position| **position >= fixed.len())
+                .map(|position| distribution(*position))
+                .collect();
+            probability *= match required {
+                Some(byte) => match Alphabet::BITCOIN.digit(byte as char) {
+                    Some(digit) => free.iter().map(|probabilities| probabilities[digit]).product(),
+                    None => 0.0,
+                },
+                None => (0..58)
+                    .map(|digit| free.iter().map(|probabilities| probabilities[digit]).product::<f64>())
+                    .sum(),
+            };
+        }
+        probability
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for Shape {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let mut elements = Vec::new();
+        let mut variables: [Option<usize>; 26] = [None; 26];
+        let mut chars = source.chars();
+        while let Some(c) = chars.next() {
+            let position = elements.len();
+            let element = match c {
+                '?' => Element::Any,
+                'a'..='z' => {
+                    let slot = &mut variables[(c as u8 - b'a') as usize];
+                    match slot {
+                        Some(first) => Element::Same(*first),
+                        None => {
+                            *slot = Some(position);
+                            Element::Any
+                        }
+                    }
+                }
+                '\\' => {
+                    let Some(literal) = chars.next() else {
+                        return Err(format!("形状 \"{}\" 以 '\\' 结尾，缺少要转义的字符", source));
+                    };
+                    literal_element(source, position, literal)?
+                }
+                _ => literal_element(source, position, c)?,
+            };
+            elements.push(element);
+        }
+        if elements.is_empty() {
+            return Err("形状不能为空".to_string());
+        }
+        if elements.len() > POSITIONS {
+            return Err(format!("形状 \"{}\" 超过了地址的最大长度 {}", source, POSITIONS));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            elements,
+        })
+    }
+}
+
+/// 字面字符必须在 Base58 字母表中
+fn literal_element(source: &str, position: usize, c: char) -> Result<Element, String> {
+    if Alphabet::BITCOIN.contains(c) {
+        Ok(Element::Literal(c as u8))
+    } else {
+        Err(format!(
+            "形状 \"{}\" 第 {} 位的字符 '{}' 不在 Base58 字母表中（Base58 不含 0、O、I、l），永远不会匹配",
+            source,
+            position + 1,
+            c
+        ))
+    }
+}
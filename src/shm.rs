@@ -0,0 +1,305 @@
+//! 共享内存环形缓冲区（需要 `shm` 特性）
+//!
+//! 高吞吐的流水线不想为每个匹配读写文件。`--shm NAME` 把匹配实时写进一块内存映射的环形缓冲区，
+//! 另一个进程映射同一个文件就能读取。Linux 上 `NAME` 位于 `/dev/shm`（与 `shm_open("/NAME")`
+//! 是同一个对象），其他系统位于临时目录；含 `/` 时按路径处理，可以放在任意 tmpfs 上。
+//!
+//! 布局（整数均为小端，偏移以字节计）：
+//!
+//! | 偏移 | 大小 | 内容 |
+//! |------|------|------|
+//! | 0 | 8 | 魔数 `SOLVSHM1` |
+//! | 8 | 4 | 版本，当前为 1 |
+//! | 12 | 4 | 槽位大小，当前为 256 |
+//! | 16 | 8 | 槽位数 N |
+//! | 24 | 8 | 写入序号：已写完的记录总数（原子变量） |
+//! | 64 | 256 × N | 槽位，第 n 条记录（从 0 开始）位于第 n mod N 个槽位 |
+//!
+//! 每个槽位：
+//!
+//! | 偏移 | 大小 | 内容 |
+//! |------|------|------|
+//! | 0 | 8 | 槽位序号（原子变量），见下文 |
+//! | 8 | 8 | 找到的时间，Unix 纳秒 |
+//! | 16 | 1 | 地址长度 |
+//! | 17 | 1 | 前缀长度 |
+//! | 24 | 48 | 地址（Base58） |
+//! | 72 | 64 | 密钥对：种子 ‖ 公钥 |
+//! | 136 | 120 | 匹配的前缀，超长时截断 |
+//!
+//! 同步采用每个槽位一个顺序锁，只有一个写入方（写入线程），读取方不需要任何锁，也不会阻塞写入方：
+//!
+//! 1. 写入方写第 n 条记录时，先把槽位序号设为 `2n+1`（写入中），写数据，再设为 `2n+2`（已完成，
+//!    Release），最后把写入序号设为 `n+1`（Release）。
+//! 2. 读取方读第 n 条记录时，先读槽位序号（Acquire）：小于 `2n+2` 表示还没写完，大于表示已被后面的记录覆盖；
+//!    等于时复制数据，再读一次槽位序号，两次相同才说明复制期间没有被覆盖。
+//!
+//! 缓冲区满后最旧的记录被覆盖，读取方落后超过 N 条会丢失记录，可以从写入序号判断丢了多少。
+//! 文件权限为 0600，其中含有明文私钥；程序退出后文件保留，由使用方负责删除。
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering, fence};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use memmap2::{Mmap, MmapMut};
+use zeroize::Zeroize;
+
+/// 文件开头的魔数
+pub const MAGIC: [u8; 8] = *b"SOLVSHM1";
+
+/// 布局版本
+pub const VERSION: u32 = 1;
+
+/// 文件头大小
+pub const HEADER_SIZE: usize = 64;
+
+/// 每个槽位的大小
+pub const SLOT_SIZE: usize = 256;
+
+/// 默认的槽位数
+pub const DEFAULT_SLOTS: usize = 4096;
+
+/// 前缀最多保存的字节数
+pub const MAX_PREFIX_LEN: usize = 120;
+
+const HEADER_VERSION: usize = 8;
+const HEADER_SLOT_SIZE: usize = 12;
+const HEADER_SLOTS: usize = 16;
+const HEADER_WRITE_SEQ: usize = 24;
+
+const SLOT_SEQ: usize = 0;
+const SLOT_TIME: usize = 8;
+const SLOT_ADDRESS_LEN: usize = 16;
+const SLOT_PREFIX_LEN: usize = 17;
+const SLOT_ADDRESS: usize = 24;
+const SLOT_KEYPAIR: usize = 72;
+const SLOT_PREFIX: usize = 136;
+const MAX_ADDRESS_LEN: usize = SLOT_KEYPAIR - SLOT_ADDRESS;
+
+/// `--shm` 的名字对应的文件：含 `/` 时按路径处理，否则在 `/dev/shm`（Linux）或临时目录中
+pub fn resolve_path(name: &str) -> PathBuf {
+    if name.contains('/') {
+        return PathBuf::from(name);
+    }
+    let dir = if cfg!(target_os = "linux") {
+        PathBuf::from("/dev/shm")
+    } else {
+        std::env::temp_dir()
+    };
+    dir.join(name)
+}
+
+/// 映射区中偏移 `offset` 处的原子变量，偏移必须是 8 的倍数
+///
+/// # Safety
+///
+/// `base` 必须指向至少 `offset + 8` 字节、按页对齐的有效映射。
+unsafe fn atomic_at<'a>(base: *const u8, offset: usize) -> &'a AtomicU64 {
+    debug_assert_eq!(offset % 8, 0);
+    // SAFETY: 映射按页对齐，偏移是 8 的倍数，满足 AtomicU64 的对齐要求；长度由调用方保证
+    unsafe { &*(base.add(offset) as *const AtomicU64) }
+}
+
+/// 写入方，由写入线程独占
+pub struct ShmWriter {
+    path: PathBuf,
+    map: MmapMut,
+    slots: u64,
+    next: u64,
+}
+
+impl ShmWriter {
+    /// 创建（截断）文件、写入文件头并映射，权限 0600
+    pub fn create(path: impl AsRef<Path>, slots: usize) -> io::Result<Self> {
+        let path = path.as_ref();
+        if slots == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "共享内存的槽位数必须大于 0"));
+        }
+        let mut options = OpenOptions::new();
+        options.read(true).write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options.open(path)?;
+        file.set_len((HEADER_SIZE + slots * SLOT_SIZE) as u64)?;
+        // SAFETY: 文件由本进程刚刚创建并截断，只有这一个写入方；读取方按顺序锁协议只读访问
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+        map[..8].copy_from_slice(&MAGIC);
+        map[HEADER_VERSION..HEADER_VERSION + 4].copy_from_slice(&VERSION.to_le_bytes());
+        map[HEADER_SLOT_SIZE..HEADER_SLOT_SIZE + 4].copy_from_slice(&(SLOT_SIZE as u32).to_le_bytes());
+        map[HEADER_SLOTS..HEADER_SLOTS + 8].copy_from_slice(&(slots as u64).to_le_bytes());
+        Ok(Self {
+            path: path.to_path_buf(),
+            map,
+            slots: slots as u64,
+            next: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 写入一条记录，缓冲区满时覆盖最旧的一条
+    pub fn push(&mut self, address: &str, prefix: &str, keypair: &[u8; 64]) {
+        let sequence = self.next;
+        let slot = HEADER_SIZE + (sequence % self.slots) as usize * SLOT_SIZE;
+        let found_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        let address = &address.as_bytes()[..address.len().min(MAX_ADDRESS_LEN)];
+        let prefix = &prefix.as_bytes()[..prefix.len().min(MAX_PREFIX_LEN)];
+
+        let base = self.map.as_mut_ptr();
+        // SAFETY: 槽位和文件头都在映射范围内；只有本写入方修改映射，读取方通过槽位序号发现并发修改
+        unsafe {
+            let slot_seq = atomic_at(base, slot + SLOT_SEQ);
+            slot_seq.store(2 * sequence + 1, Ordering::Relaxed);
+            fence(Ordering::Release);
+            write_bytes(base.add(slot + SLOT_TIME), &found_at.to_le_bytes());
+            write_bytes(base.add(slot + SLOT_ADDRESS_LEN), &[address.len() as u8, prefix.len() as u8]);
+            write_bytes(base.add(slot + SLOT_ADDRESS), address);
+            write_bytes(base.add(slot + SLOT_KEYPAIR), keypair);
+            write_bytes(base.add(slot + SLOT_PREFIX), prefix);
+            slot_seq.store(2 * sequence + 2, Ordering::Release);
+            atomic_at(base, HEADER_WRITE_SEQ).store(sequence + 1, Ordering::Release);
+        }
+        self.next += 1;
+    }
+}
+
+/// 逐字节易失写入，避免编译器把顺序锁保护的写入合并或重排到锁之外
+unsafe fn write_bytes(dest: *mut u8, bytes: &[u8]) {
+    for (index, byte) in bytes.iter().enumerate() {
+        // SAFETY: 由调用方保证目标范围在映射内
+        unsafe { dest.add(index).write_volatile(*byte) };
+    }
+}
+
+/// 逐字节易失读取，读到的内容可能是写了一半的，由调用方用槽位序号判断
+unsafe fn read_bytes(src: *const u8, dest: &mut [u8]) {
+    for (index, byte) in dest.iter_mut().enumerate() {
+        // SAFETY: 由调用方保证来源范围在映射内
+        *byte = unsafe { src.add(index).read_volatile() };
+    }
+}
+
+/// 从缓冲区读出的一条记录
+pub struct ShmEntry {
+    /// 记录序号，从 0 开始
+    pub sequence: u64,
+    /// 找到的时间，Unix 纳秒
+    pub found_at_nanos: u64,
+    pub address: String,
+    pub prefix: String,
+    /// 64 字节密钥对：种子 || 公钥
+    pub keypair: [u8; 64],
+}
+
+/// 记录丢弃时清零内存中的密钥对
+impl Drop for ShmEntry {
+    fn drop(&mut self) {
+        self.keypair.zeroize();
+    }
+}
+
+/// 读取一条记录的结果
+pub enum ShmRead {
+    Entry(ShmEntry),
+    /// 这条记录还没写完
+    Pending,
+    /// 这条记录已被后面的记录覆盖，读取方落后太多
+    Overwritten,
+}
+
+/// 读取方，可以在另一个进程中打开
+pub struct ShmReader {
+    map: Mmap,
+    slots: u64,
+}
+
+impl ShmReader {
+    /// 映射已有的文件并检查文件头
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let file = File::open(path)?;
+        // SAFETY: 只读映射；写入方按顺序锁协议修改，读到的不一致内容会被槽位序号检查丢弃
+        let map = unsafe { Mmap::map(&file)? };
+        if map.len() < HEADER_SIZE || map[..8] != MAGIC {
+            return Err(invalid(format!("{} 不是匹配结果的共享内存缓冲区", path.display())));
+        }
+        let u32_at = |offset: usize| u32::from_le_bytes(map[offset..offset + 4].try_into().unwrap());
+        let version = u32_at(HEADER_VERSION);
+        let slot_size = u32_at(HEADER_SLOT_SIZE) as usize;
+        if version != VERSION || slot_size != SLOT_SIZE {
+            return Err(invalid(format!(
+                "{} 的版本 {}（槽位 {} 字节）与本程序支持的版本 {}（槽位 {} 字节）不一致",
+                path.display(),
+                version,
+                slot_size,
+                VERSION,
+                SLOT_SIZE
+            )));
+        }
+        let slots = u64::from_le_bytes(map[HEADER_SLOTS..HEADER_SLOTS + 8].try_into().unwrap());
+        if slots == 0 || map.len() as u64 != HEADER_SIZE as u64 + slots * SLOT_SIZE as u64 {
+            return Err(invalid(format!("{} 的大小与文件头中的槽位数 {} 不符", path.display(), slots)));
+        }
+        Ok(Self { map, slots })
+    }
+
+    pub fn slots(&self) -> u64 {
+        self.slots
+    }
+
+    /// 已写完的记录总数
+    pub fn write_sequence(&self) -> u64 {
+        // SAFETY: 文件头在映射范围内，偏移对齐
+        unsafe { atomic_at(self.map.as_ptr(), HEADER_WRITE_SEQ) }.load(Ordering::Acquire)
+    }
+
+    /// 读取第 `sequence` 条记录
+    pub fn read(&self, sequence: u64) -> ShmRead {
+        let slot = HEADER_SIZE + (sequence % self.slots) as usize * SLOT_SIZE;
+        let base = self.map.as_ptr();
+        let expected = 2 * sequence + 2;
+        // SAFETY: 槽位在映射范围内（打开时已核对文件大小），偏移对齐
+        let slot_seq = unsafe { atomic_at(base, slot + SLOT_SEQ) };
+        let before = slot_seq.load(Ordering::Acquire);
+        if before < expected {
+            return ShmRead::Pending;
+        }
+        if before > expected {
+            return ShmRead::Overwritten;
+        }
+
+        let mut raw = [0u8; SLOT_SIZE];
+        // SAFETY: 同上
+        unsafe { read_bytes(base.add(slot), &mut raw) };
+        fence(Ordering::Acquire);
+        if slot_seq.load(Ordering::Relaxed) != before {
+            raw.zeroize();
+            return ShmRead::Overwritten;
+        }
+
+        let address_len = (raw[SLOT_ADDRESS_LEN] as usize).min(MAX_ADDRESS_LEN);
+        let prefix_len = (raw[SLOT_PREFIX_LEN] as usize).min(MAX_PREFIX_LEN);
+        let mut keypair = [0u8; 64];
+        keypair.copy_from_slice(&raw[SLOT_KEYPAIR..SLOT_KEYPAIR + 64]);
+        let entry = ShmEntry {
+            sequence,
+            found_at_nanos: u64::from_le_bytes(raw[SLOT_TIME..SLOT_TIME + 8].try_into().unwrap()),
+            address: String::from_utf8_lossy(&raw[SLOT_ADDRESS..SLOT_ADDRESS + address_len]).into_owned(),
+            prefix: String::from_utf8_lossy(&raw[SLOT_PREFIX..SLOT_PREFIX + prefix_len]).into_owned(),
+            keypair,
+        };
+        raw.zeroize();
+        keypair.zeroize();
+        ShmRead::Entry(entry)
+    }
+}
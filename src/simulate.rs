@@ -0,0 +1,188 @@
+//! 蒙特卡洛模拟：用真实的密钥生成和匹配检验难度估计
+//!
+//! [`crate::difficulty`] 的概率是解析推导的，首字符分布不均匀、字符类等情况尤其容易出错。
+//! `simulate` 子命令反复运行真实的生成器和匹配器，每次试验一直生成到第一次命中（或达到上限），
+//! 把“第一次命中所需的尝试次数”的经验分布与几何分布的解析预测比较。
+//!
+//! 判定用的统计量：把所有试验的尝试加在一起，总命中数在解析概率 p 下近似服从均值为
+//! `p × 总尝试次数` 的泊松分布，z = (命中数 − 期望) / √期望。被上限截断的试验也计入总尝试次数，
+//! 因此截断不会带来偏差。|z| 不超过容差（默认 3）时判定通过。
+
+use solana_sdk::signer::Signer;
+
+use crate::keygen::KeyGenerator;
+use crate::search::Search;
+
+/// 报告的分位点
+pub const QUANTILES: [f64; 5] = [0.1, 0.25, 0.5, 0.75, 0.9];
+
+/// 一次试验：生成到第一次命中为止，返回所用的尝试次数，达到 `max_attempts` 仍未命中时返回 `None`
+pub fn run_trial(search: &Search, keygen: &mut KeyGenerator, max_attempts: u64) -> Option<u64> {
+    (1..=max_attempts).find(|_| {
+        let address = keygen.generate().pubkey().to_string();
+        search.find(&address).is_some() && search.accepts_address(&address)
+    })
+}
+
+/// 几何分布的 q 分位点：第一次命中所需尝试次数不超过它的概率至少为 q
+pub fn geometric_quantile(probability: f64, q: f64) -> f64 {
+    ((-q).ln_1p() / (-probability).ln_1p()).ceil().max(1.0)
+}
+
+/// 一个分位点的经验值与解析值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantile {
+    pub q: f64,
+    /// 经验分位点，落在被截断的试验上时为 `None`
+    pub empirical: Option<u64>,
+    pub analytic: f64,
+}
+
+/// 模拟结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub trials: usize,
+    /// 命中的试验数，其余被上限截断
+    pub hits: u64,
+    /// 所有试验的尝试次数之和，截断的试验按上限计
+    pub total_attempts: u64,
+    /// 解析概率
+    pub probability: f64,
+    /// 经验概率：命中数 / 总尝试次数
+    pub empirical_probability: f64,
+    /// 命中数相对期望的偏离，单位为标准差
+    pub z: f64,
+    /// 没有试验被截断时的平均尝试次数
+    pub mean: Option<f64>,
+    pub quantiles: Vec<Quantile>,
+}
+
+impl Report {
+    /// 汇总各次试验的结果
+    pub fn new(outcomes: &[Option<u64>], max_attempts: u64, probability: f64) -> Self {
+        let hits = outcomes.iter().flatten().count() as u64;
+        let total_attempts: u64 = outcomes.iter().map(|outcome| outcome.unwrap_or(max_attempts)).sum();
+        let expected = probability * total_attempts as f64;
+        let z = if expected > 0.0 {
+            (hits as f64 - expected) / expected.sqrt()
+        } else if hits > 0 {
+            f64::INFINITY
+        } else {
+            0.0
+        };
+
+        // 截断的试验排在最后
+        let mut sorted: Vec<Option<u64>> = outcomes.to_vec();
+        sorted.sort_by_key(|outcome| outcome.unwrap_or(u64::MAX));
+        let quantiles = QUANTILES
+            .iter()
+            .map(|&q| {
+                let index = ((q * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len().max(1)) - 1;
+                Quantile {
+                    q,
+                    empirical: sorted.get(index).copied().flatten(),
+                    analytic: geometric_quantile(probability, q),
+                }
+            })
+            .collect();
+
+        Self {
+            trials: outcomes.len(),
+            hits,
+            total_attempts,
+            probability,
+            empirical_probability: if total_attempts > 0 { hits as f64 / total_attempts as f64 } else { 0.0 },
+            z,
+            mean: (hits as usize == outcomes.len() && hits > 0).then(|| total_attempts as f64 / hits as f64),
+            quantiles,
+        }
+    }
+
+    /// 偏离是否在容差（标准差个数）之内
+    pub fn passes(&self, tolerance: f64) -> bool {
+        self.z.abs() <= tolerance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty;
+
+    #[test]
+    fn geometric_quantile_known_values() {
+        // p = 1/2：一次命中的概率 0.5，两次内 0.75，三次内 0.875
+        assert_eq!(geometric_quantile(0.5, 0.5), 1.0);
+        assert_eq!(geometric_quantile(0.5, 0.75), 2.0);
+        assert_eq!(geometric_quantile(0.5, 0.8), 3.0);
+        assert_eq!(geometric_quantile(1.0 / 256.0, 0.5), 178.0);
+        assert_eq!(geometric_quantile(0.9, 0.1), 1.0);
+    }
+
+    #[test]
+    fn report_counts_truncated_trials_at_the_cap() {
+        let outcomes = [Some(1), Some(3), None, Some(2)];
+        let report = Report::new(&outcomes, 10, 0.25);
+        assert_eq!(report.trials, 4);
+        assert_eq!(report.hits, 3);
+        assert_eq!(report.total_attempts, 16);
+        assert_eq!(report.empirical_probability, 3.0 / 16.0);
+        // 期望 4 次命中，实际 3 次
+        assert_eq!(report.z, -0.5);
+        assert_eq!(report.mean, None);
+        // 90% 分位点落在被截断的试验上
+        assert_eq!(report.quantiles.last().unwrap().empirical, None);
+        assert_eq!(report.quantiles[2].empirical, Some(2));
+        assert!(report.passes(0.5));
+        assert!(!report.passes(0.4));
+    }
+
+    #[test]
+    fn report_mean_without_truncation() {
+        let report = Report::new(&[Some(2), Some(4)], 100, 0.25);
+        assert_eq!(report.mean, Some(3.0));
+        assert_eq!(report.z, (2.0 - 1.5) / 1.5f64.sqrt());
+        let empty = Report::new(&[], 100, 0.25);
+        assert_eq!((empty.hits, empty.z, empty.mean), (0, 0.0, None));
+    }
+
+    /// 用固定种子的真实生成器和匹配器跑 `trials` 次试验
+    fn seeded_report(prefixes: &[&str], trials: u64, max_attempts: u64, seed: u64) -> Report {
+        let search = Search::new(prefixes.iter().copied());
+        let probability: f64 = difficulty::effective_probabilities(&search).iter().sum();
+        let outcomes: Vec<Option<u64>> = (0..trials)
+            .map(|trial| run_trial(&search, &mut KeyGenerator::seeded(seed, trial, trials), max_attempts))
+            .collect();
+        Report::new(&outcomes, max_attempts, probability)
+    }
+
+    #[test]
+    fn seeded_trials_agree_with_the_estimate() {
+        // 固定种子，结果确定；首字符不均匀（`2` 约 1/17、`z` 约 1/1000）、字符类和开头的 `1` 各一组
+        for (prefixes, trials, max_attempts) in [
+            (&["2"][..], 300, 1_000),
+            (&["[2-4]"][..], 300, 1_000),
+            (&["1", "z"][..], 60, 5_000),
+        ] {
+            let report = seeded_report(prefixes, trials, max_attempts, 178);
+            assert!(report.passes(3.0), "{:?}: z = {:.2}", prefixes, report.z);
+            assert!(report.hits > 0);
+        }
+    }
+
+    #[test]
+    fn seeded_trials_are_reproducible() {
+        assert_eq!(seeded_report(&["2"], 50, 1_000, 7), seeded_report(&["2"], 50, 1_000, 7));
+    }
+
+    #[test]
+    fn wrong_estimate_is_detected() {
+        // 把 `2` 的概率当成“每个字符 1/58”，命中数会偏离很多个标准差
+        let search = Search::new(["2"]);
+        let outcomes: Vec<Option<u64>> = (0..300)
+            .map(|trial| run_trial(&search, &mut KeyGenerator::seeded(178, trial, 300), 1_000))
+            .collect();
+        let report = Report::new(&outcomes, 1_000, 1.0 / 58.0);
+        assert!(!report.passes(3.0), "z = {:.2}", report.z);
+    }
+}
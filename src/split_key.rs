@@ -0,0 +1,255 @@
+//! 分离密钥（split-key）靓号搜索
+//!
+//! 类似 vanitygen 的 split-key 模式：密钥所有者持有私有标量 `a`，只把公钥点
+//! `A = a·B` 交给代算方；代算方搜索附加标量（tweak）`b`，使 `A + b·B` 的地址带有
+//! 指定前缀，然后把 `b` 交回。只有所有者能算出最终私钥 `a + b`，代算方从头到尾
+//! 都接触不到完整私钥。
+//!
+//! 所有者的 `A` 就是普通的 Solana 地址：`a` 取自其 32 字节种子经 SHA-512 扩展、
+//! 钳位后的前半部分，与 Ed25519 签名时使用的标量相同。
+//!
+//! 注意：最终私钥只能以 64 字节“扩展私钥”（标量 || nonce 前缀）的形式导出。
+//! Solana 钱包和 `solana-keygen` 使用的 64 字节格式是“种子 || 公钥”，而相加得到的
+//! 标量没有对应的种子，所以结果无法导入这些工具，只能用支持扩展私钥签名的程序使用。
+
+use curve25519_dalek::constants::{ED25519_BASEPOINT_POINT, ED25519_BASEPOINT_TABLE};
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use ed25519_dalek::{ExpandedSecretKey, PublicKey, Verifier};
+use rand::RngCore;
+use sha2::{Digest, Sha512};
+
+/// 把 Base58 地址解码为曲线上的点
+pub fn decode_point(address: &str) -> Result<EdwardsPoint, String> {
+    let bytes = bs58::decode(address)
+        .into_vec()
+        .map_err(|err| format!("公钥不是有效的 Base58: {}", err))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| format!("公钥应为 32 字节，实际为 {} 字节", bytes.len()))?;
+    CompressedEdwardsY(bytes)
+        .decompress()
+        .ok_or_else(|| "公钥不在曲线上".to_string())
+}
+
+/// 把曲线上的点编码为 Base58 地址
+pub fn encode_point(point: &EdwardsPoint) -> String {
+    bs58::encode(point.compress().as_bytes()).into_string()
+}
+
+/// 从 32 字节种子推导 Ed25519 签名用的标量和 nonce 前缀
+pub fn expand_seed(seed: &[u8; 32]) -> (Scalar, [u8; 32]) {
+    let hash = Sha512::digest(seed);
+    let mut lower = [0u8; 32];
+    let mut upper = [0u8; 32];
+    lower.copy_from_slice(&hash[..32]);
+    upper.copy_from_slice(&hash[32..]);
+
+    lower[0] &= 248;
+    lower[31] &= 127;
+    lower[31] |= 64;
+
+    (Scalar::from_bytes_mod_order(lower), upper)
+}
+
+/// 代算方一侧的搜索状态
+///
+/// 从随机的 `b` 开始，每一步把 `b` 加一、把点加上基点 `B`，
+/// 比每次做一遍标量乘法便宜得多。
+pub struct SplitGrinder {
+    tweak: Scalar,
+    point: EdwardsPoint,
+}
+
+impl SplitGrinder {
+    /// 以对方公钥 `A` 和一个随机起点创建搜索
+    pub fn new(partner: &EdwardsPoint) -> Self {
+        let mut wide = [0u8; 64];
+        rand::thread_rng().fill_bytes(&mut wide);
+        let tweak = Scalar::from_bytes_mod_order_wide(&wide);
+        let point = partner + &tweak * &ED25519_BASEPOINT_TABLE;
+        Self { tweak, point }
+    }
+
+    /// 当前 tweak 对应的地址
+    pub fn address(&self) -> String {
+        encode_point(&self.point)
+    }
+
+    /// 当前 tweak 的 Base58 编码
+    pub fn tweak(&self) -> String {
+        bs58::encode(self.tweak.as_bytes()).into_string()
+    }
+
+    /// 前进到下一个 tweak
+    pub fn advance(&mut self) {
+        self.tweak += Scalar::one();
+        self.point += ED25519_BASEPOINT_POINT;
+    }
+}
+
+/// 所有者合并得到的最终密钥
+pub struct CombinedKey {
+    pub address: String,
+    /// 64 字节扩展私钥：标量 || nonce 前缀
+    pub expanded_secret: [u8; 64],
+}
+
+/// 所有者一侧：用自己的 64 字节密钥对和 tweak 算出最终扩展私钥
+///
+/// 合并后会用扩展私钥签名一条消息并用推导出的地址验签，确保结果可用。
+pub fn combine(my_keypair: &str, tweak: &str) -> Result<CombinedKey, String> {
+    let keypair = bs58::decode(my_keypair)
+        .into_vec()
+        .map_err(|err| format!("私钥不是有效的 Base58: {}", err))?;
+    if keypair.len() != 64 {
+        return Err(format!("私钥应为 64 字节密钥对，实际为 {} 字节", keypair.len()));
+    }
+    let tweak = bs58::decode(tweak)
+        .into_vec()
+        .map_err(|err| format!("tweak 不是有效的 Base58: {}", err))?;
+    let tweak: [u8; 32] = tweak
+        .try_into()
+        .map_err(|tweak: Vec<u8>| format!("tweak 应为 32 字节，实际为 {} 字节", tweak.len()))?;
+    let tweak = Scalar::from_canonical_bytes(tweak).ok_or("tweak 不是规范的标量")?;
+
+    let mut seed = [0u8; 32];
+    seed.copy_from_slice(&keypair[..32]);
+    let (scalar, nonce) = expand_seed(&seed);
+    if (&scalar * &ED25519_BASEPOINT_TABLE).compress().as_bytes() != &keypair[32..] {
+        return Err("私钥后 32 字节与推导出的公钥不一致".to_string());
+    }
+
+    let combined = scalar + tweak;
+    let point = &combined * &ED25519_BASEPOINT_TABLE;
+
+    // 重新派生 nonce 前缀，避免与原密钥共用
+    let nonce = Sha512::new()
+        .chain_update(nonce)
+        .chain_update(tweak.as_bytes())
+        .finalize();
+    let mut expanded_secret = [0u8; 64];
+    expanded_secret[..32].copy_from_slice(combined.as_bytes());
+    expanded_secret[32..].copy_from_slice(&nonce[..32]);
+
+    let public = PublicKey::from_bytes(point.compress().as_bytes()).map_err(|err| err.to_string())?;
+    let signer = ExpandedSecretKey::from_bytes(&expanded_secret).map_err(|err| err.to_string())?;
+    let message = b"solana-vanity-address split-key check";
+    let signature = signer.sign(message, &public);
+    public
+        .verify(message, &signature)
+        .map_err(|err| format!("合并后的密钥签名校验失败: {}", err))?;
+
+    Ok(CombinedKey {
+        address: encode_point(&point),
+        expanded_secret,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Signature;
+    use solana_sdk::signature::Keypair;
+    use solana_sdk::signer::Signer;
+
+    /// 所有者：生成密钥对，只把地址交给代算方
+    fn owner() -> (String, String) {
+        let keypair = Keypair::new();
+        (keypair.to_base58_string(), keypair.pubkey().to_string())
+    }
+
+    /// 合并失败时的错误信息；`CombinedKey` 含私钥，不实现 `Debug`，不能用 `unwrap_err`
+    fn combine_error(secret: &str, tweak: &str) -> String {
+        combine(secret, tweak).err().expect("合并应当失败")
+    }
+
+    /// 代算方：只拿到地址，搜索到以 `prefix` 开头的地址为止，交回 tweak 和地址
+    fn grind(partner: &str, prefix: &str) -> (String, String) {
+        let mut grinder = SplitGrinder::new(&decode_point(partner).unwrap());
+        while !grinder.address().starts_with(prefix) {
+            grinder.advance();
+        }
+        (grinder.tweak(), grinder.address())
+    }
+
+    #[test]
+    fn tweaked_point_equals_the_combined_key() {
+        for prefix in ["", "A", "z", "9"] {
+            let (secret, public) = owner();
+            let (tweak, address) = grind(&public, prefix);
+            let combined = combine(&secret, &tweak).unwrap();
+            assert_eq!(combined.address, address);
+            assert!(combined.address.starts_with(prefix));
+        }
+    }
+
+    #[test]
+    fn every_step_of_the_grinder_matches_the_owner_side() {
+        let (secret, public) = owner();
+        let mut grinder = SplitGrinder::new(&decode_point(&public).unwrap());
+        for _ in 0..20 {
+            assert_eq!(combine(&secret, &grinder.tweak()).unwrap().address, grinder.address());
+            grinder.advance();
+        }
+    }
+
+    #[test]
+    fn combined_secret_signs_and_verifies() {
+        let (secret, public) = owner();
+        let (tweak, address) = grind(&public, "B");
+        let combined = combine(&secret, &tweak).unwrap();
+        let key = PublicKey::from_bytes(&bs58::decode(&address).into_vec().unwrap()).unwrap();
+        let signer = ExpandedSecretKey::from_bytes(&combined.expanded_secret).unwrap();
+        let message = b"transfer 1 SOL";
+        let signature: Signature = signer.sign(message, &key);
+        assert!(key.verify(message, &signature).is_ok());
+        assert!(key.verify(b"transfer 2 SOL", &signature).is_err());
+    }
+
+    #[test]
+    fn wrong_tweak_gives_a_different_address() {
+        let (secret, public) = owner();
+        let (_, address) = grind(&public, "");
+        let (other_tweak, _) = grind(&public, "");
+        assert_ne!(combine(&secret, &other_tweak).unwrap().address, address);
+    }
+
+    #[test]
+    fn malformed_tweak_is_rejected() {
+        let (secret, _) = owner();
+        let short = bs58::encode([1u8; 31]).into_string();
+        assert!(combine_error(&secret, &short).contains("32 字节"));
+        // 大于群阶的 32 字节不是规范的标量
+        let noncanonical = bs58::encode([0xffu8; 32]).into_string();
+        assert!(combine_error(&secret, &noncanonical).contains("规范"));
+        assert!(combine_error(&secret, "0OIl").contains("Base58"));
+    }
+
+    #[test]
+    fn wrong_base_share_is_rejected() {
+        let (_, public) = owner();
+        let (tweak, address) = grind(&public, "");
+
+        // 另一个所有者的密钥对与同一个 tweak 合并，得不到代算方找到的地址
+        let (other_secret, _) = owner();
+        assert_ne!(combine(&other_secret, &tweak).unwrap().address, address);
+
+        // 种子与后 32 字节的公钥不对应
+        let (secret, _) = owner();
+        let mut bytes = bs58::decode(&secret).into_vec().unwrap();
+        bytes[32..].copy_from_slice(&bs58::decode(&public).into_vec().unwrap());
+        let mismatched = bs58::encode(bytes).into_string();
+        assert!(combine_error(&mismatched, &tweak).contains("不一致"));
+
+        // 只有 32 字节种子
+        let seed = bs58::encode(&bs58::decode(&secret).into_vec().unwrap()[..32]).into_string();
+        assert!(combine_error(&seed, &tweak).contains("64 字节"));
+    }
+
+    #[test]
+    fn invalid_partner_pubkeys_are_rejected() {
+        assert!(decode_point("not-base58!").is_err());
+        assert!(decode_point(&bs58::encode([1u8; 31]).into_string()).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! Squads v4 多签金库地址推导（`grind-squads` 子命令）
+//!
+//! Squads 多签真正存放资金的是金库（vault）PDA，它由多签 PDA 推导，多签 PDA 又由创建时
+//! 随机选取的 `create_key` 公钥推导，与 Squads v4 程序中的种子完全一致：
+//!
+//! - 多签：`["multisig", "multisig", create_key]`
+//! - 金库：`["multisig", multisig, "vault", vault_index]`，`vault_index` 为一个字节
+//!
+//! 所以想要靓号金库，就要不断随机生成 `create_key` 密钥对，推导出金库地址再匹配。
+//! 每次尝试要做两次 `find_program_address`，比普通搜索慢得多。创建多签时 `create_key`
+//! 必须签名，输出中保存它的私钥。推导是纯计算，不访问网络。
+
+use solana_sdk::pubkey::Pubkey;
+
+/// Squads v4 主网程序
+pub const PROGRAM_ID: Pubkey = solana_sdk::pubkey!("SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf");
+
+const SEED_PREFIX: &[u8] = b"multisig";
+const SEED_MULTISIG: &[u8] = b"multisig";
+const SEED_VAULT: &[u8] = b"vault";
+
+/// 由一个 `create_key` 推导出的多签和金库地址
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SquadsAddresses {
+    pub multisig: Pubkey,
+    pub multisig_bump: u8,
+    pub vault: Pubkey,
+    pub vault_bump: u8,
+}
+
+/// 多签 PDA 及其 bump
+pub fn multisig_address(program: &Pubkey, create_key: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEED_PREFIX, SEED_MULTISIG, create_key.as_ref()], program)
+}
+
+/// 多签的第 `index` 个金库 PDA 及其 bump
+pub fn vault_address(program: &Pubkey, multisig: &Pubkey, index: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEED_PREFIX, multisig.as_ref(), SEED_VAULT, &[index]], program)
+}
+
+/// 由 `create_key` 依次推导多签和金库
+pub fn derive(program: &Pubkey, create_key: &Pubkey, vault_index: u8) -> SquadsAddresses {
+    let (multisig, multisig_bump) = multisig_address(program, create_key);
+    let (vault, vault_bump) = vault_address(program, &multisig, vault_index);
+    SquadsAddresses {
+        multisig,
+        multisig_bump,
+        vault,
+        vault_bump,
+    }
+}
+
+/// 解析 Squads 程序地址
+pub fn parse_program(value: &str) -> Result<Pubkey, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("无效的 Squads 程序地址: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+
+    /// 不经过 solana_sdk，按链上的规则从头推导 PDA：从 bump 255 往下试，
+    /// `sha256(种子 ‖ bump ‖ 程序 ‖ "ProgramDerivedAddress")` 第一个不在曲线上的就是
+    fn pda(seeds: &[&[u8]], program: &Pubkey) -> (Pubkey, u8) {
+        for bump in (0..=255u8).rev() {
+            let mut hasher = Sha256::new();
+            for seed in seeds {
+                hasher.update(seed);
+            }
+            hasher.update([bump]);
+            hasher.update(program.as_ref());
+            hasher.update(b"ProgramDerivedAddress");
+            let hash: [u8; 32] = hasher.finalize().into();
+            if CompressedEdwardsY(hash).decompress().is_none() {
+                return (Pubkey::new_from_array(hash), bump);
+            }
+        }
+        panic!("没有可用的 bump");
+    }
+
+    // 离线环境中拿不到可以核对的主网 (create_key, multisig, vault) 三元组，
+    // 这里用独立实现的 PDA 规则逐字节核对 Squads v4 的种子
+    #[test]
+    fn derivation_matches_the_pda_rule_with_squads_seeds() {
+        for _ in 0..50 {
+            let create_key = Pubkey::new_unique();
+            let (multisig, multisig_bump) = pda(&[b"multisig", b"multisig", create_key.as_ref()], &PROGRAM_ID);
+            for index in [0u8, 1, 7, 255] {
+                let (vault, vault_bump) = pda(&[b"multisig", multisig.as_ref(), b"vault", &[index]], &PROGRAM_ID);
+                let derived = derive(&PROGRAM_ID, &create_key, index);
+                assert_eq!(
+                    derived,
+                    SquadsAddresses {
+                        multisig,
+                        multisig_bump,
+                        vault,
+                        vault_bump
+                    }
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn vaults_differ_by_index_and_program() {
+        let create_key = Pubkey::new_unique();
+        let first = derive(&PROGRAM_ID, &create_key, 0);
+        assert_ne!(first.vault, derive(&PROGRAM_ID, &create_key, 1).vault);
+        let other = derive(&Pubkey::new_unique(), &create_key, 0);
+        assert_ne!(first.multisig, other.multisig);
+        assert_ne!(first.vault, other.vault);
+        assert!(!first.vault.is_on_curve());
+        assert!(!first.multisig.is_on_curve());
+    }
+
+    #[test]
+    fn program_is_parsed() {
+        assert_eq!(parse_program(" SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf ").unwrap(), PROGRAM_ID);
+        assert!(parse_program("not-a-key").is_err());
+    }
+}
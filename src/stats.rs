@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+/// 某一时刻的搜索统计快照
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    /// 已生成的地址数量
+    pub generated: u64,
+    /// 匹配的地址数量；一个地址同时满足几个前缀时只算一次，所以可能小于 `per_prefix` 各项之和
+    pub matched: u64,
+    /// 每个前缀各自的匹配数量，按前缀排序
+    pub per_prefix: Vec<(String, u64)>,
+    /// 自开始以来的平均速率（个/秒），按不含暂停的运行时间计算
+    pub rate: f64,
+    /// 自开始以来的运行时间，不含暂停时间
+    pub elapsed: Duration,
+    /// 工作线程最近使用的批大小，自适应模式下即收敛后的值
+    pub batch_size: u64,
+    /// 已保存的非匹配地址数量
+    pub samples_taken: usize,
+    /// 要保存的非匹配地址数量，0 表示不采样
+    pub sample_target: usize,
+    /// 每个前缀目前为止被任意地址匹配到的最长长度，与 `per_prefix` 顺序一致
+    pub best_partial: Vec<usize>,
+    /// 每个前缀的近似命中（地址，重合字符数），从长到短排列，不含私钥
+    pub near_misses: Vec<Vec<(String, usize)>>,
+    /// 每个前缀开始参与搜索时的生成总数，运行中添加的前缀不为 0，与 `per_prefix` 顺序一致
+    pub added_at: Vec<u64>,
+}
+
+impl Stats {
+    /// 平均多少个地址出现一个匹配，还没有匹配时返回 `None`
+    pub fn attempts_per_match(&self) -> Option<u64> {
+        self.generated.checked_div(self.matched)
+    }
+
+    /// 前缀 `index` 参与搜索以来生成的地址数，运行中添加的前缀从添加时算起
+    pub fn attempts_for(&self, index: usize) -> u64 {
+        self.generated.saturating_sub(self.added_at.get(index).copied().unwrap_or(0))
+    }
+
+    /// 每个前缀的最好部分匹配：（前缀，已匹配的部分）
+    ///
+    /// 已匹配的部分取自近似命中榜第一名的地址，前缀含字符类时显示的是实际的字符。
+    pub fn best_partial_matches(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.per_prefix.iter().zip(&self.near_misses).map(|((prefix, _), board)| {
+            let best = board.first().map_or("", |(address, matched)| &address[..*matched]);
+            (prefix.as_str(), best)
+        })
+    }
+}
@@ -0,0 +1,142 @@
+//! 通过文件控制运行中的搜索
+//!
+//! 调度系统无法发送信号时，可以创建一个约定的文件来控制搜索：文件出现即按 Ctrl+C 的方式
+//! 优雅退出；文件内容为 `pause` 或 `resume` 时改为暂停或恢复。
+
+use std::io;
+use std::path::PathBuf;
+
+/// 控制文件表达的指令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopCommand {
+    /// 优雅退出（空文件、`stop` 或其他任何内容）
+    Stop,
+    Pause,
+    Resume,
+}
+
+impl StopCommand {
+    fn parse(content: &str) -> Self {
+        match content.trim().to_ascii_lowercase().as_str() {
+            "pause" => StopCommand::Pause,
+            "resume" => StopCommand::Resume,
+            _ => StopCommand::Stop,
+        }
+    }
+}
+
+/// 被轮询的控制文件
+#[derive(Debug, Clone)]
+pub struct StopFile {
+    path: PathBuf,
+    /// 读取后删除文件，指令只生效一次
+    consume: bool,
+}
+
+impl StopFile {
+    pub fn new(path: impl Into<PathBuf>, consume: bool) -> Self {
+        Self {
+            path: path.into(),
+            consume,
+        }
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// 检查文件是否存在，存在时返回其中的指令
+    pub fn poll(&self) -> io::Result<Option<StopCommand>> {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        if self.consume {
+            match std::fs::remove_file(&self.path) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(Some(StopCommand::parse(&content)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试专用的空临时目录
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sva-stop-file-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn missing_file_is_no_command() {
+        let dir = temp_dir("missing");
+        let stop_file = StopFile::new(dir.join("STOP"), false);
+        assert_eq!(stop_file.poll().unwrap(), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn content_selects_the_command() {
+        let dir = temp_dir("content");
+        let path = dir.join("STOP");
+        let stop_file = StopFile::new(&path, false);
+        for (content, expected) in [
+            ("", StopCommand::Stop),
+            ("stop\n", StopCommand::Stop),
+            ("anything else", StopCommand::Stop),
+            ("pause", StopCommand::Pause),
+            ("  PAUSE\r\n", StopCommand::Pause),
+            ("resume\n", StopCommand::Resume),
+            ("Resume", StopCommand::Resume),
+        ] {
+            std::fs::write(&path, content).unwrap();
+            assert_eq!(stop_file.poll().unwrap(), Some(expected), "{:?}", content);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn without_consume_the_command_repeats() {
+        let dir = temp_dir("repeat");
+        let path = dir.join("STOP");
+        std::fs::write(&path, "pause").unwrap();
+        let stop_file = StopFile::new(&path, false);
+        assert_eq!(stop_file.poll().unwrap(), Some(StopCommand::Pause));
+        assert_eq!(stop_file.poll().unwrap(), Some(StopCommand::Pause));
+        assert!(path.exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn consume_removes_the_file_after_one_read() {
+        let dir = temp_dir("consume");
+        let path = dir.join("STOP");
+        std::fs::write(&path, "pause").unwrap();
+        let stop_file = StopFile::new(&path, true);
+        assert_eq!(stop_file.poll().unwrap(), Some(StopCommand::Pause));
+        assert!(!path.exists());
+        assert_eq!(stop_file.poll().unwrap(), None);
+
+        std::fs::write(&path, "resume").unwrap();
+        assert_eq!(stop_file.poll().unwrap(), Some(StopCommand::Resume));
+        assert_eq!(stop_file.poll().unwrap(), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_path_is_an_error() {
+        // 路径是目录时读取失败，应报错而不是当作没有指令
+        let dir = temp_dir("directory");
+        let stop_file = StopFile::new(&dir, false);
+        assert!(stop_file.poll().is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+//! 回文和镜像地址（`--palindrome`、`--mirror`）
+//!
+//! - 回文：地址开头 N 个字符正读反读相同，如 `AbCbA…`
+//! - 镜像：地址开头 N 个字符倒过来就是结尾 N 个字符，如 `AbC…CbA`
+//!
+//! 镜像涉及地址结尾，无法用前缀或 `--pattern` 的形状表达，所以做成专门的匹配器，
+//! 作为 [`crate::matcher::Matcher::Symmetry`] 与 `-p` 的前缀一起放进 [`crate::Search`]，是“任一命中即匹配”的关系。
+//! 它们用 [`Symmetry::label`] 作为名字参与计数，输出文件的前缀列记录实际的回文或镜像字符串。
+//! 与前缀一样区分大小写。
+
+use std::ops::Range;
+
+use crate::difficulty::address_classes;
+
+/// 回文的最大长度：地址最短约 43 个字符
+pub const MAX_PALINDROME: usize = 43;
+
+/// 镜像的最大长度：开头和结尾各占一半
+pub const MAX_MIRROR: usize = 21;
+
+/// 一个对称匹配器
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    /// 开头 N 个字符是回文
+    Palindrome(usize),
+    /// 开头 N 个字符倒过来等于结尾 N 个字符
+    Mirror(usize),
+}
+
+impl Symmetry {
+    /// 显示用的名字：`<palindrome 5>` 或 `<mirror 3>`
+    pub fn label(&self) -> String {
+        match self {
+            Symmetry::Palindrome(length) => format!("<palindrome {}>", length),
+            Symmetry::Mirror(length) => format!("<mirror {}>", length),
+        }
+    }
+
+    /// 从 [`Symmetry::label`] 的写法解析，不是对称匹配器时返回 `None`；用于读取文本形式的目标列表，如模糊测试的输入
+    pub fn from_label(label: &str) -> Option<Self> {
+        let (kind, length) = label.strip_prefix('<')?.strip_suffix('>')?.split_once(' ')?;
+        let length: usize = length.parse().ok()?;
+        match kind {
+            "palindrome" => parse_palindrome(&length.to_string()).ok(),
+            "mirror" => parse_mirror(&length.to_string()).ok(),
+            _ => None,
+        }
+    }
+
+    /// 检查地址是否符合
+    pub fn matches(&self, address: &str) -> bool {
+        let bytes = address.as_bytes();
+        match *self {
+            Symmetry::Palindrome(length) => {
+                bytes.len() >= length && (0..length / 2).all(|i| bytes[i] == bytes[length - 1 - i])
+            }
+            Symmetry::Mirror(length) => {
+                bytes.len() >= length && (0..length).all(|i| bytes[i] == bytes[bytes.len() - 1 - i])
+            }
+        }
+    }
+
+    /// 匹配地址中实际对称的部分，写入输出文件的前缀列：回文为开头 N 个字符，镜像为 `开头…结尾`
+    pub fn matched_text(&self, address: &str) -> String {
+        match *self {
+            Symmetry::Palindrome(length) => address[..length].to_string(),
+            Symmetry::Mirror(length) => format!("{}…{}", &address[..length], &address[address.len() - length..]),
+        }
+    }
+
+    /// 匹配地址中对称部分的位置：回文为开头 N 个字符，镜像为开头和结尾各 N 个字符
+    pub fn spans(&self, address: &str) -> Vec<Range<usize>> {
+        match *self {
+            Symmetry::Palindrome(length) => std::iter::once(0..length).collect(),
+            Symmetry::Mirror(length) => vec![0..length, address.len() - length..address.len()],
+        }
+    }
+
+    /// 随机地址符合的概率
+    ///
+    /// 按 [`address_classes`] 把地址分成几类，每一类中各位独立：每一对必须相同的位置
+    /// 相同的概率为对每个数字求两位概率之积再求和，各对相乘。首字符的分布不均匀，
+    /// 43 和 44 个字符的地址结尾对应的位置也不同，所以不能简单地按每对 1/58 计算。
+    pub fn probability(&self) -> f64 {
+        address_classes()
+            .iter()
+            .map(|(weight, positions)| {
+                let Some(pairs) = self.pairs(positions.len()) else {
+                    return 0.0;
+                };
+                let same: f64 = pairs
+                    .iter()
+                    .map(|(a, b)| (0..58).map(|digit| positions[*a][digit] * positions[*b][digit]).sum::<f64>())
+                    .product();
+                weight * same
+            })
+            .sum()
+    }
+
+    /// 长度为 `length` 的地址中必须相同的位置对，地址太短时为 `None`
+    fn pairs(&self, length: usize) -> Option<Vec<(usize, usize)>> {
+        match *self {
+            Symmetry::Palindrome(n) if n <= length => Some((0..n / 2).map(|i| (i, n - 1 - i)).collect()),
+            // 镜像超过地址一半时，后一半的约束与前一半重复
+            Symmetry::Mirror(n) if n <= length => Some((0..n.min(length / 2)).map(|i| (i, length - 1 - i)).collect()),
+            _ => None,
+        }
+    }
+}
+
+/// 解析 `--palindrome` 的长度，2 到 [`MAX_PALINDROME`]
+pub fn parse_palindrome(value: &str) -> Result<Symmetry, String> {
+    match value.parse::<usize>() {
+        Ok(length) if (2..=MAX_PALINDROME).contains(&length) => Ok(Symmetry::Palindrome(length)),
+        _ => Err(format!("回文长度应为 2 到 {} 之间的整数，而不是 {}", MAX_PALINDROME, value)),
+    }
+}
+
+/// 解析 `--mirror` 的长度，1 到 [`MAX_MIRROR`]
+pub fn parse_mirror(value: &str) -> Result<Symmetry, String> {
+    match value.parse::<usize>() {
+        Ok(length) if (1..=MAX_MIRROR).contains(&length) => Ok(Symmetry::Mirror(length)),
+        _ => Err(format!("镜像长度应为 1 到 {} 之间的整数，而不是 {}", MAX_MIRROR, value)),
+    }
+}
@@ -0,0 +1,93 @@
+//! 前缀中的动态占位符
+//!
+//! 周期性的活动经常需要带当天日期或期数的地址，每次手改命令容易出错。前缀中可以写
+//! `{YYYY}`、`{MM}`、`{DD}`（启动时的本地日期）和 `{RUN}`（保存在状态文件中、每次运行
+//! 加一的计数器），启动时先展开成具体的前缀，再做 Base58 校验。
+//!
+//! Base58 不含 `0`，所以含 `0` 的日期（如 10 月、20 日）或计数值展开后会在校验时报错。
+
+use std::io;
+use std::path::Path;
+
+use chrono::{Datelike, NaiveDate};
+
+/// 默认的运行计数器状态文件
+pub const DEFAULT_COUNTER_FILE: &str = "data/run_counter.txt";
+
+/// 支持的占位符名称
+pub const TOKENS: [&str; 4] = ["YYYY", "MM", "DD", "RUN"];
+
+/// 展开占位符时使用的值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenValues {
+    pub date: NaiveDate,
+    /// 本次运行的计数值，前缀中没有 `{RUN}` 时可以为 `None`
+    pub run: Option<u64>,
+}
+
+/// 前缀是否含有占位符
+pub fn has_tokens(pattern: &str) -> bool {
+    pattern.contains('{') || pattern.contains('}')
+}
+
+/// 前缀是否用到运行计数器
+pub fn uses_run_counter(pattern: &str) -> bool {
+    pattern.contains("{RUN}")
+}
+
+/// 展开前缀中的占位符，未知的占位符或不成对的括号报错
+pub fn expand(pattern: &str, values: &TokenValues) -> Result<String, String> {
+    let mut expanded = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return Err(format!("前缀 \"{}\" 中有多余的 '}}'", pattern));
+        }
+        expanded.push_str(&rest[..start]);
+        let Some(length) = rest[start + 1..].find('}') else {
+            return Err(format!("前缀 \"{}\" 中的 '{{' 没有对应的 '}}'", pattern));
+        };
+        let name = &rest[start + 1..start + 1 + length];
+        match name {
+            "YYYY" => expanded.push_str(&format!("{:04}", values.date.year())),
+            "MM" => expanded.push_str(&format!("{:02}", values.date.month())),
+            "DD" => expanded.push_str(&format!("{:02}", values.date.day())),
+            "RUN" => match values.run {
+                Some(run) => expanded.push_str(&run.to_string()),
+                None => return Err(format!("前缀 \"{}\" 用到了 {{RUN}}，但没有读取运行计数器", pattern)),
+            },
+            _ => {
+                return Err(format!(
+                    "前缀 \"{}\" 中的占位符 {{{}}} 不受支持，可用的占位符: {}",
+                    pattern,
+                    name,
+                    TOKENS.map(|token| format!("{{{}}}", token)).join("、")
+                ));
+            }
+        }
+        rest = &rest[start + 1 + length + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// 读取运行计数器的当前值，文件不存在时为 0
+pub fn read_counter(path: impl AsRef<Path>) -> io::Result<u64> {
+    let path = path.as_ref();
+    match std::fs::read_to_string(path) {
+        Ok(content) => content.trim().parse().map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}: 不是有效的计数值 ({})", path.display(), err))
+        }),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(err) => Err(err),
+    }
+}
+
+/// 保存运行计数器，必要时创建目录
+pub fn write_counter(path: impl AsRef<Path>, value: u64) -> io::Result<()> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, format!("{}\n", value))
+}
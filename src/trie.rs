@@ -0,0 +1,221 @@
+//! 前缀树匹配
+//!
+//! 逐个比较前缀时每个地址的开销与前缀数量成正比，前缀上万时吞吐量会明显下降。
+//! 启动时把（已按字典序排好的）前缀建成一棵前缀树，匹配只需沿地址的字符走一遍，
+//! 开销只与地址长度有关。
+//!
+//! 因为前缀按字典序排列，任意节点子树里的前缀在排序后是连续的一段，节点只需记录
+//! 这一段的下标范围。沿地址走过的终止节点就是地址匹配的全部前缀，第一个是字典序最靠前
+//! （也是最短）的一个。
+
+use std::ops::Range;
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    /// 按字节排序的子节点
+    children: Vec<(u8, u32)>,
+    /// 恰好在此结束的前缀下标
+    terminal: Option<usize>,
+    /// 子树中的前缀下标范围
+    range: Range<usize>,
+}
+
+impl Node {
+    fn child(&self, byte: u8) -> Option<usize> {
+        self.children
+            .binary_search_by_key(&byte, |(key, _)| *key)
+            .ok()
+            .map(|index| self.children[index].1 as usize)
+    }
+}
+
+/// 由一组按字典序排列、互不相同的前缀构成的前缀树
+#[derive(Debug, Clone)]
+pub struct PrefixTrie {
+    nodes: Vec<Node>,
+}
+
+impl PrefixTrie {
+    /// 建树，`prefixes` 必须按字典序排列且没有重复（[`crate::Search`] 保证这一点）
+    pub fn new(prefixes: &[String]) -> Self {
+        let mut nodes = vec![Node {
+            range: 0..prefixes.len(),
+            ..Node::default()
+        }];
+        for (index, prefix) in prefixes.iter().enumerate() {
+            let mut current = 0;
+            for byte in prefix.bytes() {
+                current = match nodes[current].child(byte) {
+                    Some(child) => child,
+                    None => {
+                        let child = nodes.len();
+                        nodes.push(Node {
+                            range: index..index,
+                            ..Node::default()
+                        });
+                        let children = &mut nodes[current].children;
+                        let position = children.partition_point(|(key, _)| *key < byte);
+                        children.insert(position, (byte, child as u32));
+                        child
+                    }
+                };
+                nodes[current].range.end = index + 1;
+            }
+            nodes[current].terminal = Some(index);
+        }
+        Self { nodes }
+    }
+
+    /// 地址匹配的第一个（字典序最靠前，也就是最短的）前缀下标
+    pub fn find(&self, address: &str) -> Option<usize> {
+        let mut current = &self.nodes[0];
+        if current.terminal.is_some() {
+            return current.terminal;
+        }
+        for byte in address.bytes() {
+            current = &self.nodes[current.child(byte)?];
+            if current.terminal.is_some() {
+                return current.terminal;
+            }
+        }
+        None
+    }
+
+    /// 地址匹配的全部前缀下标，从短到长（也就是按字典序）排列
+    pub fn find_all(&self, address: &str) -> Vec<usize> {
+        let mut current = &self.nodes[0];
+        let mut found: Vec<usize> = current.terminal.into_iter().collect();
+        for byte in address.bytes() {
+            let Some(child) = current.child(byte) else {
+                break;
+            };
+            current = &self.nodes[child];
+            found.extend(current.terminal);
+        }
+        found
+    }
+
+    /// 对每个与地址至少有一个公共开头字符的前缀，回调它的下标和公共开头的长度
+    ///
+    /// 沿地址走到深度 d 的节点时，子树中的前缀与地址至少重合 d 个字符；
+    /// 不在更深一层子树中的恰好重合 d 个。完全不重合的前缀不会被访问。
+    pub fn for_each_common_prefix(&self, address: &str, mut visit: impl FnMut(usize, usize)) {
+        let mut path: Vec<&Range<usize>> = Vec::new();
+        let mut current = &self.nodes[0];
+        for byte in address.bytes() {
+            match current.child(byte) {
+                Some(child) => {
+                    current = &self.nodes[child];
+                    path.push(&current.range);
+                }
+                None => break,
+            }
+        }
+        for depth in (1..=path.len()).rev() {
+            let range = path[depth - 1];
+            let deeper = path.get(depth).map_or(range.start..range.start, |deeper| (*deeper).clone());
+            for index in range.start..deeper.start {
+                visit(index, depth);
+            }
+            for index in deeper.end..range.end {
+                visit(index, depth);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    use super::*;
+    use crate::base58::ALPHABET;
+
+    /// 随机的 Base58 字符串，长度在 `lengths` 范围内；字母表只取前 `width` 个字符，窄字母表让前缀更容易互相嵌套
+    fn random_string(rng: &mut ChaCha20Rng, lengths: Range<usize>, width: usize) -> String {
+        let length = rng.gen_range(lengths);
+        (0..length).map(|_| ALPHABET.as_bytes()[rng.gen_range(0..width)] as char).collect()
+    }
+
+    /// 按 [`PrefixTrie::new`] 的要求排序去重
+    fn sorted(mut prefixes: Vec<String>) -> Vec<String> {
+        prefixes.sort();
+        prefixes.dedup();
+        prefixes
+    }
+
+    fn naive_find_all(prefixes: &[String], address: &str) -> Vec<usize> {
+        let mut found: Vec<usize> = (0..prefixes.len()).filter(|index| address.starts_with(&prefixes[*index])).collect();
+        found.sort_by_key(|index| prefixes[*index].len());
+        found
+    }
+
+    fn common_len(a: &str, b: &str) -> usize {
+        a.bytes().zip(b.bytes()).take_while(|(x, y)| x == y).count()
+    }
+
+    #[test]
+    fn matches_naive_on_random_sets() {
+        let mut rng = ChaCha20Rng::seed_from_u64(169);
+        for round in 0..200 {
+            let width = [3, 8, 58][round % 3];
+            let count = rng.gen_range(1..50);
+            let prefixes = sorted((0..count).map(|_| random_string(&mut rng, 0..6, width)).collect());
+            let trie = PrefixTrie::new(&prefixes);
+            for _ in 0..100 {
+                let address = random_string(&mut rng, 0..10, width);
+                let expected = naive_find_all(&prefixes, &address);
+                assert_eq!(trie.find_all(&address), expected, "{:?} {:?}", prefixes, address);
+                assert_eq!(trie.find(&address), expected.first().copied());
+            }
+        }
+    }
+
+    #[test]
+    fn common_prefix_visits_exactly_the_overlapping_prefixes() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1690);
+        for _ in 0..200 {
+            let prefixes = sorted((0..rng.gen_range(1..40)).map(|_| random_string(&mut rng, 1..6, 4)).collect());
+            let trie = PrefixTrie::new(&prefixes);
+            let address = random_string(&mut rng, 0..8, 4);
+            let mut visited = Vec::new();
+            trie.for_each_common_prefix(&address, |index, length| visited.push((index, length)));
+            visited.sort_unstable();
+            let expected: Vec<(usize, usize)> = prefixes
+                .iter()
+                .enumerate()
+                .map(|(index, prefix)| (index, common_len(prefix, &address)))
+                .filter(|(_, length)| *length > 0)
+                .collect();
+            assert_eq!(visited, expected, "{:?} {:?}", prefixes, address);
+        }
+    }
+
+    #[test]
+    fn empty_prefix_matches_everything() {
+        let prefixes = sorted(vec![String::new(), "A".to_string()]);
+        let trie = PrefixTrie::new(&prefixes);
+        assert_eq!(trie.find_all("AB"), vec![0, 1]);
+        assert_eq!(trie.find("zz"), Some(0));
+        assert_eq!(trie.find_all(""), vec![0]);
+    }
+
+    #[test]
+    fn no_prefixes_match_nothing() {
+        let trie = PrefixTrie::new(&[]);
+        assert_eq!(trie.find("Sol"), None);
+        assert!(trie.find_all("Sol").is_empty());
+    }
+
+    #[test]
+    fn large_set_matches_naive() {
+        let mut rng = ChaCha20Rng::seed_from_u64(100_000);
+        let prefixes = sorted((0..10_000).map(|_| random_string(&mut rng, 1..5, 58)).collect());
+        let trie = PrefixTrie::new(&prefixes);
+        for _ in 0..2_000 {
+            let address = random_string(&mut rng, 32..45, 58);
+            assert_eq!(trie.find_all(&address), naive_find_all(&prefixes, &address));
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! 线程数调优
+//!
+//! 在超线程和大小核 CPU 上，用满所有逻辑核心不一定最快。`--tune` 在开始搜索前
+//! 用几个候选线程数各跑一小段计时测试（生成密钥对并编码地址，与真实的搜索循环相同），
+//! 取速率最高的一个。结果按 CPU 型号保存在缓存文件中，`--tune cached` 直接复用。
+//!
+//! 没有用 `--threads` 指定线程数、搜索又有明确的终点（`--max-matches` 或 `--per-prefix`）时，
+//! [`scaled_threads`] 按期望尝试次数缩减线程数：只需几十次尝试的简单前缀启动几十个线程，
+//! 建线程、初始化随机数生成器的时间比搜索本身还长。每个线程至少分到
+//! [`ATTEMPTS_PER_THREAD`] 次期望尝试（单线程约零点几秒的工作量）才值得启动，
+//! 难一些的前缀照常用满所有线程。
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use solana_sdk::signer::Signer;
+
+use crate::keygen::{KeyGenerator, RngSource};
+
+/// 缓存文件相对于用户主目录的路径
+pub const DEFAULT_CACHE_RELATIVE_PATH: &str = ".solvanity/tune.json";
+
+/// 每个候选线程数的计时时长
+pub const TRIAL_DURATION: Duration = Duration::from_secs(2);
+
+/// 计时前的预热时长，让 CPU 频率稳定下来
+const WARMUP: Duration = Duration::from_millis(300);
+
+/// 值得多启动一个线程的期望尝试次数
+pub const ATTEMPTS_PER_THREAD: f64 = 20_000.0;
+
+/// `--tune` 的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TuneMode {
+    /// 重新测试并更新缓存
+    Run,
+    /// 有这台机器的缓存结果时直接使用，否则重新测试
+    Cached,
+}
+
+/// 一个候选线程数的测试结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Trial {
+    pub threads: usize,
+    /// 每秒生成的地址数
+    pub rate: f64,
+}
+
+/// 缓存中一台机器的调优结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedTune {
+    pub threads: usize,
+    pub rate: f64,
+    pub measured_at: String,
+}
+
+/// 默认缓存路径，找不到用户主目录时返回 `None`
+pub fn default_cache_path() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|home| !home.is_empty())
+        .map(|home| PathBuf::from(home).join(DEFAULT_CACHE_RELATIVE_PATH))
+}
+
+/// 缓存的键：CPU 型号和逻辑核心数，换了机器或虚拟机的核心数变了都会重新测试
+pub fn machine_key(logical: usize) -> String {
+    format!("{} ({} 个逻辑核心)", cpu_model(), logical)
+}
+
+/// CPU 型号，依次尝试 Linux 的 `/proc/cpuinfo` 和 macOS 的 `sysctl`，都失败时为 `unknown`
+pub fn cpu_model() -> String {
+    let from_cpuinfo = std::fs::read_to_string("/proc/cpuinfo").ok().and_then(|info| {
+        info.lines()
+            .find(|line| line.starts_with("model name") || line.starts_with("Model"))
+            .and_then(|line| line.split_once(':'))
+            .map(|(_, model)| model.trim().to_string())
+    });
+    from_cpuinfo
+        .or_else(|| {
+            let output = std::process::Command::new("sysctl")
+                .args(["-n", "machdep.cpu.brand_string"])
+                .output()
+                .ok()?;
+            output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        })
+        .filter(|model| !model.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// 候选线程数：2 的幂、逻辑核心数的 1/2、2/3、3/4、5/6，以及逻辑核心数和它减一、减二
+///
+/// 几个分数覆盖大小核的常见配比，如 8 个大核（16 线程）加 16 个小核的 24 个逻辑核心中的 20。
+pub fn candidates(logical: usize) -> Vec<usize> {
+    let logical = logical.max(1);
+    let mut candidates: Vec<usize> = std::iter::successors(Some(1usize), |threads| threads.checked_mul(2))
+        .take_while(|threads| *threads <= logical)
+        .chain([logical / 2, logical * 2 / 3, logical * 3 / 4, logical * 5 / 6])
+        .chain([logical.saturating_sub(2), logical.saturating_sub(1), logical])
+        .filter(|threads| *threads > 0)
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+    candidates
+}
+
+/// 用 `threads` 个线程生成密钥对并编码地址，返回计时期间的速率（个/秒）
+pub fn measure(threads: usize, rng: RngSource, duration: Duration) -> f64 {
+    let measuring = AtomicBool::new(false);
+    let stop = AtomicBool::new(false);
+    let generated = AtomicU64::new(0);
+    let elapsed = std::thread::scope(|scope| {
+        for worker in 0..threads {
+            let (measuring, stop, generated) = (&measuring, &stop, &generated);
+            scope.spawn(move || {
+                let mut keygen = KeyGenerator::with_extra_entropy(rng, None, worker as u64);
+                let mut local = 0u64;
+                while !stop.load(Ordering::Relaxed) {
+                    for _ in 0..64 {
+                        std::hint::black_box(keygen.generate().pubkey().to_string());
+                    }
+                    if measuring.load(Ordering::Relaxed) {
+                        local += 64;
+                    }
+                }
+                generated.fetch_add(local, Ordering::Relaxed);
+            });
+        }
+        std::thread::sleep(WARMUP);
+        measuring.store(true, Ordering::Relaxed);
+        let start = Instant::now();
+        std::thread::sleep(duration);
+        stop.store(true, Ordering::Relaxed);
+        start.elapsed()
+    });
+    generated.load(Ordering::Relaxed) as f64 / elapsed.as_secs_f64()
+}
+
+/// 依次测试各个候选线程数，`report` 在每个结果出来后调用，返回全部结果
+pub fn run(candidates: &[usize], rng: RngSource, mut report: impl FnMut(&Trial)) -> Vec<Trial> {
+    candidates
+        .iter()
+        .map(|threads| {
+            let trial = Trial {
+                threads: *threads,
+                rate: measure(*threads, rng, TRIAL_DURATION),
+            };
+            report(&trial);
+            trial
+        })
+        .collect()
+}
+
+/// 按期望尝试次数缩减线程数：每个线程至少分到 [`ATTEMPTS_PER_THREAD`] 次，不超过 `available`，至少 1 个
+///
+/// 期望尝试次数不是有限的正数（没有终点或不可能匹配）时用满 `available`。
+pub fn scaled_threads(expected_attempts: f64, available: usize) -> usize {
+    let available = available.max(1);
+    if !expected_attempts.is_finite() || expected_attempts <= 0.0 {
+        return available;
+    }
+    let wanted = (expected_attempts / ATTEMPTS_PER_THREAD).ceil();
+    if wanted >= available as f64 { available } else { (wanted as usize).max(1) }
+}
+
+/// 速率最高的结果
+pub fn best(trials: &[Trial]) -> Option<Trial> {
+    trials.iter().copied().max_by(|a, b| a.rate.total_cmp(&b.rate))
+}
+
+/// 读取缓存，文件不存在时为空
+pub fn load_cache(path: impl AsRef<Path>) -> io::Result<BTreeMap<String, CachedTune>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(err) => Err(err),
+    }
+}
+
+/// 更新缓存中一台机器的结果，其他机器的结果保留
+pub fn save_cache(path: impl AsRef<Path>, key: &str, entry: CachedTune) -> io::Result<()> {
+    let path = path.as_ref();
+    let mut cache = load_cache(path).unwrap_or_default();
+    cache.insert(key.to_string(), entry);
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string_pretty(&cache).map_err(io::Error::other)?;
+    std::fs::write(path, json + "\n")
+}
@@ -0,0 +1,539 @@
+//! 上传结果到 S3 兼容的存储
+//!
+//! 在云主机上长时间运行时，实例可能随时被回收。`--s3-upload s3://bucket/prefix`
+//! 在后台线程里定期把匹配文件和摘要快照上传到对象存储，结束时再上传一次。
+//!
+//! 凭据和区域按 AWS 的惯例读取：先看环境变量 `AWS_ACCESS_KEY_ID`、`AWS_SECRET_ACCESS_KEY`、
+//! `AWS_SESSION_TOKEN`、`AWS_REGION`（或 `AWS_DEFAULT_REGION`），再看 `~/.aws/credentials` 和
+//! `~/.aws/config` 中 `AWS_PROFILE`（默认 `default`）一节。`AWS_ENDPOINT_URL_S3` 或
+//! `AWS_ENDPOINT_URL` 指定 MinIO、R2 等兼容服务的地址；统一使用路径风格的 URL
+//! （`<endpoint>/<bucket>/<key>`），请求按 SigV4 签名。
+//!
+//! 上传失败只打印警告并按退避重试，下个周期还会再试，永远不会中断搜索。凭据或权限错误
+//! （除 408、429 之外的 4xx 状态）重试也不会成功，不再退避重试。
+//!
+//! 本模块只负责上传；匹配文件含有明文私钥时是否允许上传由调用方决定，命令行程序默认拒绝。
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+
+use crate::offline;
+
+/// 默认的上传周期（秒）
+pub const DEFAULT_INTERVAL_SECS: u64 = 300;
+
+/// 未指定区域时使用的区域
+pub const DEFAULT_REGION: &str = "us-east-1";
+
+/// 单个文件每轮最多尝试的次数，两次尝试之间按 1、2、4 秒退避
+const ATTEMPTS: u32 = 4;
+
+/// 上传目标：桶名和对象键前缀
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Target {
+    pub bucket: String,
+    /// 对象键前缀，不含首尾的 `/`，可以为空
+    pub prefix: String,
+}
+
+impl S3Target {
+    /// 文件在桶中的对象键：`<prefix>/<run_id>/<文件名>`
+    pub fn key(&self, run_id: &str, file_name: &str) -> String {
+        if self.prefix.is_empty() {
+            format!("{}/{}", run_id, file_name)
+        } else {
+            format!("{}/{}/{}", self.prefix, run_id, file_name)
+        }
+    }
+}
+
+impl fmt::Display for S3Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.prefix.is_empty() {
+            write!(f, "s3://{}", self.bucket)
+        } else {
+            write!(f, "s3://{}/{}", self.bucket, self.prefix)
+        }
+    }
+}
+
+impl FromStr for S3Target {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let rest = value
+            .strip_prefix("s3://")
+            .ok_or_else(|| format!("无效的上传目标: {}（应为 s3://bucket/prefix）", value))?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(format!("上传目标 {} 缺少桶名", value));
+        }
+        Ok(Self {
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+        })
+    }
+}
+
+/// 访问凭据
+#[derive(Clone)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 连接对象存储所需的配置
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    pub credentials: Credentials,
+    pub region: String,
+    /// 服务地址，如 `https://s3.us-east-1.amazonaws.com`，不含末尾的 `/`
+    pub endpoint: String,
+}
+
+impl S3Config {
+    /// 按 AWS 的惯例从环境变量和 `~/.aws` 下的配置文件读取
+    pub fn from_env() -> Result<Self, String> {
+        let env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let profile = env("AWS_PROFILE").unwrap_or_else(|| "default".to_string());
+        let aws_dir = env("HOME").map(|home| Path::new(&home).join(".aws"));
+        let credentials_file = env("AWS_SHARED_CREDENTIALS_FILE")
+            .map(PathBuf::from)
+            .or_else(|| aws_dir.as_ref().map(|dir| dir.join("credentials")));
+        let config_file = env("AWS_CONFIG_FILE")
+            .map(PathBuf::from)
+            .or_else(|| aws_dir.as_ref().map(|dir| dir.join("config")));
+        // credentials 文件的节名就是配置名，config 文件中非默认配置的节名带 `profile ` 前缀
+        let from_credentials = credentials_file
+            .and_then(|path| read_ini_section(&path, &profile))
+            .unwrap_or_default();
+        let config_section = if profile == "default" {
+            profile.clone()
+        } else {
+            format!("profile {}", profile)
+        };
+        let from_config = config_file
+            .and_then(|path| read_ini_section(&path, &config_section))
+            .unwrap_or_default();
+        let setting = |env_name: &str, key: &str| {
+            env(env_name)
+                .or_else(|| from_credentials.get(key).cloned())
+                .or_else(|| from_config.get(key).cloned())
+        };
+
+        let (Some(access_key_id), Some(secret_access_key)) = (
+            setting("AWS_ACCESS_KEY_ID", "aws_access_key_id"),
+            setting("AWS_SECRET_ACCESS_KEY", "aws_secret_access_key"),
+        ) else {
+            return Err(format!(
+                "找不到 S3 凭据：请设置 AWS_ACCESS_KEY_ID 和 AWS_SECRET_ACCESS_KEY，或在 ~/.aws/credentials 的 [{}] 中配置",
+                profile
+            ));
+        };
+        let session_token = setting("AWS_SESSION_TOKEN", "aws_session_token");
+        let region = env("AWS_REGION")
+            .or_else(|| env("AWS_DEFAULT_REGION"))
+            .or_else(|| from_config.get("region").cloned())
+            .unwrap_or_else(|| DEFAULT_REGION.to_string());
+        let endpoint = env("AWS_ENDPOINT_URL_S3")
+            .or_else(|| env("AWS_ENDPOINT_URL"))
+            .or_else(|| from_config.get("endpoint_url").cloned())
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+        Ok(Self {
+            credentials: Credentials {
+                access_key_id,
+                secret_access_key,
+                session_token,
+            },
+            region,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+/// 读取 INI 文件中一节的键值，文件或节不存在时为 `None`
+fn read_ini_section(path: &Path, section: &str) -> Option<HashMap<String, String>> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let mut values = None;
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            if values.is_some() {
+                break;
+            }
+            if name.trim() == section {
+                values = Some(HashMap::new());
+            }
+        } else if let Some(values) = values.as_mut()
+            && let Some((key, value)) = line.split_once('=')
+        {
+            values.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    values
+}
+
+/// 一个已签名的 PUT 请求
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedRequest {
+    pub url: String,
+    /// 除 Host 之外需要设置的请求头，Host 由 HTTP 客户端按 URL 填写
+    pub headers: Vec<(String, String)>,
+}
+
+/// 按 SigV4 为上传对象的 PUT 请求签名，`amz_date` 形如 `20130524T000000Z`
+pub fn sign_put(config: &S3Config, bucket: &str, key: &str, body: &[u8], amz_date: &str) -> SignedRequest {
+    let (scheme, rest) = config.endpoint.split_once("://").unwrap_or(("https", &config.endpoint));
+    let (authority, base_path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    // HTTP 客户端发送的 Host 不带默认端口，签名时必须一致
+    let default_port = if scheme == "http" { ":80" } else { ":443" };
+    let host = authority.strip_suffix(default_port).unwrap_or(authority);
+    let path = format!(
+        "{}/{}/{}",
+        base_path.trim_end_matches('/'),
+        uri_encode(bucket, false),
+        uri_encode(key, true)
+    );
+
+    let payload_hash = hex(&Sha256::digest(body));
+    let date = &amz_date[..8];
+    let mut headers = vec![
+        ("host".to_string(), host.to_string()),
+        ("x-amz-content-sha256".to_string(), payload_hash.clone()),
+        ("x-amz-date".to_string(), amz_date.to_string()),
+    ];
+    if let Some(token) = &config.credentials.session_token {
+        headers.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value)).collect();
+    let signed_headers = headers.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(";");
+    let canonical_request = format!("PUT\n{}\n\n{}\n{}\n{}", path, canonical_headers, signed_headers, payload_hash);
+
+    let scope = format!("{}/{}/s3/aws4_request", date, config.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let signing_key = ["aws4_request", "s3", config.region.as_str(), date]
+        .iter()
+        .rev()
+        .fold(
+            format!("AWS4{}", config.credentials.secret_access_key).into_bytes(),
+            |key, part| hmac_sha256(&key, part.as_bytes()).to_vec(),
+        );
+    let signature = hex(&hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+    headers.remove(0);
+    headers.push((
+        "authorization".to_string(),
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            config.credentials.access_key_id, scope, signed_headers, signature
+        ),
+    ));
+    SignedRequest {
+        url: format!("{}://{}{}", scheme, authority, path),
+        headers,
+    }
+}
+
+/// HMAC-SHA256
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK: usize = 64;
+    let mut block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.map(|b| b ^ byte);
+    let inner = Sha256::new().chain_update(pad(0x36)).chain_update(message).finalize();
+    Sha256::new().chain_update(pad(0x5c)).chain_update(inner).finalize().into()
+}
+
+/// SigV4 的 URI 编码：只保留非保留字符，对象键中的 `/` 按需保留
+fn uri_encode(value: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// 上传失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PutError {
+    /// 服务返回的错误状态和响应内容
+    Status(u16, String),
+    /// 连接失败、超时或离线模式
+    Other(String),
+}
+
+impl PutError {
+    /// 服务端错误、超时和限流值得重试，其余 4xx（如签名或权限错误）重试也不会成功
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            PutError::Status(code, _) => *code >= 500 || *code == 408 || *code == 429,
+            PutError::Other(_) => true,
+        }
+    }
+}
+
+impl fmt::Display for PutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PutError::Status(code, body) => write!(f, "HTTP {}: {}", code, body),
+            PutError::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+/// 上传一个对象
+pub fn put_object(config: &S3Config, bucket: &str, key: &str, body: &[u8]) -> Result<(), PutError> {
+    offline::ensure_online("上传到 S3").map_err(PutError::Other)?;
+    let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let request = sign_put(config, bucket, key, body, &amz_date);
+    let mut put = ureq::put(&request.url).timeout(Duration::from_secs(60));
+    for (name, value) in &request.headers {
+        put = put.set(name, value);
+    }
+    match put.send_bytes(body) {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, response)) => {
+            let body = response.into_string().unwrap_or_default();
+            Err(PutError::Status(code, body.trim().to_string()))
+        }
+        Err(err) => Err(PutError::Other(err.to_string())),
+    }
+}
+
+/// 后台上传线程
+///
+/// 每个周期检查各文件，内容变化了才上传；不存在的文件（如尚未写出的摘要）跳过。
+/// 调用 [`Uploader::finish`] 时立即做最后一轮上传并等待结束。
+pub struct Uploader {
+    shutdown: Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl Uploader {
+    pub fn spawn(config: S3Config, target: S3Target, run_id: String, files: Vec<PathBuf>, interval: Duration) -> Self {
+        let (shutdown, receiver) = mpsc::channel();
+        let handle = thread::spawn(move || run(&config, &target, &run_id, &files, interval, receiver));
+        Self { shutdown, handle }
+    }
+
+    /// 做最后一轮上传并等待线程结束
+    pub fn finish(self) {
+        let _ = self.shutdown.send(());
+        let _ = self.handle.join();
+    }
+}
+
+fn run(config: &S3Config, target: &S3Target, run_id: &str, files: &[PathBuf], interval: Duration, shutdown: Receiver<()>) {
+    // 每个文件最后一次成功上传的内容哈希
+    let mut uploaded: HashMap<&Path, [u8; 32]> = HashMap::new();
+    loop {
+        let last = !matches!(shutdown.recv_timeout(interval), Err(RecvTimeoutError::Timeout));
+        for path in files {
+            let Ok(body) = std::fs::read(path) else {
+                continue;
+            };
+            let hash: [u8; 32] = Sha256::digest(&body).into();
+            if uploaded.get(path.as_path()) == Some(&hash) {
+                continue;
+            }
+            let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+            let key = target.key(run_id, &file_name);
+            match upload_with_retry(config, &target.bucket, &key, &body) {
+                Ok(()) => {
+                    uploaded.insert(path, hash);
+                    if last {
+                        eprintln!("已上传 {} 到 s3://{}/{}", path.display(), target.bucket, key);
+                    }
+                }
+                Err(err) => eprintln!(
+                    "警告：上传 {} 到 s3://{}/{} 失败: {}{}",
+                    path.display(),
+                    target.bucket,
+                    key,
+                    err,
+                    if last { "" } else { "（下个周期重试）" }
+                ),
+            }
+        }
+        if last {
+            break;
+        }
+    }
+}
+
+fn upload_with_retry(config: &S3Config, bucket: &str, key: &str, body: &[u8]) -> Result<(), PutError> {
+    let mut attempt = 0;
+    loop {
+        match put_object(config, bucket, key, body) {
+            Ok(()) => return Ok(()),
+            Err(err) if attempt + 1 >= ATTEMPTS || !err.is_retryable() || offline::is_offline() => return Err(err),
+            Err(_) => {
+                thread::sleep(Duration::from_secs(1 << attempt));
+                attempt += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    use super::*;
+
+    /// 模拟服务器收到的一个请求
+    #[derive(Debug)]
+    struct Request {
+        line: String,
+        headers: HashMap<String, String>,
+        body: Vec<u8>,
+    }
+
+    /// 在本机起一个模拟的对象存储，依次用 `statuses` 回复各个请求，收到的请求从通道取出
+    fn serve(statuses: &[&'static str]) -> (S3Config, Receiver<Request>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let (requests, received) = mpsc::channel();
+        let statuses = statuses.to_vec();
+        thread::spawn(move || {
+            for status in statuses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut headers = HashMap::new();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    match header.trim_end().split_once(':') {
+                        Some((name, value)) => headers.insert(name.to_ascii_lowercase(), value.trim().to_string()),
+                        None => break,
+                    };
+                }
+                let length = headers.get("content-length").map_or(0, |length| length.parse().unwrap());
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                // 先记下请求再回复，客户端收到回复时请求一定已经可以取出
+                requests.send(Request { line: line.trim_end().to_string(), headers, body }).unwrap();
+                let reason = status.split_once(' ').map_or("", |(_, reason)| reason);
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    reason.len(),
+                    reason
+                )
+                .unwrap();
+            }
+        });
+        let config = S3Config {
+            credentials: Credentials {
+                access_key_id: "AKIDEXAMPLE".to_string(),
+                secret_access_key: "secret".to_string(),
+                session_token: None,
+            },
+            region: DEFAULT_REGION.to_string(),
+            endpoint,
+        };
+        (config, received)
+    }
+
+    #[test]
+    fn put_sends_the_signed_body() {
+        let (config, requests) = serve(&["200 OK"]);
+        put_object(&config, "bucket", "runs/r1/matched.csv", b"address\n").unwrap();
+        let request = requests.recv().unwrap();
+        assert_eq!(request.line, "PUT /bucket/runs/r1/matched.csv HTTP/1.1");
+        assert_eq!(request.body, b"address\n");
+        assert_eq!(request.headers["x-amz-content-sha256"], hex(&Sha256::digest(b"address\n")));
+        assert!(
+            request.headers["authorization"].starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"),
+            "{:?}",
+            request.headers
+        );
+        assert!(request.headers["authorization"].contains("SignedHeaders=host;x-amz-content-sha256;x-amz-date,"));
+    }
+
+    #[test]
+    fn server_errors_are_retried() {
+        let (config, requests) = serve(&["503 Slow Down", "200 OK"]);
+        upload_with_retry(&config, "bucket", "key", b"body").unwrap();
+        assert_eq!(requests.try_iter().count(), 2);
+    }
+
+    #[test]
+    fn auth_failures_are_not_retried() {
+        // 第二个请求会成功，如果重试了结果就是 Ok
+        let (config, requests) = serve(&["403 Forbidden", "200 OK"]);
+        let err = upload_with_retry(&config, "bucket", "key", b"body").unwrap_err();
+        assert_eq!(err, PutError::Status(403, "Forbidden".to_string()));
+        assert_eq!(err.to_string(), "HTTP 403: Forbidden");
+        assert_eq!(requests.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn uploader_skips_missing_and_unchanged_files() {
+        let dir = std::env::temp_dir().join(format!("sva-upload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let matched = dir.join("matched.csv");
+        std::fs::write(&matched, "address\n").unwrap();
+        let (config, requests) = serve(&["200 OK", "200 OK"]);
+        let target: S3Target = "s3://bucket/runs".parse().unwrap();
+        let files = vec![matched, dir.join("report.json")];
+        let uploader = Uploader::spawn(config, target, "r1".to_string(), files, Duration::from_millis(50));
+        thread::sleep(Duration::from_millis(300));
+        uploader.finish();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let requests: Vec<Request> = requests.try_iter().collect();
+        assert_eq!(requests.len(), 1, "{:?}", requests);
+        assert_eq!(requests[0].line, "PUT /bucket/runs/r1/matched.csv HTTP/1.1");
+    }
+
+    #[test]
+    fn targets_and_keys() {
+        let target: S3Target = "s3://bucket/a/b/".parse().unwrap();
+        assert_eq!(target.key("r1", "m.csv"), "a/b/r1/m.csv");
+        assert_eq!(target.to_string(), "s3://bucket/a/b");
+        let target: S3Target = "s3://bucket".parse().unwrap();
+        assert_eq!(target.key("r1", "m.csv"), "r1/m.csv");
+        assert!("s3:///x".parse::<S3Target>().is_err());
+        assert!("https://bucket".parse::<S3Target>().is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! 精确地址观察名单
+//!
+//! 常有人问：生成的地址会不会恰好撞上别人的地址？`--watch-list` 加载一个完整地址的列表，
+//! 生成的每个公钥都与之精确比较（按 32 字节公钥查哈希表，不需要 Base58 编码，开销可以忽略），
+//! 命中时单独报告。同时给出诚实的期望时间：Ed25519 公钥约有 2²⁵² 种，
+//! 即使名单有上百万个地址、每秒生成上亿个，期望时间也远远超过宇宙的年龄。
+//!
+//! 同一个名单也可以用 `verify --watch-list` 检查已保存的结果文件中有没有名单上的地址。
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::blacklist::load_pubkeys;
+use crate::integrity;
+
+/// Ed25519 主子群的阶约为 2²⁵²，也就是可能生成的不同公钥的数量
+pub const PUBKEY_SPACE_LOG2: i32 = 252;
+
+/// 一年的秒数（按 365.25 天计）
+const SECS_PER_YEAR: f64 = 365.25 * 24.0 * 3600.0;
+
+/// 宇宙年龄约 138 亿年
+pub const UNIVERSE_AGE_YEARS: f64 = 1.38e10;
+
+/// 从文件加载的观察名单
+#[derive(Debug, Default)]
+pub struct WatchList {
+    pubkeys: HashSet<[u8; 32]>,
+}
+
+impl WatchList {
+    /// 加载观察名单文件，格式与黑名单相同：每行一个 Base58 地址，忽略空行和 `#` 开头的注释
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        Ok(Self {
+            pubkeys: load_pubkeys(path.as_ref(), "观察名单")?,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.pubkeys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pubkeys.is_empty()
+    }
+
+    /// 公钥是否在名单中
+    pub fn contains(&self, pubkey: &[u8; 32]) -> bool {
+        self.pubkeys.contains(pubkey)
+    }
+
+    /// 命中名单中任意一个地址的期望尝试次数
+    pub fn expected_attempts(&self) -> f64 {
+        2f64.powi(PUBKEY_SPACE_LOG2) / self.pubkeys.len().max(1) as f64
+    }
+
+    /// 按给定速率（个/秒）命中一次的期望年数
+    pub fn expected_years(&self, rate: f64) -> f64 {
+        self.expected_attempts() / rate / SECS_PER_YEAR
+    }
+
+    /// 检查一个结果文件，返回名单上的地址及其行号（标题为第 1 行）
+    pub fn scan_file(&self, path: impl AsRef<Path>) -> Result<Vec<(usize, String)>, String> {
+        let path = path.as_ref();
+        let read_error = |err: &dyn std::fmt::Display| format!("无法读取 {}: {}", path.display(), err);
+        let mut records = integrity::open_table(path).map_err(|err| read_error(&err))?;
+        let columns = match records.next() {
+            Some(record) => record.map_err(|err| read_error(&err))?,
+            None => return Ok(Vec::new()),
+        };
+        let address_col = columns
+            .iter()
+            .position(|column| column == "address")
+            .ok_or_else(|| format!("{} 的标题中缺少 address 列", path.display()))?;
+        let mut hits = Vec::new();
+        for (index, record) in records.enumerate() {
+            let record = record.map_err(|err| read_error(&err))?;
+            let Some(address) = record.get(address_col) else {
+                continue;
+            };
+            let mut pubkey = [0u8; 32];
+            if matches!(bs58::decode(address).into(&mut pubkey), Ok(32)) && self.contains(&pubkey) {
+                hits.push((index + 2, address.to_string()));
+            }
+        }
+        Ok(hits)
+    }
+}
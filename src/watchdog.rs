@@ -0,0 +1,338 @@
+//! 工作线程心跳看门狗（`--stall-timeout`、`--restart-stalled-workers`）
+//!
+//! 工作线程 panic 或死锁后不再合并计数，进度条停在原地，看起来像是还在运行。
+//! 每个工作线程有自己的心跳格 [`Heartbeats`]，每合并一批计数跳一次；进度线程每次刷新时
+//! 把心跳交给 [`HeartbeatMonitor::observe`]，某个线程连续超过设定时长没有心跳，
+//! 或者已经 panic，就报告是哪一个，由调用方打印错误并以非零退出码结束。
+//!
+//! 工作线程的主体包在 `catch_unwind` 里，一个线程 panic 只记在它自己的心跳格上，
+//! 不会把整个线程池带崩。指定 `--restart-stalled-workers` 时，panic 的线程在原地用全新的
+//! 状态重新开始，每个线程最多 [`MAX_RESTARTS`] 次；卡住而没有 panic 的线程无法从外部终止，
+//! 仍然按卡住处理。暂停期间心跳本来就不动，此时调用 [`HeartbeatMonitor::reset`] 重新计时。
+//! 时间由调用方传入，便于在不真正等待的情况下验证。
+
+use std::any::Any;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// 默认超时秒数
+pub const DEFAULT_STALL_TIMEOUT_SECS: u64 = 120;
+
+/// 请求停止后等待工作线程自行结束的时长，超过后直接退出进程
+pub const EXIT_GRACE: Duration = Duration::from_secs(10);
+
+/// `--restart-stalled-workers` 时每个工作线程最多重新开始的次数，超过后按卡住处理
+pub const MAX_RESTARTS: u32 = 3;
+
+/// 工作线程的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkerState {
+    Running,
+    /// 搜索结束后正常退出，不再检查
+    Exited,
+    /// panic 后没有重新开始
+    Panicked,
+}
+
+impl WorkerState {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => WorkerState::Exited,
+            2 => WorkerState::Panicked,
+            _ => WorkerState::Running,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            WorkerState::Running => 0,
+            WorkerState::Exited => 1,
+            WorkerState::Panicked => 2,
+        }
+    }
+}
+
+/// 一个工作线程的心跳，独占一个缓存行，各线程更新时不会互相争抢
+#[derive(Debug, Default)]
+#[repr(align(128))]
+struct Heartbeat {
+    /// 已完成的批数
+    batches: AtomicU64,
+    /// 已生成的密钥数
+    generated: AtomicU64,
+    state: AtomicU8,
+}
+
+/// 各工作线程的心跳，按线程序号索引，工作线程和进度线程共享
+#[derive(Debug)]
+pub struct Heartbeats {
+    cells: Vec<Heartbeat>,
+    /// 每个线程最近一次 panic 的信息和已重新开始的次数
+    panics: Mutex<Vec<(Option<String>, u32)>>,
+}
+
+impl Heartbeats {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            cells: (0..workers).map(|_| Heartbeat::default()).collect(),
+            panics: Mutex::new(vec![(None, 0); workers]),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// 第 `worker` 个线程完成了一批
+    pub fn beat(&self, worker: usize, generated: u64) {
+        let cell = &self.cells[worker];
+        cell.generated.fetch_add(generated, Ordering::Relaxed);
+        cell.batches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 第 `worker` 个线程已完成的批数和生成数
+    pub fn progress(&self, worker: usize) -> (u64, u64) {
+        let cell = &self.cells[worker];
+        (cell.batches.load(Ordering::Relaxed), cell.generated.load(Ordering::Relaxed))
+    }
+
+    pub fn state(&self, worker: usize) -> WorkerState {
+        WorkerState::from_u8(self.cells[worker].state.load(Ordering::Relaxed))
+    }
+
+    /// 搜索结束后正常退出
+    pub fn exited(&self, worker: usize) {
+        self.set_state(worker, WorkerState::Exited);
+    }
+
+    /// 记录一次 panic；`restart` 时还没用完重启次数就返回 `true`，调用方据此重新开始，
+    /// 否则把线程标记为 panic，由 [`HeartbeatMonitor`] 报告
+    pub fn panicked(&self, worker: usize, message: String, restart: bool) -> bool {
+        let mut panics = self.panics.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let (last, restarts) = &mut panics[worker];
+        *last = Some(message);
+        if restart && *restarts < MAX_RESTARTS {
+            *restarts += 1;
+            return true;
+        }
+        self.set_state(worker, WorkerState::Panicked);
+        false
+    }
+
+    /// 第 `worker` 个线程最近一次 panic 的信息和已重新开始的次数
+    pub fn panic_info(&self, worker: usize) -> (Option<String>, u32) {
+        self.panics.lock().unwrap_or_else(|poisoned| poisoned.into_inner())[worker].clone()
+    }
+
+    /// 所有线程重新开始的总次数
+    pub fn total_restarts(&self) -> u32 {
+        let panics = self.panics.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        panics.iter().map(|(_, restarts)| restarts).sum()
+    }
+
+    fn set_state(&self, worker: usize, state: WorkerState) {
+        self.cells[worker].state.store(state.to_u8(), Ordering::Relaxed);
+    }
+}
+
+/// 从 panic 的载荷中取出信息，`panic!` 的参数不是字符串时给出一个占位说明
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "（panic 信息不是字符串）".to_string())
+}
+
+/// 一个卡住的工作线程
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StalledWorker {
+    pub worker: usize,
+    /// 已完成的批数和生成数
+    pub batches: u64,
+    pub generated: u64,
+    /// 距上次心跳的时长
+    pub stalled: Duration,
+    /// panic 的信息，卡住而没有 panic 时为 `None`
+    pub panic: Option<String>,
+    /// 已重新开始的次数
+    pub restarts: u32,
+}
+
+/// 在进度线程中检查心跳：记下每个线程上次心跳变化的时间
+#[derive(Debug, Clone)]
+pub struct HeartbeatMonitor {
+    /// 心跳多久没有变化就算卡住，`None` 时只报告 panic
+    timeout: Option<Duration>,
+    last: Vec<(u64, Instant)>,
+}
+
+impl HeartbeatMonitor {
+    pub fn new(timeout: Option<Duration>, workers: usize, now: Instant) -> Self {
+        Self {
+            timeout,
+            last: vec![(0, now); workers],
+        }
+    }
+
+    /// 暂停时调用：所有线程从 `now` 起重新计时
+    pub fn reset(&mut self, now: Instant) {
+        for (_, since) in &mut self.last {
+            *since = now;
+        }
+    }
+
+    /// 检查一次心跳，返回已经 panic 或超过时长没有心跳的线程，已正常退出的线程不算
+    pub fn observe(&mut self, heartbeats: &Heartbeats, now: Instant) -> Vec<StalledWorker> {
+        let mut stalled = Vec::new();
+        for (worker, (last_batches, since)) in self.last.iter_mut().enumerate() {
+            let (batches, generated) = heartbeats.progress(worker);
+            if batches != *last_batches {
+                *last_batches = batches;
+                *since = now;
+            }
+            let elapsed = now.saturating_duration_since(*since);
+            let is_stalled = match heartbeats.state(worker) {
+                WorkerState::Exited => false,
+                WorkerState::Panicked => true,
+                WorkerState::Running => self.timeout.is_some_and(|timeout| elapsed >= timeout),
+            };
+            if is_stalled {
+                let (panic, restarts) = heartbeats.panic_info(worker);
+                stalled.push(StalledWorker {
+                    worker,
+                    batches,
+                    generated,
+                    stalled: elapsed,
+                    panic: panic.filter(|_| heartbeats.state(worker) == WorkerState::Panicked),
+                    restarts,
+                });
+            }
+        }
+        stalled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(120);
+
+    fn secs(start: Instant, secs: u64) -> Instant {
+        start + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn progressing_workers_are_never_reported() {
+        let heartbeats = Heartbeats::new(4);
+        let start = Instant::now();
+        let mut monitor = HeartbeatMonitor::new(Some(TIMEOUT), 4, start);
+        for second in (0..3600).step_by(30) {
+            for worker in 0..4 {
+                heartbeats.beat(worker, 1000);
+            }
+            assert!(monitor.observe(&heartbeats, secs(start, second)).is_empty());
+        }
+    }
+
+    #[test]
+    fn simulated_stall_is_reported_after_the_timeout() {
+        let heartbeats = Heartbeats::new(3);
+        let start = Instant::now();
+        let mut monitor = HeartbeatMonitor::new(Some(TIMEOUT), 3, start);
+        // 第 1 个线程在 60 秒后停止心跳，其余继续
+        for second in (0..=60).step_by(10) {
+            (0..3).for_each(|worker| heartbeats.beat(worker, 100));
+            assert!(monitor.observe(&heartbeats, secs(start, second)).is_empty());
+        }
+        for second in (70..180).step_by(10) {
+            heartbeats.beat(0, 100);
+            heartbeats.beat(2, 100);
+            assert!(monitor.observe(&heartbeats, secs(start, second)).is_empty(), "{} 秒", second);
+        }
+        let stalled = monitor.observe(&heartbeats, secs(start, 180));
+        assert_eq!(
+            stalled,
+            vec![StalledWorker {
+                worker: 1,
+                batches: 7,
+                generated: 700,
+                stalled: Duration::from_secs(120),
+                panic: None,
+                restarts: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn all_workers_stalled_are_all_reported() {
+        let heartbeats = Heartbeats::new(2);
+        let start = Instant::now();
+        let mut monitor = HeartbeatMonitor::new(Some(TIMEOUT), 2, start);
+        let stalled = monitor.observe(&heartbeats, secs(start, 121));
+        assert_eq!(stalled.iter().map(|worker| worker.worker).collect::<Vec<_>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn exited_workers_are_not_stalled() {
+        let heartbeats = Heartbeats::new(2);
+        let start = Instant::now();
+        let mut monitor = HeartbeatMonitor::new(Some(TIMEOUT), 2, start);
+        heartbeats.exited(0);
+        heartbeats.exited(1);
+        assert!(monitor.observe(&heartbeats, secs(start, 1000)).is_empty());
+    }
+
+    #[test]
+    fn pause_resets_the_timer() {
+        let heartbeats = Heartbeats::new(1);
+        let start = Instant::now();
+        let mut monitor = HeartbeatMonitor::new(Some(TIMEOUT), 1, start);
+        // 暂停了 10 分钟，恢复后重新计时
+        monitor.reset(secs(start, 600));
+        assert!(monitor.observe(&heartbeats, secs(start, 700)).is_empty());
+        assert_eq!(monitor.observe(&heartbeats, secs(start, 720)).len(), 1);
+    }
+
+    #[test]
+    fn panics_are_reported_immediately_even_without_a_timeout() {
+        let heartbeats = Heartbeats::new(2);
+        let start = Instant::now();
+        let mut monitor = HeartbeatMonitor::new(None, 2, start);
+        assert!(monitor.observe(&heartbeats, secs(start, 10_000)).is_empty());
+        assert!(!heartbeats.panicked(1, "boom".to_string(), false));
+        let stalled = monitor.observe(&heartbeats, secs(start, 10_001));
+        assert_eq!(stalled.len(), 1);
+        assert_eq!(stalled[0].worker, 1);
+        assert_eq!(stalled[0].panic.as_deref(), Some("boom"));
+    }
+
+    #[test]
+    fn restarts_are_limited() {
+        let heartbeats = Heartbeats::new(1);
+        for restart in 1..=MAX_RESTARTS {
+            assert!(heartbeats.panicked(0, format!("panic {}", restart), true));
+            assert_eq!(heartbeats.state(0), WorkerState::Running);
+        }
+        assert!(!heartbeats.panicked(0, "last".to_string(), true));
+        assert_eq!(heartbeats.state(0), WorkerState::Panicked);
+        assert_eq!(heartbeats.panic_info(0), (Some("last".to_string()), MAX_RESTARTS));
+        assert_eq!(heartbeats.total_restarts(), MAX_RESTARTS);
+    }
+
+    #[test]
+    fn panic_messages_are_extracted() {
+        let payload = std::panic::catch_unwind(|| panic!("static")).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "static");
+        let payload = std::panic::catch_unwind(|| panic!("worker {}", 3)).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "worker 3");
+        let payload = std::panic::catch_unwind(|| std::panic::panic_any(42)).unwrap_err();
+        assert!(panic_message(payload.as_ref()).contains("不是字符串"));
+    }
+}
@@ -0,0 +1,110 @@
+//! 匹配通知
+//!
+//! `--webhook-url` 在每次匹配时向该地址 POST 一个 JSON 对象 `{"address": ..., "prefix": ...}`，
+//! 用于把“找到了”推送到聊天工具或内部服务。请求体只有公开的地址和前缀，永远不含私钥。
+//! 与匹配钩子一样在写入线程里逐个发送，失败只打印警告，不影响搜索。
+
+use std::time::Duration;
+
+use crate::offline;
+
+/// 单次请求的超时
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 检查地址的形式，只接受 http 和 https
+pub fn parse_url(url: &str) -> Result<String, String> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .ok_or_else(|| format!("webhook 地址必须以 http:// 或 https:// 开头: {}", url))?;
+    if rest.is_empty() || rest.starts_with('/') {
+        return Err(format!("webhook 地址缺少主机名: {}", url));
+    }
+    Ok(url.to_string())
+}
+
+/// 请求体
+pub fn payload(address: &str, prefix: &str) -> String {
+    serde_json::json!({ "address": address, "prefix": prefix }).to_string()
+}
+
+/// 发送一次通知，失败时返回错误说明
+pub fn notify(url: &str, address: &str, prefix: &str) -> Result<(), String> {
+    offline::ensure_online("发送 webhook 通知")?;
+    let request = ureq::post(url).timeout(TIMEOUT).set("Content-Type", "application/json");
+    match request.send_string(&payload(address, prefix)) {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, response)) => {
+            let body = response.into_string().unwrap_or_default();
+            Err(format!("HTTP {}: {}", code, body.trim()))
+        }
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+
+    /// 在本机起一个只处理一个请求的 HTTP 服务器，返回地址和收到的请求（请求行、请求头、请求体）
+    fn serve_once(status: &'static str) -> (String, thread::JoinHandle<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut head = String::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                if line == "\r\n" {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let mut stream = stream;
+            write!(stream, "HTTP/1.1 {}\r\nContent-Length: 4\r\nConnection: close\r\n\r\nnope", status).unwrap();
+            (head, String::from_utf8(body).unwrap())
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn posts_address_and_prefix_only() {
+        let (url, server) = serve_once("200 OK");
+        notify(&url, "So1abc", "So1").unwrap();
+        let (head, body) = server.join().unwrap();
+        assert!(head.starts_with("POST /hook HTTP/1.1\r\n"), "{}", head);
+        assert!(head.to_ascii_lowercase().contains("content-type: application/json"));
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json, serde_json::json!({ "address": "So1abc", "prefix": "So1" }));
+    }
+
+    #[test]
+    fn error_status_is_reported() {
+        let (url, server) = serve_once("500 Internal Server Error");
+        let err = notify(&url, "So1abc", "So1").unwrap_err();
+        assert_eq!(err, "HTTP 500: nope");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn urls_are_checked() {
+        assert_eq!(parse_url("https://example.com/x").unwrap(), "https://example.com/x");
+        assert!(parse_url("http://127.0.0.1:8080").is_ok());
+        assert!(parse_url("ftp://example.com").is_err());
+        assert!(parse_url("example.com").is_err());
+        assert!(parse_url("https://").is_err());
+        assert!(parse_url("https:///path").is_err());
+    }
+}
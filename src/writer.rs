@@ -0,0 +1,685 @@
+//! 输出写入线程
+//!
+//! 工作线程只负责生成和匹配，把结果通过有界通道发给写入线程；
+//! 写文件、写密钥文件、执行匹配钩子都在写入线程里完成，不拖慢生成循环。
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use solana_sdk::pubkey::Pubkey;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::ata;
+use crate::char_count;
+use crate::disk_space::{self, DiskGuard};
+use crate::format::{self, Column, OutputFormat, RecordWriter, Schema, UTF8_BOM};
+use crate::integrity;
+#[cfg(feature = "keyring")]
+use crate::keyring;
+use crate::known_addresses::KnownMark;
+use crate::luck::Luck;
+use crate::manifest::MatchRecord;
+use crate::offline;
+use crate::score::{BeautyScore, Scorer, Threshold, TopN};
+use crate::secret::Secret;
+use crate::shamir::ShamirScheme;
+#[cfg(feature = "shm")]
+use crate::shm::ShmWriter;
+use crate::webhook;
+
+/// 通道容量，写入线程跟不上时工作线程会在发送处等待
+pub const CHANNEL_CAPACITY: usize = 1024;
+
+/// 非匹配地址文件的刷新周期
+const SAMPLE_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 一个待写入的地址记录
+pub struct Record {
+    pub address: String,
+    /// 匹配到的前缀，非匹配地址为空
+    pub prefix: String,
+    /// 64 字节密钥对：种子 || 公钥
+    pub keypair: [u8; 64],
+    /// 匹配的运气，非匹配地址为 `None`
+    pub luck: Option<Luck>,
+    /// 匹配的发现顺序，工作线程在交出匹配时从同一个计数器领取，非匹配地址为 `None`
+    pub index: Option<u64>,
+    /// 匹配的地址在 `--known-addresses` 快照中时的标记
+    pub known: Option<KnownMark>,
+}
+
+impl Record {
+    /// 地址对应的公钥
+    pub fn pubkey(&self) -> Pubkey {
+        Pubkey::new_from_array(self.keypair[32..].try_into().unwrap())
+    }
+
+    /// 64 字节密钥对的 Base58 编码，用完后自动清零
+    pub fn secret_key(&self) -> Secret {
+        Secret::new(bs58::encode(&self.keypair).into_string())
+    }
+
+    /// 32 字节种子的 Base58 编码，用完后自动清零
+    pub fn seed(&self) -> Secret {
+        Secret::new(bs58::encode(&self.keypair[..32]).into_string())
+    }
+}
+
+/// 记录写完丢弃时清零内存中的密钥对
+impl Drop for Record {
+    fn drop(&mut self) {
+        self.keypair.zeroize();
+    }
+}
+
+/// 发给写入线程的消息
+pub enum Message {
+    /// 匹配的地址
+    Matched(Record),
+    /// 非匹配的采样地址
+    Sample(Record),
+    /// 分数可能进入排行榜的地址
+    Ranked(Record, u32),
+}
+
+/// 排行榜选项
+#[derive(Debug, Clone)]
+pub struct RankOptions {
+    /// 保留的地址数量
+    pub top_n: usize,
+    /// 结束时写出排行榜的文件
+    pub path: PathBuf,
+    /// 入榜门槛，写入线程在榜满后更新
+    pub threshold: Arc<Threshold>,
+}
+
+/// 写入线程的选项
+#[derive(Debug, Clone, Default)]
+pub struct WriterOptions {
+    /// 匹配文件的格式和列
+    pub matched_schema: Schema,
+    /// 非匹配文件的格式和列
+    pub sample_schema: Schema,
+    /// 为每个匹配地址写一个 solana CLI 格式的密钥文件
+    pub keypair_dir: Option<PathBuf>,
+    /// 匹配时执行的命令模板
+    pub on_match: Option<String>,
+    /// 匹配时通知的 webhook 地址，见 [`crate::webhook`]
+    pub webhook_url: Option<String>,
+    /// 把匹配私钥拆分成 Shamir 分享写入该目录，此时不写明文私钥
+    pub shamir: Option<(ShamirScheme, PathBuf)>,
+    /// 保留分数最高的地址，结束时写出
+    pub rank: Option<RankOptions>,
+    /// 只保留美观度分数最高的这么多个匹配，结束时按分数从高到低写入，
+    /// 密钥文件、分享和匹配钩子也只针对保留下来的匹配
+    pub keep_best: Option<(usize, BeautyScore)>,
+    /// 同时把匹配写入这个共享内存环形缓冲区，以及它的槽位数
+    #[cfg(feature = "shm")]
+    pub shm: Option<(PathBuf, usize)>,
+    /// 每隔这么久把匹配文件改名为带时间戳的文件名并重新开始，见 [`rotated_path`]
+    pub rotate_every: Option<Duration>,
+    /// 定期检查剩余空间，不足时丢弃非匹配记录
+    pub disk_guard: Option<Arc<DiskGuard>>,
+    /// 匹配文件已存在时追加写入，标题必须与 `matched_schema` 一致
+    pub append: bool,
+    /// 把私钥列写入这个单独的文件及其格式和列（`--split-output`），此时 `matched_schema` 只含公开的列，
+    /// 见 [`Schema::split_secrets`]
+    pub split_output: Option<(PathBuf, Schema)>,
+    /// 匹配私钥只存入系统密钥存储，`secret_ref` 列写它的引用（`--output-append-secret-env`），
+    /// 见 [`Schema::with_secret_ref`]；没有启用 `keyring` 特性时总是 `false`
+    pub secret_store: bool,
+}
+
+/// 命令模板支持的占位符
+pub const HOOK_PLACEHOLDERS: [&str; 3] = ["{address}", "{prefix}", "{keypair_file}"];
+
+/// 检查命令模板，`{keypair_file}` 只能在指定了密钥目录时使用
+pub fn validate_hook(template: &str, keypair_dir: Option<&Path>) -> Result<(), String> {
+    if template.contains("{keypair_file}") && keypair_dir.is_none() {
+        return Err("--on-match 使用 {keypair_file} 时必须同时指定 --keypair-dir".to_string());
+    }
+    Ok(())
+}
+
+/// 写入线程持有的输出文件
+pub struct OutputWriter {
+    matched: RecordWriter<File>,
+    matched_path: PathBuf,
+    /// 当前这段匹配文件开始的时间和已写入的匹配数，用于 `rotate_every`
+    segment: (Instant, DateTime<Local>, usize),
+    /// 非匹配文件在第一个非匹配地址到来时才创建，不采样时不留下只有标题的文件
+    samples: Option<RecordWriter<File>>,
+    sample_path: PathBuf,
+    /// `split_output` 时的私钥文件，与匹配文件逐行对应
+    secrets: Option<RecordWriter<File>>,
+    options: WriterOptions,
+    top: Option<TopN<Record>>,
+    /// `keep_best` 时暂存的匹配及各自找到的时间
+    best: Option<TopN<(Record, String)>>,
+    #[cfg(feature = "shm")]
+    shm: Option<ShmWriter>,
+    output: WriterOutput,
+}
+
+/// 写入线程结束时交回的产物
+#[derive(Debug, Default)]
+pub struct WriterOutput {
+    /// 已写入的密钥文件、分享文件、排行榜文件和 `split_output` 的私钥文件
+    pub files: Vec<PathBuf>,
+    /// 按写入顺序的匹配及各自的文件
+    pub matches: Vec<MatchRecord>,
+    /// `rotate_every` 时已完成的匹配文件，按时间顺序，也包含在 `files` 中
+    pub rotated: Vec<PathBuf>,
+    /// 写入过非匹配地址时为非匹配文件，否则没有创建这个文件
+    pub sample_file: Option<PathBuf>,
+}
+
+impl OutputWriter {
+    /// 创建（截断）匹配文件并写入标题，必要时创建目录
+    ///
+    /// `append` 时已有内容的匹配文件改为追加，不再写标题，`split_output` 的私钥文件同样处理。
+    /// 非匹配文件推迟到第一个非匹配地址到来时才创建，见 [`WriterOutput::sample_file`]。
+    pub fn create(
+        matched_path: impl AsRef<Path>,
+        sample_path: impl AsRef<Path>,
+        options: WriterOptions,
+    ) -> io::Result<Self> {
+        let matched_path = matched_path.as_ref();
+        let append = options.append && std::fs::metadata(matched_path).is_ok_and(|metadata| metadata.len() > 0);
+        let matched_file = if append {
+            check_header(matched_path, &options.matched_schema)?;
+            OpenOptions::new().append(true).open(matched_path)?
+        } else {
+            create_file(matched_path)?
+        };
+        let mut matched = if append {
+            options.matched_schema.record_writer(matched_file)
+        } else {
+            options.matched_schema.header_writer(matched_file)?
+        };
+        matched.flush()?;
+        let secrets = match &options.split_output {
+            Some((path, schema)) => {
+                let append = options.append && std::fs::metadata(path).is_ok_and(|metadata| metadata.len() > 0);
+                let mut secrets = if append {
+                    check_header(path, schema)?;
+                    schema.record_writer(OpenOptions::new().append(true).open(path)?)
+                } else {
+                    schema.header_writer(create_secret_file(path)?)?
+                };
+                secrets.flush()?;
+                Some(secrets)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            matched,
+            matched_path: matched_path.to_path_buf(),
+            segment: (Instant::now(), Local::now(), 0),
+            samples: None,
+            sample_path: sample_path.as_ref().to_path_buf(),
+            secrets,
+            top: options.rank.as_ref().map(|rank| TopN::new(rank.top_n)),
+            best: options.keep_best.map(|(count, _)| TopN::new(count)),
+            #[cfg(feature = "shm")]
+            shm: match &options.shm {
+                Some((path, slots)) => Some(ShmWriter::create(path, *slots)?),
+                None => None,
+            },
+            options,
+            output: WriterOutput::default(),
+        })
+    }
+
+    /// 在新线程中运行，直到所有发送端都被丢弃，返回前刷新所有文件
+    pub fn spawn(self, receiver: Receiver<Message>) -> JoinHandle<io::Result<WriterOutput>> {
+        std::thread::spawn(move || self.run(receiver))
+    }
+
+    /// 处理消息直到通道关闭，返回写入过的文件和各个匹配的记录
+    pub fn run(mut self, receiver: Receiver<Message>) -> io::Result<WriterOutput> {
+        let mut last_flush = Instant::now();
+        let mut last_disk_check = Instant::now();
+        loop {
+            match receiver.recv_timeout(SAMPLE_FLUSH_INTERVAL) {
+                Ok(message) => self.handle(message)?,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if let Some(guard) = &self.options.disk_guard
+                && last_disk_check.elapsed() >= disk_space::CHECK_INTERVAL
+            {
+                guard.check();
+                last_disk_check = Instant::now();
+            }
+            if last_flush.elapsed() >= SAMPLE_FLUSH_INTERVAL
+                && let Some(samples) = self.samples.as_mut()
+            {
+                samples.flush()?;
+                last_flush = Instant::now();
+            }
+            // 每个匹配都在一次 write_match 中完整写入并刷新，只在两条消息之间轮换
+            if let Some(every) = self.options.rotate_every
+                && self.segment.0.elapsed() >= every
+            {
+                self.rotate()?;
+            }
+        }
+        if let Some(best) = self.best.take() {
+            for (_, (record, found_at)) in best.into_sorted() {
+                self.write_match(&record, found_at)?;
+            }
+        }
+        self.matched.flush()?;
+        if let (Some(secrets), Some((path, _))) = (self.secrets.as_mut(), &self.options.split_output) {
+            secrets.flush()?;
+            self.output.files.push(path.clone());
+        }
+        // 结束时最后一段也算完成
+        if self.options.rotate_every.is_some() {
+            self.rotate()?;
+        }
+        if let Some(samples) = self.samples.as_mut() {
+            samples.flush()?;
+            self.output.sample_file = Some(self.sample_path.clone());
+        }
+        if let (Some(top), Some(rank)) = (self.top, &self.options.rank) {
+            write_ranking(&rank.path, top)?;
+            self.output.files.push(rank.path.clone());
+        }
+        Ok(self.output)
+    }
+
+    fn handle(&mut self, message: Message) -> io::Result<()> {
+        match message {
+            Message::Matched(record) => {
+                let found_at = Local::now().to_rfc3339();
+                match (self.best.as_mut(), self.options.keep_best) {
+                    (Some(best), Some((_, weights))) => best.push(weights.score(&record.address), (record, found_at)),
+                    _ => self.write_match(&record, found_at)?,
+                }
+            }
+            Message::Sample(record) => match &self.options.disk_guard {
+                Some(guard) if guard.is_low() => guard.drop_sample(),
+                _ => {
+                    let samples = match self.samples.as_mut() {
+                        Some(samples) => samples,
+                        None => {
+                            let file = create_file(&self.sample_path)?;
+                            self.samples.insert(self.options.sample_schema.header_writer(file)?)
+                        }
+                    };
+                    write_record(samples, &self.options.sample_schema, &record, None, "", "")?
+                }
+            },
+            Message::Ranked(record, score) => {
+                if let (Some(top), Some(rank)) = (self.top.as_mut(), &self.options.rank) {
+                    top.push(score, record);
+                    if let Some(min) = top.min_score() {
+                        rank.threshold.set(min);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `secret_store` 时把匹配私钥存入系统密钥存储，返回 `secret_ref` 列的引用，否则返回空字符串
+    #[cfg(feature = "keyring")]
+    fn store_secret(&self, record: &Record) -> io::Result<String> {
+        if !self.options.secret_store {
+            return Ok(String::new());
+        }
+        let reference = keyring::new_reference();
+        keyring::store(&reference, &record.address, record.secret_key().expose())
+            .map_err(|err| io::Error::other(format!("无法把 {} 的私钥存入系统密钥存储: {}", record.address, err)))?;
+        Ok(reference)
+    }
+
+    #[cfg(not(feature = "keyring"))]
+    fn store_secret(&self, _record: &Record) -> io::Result<String> {
+        Ok(String::new())
+    }
+
+    /// 写入一个匹配：匹配文件、分享、密钥文件，然后执行匹配钩子
+    fn write_match(&mut self, record: &Record, found_at: String) -> io::Result<()> {
+        let mut files = Vec::new();
+        let scheme = match &self.options.shamir {
+            Some((scheme, dir)) => {
+                files.extend(write_shares(dir, *scheme, record)?);
+                Some(*scheme)
+            }
+            None => None,
+        };
+        // 私钥先存好，匹配文件中出现的地址在私钥文件或密钥存储中一定已经有了
+        let secret_ref = self.store_secret(record)?;
+        if let (Some(secrets), Some((_, schema))) = (self.secrets.as_mut(), &self.options.split_output) {
+            write_record(secrets, schema, record, None, &found_at, "")?;
+            secrets.flush()?;
+        }
+        write_record(&mut self.matched, &self.options.matched_schema, record, scheme, &found_at, &secret_ref)?;
+        self.matched.flush()?;
+        self.segment.2 += 1;
+        #[cfg(feature = "shm")]
+        if let Some(shm) = self.shm.as_mut() {
+            shm.push(&record.address, &record.prefix, &record.keypair);
+        }
+
+        let keypair_file = match &self.options.keypair_dir {
+            Some(dir) => {
+                let path = write_keypair_file(dir, record)?;
+                files.push(path.clone());
+                Some(path)
+            }
+            None => None,
+        };
+        if let Some(template) = &self.options.on_match {
+            run_hook(template, record, keypair_file.as_deref());
+        }
+        if let Some(url) = &self.options.webhook_url
+            && let Err(err) = webhook::notify(url, &record.address, &record.prefix)
+        {
+            eprintln!("webhook 通知失败: {}", err);
+        }
+        self.output.files.extend(files.iter().cloned());
+        self.output.matches.push(MatchRecord {
+            address: record.address.clone(),
+            prefix: record.prefix.clone(),
+            found_at,
+            files,
+        });
+        Ok(())
+    }
+
+    /// 把写过匹配的当前这段改名为带时间戳的文件名，在原路径重新创建只有标题的文件；
+    /// 这段没有匹配时不生成文件，只重新计时
+    fn rotate(&mut self) -> io::Result<()> {
+        let (_, started_at, count) = self.segment;
+        self.segment = (Instant::now(), Local::now(), 0);
+        if count == 0 {
+            return Ok(());
+        }
+        self.matched.flush()?;
+        let path = rotated_path(&self.matched_path, started_at);
+        std::fs::rename(&self.matched_path, &path)?;
+        self.matched = self.options.matched_schema.header_writer(create_file(&self.matched_path)?)?;
+        self.matched.flush()?;
+        self.output.files.push(path.clone());
+        self.output.rotated.push(path);
+        Ok(())
+    }
+}
+
+/// 轮换后的文件名：在扩展名前插入这段开始的本地时间，如
+/// `data/matched_addresses.csv` → `data/matched_addresses.20240101T120000.csv`
+///
+/// 同一秒内已有同名文件时（只可能在多次运行之间发生）再追加 `-1`、`-2`……
+pub fn rotated_path(path: &Path, started_at: DateTime<Local>) -> PathBuf {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = path.extension().map(|extension| format!(".{}", extension.to_string_lossy()));
+    let timestamp = started_at.format("%Y%m%dT%H%M%S");
+    let candidate = |suffix: String| {
+        path.with_file_name(format!("{}.{}{}{}", stem, timestamp, suffix, extension.as_deref().unwrap_or("")))
+    };
+    std::iter::once(String::new())
+        .chain((1..).map(|index| format!("-{}", index)))
+        .map(candidate)
+        .find(|candidate| !candidate.exists())
+        .unwrap()
+}
+
+/// 追加前检查已有文件的标题行与当前的格式和输出列一致，避免同一个文件里混着两种写法；
+/// ndjson 没有标题行，检查第一条记录的键
+fn check_header(path: &Path, schema: &Schema) -> io::Result<()> {
+    let mut existing = String::new();
+    BufReader::new(File::open(path)?).read_line(&mut existing)?;
+    if schema.format == OutputFormat::Ndjson {
+        let keys: Vec<String> = format::parse_ndjson_line(&existing)
+            .map_err(|err| io::Error::other(format!("{} 不是 ndjson 文件（{}），不能追加", path.display(), err)))?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        if keys != schema.header() {
+            return Err(io::Error::other(format!(
+                "{} 的列（{}）与当前的输出列（{}）不同，不能追加",
+                path.display(),
+                keys.join(","),
+                schema.header().join(",")
+            )));
+        }
+        return Ok(());
+    }
+    let expected = schema.header_writer(Vec::new())?.into_inner()?;
+    let expected = String::from_utf8_lossy(&expected);
+    if existing.starts_with(UTF8_BOM) != expected.starts_with(UTF8_BOM) {
+        return Err(io::Error::other(format!(
+            "{} 与当前输出一个是 --excel-compat 写法、一个不是，不能追加",
+            path.display()
+        )));
+    }
+    if existing.trim_end() != expected.trim_end() {
+        return Err(io::Error::other(format!(
+            "{} 的标题（{}）与当前的格式和输出列（{}）不同，不能追加",
+            path.display(),
+            existing.trim_end(),
+            expected.trim_end()
+        )));
+    }
+    Ok(())
+}
+
+fn create_file(path: &Path) -> io::Result<File> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+}
+
+/// 按输出列写入一行地址记录，`shamir` 列写门限方案，`found_at` 和 `secret_ref` 列写对应的参数
+fn write_record(
+    writer: &mut RecordWriter<impl Write>,
+    schema: &Schema,
+    record: &Record,
+    scheme: Option<ShamirScheme>,
+    found_at: &str,
+    secret_ref: &str,
+) -> io::Result<()> {
+    let row = schema.row(|column| match column {
+        Column::Address => record.address.clone(),
+        Column::PrivateKey | Column::Keypair64 => record.secret_key().expose().to_string(),
+        Column::Secret32 => record.seed().expose().to_string(),
+        Column::Prefix => record.prefix.clone(),
+        Column::PubkeyHex => integrity::pubkey_hex(&record.pubkey()),
+        Column::Shamir => scheme.map(|scheme| scheme.to_string()).unwrap_or_default(),
+        Column::SecretRef => secret_ref.to_string(),
+        Column::Ata(mint) => ata::associated_token_address(&record.pubkey(), &mint).to_string(),
+        Column::CharCount(ch) => char_count::describe(ch, &record.address),
+        Column::Source => schema.source.clone(),
+        Column::Index => record.index.map(|index| index.to_string()).unwrap_or_default(),
+        Column::FoundAt => found_at.to_string(),
+        Column::Attempts => record.luck.map(|luck| luck.attempts.to_string()).unwrap_or_default(),
+        Column::Luck => record.luck.map(|luck| format!("{:.3}", luck.factor)).unwrap_or_default(),
+        Column::LuckPercentile => record.luck.map(|luck| format!("{:.4}", luck.percentile)).unwrap_or_default(),
+        Column::Known => record.known.map(|mark| mark.as_str().to_string()).unwrap_or_default(),
+        Column::RowHash => String::new(),
+    });
+    let row = Zeroizing::new(row);
+    writer.write_row(&row)
+}
+
+/// 把一组记录按输出格式写入新文件（截断已有内容），用于会话模式结束时导出
+pub fn export_records(path: &Path, schema: &Schema, records: &[&Record]) -> io::Result<()> {
+    let mut writer = schema.header_writer(create_file(path)?)?;
+    for record in records {
+        write_record(&mut writer, schema, record, None, "", "")?;
+    }
+    writer.flush()
+}
+
+/// 按分数从高到低写出排行榜
+fn write_ranking(path: &Path, top: TopN<Record>) -> io::Result<()> {
+    let mut writer = csv::Writer::from_writer(create_file(path)?);
+    writer.write_record(["rank", "score", "address", "private_key"])?;
+    for (index, (score, record)) in top.into_sorted().into_iter().enumerate() {
+        let (rank, score) = ((index + 1).to_string(), score.to_string());
+        writer.write_record([&rank, &score, &record.address, record.secret_key().expose()])?;
+    }
+    writer.flush()
+}
+
+/// 把种子拆分成分享，第 i 份写入 `<目录>/<i>/<地址>.txt`，返回写入的路径
+fn write_shares(dir: &Path, scheme: ShamirScheme, record: &Record) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::with_capacity(scheme.shares as usize);
+    for (index, share) in scheme.split(&record.address, &record.keypair[..32]).iter().enumerate() {
+        let path = ShamirScheme::share_path(dir, index + 1, &record.address);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, share.to_string())?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// 创建（截断）含私钥的文件，Unix 上只有所有者可读写，必要时创建目录
+pub fn create_secret_file(path: &Path) -> io::Result<File> {
+    open_secret_file(path, false)
+}
+
+/// 与 [`create_secret_file`] 相同，但文件已存在时返回 [`io::ErrorKind::AlreadyExists`]，不覆盖其中的私钥
+pub fn create_new_secret_file(path: &Path) -> io::Result<File> {
+    open_secret_file(path, true)
+}
+
+fn open_secret_file(path: &Path, create_new: bool) -> io::Result<File> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut options = OpenOptions::new();
+    if create_new {
+        options.write(true).create_new(true);
+    } else {
+        options.write(true).create(true).truncate(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// 以 solana CLI 的 JSON 字节数组格式写入 `<地址>.json`，目录在第一次写入时创建
+fn write_keypair_file(dir: &Path, record: &Record) -> io::Result<PathBuf> {
+    let path = dir.join(format!("{}.json", record.address));
+    let json = Zeroizing::new(serde_json::to_string(&record.keypair[..]).map_err(io::Error::other)?);
+    create_secret_file(&path)?.write_all(json.as_bytes())?;
+    Ok(path)
+}
+
+/// 替换占位符后通过 shell 执行匹配钩子
+///
+/// 钩子在写入线程里逐个执行并等待结束，不会并发启动。命令行里永远不会出现私钥，
+/// 需要私钥的集成应使用 `{keypair_file}`。执行失败只打印警告，不影响搜索。
+fn run_hook(template: &str, record: &Record, keypair_file: Option<&Path>) {
+    if let Err(err) = offline::ensure_online("执行匹配钩子") {
+        eprintln!("{}", err);
+        return;
+    }
+
+    let keypair_file = keypair_file
+        .map(|path| shell_quote(&path.to_string_lossy()))
+        .unwrap_or_default();
+    let command = template
+        .replace("{address}", &record.address)
+        .replace("{prefix}", &shell_quote(&record.prefix))
+        .replace("{keypair_file}", &keypair_file);
+
+    #[cfg(windows)]
+    let status = Command::new("cmd").args(["/C", &command]).status();
+    #[cfg(not(windows))]
+    let status = Command::new("sh").args(["-c", &command]).status();
+
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!("匹配钩子退出状态异常 ({}): {}", status, command),
+        Err(err) => eprintln!("无法执行匹配钩子: {}", err),
+    }
+}
+
+/// 按当前平台的 shell 规则给参数加引号
+#[cfg(windows)]
+pub fn shell_quote(value: &str) -> String {
+    format!("\"{}\"", value)
+}
+
+/// 按当前平台的 shell 规则给参数加引号
+#[cfg(not(windows))]
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signature::Keypair;
+    use solana_sdk::signer::Signer;
+    use solana_sdk::signer::keypair::keypair_from_seed;
+
+    use super::*;
+
+    /// 固定种子的匹配记录
+    fn record(seed: u8) -> Record {
+        let keypair = keypair_from_seed(&[seed; 32]).unwrap();
+        Record {
+            address: keypair.pubkey().to_string(),
+            prefix: String::new(),
+            keypair: keypair.to_bytes(),
+            luck: None,
+            index: None,
+            known: None,
+        }
+    }
+
+    #[test]
+    fn both_secret_forms_give_the_same_pubkey() {
+        for seed in 0u8..32 {
+            let record = record(seed);
+            let secret32 = bs58::decode(record.seed().expose()).into_vec().unwrap();
+            let keypair64 = bs58::decode(record.secret_key().expose()).into_vec().unwrap();
+            assert_eq!(secret32.len(), 32);
+            assert_eq!(keypair64.len(), 64);
+            assert_eq!(secret32, keypair64[..32]);
+
+            let from_seed = keypair_from_seed(&secret32).unwrap().pubkey();
+            let from_keypair = Keypair::from_bytes(&keypair64).unwrap().pubkey();
+            assert_eq!(from_seed, from_keypair);
+            assert_eq!(from_seed, record.pubkey());
+            assert_eq!(from_seed.to_string(), record.address);
+
+            integrity::check_seed(&record.address, record.seed().expose()).unwrap();
+            integrity::check_keypair(&record.address, record.secret_key().expose()).unwrap();
+        }
+    }
+
+    #[test]
+    fn secret_forms_of_another_key_are_rejected() {
+        let (a, b) = (record(1), record(2));
+        assert!(integrity::check_seed(&a.address, b.seed().expose()).is_err());
+        assert!(integrity::check_keypair(&a.address, b.secret_key().expose()).is_err());
+        // 64 字节私钥放进 32 字节的列，或者反过来
+        assert!(integrity::check_seed(&a.address, a.secret_key().expose()).is_err());
+        assert!(integrity::check_keypair(&a.address, a.seed().expose()).is_err());
+    }
+}
@@ -0,0 +1,34 @@
+//! `--both-secret-forms`：32 字节种子和 64 字节密钥对两列推导出同一个公钥
+
+mod common;
+
+use common::{run, stderr, stdout, temp_dir};
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::signer::keypair::keypair_from_seed;
+
+#[test]
+fn both_columns_derive_the_address() {
+    let dir = temp_dir("both-secret-forms");
+    let output = run(&dir, &["-p", "A", "--max-matches", "1", "--insecure-seed", "7", "-t", "1", "--both-secret-forms"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let mut reader = csv::Reader::from_path(dir.join("data/matched_addresses.csv")).unwrap();
+    assert_eq!(reader.headers().unwrap(), vec!["address", "secret32_bs58", "keypair64_bs58"]);
+    let mut rows = 0;
+    for record in reader.records() {
+        let record = record.unwrap();
+        let seed = bs58::decode(&record[1]).into_vec().unwrap();
+        let keypair = bs58::decode(&record[2]).into_vec().unwrap();
+        assert_eq!(seed.len(), 32);
+        assert_eq!(seed, keypair[..32]);
+        assert_eq!(keypair_from_seed(&seed).unwrap().pubkey().to_string(), &record[0]);
+        assert_eq!(Keypair::from_bytes(&keypair).unwrap().pubkey().to_string(), &record[0]);
+        rows += 1;
+    }
+    assert!(rows >= 1);
+
+    let output = run(&dir, &["verify", "data/matched_addresses.csv"]);
+    assert!(output.status.success(), "{}{}", stdout(&output), stderr(&output));
+    std::fs::remove_dir_all(dir).unwrap();
+}
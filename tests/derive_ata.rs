@@ -0,0 +1,63 @@
+//! `--derive-ata`：匹配文件中原版 SPL Token 和 Token-2022 的 ATA 列与官方推导一致，`verify` 检查这些列
+
+mod common;
+
+use solana_sdk::pubkey::Pubkey;
+
+use common::{run, stderr, stdout, temp_dir};
+
+const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+/// 官方 `get_associated_token_address_with_program_id` 的推导
+fn official(wallet: &Pubkey, mint: &Pubkey, token_program: &str) -> String {
+    let token_program: Pubkey = token_program.parse().unwrap();
+    let program: Pubkey = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL".parse().unwrap();
+    Pubkey::find_program_address(&[&wallet.to_bytes(), &token_program.to_bytes(), &mint.to_bytes()], &program)
+        .0
+        .to_string()
+}
+
+#[test]
+fn token_and_token_2022_columns_are_written_and_verified() {
+    let dir = temp_dir("derive-ata");
+    let mints = format!("{},{}:token-2022", USDC, USDC);
+    let output = run(&dir, &["-p", "2", "-t", "1", "--max-matches", "3", "--derive-ata", &mints]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let path = dir.join("data/matched_addresses.csv");
+    let mut reader = csv::Reader::from_path(&path).unwrap();
+    let token_column = format!("ata_{}", USDC);
+    let token_2022_column = format!("ata_{}:token-2022", USDC);
+    assert_eq!(
+        reader.headers().unwrap(),
+        vec!["address", "private_key", token_column.as_str(), token_2022_column.as_str()]
+    );
+    let mint: Pubkey = USDC.parse().unwrap();
+    let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+    assert_eq!(rows.len(), 3);
+    for row in &rows {
+        let wallet: Pubkey = row[0].parse().unwrap();
+        assert_eq!(&row[2], official(&wallet, &mint, "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"));
+        assert_eq!(&row[3], official(&wallet, &mint, "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"));
+    }
+    let output = run(&dir, &["verify", "data/matched_addresses.csv"]);
+    assert!(output.status.success(), "{}{}", stdout(&output), stderr(&output));
+
+    // 把两列对调，verify 必须发现两列都不对
+    let content = std::fs::read_to_string(&path).unwrap();
+    let swapped: Vec<String> = content
+        .lines()
+        .enumerate()
+        .map(|(number, line)| {
+            let fields: Vec<&str> = line.split(',').collect();
+            if number == 0 { line.to_string() } else { [fields[0], fields[1], fields[3], fields[2]].join(",") }
+        })
+        .collect();
+    std::fs::write(&path, swapped.join("\n") + "\n").unwrap();
+    let output = run(&dir, &["verify", "data/matched_addresses.csv"]);
+    assert!(!output.status.success());
+    let report = format!("{}{}", stdout(&output), stderr(&output));
+    assert_eq!(report.matches("的关联代币账户应为").count(), 6, "{}", report);
+    assert!(report.contains(&format!("代币 {}:token-2022 的关联代币账户应为", USDC)), "{}", report);
+    std::fs::remove_dir_all(dir).unwrap();
+}
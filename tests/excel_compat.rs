@@ -0,0 +1,28 @@
+//! `--excel-compat`：写出的文件带 BOM 和 CRLF，形如公式的字段被转义，`verify` 读回后校验通过
+
+mod common;
+
+use common::{run, stderr, stdout, temp_dir};
+
+#[test]
+fn excel_compat_output_round_trips_through_verify() {
+    let dir = temp_dir("excel-compat");
+    let tag = "=1+1";
+    let output = run(
+        &dir,
+        &["-p", "A", "--max-matches", "1", "--insecure-seed", "7", "-t", "1", "--excel-compat", "--row-hash", "--tag", tag],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let content = std::fs::read_to_string(dir.join("data/matched_addresses.csv")).unwrap();
+    assert!(content.starts_with('\u{feff}'));
+    let lines: Vec<&str> = content.split_terminator("\r\n").collect();
+    assert!(lines.len() >= 2, "{:?}", content);
+    assert!(lines.iter().all(|line| !line.contains('\n')), "只应使用 CRLF 换行");
+    assert!(lines[1..].iter().all(|line| line.contains(",'=1+1,")), "{:?}", lines);
+
+    let output = run(&dir, &["verify", "data/matched_addresses.csv"]);
+    assert!(output.status.success(), "{}{}", stdout(&output), stderr(&output));
+    assert!(stdout(&output).contains(&format!("{} 行带行哈希", lines.len() - 1)), "{}", stdout(&output));
+    std::fs::remove_dir_all(dir).unwrap();
+}
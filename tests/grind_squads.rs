@@ -0,0 +1,44 @@
+//! grind-squads 的输出文件含 create_key 的私钥：新建时只有所有者可读写，已存在时拒绝覆盖
+
+mod common;
+
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use common::{run, stderr, temp_dir};
+
+#[test]
+fn refuses_to_overwrite_an_existing_output() {
+    let dir = temp_dir("squads-exists");
+    std::fs::write(dir.join("vaults.csv"), "old secrets\n").unwrap();
+    let output = run(&dir, &["grind-squads", "-p", "2", "-t", "1", "-o", "vaults.csv"]);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(stderr(&output).contains("vaults.csv 已存在"), "{}", stderr(&output));
+    assert_eq!(std::fs::read_to_string(dir.join("vaults.csv")).unwrap(), "old secrets\n");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn new_output_is_readable_only_by_the_owner() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = temp_dir("squads-mode");
+    let path = dir.join("out/vaults.csv");
+    // grind-squads 不会自己结束，等文件创建后结束进程
+    let mut child = Command::new(env!("CARGO_BIN_EXE_solana_vanity_address"))
+        .args(["grind-squads", "-p", "zzzzzz", "-t", "1", "-o", "out/vaults.csv"])
+        .current_dir(&dir)
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    let started = Instant::now();
+    while !path.exists() {
+        assert!(started.elapsed() < Duration::from_secs(10), "没有写出 {}", path.display());
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    std::fs::remove_dir_all(dir).unwrap();
+}
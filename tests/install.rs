@@ -0,0 +1,43 @@
+//! `install` 子命令：原有的密钥对先备份，绝不直接覆盖；目标不是普通文件时拒绝
+
+mod common;
+
+use common::{run, stderr, stdout, temp_dir};
+
+#[test]
+fn install_backs_up_and_refuses() {
+    let dir = temp_dir("install");
+    let output = run(&dir, &["-p", "A", "--max-matches", "1", "--insecure-seed", "7", "-t", "1"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let mut reader = csv::Reader::from_path(dir.join("data/matched_addresses.csv")).unwrap();
+    let address = reader.records().next().unwrap().unwrap()[0].to_string();
+
+    let old = "[1,2,3]";
+    std::fs::create_dir_all(dir.join("solana")).unwrap();
+    std::fs::write(dir.join("solana/id.json"), old).unwrap();
+    let install = |path: &str| run(&dir, &["install", "--address", &address, "--path", path, "data/matched_addresses.csv"]);
+
+    let output = install("solana/id.json");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("原有的密钥对已备份到"), "{}", stdout(&output));
+    let backups: Vec<_> = std::fs::read_dir(dir.join("solana"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.file_name().unwrap().to_string_lossy().starts_with("id.json.bak-"))
+        .collect();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(std::fs::read_to_string(&backups[0]).unwrap(), old);
+    let written: Vec<u8> = serde_json::from_str(&std::fs::read_to_string(dir.join("solana/id.json")).unwrap()).unwrap();
+    assert_eq!(written.len(), 64);
+
+    // 再装一次是同一个密钥对，不再备份
+    let output = install("solana/id.json");
+    assert!(stdout(&output).contains("没有改动"), "{}", stdout(&output));
+
+    // 目标是目录时拒绝
+    std::fs::create_dir(dir.join("occupied")).unwrap();
+    let output = install("occupied");
+    assert_eq!(output.status.code(), Some(2), "{}", stdout(&output));
+    assert!(stderr(&output).contains("不是普通文件"), "{}", stderr(&output));
+    std::fs::remove_dir_all(dir).unwrap();
+}
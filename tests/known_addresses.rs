@@ -0,0 +1,42 @@
+//! `--known-addresses` 命中的匹配在匹配文件的 `known` 列中标出，其余行为空
+
+mod common;
+
+use common::{run, stderr, temp_dir};
+
+/// 固定种子的一次运行，返回匹配文件的标题和各行
+fn seeded_run(dir: &std::path::Path, extra: &[&str]) -> (csv::StringRecord, Vec<csv::StringRecord>) {
+    let mut args = vec!["-p", "2", "-t", "1", "--insecure-seed", "7", "--max-matches", "4", "--no-interactive"];
+    args.extend_from_slice(extra);
+    let output = run(dir, &args);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let mut reader = csv::Reader::from_path(dir.join("data/matched_addresses.csv")).unwrap();
+    let header = reader.headers().unwrap().clone();
+    let rows = reader.records().map(Result::unwrap).collect();
+    (header, rows)
+}
+
+#[test]
+fn known_hits_are_marked_in_the_output() {
+    let dir = temp_dir("known-plain");
+    let (header, rows) = seeded_run(&dir, &[]);
+    assert!(!header.iter().any(|name| name == "known"), "{:?}", header);
+    let known = rows[1][0].to_string();
+    std::fs::remove_dir_all(dir).unwrap();
+
+    let dir = temp_dir("known-marked");
+    std::fs::write(dir.join("known.txt"), format!("{}\n", known)).unwrap();
+    let (header, rows) = seeded_run(&dir, &["--known-addresses", "known.txt", "--report", "report.json"]);
+    let column = header.iter().position(|name| name == "known").expect("缺少 known 列");
+    let marks: Vec<(&str, &str)> = rows.iter().map(|row| (&row[0], &row[column])).collect();
+    assert_eq!(marks.len(), 4);
+    for (address, mark) in marks {
+        let expected = if address == known { "confirmed" } else { "" };
+        assert_eq!(mark, expected, "{}", address);
+    }
+
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("report.json")).unwrap()).unwrap();
+    assert_eq!(report["known_hits"], serde_json::json!([known]));
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,82 @@
+//! `--max-matches`：多个线程同时找到匹配时也恰好写出 N 个，不丢失也不重复
+
+mod common;
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use common::{run, stderr, stdout, temp_dir};
+
+/// 读出匹配文件的各行，检查地址和编号都不重复、编号恰好是 1..=N，且私钥与地址一致
+fn assert_exact(dir: &Path, matched: &str, expected: usize) {
+    let mut reader = csv::Reader::from_path(dir.join(matched)).unwrap();
+    assert_eq!(reader.headers().unwrap(), vec!["index", "address", "private_key"]);
+    let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+    assert_eq!(rows.len(), expected, "{:?}", rows);
+    let addresses: HashSet<&str> = rows.iter().map(|row| &row[1]).collect();
+    assert_eq!(addresses.len(), expected, "地址重复");
+    let mut indices: Vec<u64> = rows.iter().map(|row| row[0].parse().unwrap()).collect();
+    indices.sort_unstable();
+    assert_eq!(indices, (1..=expected as u64).collect::<Vec<_>>());
+
+    let output = run(dir, &["verify", matched]);
+    assert!(output.status.success(), "{}{}", stdout(&output), stderr(&output));
+}
+
+#[test]
+fn burst_then_stop_writes_exactly_max() {
+    // `2` 开头约每 17 个地址一个，4 个线程几乎同时找到很多匹配
+    for max in ["1", "5", "20"] {
+        let dir = temp_dir("max-matches");
+        let output = run(
+            &dir,
+            &["-p", "2", "-t", "4", "--batch-size", "512", "--max-matches", max, "--columns", "index,address,private_key"],
+        );
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert!(!stderr(&output).contains("错误"), "{}", stderr(&output));
+        assert!(stdout(&output).contains(&format!("匹配 {} 个", max)), "{}", stdout(&output));
+        assert_exact(&dir, "data/matched_addresses.csv", max.parse().unwrap());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
+
+#[test]
+fn continue_sample_keeps_exactly_max() {
+    let dir = temp_dir("max-matches");
+    let output = run(
+        &dir,
+        &[
+            "-p", "2", "-t", "4", "--max-matches", "3", "--then", "continue-sample", "-n", "50", "--columns",
+            "index,address,private_key",
+        ],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_exact(&dir, "data/matched_addresses.csv", 3);
+    let samples = std::fs::read_to_string(dir.join("data/solana_addresses.csv")).unwrap();
+    assert_eq!(samples.lines().count(), 51);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn jobs_write_exactly_their_max() {
+    let dir = temp_dir("max-matches");
+    std::fs::write(
+        dir.join("jobs.json"),
+        r#"{"jobs": [
+            {"name": "a", "prefixes": ["2"], "max_matches": 2},
+            {"name": "b", "prefixes": ["3"], "max_matches": 7}
+        ]}"#,
+    )
+    .unwrap();
+    let output = run(&dir, &["--jobs", "jobs.json", "-t", "4"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!stderr(&output).contains("错误"), "{}", stderr(&output));
+    for (file, expected) in [("data/a_matched.csv", 2), ("data/b_matched.csv", 7)] {
+        let rows: Vec<csv::StringRecord> =
+            csv::Reader::from_path(dir.join(file)).unwrap().records().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), expected, "{}: {:?}", file, rows);
+        let addresses: HashSet<&str> = rows.iter().map(|row| &row[0]).collect();
+        assert_eq!(addresses.len(), expected);
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,43 @@
+//! `--offline`：参数校验阶段拒绝所有可能访问网络的选项，不创建任何文件
+
+mod common;
+
+use common::{run, stderr, stdout, temp_dir};
+
+/// 以退出码 2 拒绝，错误信息列出 `options`，且没有写出任何文件
+fn assert_rejected(args: &[&str], options: &str) {
+    let dir = temp_dir("offline");
+    let mut all = vec!["-p", "2", "-t", "1", "--offline"];
+    all.extend_from_slice(args);
+    let output = run(&dir, &all);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    let expected = format!("--offline 不能与以下可能访问网络的选项一起使用: {}", options);
+    assert!(stderr(&output).contains(&expected), "{}", stderr(&output));
+    assert!(!stdout(&output).contains("OFFLINE MODE"));
+    assert!(!dir.join("data").exists());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn webhook_url_is_rejected() {
+    assert_rejected(&["--webhook-url", "https://example.com/hook"], "--webhook-url");
+}
+
+#[test]
+fn every_network_option_is_listed() {
+    assert_rejected(&["--s3-upload", "s3://bucket/prefix"], "--s3-upload");
+    assert_rejected(&["--on-match", "true"], "--on-match");
+    assert_rejected(
+        &["--on-match", "true", "--webhook-url", "http://127.0.0.1:9/", "--s3-upload", "s3://bucket"],
+        "--on-match, --webhook-url, --s3-upload",
+    );
+}
+
+#[test]
+fn offline_run_prints_the_banner() {
+    let dir = temp_dir("offline");
+    let output = run(&dir, &["-p", "2", "-t", "1", "--offline", "--max-matches", "1"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output).matches("OFFLINE MODE").count(), 2, "{}", stdout(&output));
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,35 @@
+//! 前缀中的非 ASCII 和非 Base58 字符在开始搜索之前就被拒绝
+
+mod common;
+
+use common::{run, stderr, temp_dir};
+
+/// 以退出码 2 拒绝，错误信息含有 `expected`，且没有写出任何文件
+fn assert_rejected(prefixes: &str, expected: &str) {
+    let dir = temp_dir("prefix-validation");
+    let output = run(&dir, &["-p", prefixes, "-t", "1"]);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(stderr(&output).contains(expected), "{}", stderr(&output));
+    assert!(!dir.join("data").exists());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fullwidth_prefix_is_rejected_with_hint() {
+    assert_rejected("Ｓo", "第 1 个字符 'Ｓ'（U+FF33）不是 ASCII 字符，可能是误输入的全角或带重音的字符，是否想输入半角的 'S'？");
+}
+
+#[test]
+fn accented_prefix_is_rejected_with_position() {
+    assert_rejected("So,Soxé", "前缀 \"Soxé\" 的第 4 个字符 'é'（U+00E9）");
+}
+
+#[test]
+fn emoji_prefix_is_rejected() {
+    assert_rejected("🚀", "第 1 个字符 '🚀'（U+1F680）");
+}
+
+#[test]
+fn excluded_ascii_is_rejected() {
+    assert_rejected("So0", "第 3 个字符 '0' 不在 Base58 字母表中");
+}
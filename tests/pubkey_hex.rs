@@ -0,0 +1,31 @@
+//! `--include-pubkey-bytes`：输出文件的 `pubkey_hex` 列还原出的公钥就是地址，`verify` 校验通过
+
+mod common;
+
+use common::{run, stderr, stdout, temp_dir};
+
+#[test]
+fn pubkey_hex_column_matches_the_address() {
+    let dir = temp_dir("pubkey-hex");
+    let output = run(&dir, &["-p", "A", "--max-matches", "1", "--insecure-seed", "7", "-t", "1", "--include-pubkey-bytes"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let path = dir.join("data/matched_addresses.csv");
+    let mut reader = csv::Reader::from_path(&path).unwrap();
+    assert_eq!(reader.headers().unwrap(), vec!["address", "private_key", "pubkey_hex"]);
+    let mut rows = 0;
+    for record in reader.records() {
+        let record = record.unwrap();
+        let hex = &record[2];
+        assert_eq!(hex.len(), 64);
+        let bytes: Vec<u8> = (0..64).step_by(2).map(|start| u8::from_str_radix(&hex[start..start + 2], 16).unwrap()).collect();
+        assert_eq!(bs58::encode(bytes).into_string(), &record[0]);
+        rows += 1;
+    }
+    assert!(rows >= 1);
+
+    let output = run(&dir, &["verify", "data/matched_addresses.csv"]);
+    assert!(output.status.success(), "{}{}", stdout(&output), stderr(&output));
+    assert!(stdout(&output).contains("校验通过"), "{}", stdout(&output));
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,78 @@
+//! 私钥只出现在私钥文件里：完整运行一次，终端输出、审计日志、吞吐量和尝试次数日志、
+//! 心跳、摘要和匹配文件中都找不到任何一把写出的私钥
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::{run, stderr, stdout, temp_dir};
+
+/// `dir` 下除 `except` 以外的所有文件
+fn files_except(dir: &Path, except: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files.extend(files_except(&path, except));
+        } else if path != except {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// 限速运行几秒，让心跳和吞吐量日志都有输出；返回私钥文件 `secret_file`（第二列是私钥）中的私钥
+/// 和其余所有输出
+fn full_run(secret_file: &str, extra: &[&str]) -> (Vec<String>, Vec<(String, String)>) {
+    let dir = temp_dir("redaction");
+    let mut args = vec![
+        "-p", "2", "-t", "2", "--max-matches", "20", "--limit-rate", "100", "--no-interactive", "--audit-log",
+        "audit.jsonl", "--heartbeat", "1", "--rate-log", "rate.csv", "--rate-log-interval", "1", "--attempts-log",
+        "attempts.csv", "--report", "report.json",
+    ];
+    args.extend_from_slice(extra);
+    let output = run(&dir, &args);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("generated="), "没有心跳输出: {}", stdout(&output));
+
+    let secret_file = dir.join(secret_file);
+    let secrets: Vec<String> = csv::Reader::from_path(&secret_file)
+        .unwrap()
+        .records()
+        .map(|row| row.unwrap()[1].to_string())
+        .collect();
+    assert_eq!(secrets.len(), 20);
+
+    let mut outputs = vec![("stdout".to_string(), stdout(&output)), ("stderr".to_string(), stderr(&output))];
+    for path in files_except(&dir, &secret_file) {
+        let content = String::from_utf8_lossy(&std::fs::read(&path).unwrap()).into_owned();
+        outputs.push((path.display().to_string(), content));
+    }
+    for name in ["audit.jsonl", "rate.csv", "attempts.csv", "report.json", "data/MANIFEST.json"] {
+        assert!(dir.join(name).exists(), "没有写出 {}", name);
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+    (secrets, outputs)
+}
+
+#[test]
+fn secrets_appear_only_in_the_secret_file() {
+    let (secrets, outputs) = full_run("secrets.csv", &["--split-output", "secrets.csv"]);
+    for secret in &secrets {
+        for (name, content) in &outputs {
+            assert!(!content.contains(secret.as_str()), "{} 中出现了私钥", name);
+        }
+    }
+}
+
+#[test]
+fn reveal_partial_shows_only_the_first_characters() {
+    let (secrets, outputs) = full_run("data/matched_addresses.csv", &["--reveal-partial"]);
+    for secret in &secrets {
+        // 只会显示前 4 个字符；取前 8 个字符比较，避免与其他随机文本偶然重合
+        let longer = &secret[..8];
+        for (name, content) in &outputs {
+            assert!(!content.contains(longer), "{} 中出现了超过 4 个字符的私钥", name);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! `--s3-upload`：匹配文件含有明文私钥时默认拒绝上传，不读取凭据也不开始搜索
+
+mod common;
+
+use common::{run, stderr, temp_dir};
+
+#[test]
+fn plaintext_secrets_are_refused_by_default() {
+    let dir = temp_dir("s3-upload");
+    let output = run(&dir, &["-p", "2", "-t", "1", "--s3-upload", "s3://bucket/runs"]);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("data/matched_addresses.csv 含有明文私钥，--s3-upload 默认不上传"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(stderr(&output).contains("--s3-upload-plaintext"), "{}", stderr(&output));
+    assert!(!dir.join("data/matched_addresses.csv").exists());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn plaintext_flag_requires_an_upload_target() {
+    let dir = temp_dir("s3-upload");
+    let output = run(&dir, &["-p", "2", "-t", "1", "--s3-upload-plaintext"]);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(stderr(&output).contains("--s3-upload <URL>"), "{}", stderr(&output));
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,83 @@
+//! `--insecure-seed`：同样的种子写出逐字节相同的匹配文件，`--max-matches` 恰好写出密钥流中最前面的 N 个
+
+mod common;
+
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use solana_sdk::signer::Signer;
+use solana_sdk::signer::keypair::keypair_from_seed;
+
+use common::{run, stderr, temp_dir};
+
+/// 用固定种子搜索，返回匹配文件的内容
+fn seeded_run(extra: &[&str]) -> String {
+    let dir = temp_dir("seeded-run");
+    let mut args = vec!["-p", "2", "--insecure-seed", "7", "--max-matches", "5", "--columns", "index,address,prefix,private_key"];
+    args.extend_from_slice(extra);
+    let output = run(&dir, &args);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!stderr(&output).contains("错误"), "{}", stderr(&output));
+    let content = std::fs::read_to_string(dir.join("data/matched_addresses.csv")).unwrap();
+    std::fs::remove_dir_all(dir).unwrap();
+    content
+}
+
+/// 不经过程序本身，直接从 ChaCha20 密钥流推出前 `count` 个以 `prefix` 开头的地址和私钥：
+/// 第 i 个密钥的种子是种子为 `seed` 的 ChaCha20 输出的第 32i 到 32i+31 字节
+fn expected_matches(seed: u64, prefix: &str, count: usize) -> Vec<(String, String)> {
+    let mut rng = ChaCha20Rng::seed_from_u64(seed);
+    let mut found = Vec::new();
+    while found.len() < count {
+        let mut key = [0u8; 32];
+        rng.fill_bytes(&mut key);
+        let keypair = keypair_from_seed(&key).unwrap();
+        let address = keypair.pubkey().to_string();
+        if address.starts_with(prefix) {
+            found.push((address, keypair.to_base58_string()));
+        }
+    }
+    found
+}
+
+#[test]
+fn seeded_search_finds_the_expected_addresses() {
+    let expected = expected_matches(7, "2", 5);
+    // 固定下来的前三个，密钥流的推导方式一旦改变，同样的种子就不再得到同样的地址
+    let pinned = [
+        "27vNnDsc1AhoKk9pv3Qpsapk981wRKZ6EDehKdQZ72Bu",
+        "2Dr3Em9kw3JmL79b8uFWiP6dEesVM53RocvceusX6t5N",
+        "214VnU8nQA7Mw9dwWeHrSf38r3deTaeLcBwBN4xzBiUB",
+    ];
+    assert_eq!(expected.iter().take(3).map(|(address, _)| address.as_str()).collect::<Vec<_>>(), pinned);
+
+    for threads in ["1", "3"] {
+        let content = seeded_run(&["-t", threads]);
+        let mut lines = content.lines();
+        assert_eq!(lines.next(), Some("index,address,prefix,private_key"));
+        let rows: Vec<String> = lines.map(str::to_string).collect();
+        let wanted: Vec<String> = expected
+            .iter()
+            .enumerate()
+            .map(|(index, (address, secret))| format!("{},{},2,{}", index + 1, address, secret))
+            .collect();
+        assert_eq!(rows, wanted, "-t {}", threads);
+    }
+}
+
+#[test]
+fn identical_seeded_runs_are_byte_identical() {
+    let first = seeded_run(&["-t", "3"]);
+    assert_eq!(first.lines().count(), 6, "{}", first);
+    assert_eq!(seeded_run(&["-t", "3"]), first);
+}
+
+#[test]
+fn final_round_is_trimmed_by_key_position() {
+    // 每轮生成几百个密钥，`2` 开头约每 17 个一个，最后一轮的匹配远多于剩余的名额；
+    // 按位置裁剪后与线程数、轮的大小都无关
+    let single = seeded_run(&["-t", "1"]);
+    assert_eq!(single.lines().count(), 6, "{}", single);
+    for extra in [&["-t", "2"][..], &["-t", "4"], &["-t", "3", "--batch-size", "7"], &["-t", "1", "--batch-size", "1000"]] {
+        assert_eq!(seeded_run(extra), single, "{:?}", extra);
+    }
+}
@@ -0,0 +1,42 @@
+//! `simulate` 子命令的快速版本：少量试验，CI 中几秒内跑完
+//!
+//! 随机数来自操作系统，结果不确定，容差放宽到 5 个标准差（误报概率约 6e-7）；
+//! 固定种子的确定性检验见 `simulate` 模块的单元测试。
+
+mod common;
+
+use common::{run, stderr, stdout, temp_dir};
+
+fn simulate(prefixes: &str, trials: &str, max_attempts: &str, tolerance: &str) -> std::process::Output {
+    let dir = temp_dir("simulate");
+    let output = run(
+        &dir,
+        &["simulate", "-p", prefixes, "--trials", trials, "--max-attempts", max_attempts, "-t", "1", "--tolerance", tolerance],
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+    output
+}
+
+#[test]
+fn estimate_passes_for_common_first_characters() {
+    for (prefixes, trials, max_attempts) in [("2", "200", "1000"), ("[2-4]", "200", "1000"), ("1,z", "50", "5000")] {
+        let output = simulate(prefixes, trials, max_attempts, "5");
+        assert!(output.status.success(), "{}: {}{}", prefixes, stdout(&output), stderr(&output));
+        assert!(stdout(&output).contains("通过：命中数偏离期望"), "{}", stdout(&output));
+        assert!(stdout(&output).contains("分位点"), "{}", stdout(&output));
+    }
+}
+
+#[test]
+fn zero_tolerance_fails_with_exit_code_1() {
+    // 命中数是整数而期望不是，偏离不可能恰好为 0
+    let output = simulate("2", "50", "1000", "0");
+    assert_eq!(output.status.code(), Some(1), "{}", stdout(&output));
+    assert!(stdout(&output).contains("不通过"), "{}", stdout(&output));
+}
+
+#[test]
+fn impossible_prefix_is_rejected() {
+    let output = simulate("0", "10", "10", "3");
+    assert_eq!(output.status.code(), Some(2), "{}", stdout(&output));
+}
@@ -0,0 +1,40 @@
+//! `--stop-file`：控制文件出现后搜索优雅退出，已找到的匹配照常写出
+
+mod common;
+
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use common::temp_dir;
+
+#[test]
+fn stop_file_ends_the_run_gracefully() {
+    let dir = temp_dir("stop-file");
+    // 前缀很长，不会在测试期间找到，只能靠控制文件结束
+    let mut child = Command::new(env!("CARGO_BIN_EXE_solana_vanity_address"))
+        .args(["-p", "zzzzzzzz", "-t", "1", "--stop-file", "STOP", "--consume-stop-file"])
+        .current_dir(&dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(1500));
+    assert!(child.try_wait().unwrap().is_none(), "没有控制文件时不应结束");
+
+    std::fs::write(dir.join("STOP"), "stop\n").unwrap();
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        assert!(Instant::now() < deadline, "控制文件出现后没有退出");
+        std::thread::sleep(Duration::from_millis(100));
+    };
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(status.success(), "{}\n{}", stdout, String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("搜索结束"), "{}", stdout);
+    // --consume-stop-file 读取后删除控制文件
+    assert!(!dir.join("STOP").exists());
+    std::fs::remove_dir_all(dir).unwrap();
+}
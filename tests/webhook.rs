@@ -0,0 +1,55 @@
+//! `--webhook-url`：每个匹配向本机的模拟服务器 POST 一次，请求体只含地址和前缀
+
+mod common;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::thread;
+
+use common::{run, stderr, temp_dir};
+
+#[test]
+fn each_match_is_posted_without_the_secret() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/found", listener.local_addr().unwrap());
+    let (bodies, received) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            // 先记下请求体再回复，程序收到回复后才会继续，结束时所有请求体都已记下
+            bodies.send(String::from_utf8(body).unwrap()).unwrap();
+            stream.write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n").unwrap();
+        }
+    });
+
+    let dir = temp_dir("webhook");
+    let output = run(&dir, &["-p", "2", "-t", "1", "--max-matches", "1", "--webhook-url", &url]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!stderr(&output).contains("webhook 通知失败"), "{}", stderr(&output));
+
+    let mut rows = csv::Reader::from_path(dir.join("data/matched_addresses.csv")).unwrap();
+    assert_eq!(rows.headers().unwrap(), vec!["address", "private_key"]);
+    let rows: Vec<csv::StringRecord> = rows.records().map(Result::unwrap).collect();
+    let bodies: Vec<serde_json::Value> =
+        received.try_iter().map(|body| serde_json::from_str(&body).unwrap()).collect();
+    assert_eq!(bodies.len(), rows.len());
+    for (row, body) in rows.iter().zip(&bodies) {
+        assert_eq!(body, &serde_json::json!({ "address": &row[0], "prefix": "2" }));
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
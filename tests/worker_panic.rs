@@ -0,0 +1,46 @@
+//! 固定种子时工作线程 panic：其他线程不再在轮末等待，程序在宽限时间内收尾，
+//! 以退出码 4 结束，已经找到的匹配全部写进文件
+//!
+//! 注入 panic 的 `--debug-panic-after` 只存在于调试构建中，`cargo test --release` 时跳过
+
+#![cfg(debug_assertions)]
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{run, stderr, stdout, temp_dir};
+
+#[test]
+fn seeded_run_flushes_after_a_worker_panics() {
+    let dir = temp_dir("worker-panic");
+    let started = Instant::now();
+    let output = run(
+        &dir,
+        &[
+            "-p", "2", "-t", "3", "--insecure-seed", "7", "--batch-size", "64", "--max-matches", "100000",
+            "--debug-panic-after", "3", "--report", "report.json",
+        ],
+    );
+    // 看门狗的强制退出要等 10 秒，远早于此结束说明没有卡在轮末
+    assert!(started.elapsed() < Duration::from_secs(8), "{:?}", started.elapsed());
+    assert_eq!(output.status.code(), Some(4), "{}", stderr(&output));
+    assert!(stderr(&output).contains("工作线程 0 panic：--debug-panic-after 注入的 panic"), "{}", stderr(&output));
+    assert!(!stderr(&output).contains("强制退出"), "{}", stderr(&output));
+
+    // 前 3 轮共 3 × 3 × 64 个密钥，`2` 开头约每 17 个一个
+    let rows: Vec<csv::StringRecord> = csv::Reader::from_path(dir.join("data/matched_addresses.csv"))
+        .unwrap()
+        .records()
+        .map(Result::unwrap)
+        .collect();
+    assert!(rows.len() >= 10, "{:?}", rows);
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("report.json")).unwrap()).unwrap();
+    assert_eq!(report["state"], "stalled");
+    assert_eq!(report["matched"], rows.len() as u64);
+
+    let output = run(&dir, &["verify", "data/matched_addresses.csv"]);
+    assert!(output.status.success(), "{}{}", stdout(&output), stderr(&output));
+    std::fs::remove_dir_all(dir).unwrap();
+}